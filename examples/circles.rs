@@ -66,7 +66,7 @@ fn main() -> Result<(), std::io::Error> {
                 win = size.to_physical(window.hidpi_factor());
 
                 let (w, h) = (win.width as u32, win.height as u32);
-                chain = r.swap_chain(w, h, PresentMode::default());
+                r.resize(&mut chain, w, h);
             }
             _ => {}
         },
@@ -108,7 +108,7 @@ fn main() -> Result<(), std::io::Error> {
                 }
             }
 
-            let buffer = batch.finish(&r);
+            let buffer = batch.buffer(&r);
 
             ///////////////////////////////////////////////////////////////////////////
             // Create frame
@@ -127,7 +127,7 @@ fn main() -> Result<(), std::io::Error> {
                 let pass = &mut frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &out);
 
                 pass.set_pipeline(&pip);
-                pass.draw_buffer(&buffer);
+                pass.draw_indexed_buffer(&buffer);
             }
             r.present(frame);
         }
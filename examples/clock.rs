@@ -65,7 +65,7 @@ fn main() -> Result<(), std::io::Error> {
 
                 let (w, h) = (win.width as u32, win.height as u32);
 
-                textures = r.swap_chain(w, h, PresentMode::default());
+                r.resize(&mut textures, w, h);
 
                 *control_flow = ControlFlow::Poll;
             }
@@ -196,7 +196,7 @@ fn main() -> Result<(), std::io::Error> {
                     ));
                 }
 
-                let buffer = batch.finish(&r);
+                let buffer = batch.buffer(&r);
 
                 ///////////////////////////////////////////////////////////////////////////
                 // Create frame
@@ -216,7 +216,7 @@ fn main() -> Result<(), std::io::Error> {
                     let pass = &mut frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &out);
 
                     pass.set_pipeline(&pip);
-                    pass.draw_buffer(&buffer);
+                    pass.draw_indexed_buffer(&buffer);
                 }
                 r.present(frame);
 
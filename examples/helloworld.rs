@@ -67,7 +67,7 @@ fn main() -> Result<(), std::io::Error> {
             }
             WindowEvent::Resized(size) => {
                 let (w, h) = (size.width as u32, size.height as u32);
-                textures = renderer.swap_chain(w, h, PresentMode::default());
+                renderer.resize(&mut textures, w, h);
                 *control_flow = ControlFlow::Poll;
             }
             WindowEvent::KeyboardInput {
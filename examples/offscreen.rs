@@ -194,7 +194,7 @@ fn main() -> Result<(), std::io::Error> {
                 let physical = size.to_physical(window.hidpi_factor());
                 let (w, h) = (physical.width as u32, physical.height as u32);
 
-                textures = r.swap_chain(w, h, PresentMode::default());
+                r.resize(&mut textures, w, h);
             }
             _ => {}
         },
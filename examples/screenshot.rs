@@ -142,7 +142,7 @@ fn main() -> Result<(), std::io::Error> {
         Stroke::new(3.0, Rgba::new(1.0, 0.0, 1.0, 1.0)),
         Fill::Empty(),
     ))
-    .finish(&r);
+    .buffer(&r);
 
     ///////////////////////////////////////////////////////////////////////////
     // Create frame & on-screen output texture
@@ -165,7 +165,7 @@ fn main() -> Result<(), std::io::Error> {
     {
         let pass = &mut frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &framebuffer.target);
         pass.set_pipeline(&offscreen);
-        pass.draw_buffer(&buffer);
+        pass.draw_indexed_buffer(&buffer);
     }
 
     {
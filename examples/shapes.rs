@@ -9,7 +9,10 @@ use rgx::kit::{self, ZDepth};
 use rgx::math::*;
 
 use winit::{
-    event::{ElementState, Event, KeyboardInput, StartCause, VirtualKeyCode, WindowEvent},
+    event::{
+        ElementState, Event, KeyboardInput, MouseScrollDelta, StartCause, VirtualKeyCode,
+        WindowEvent,
+    },
     event_loop::{ControlFlow, EventLoop},
     window::Window,
 };
@@ -28,6 +31,7 @@ fn main() -> Result<(), std::io::Error> {
     let mut win = window.inner_size().to_physical(window.hidpi_factor());
 
     let pip: kit::shape2d::Pipeline = r.pipeline(Blending::default());
+    let mut camera = kit::Camera2D::new(win.width as u32, win.height as u32);
 
     ///////////////////////////////////////////////////////////////////////////
     // Render loop
@@ -54,17 +58,36 @@ fn main() -> Result<(), std::io::Error> {
                         ..
                     },
                 ..
-            } => match key {
-                VirtualKeyCode::Escape => {
-                    *control_flow = ControlFlow::Exit;
+            } => {
+                match key {
+                    VirtualKeyCode::Escape => {
+                        *control_flow = ControlFlow::Exit;
+                    }
+                    VirtualKeyCode::Left => camera.pan(sw, 0.),
+                    VirtualKeyCode::Right => camera.pan(-sw, 0.),
+                    VirtualKeyCode::Up => camera.pan(0., -sh),
+                    VirtualKeyCode::Down => camera.pan(0., sh),
+                    VirtualKeyCode::Q => camera.rotate(0.1),
+                    VirtualKeyCode::E => camera.rotate(-0.1),
+                    _ => {}
                 }
-                _ => {}
-            },
+                window.request_redraw();
+            }
             WindowEvent::CursorMoved { position, .. } => {
                 mx = position.x;
                 my = position.y;
                 window.request_redraw();
             }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let dy = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y,
+                    MouseScrollDelta::PixelDelta(p) => p.y as f32 / sh,
+                };
+                // Zoom around the cursor, whose position is from the top-left.
+                let cursor = Point2::new(mx as f32, (win.height - my) as f32);
+                camera.zoom_at(1.1f32.powf(dy), cursor);
+                window.request_redraw();
+            }
             WindowEvent::CloseRequested => {
                 *control_flow = ControlFlow::Exit;
             }
@@ -72,7 +95,8 @@ fn main() -> Result<(), std::io::Error> {
                 win = size.to_physical(window.hidpi_factor());
 
                 let (w, h) = (win.width as u32, win.height as u32);
-                textures = r.swap_chain(w, h, PresentMode::default());
+                r.resize(&mut textures, w, h);
+                camera.resize(w, h);
             }
             WindowEvent::RedrawRequested => {
                 let rows = (win.height as f32 / sh) as u32;
@@ -132,7 +156,7 @@ fn main() -> Result<(), std::io::Error> {
                     }
                 }
 
-                let buffer = batch.finish(&r);
+                let buffer = batch.buffer(&r);
 
                 ///////////////////////////////////////////////////////////////////////////
                 // Create frame
@@ -142,7 +166,7 @@ fn main() -> Result<(), std::io::Error> {
 
                 let out = textures.next();
 
-                r.update_pipeline(&pip, kit::ortho(out.width, out.height), &mut frame);
+                r.update_pipeline(&pip, &camera, &mut frame);
 
                 ///////////////////////////////////////////////////////////////////////////
                 // Draw frame
@@ -152,7 +176,7 @@ fn main() -> Result<(), std::io::Error> {
                     let pass = &mut frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &out);
 
                     pass.set_pipeline(&pip);
-                    pass.draw_buffer(&buffer);
+                    pass.draw_indexed_buffer(&buffer);
                 }
                 r.present(frame);
             }
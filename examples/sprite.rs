@@ -130,7 +130,7 @@ fn main() -> Result<(), std::io::Error> {
                     win = size.to_physical(window.hidpi_factor());
 
                     let (w, h) = (win.width as u32, win.height as u32);
-                    textures = r.swap_chain(w, h, PresentMode::default());
+                    r.resize(&mut textures, w, h);
                 }
                 _ => (),
             },
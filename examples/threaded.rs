@@ -59,7 +59,7 @@ fn main() -> Result<(), std::io::Error> {
                 Stroke::NONE,
                 Fill::Solid(Rgba::new(1., 0., 0., 1.)),
             ))
-            .finish(&renderer);
+            .buffer(&renderer);
 
             let output = chain.next();
             let mut frame = renderer.frame();
@@ -74,7 +74,7 @@ fn main() -> Result<(), std::io::Error> {
                 let mut pass = frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &output);
 
                 pass.set_pipeline(&pipeline);
-                pass.draw_buffer(&buffer);
+                pass.draw_indexed_buffer(&buffer);
             }
             renderer.present(frame);
         }
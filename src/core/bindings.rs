@@ -0,0 +1,180 @@
+//! Typed binding groups, for which the resources of a group are checked
+//! against its layout at compile time, instead of when it is created.
+//!
+//! The layout of a set is given as a tuple of binding kinds, and is checked
+//! once against the untyped layout, eg. one of `pipeline.layout.sets`.
+//! Binding groups are then created from a tuple of resources of the same
+//! kinds, in the same order.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use rgx::core::bindings::{SampledTexture, Sampler, TypedLayout};
+//! use rgx::core::{BindingGroup, Device, Error, Pipeline, Texture};
+//!
+//! fn binding(dev: &Device, pipeline: &Pipeline, texture: &Texture, sampler: &rgx::core::Sampler)
+//!     -> Result<BindingGroup, Error>
+//! {
+//!     let layout: TypedLayout<(SampledTexture, Sampler)> =
+//!         TypedLayout::new(&pipeline.layout.sets[1])?;
+//!
+//!     Ok(layout.create(dev, (texture, sampler)))
+//! }
+//! ```
+//!
+//! Resources of the wrong kind, or in the wrong number, don't compile:
+//!
+//! ```compile_fail
+//! use rgx::core::bindings::{SampledTexture, Sampler, TypedLayout};
+//! use rgx::core::{BindingGroup, Device, Texture};
+//!
+//! fn binding(dev: &Device, layout: &TypedLayout<(SampledTexture, Sampler)>, texture: &Texture)
+//!     -> BindingGroup
+//! {
+//!     layout.create(dev, (texture,))
+//! }
+//! ```
+use std::marker::PhantomData;
+
+use crate::core::{Bind, BindingGroup, BindingGroupLayout, BindingType, Device, Error};
+
+/// A kind of binding, as declared in a layout.
+pub trait Kind {
+    const TYPE: BindingType;
+}
+
+/// A uniform buffer binding.
+#[derive(Debug)]
+pub enum Uniform {}
+
+/// A uniform buffer binding with a dynamic offset.
+#[derive(Debug)]
+pub enum DynamicUniform {}
+
+/// A sampled texture binding.
+#[derive(Debug)]
+pub enum SampledTexture {}
+
+/// A sampler binding.
+#[derive(Debug)]
+pub enum Sampler {}
+
+/// A storage buffer binding, read and written by shaders.
+#[derive(Debug)]
+pub enum Storage {}
+
+/// A read-only storage buffer binding.
+#[derive(Debug)]
+pub enum ReadOnlyStorage {}
+
+/// A storage texture binding.
+#[derive(Debug)]
+pub enum StorageTexture {}
+
+impl Kind for Uniform {
+    const TYPE: BindingType = BindingType::UniformBuffer;
+}
+
+impl Kind for DynamicUniform {
+    const TYPE: BindingType = BindingType::UniformBufferDynamic;
+}
+
+impl Kind for SampledTexture {
+    const TYPE: BindingType = BindingType::SampledTexture;
+}
+
+impl Kind for Sampler {
+    const TYPE: BindingType = BindingType::Sampler;
+}
+
+impl Kind for Storage {
+    const TYPE: BindingType = BindingType::StorageBuffer;
+}
+
+impl Kind for ReadOnlyStorage {
+    const TYPE: BindingType = BindingType::StorageBufferReadOnly;
+}
+
+impl Kind for StorageTexture {
+    const TYPE: BindingType = BindingType::StorageTexture;
+}
+
+/// A resource which can be bound as a binding of kind `K`.
+pub trait Resource<K: Kind>: Bind {}
+
+impl Resource<Uniform> for crate::core::UniformBuffer {}
+impl Resource<DynamicUniform> for crate::core::UniformBuffer {}
+impl Resource<SampledTexture> for crate::core::Texture {}
+impl Resource<SampledTexture> for crate::core::Framebuffer {}
+impl Resource<SampledTexture> for crate::core::Backdrop {}
+impl Resource<Sampler> for crate::core::Sampler {}
+impl Resource<Storage> for crate::core::StorageBuffer {}
+impl Resource<ReadOnlyStorage> for crate::core::StorageBuffer {}
+impl Resource<StorageTexture> for crate::core::Texture {}
+
+/// The layout of a binding group, as a tuple of binding kinds.
+pub trait Layout {
+    fn types() -> Vec<BindingType>;
+}
+
+/// The resources of a binding group with layout `L`, as a tuple of
+/// references.
+pub trait Resources<'a, L: Layout> {
+    fn binds(self) -> Vec<&'a dyn Bind>;
+}
+
+macro_rules! tuples {
+    ($($kind:ident $res:ident $var:ident),+) => {
+        impl<$($kind: Kind),+> Layout for ($($kind,)+) {
+            fn types() -> Vec<BindingType> {
+                vec![$($kind::TYPE),+]
+            }
+        }
+
+        impl<'a, $($kind: Kind, $res: Resource<$kind>),+> Resources<'a, ($($kind,)+)>
+            for ($(&'a $res,)+)
+        {
+            fn binds(self) -> Vec<&'a dyn Bind> {
+                let ($($var,)+) = self;
+                vec![$($var as &dyn Bind),+]
+            }
+        }
+    };
+}
+
+tuples!(A RA a);
+tuples!(A RA a, B RB b);
+tuples!(A RA a, B RB b, C RC c);
+tuples!(A RA a, B RB b, C RC c, D RD d);
+tuples!(A RA a, B RB b, C RC c, D RD d, E RE e);
+tuples!(A RA a, B RB b, C RC c, D RD d, E RE e, F RF f);
+
+/// A binding group layout, checked to have the bindings of `L`.
+#[derive(Debug)]
+pub struct TypedLayout<'a, L> {
+    layout: &'a BindingGroupLayout,
+    kinds: PhantomData<L>,
+}
+
+impl<'a, L: Layout> TypedLayout<'a, L> {
+    /// Check that a layout has bindings of the kinds of `L`, in order.
+    pub fn new(layout: &'a BindingGroupLayout) -> Result<Self, Error> {
+        if layout.slots != L::types() {
+            return Err(Error::LayoutMismatch(layout.set_index));
+        }
+        Ok(Self {
+            layout,
+            kinds: PhantomData,
+        })
+    }
+
+    /// The untyped layout.
+    pub fn layout(&self) -> &'a BindingGroupLayout {
+        self.layout
+    }
+
+    /// Create a binding group from resources of the kinds of the layout.
+    pub fn create<'r, R: Resources<'r, L>>(&self, dev: &Device, resources: R) -> BindingGroup {
+        dev.create_binding_group(self.layout, &resources.binds())
+    }
+}
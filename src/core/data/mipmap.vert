@@ -0,0 +1,9 @@
+#version 450
+
+layout(location = 0) out vec2 f_uv;
+
+void main() {
+	// A triangle covering the whole target, for vertices 0 to 2.
+	f_uv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+	gl_Position = vec4(f_uv.x * 2.0 - 1.0, 1.0 - f_uv.y * 2.0, 0.0, 1.0);
+}
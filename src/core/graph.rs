@@ -0,0 +1,318 @@
+//! A small render graph, for frames made of several passes over off-screen
+//! targets, eg. with post-processing.
+//!
+//! Passes are declared with the attachments they read and the attachment
+//! they write, and are run in dependency order: a pass reading an attachment
+//! runs after every pass writing it. Passes writing the same attachment run
+//! in the order they were declared, and only the first one may clear it,
+//! later ones load its contents.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use rgx::core::graph::Graph;
+//! use rgx::core::{PassOp, Rgba};
+//!
+//! let mut graph = Graph::new();
+//!
+//! graph
+//!     .attachment("scene", &framebuffer)
+//!     .attachment("screen", &output)
+//!     .pass("blit", &["scene"], "screen", PassOp::Clear(Rgba::BLACK), |pass| {
+//!         pass.set_pipeline(&blit);
+//!         pass.draw(&quad, &scene_binding);
+//!     })
+//!     .pass("draw", &[], "scene", PassOp::Clear(Rgba::TRANSPARENT), |pass| {
+//!         pass.set_pipeline(&sprites);
+//!         pass.draw(&batch, &binding);
+//!     });
+//!
+//! // Runs "draw" before "blit".
+//! graph.execute(&mut frame)?;
+//! ```
+//!
+//! The scheduled passes can be inspected with `Graph::to_dot`, eg. to render
+//! them with Graphviz, or `Graph::to_json`.
+use std::fmt;
+use std::fmt::Write;
+
+use crate::core::{Error, Frame, Pass, PassOp, RenderTarget};
+
+/// A pass of a `Graph`.
+struct Node<'a> {
+    name: &'static str,
+    inputs: Vec<&'static str>,
+    output: &'static str,
+    op: PassOp,
+    draw: Box<dyn FnMut(&mut Pass) + 'a>,
+}
+
+impl fmt::Debug for Node<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Node")
+            .field("name", &self.name)
+            .field("inputs", &self.inputs)
+            .field("output", &self.output)
+            .field("op", &self.op)
+            .finish()
+    }
+}
+
+/// A set of named attachments, and the passes reading and writing them.
+#[derive(Default)]
+pub struct Graph<'a> {
+    attachments: Vec<(&'static str, &'a dyn RenderTarget)>,
+    passes: Vec<Node<'a>>,
+}
+
+impl fmt::Debug for Graph<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Graph")
+            .field(
+                "attachments",
+                &self.attachments.iter().map(|(n, _)| n).collect::<Vec<_>>(),
+            )
+            .field("passes", &self.passes)
+            .finish()
+    }
+}
+
+impl<'a> Graph<'a> {
+    pub fn new() -> Self {
+        Self {
+            attachments: Vec::new(),
+            passes: Vec::new(),
+        }
+    }
+
+    /// Declare a named attachment, eg. a `Framebuffer` or the swap chain
+    /// texture of the frame.
+    pub fn attachment(&mut self, name: &'static str, target: &'a dyn RenderTarget) -> &mut Self {
+        self.attachments.retain(|(n, _)| *n != name);
+        self.attachments.push((name, target));
+        self
+    }
+
+    /// Declare a pass reading the `inputs` attachments, eg. as textures,
+    /// and drawing into the `output` attachment.
+    pub fn pass<F>(
+        &mut self,
+        name: &'static str,
+        inputs: &[&'static str],
+        output: &'static str,
+        op: PassOp,
+        draw: F,
+    ) -> &mut Self
+    where
+        F: FnMut(&mut Pass) + 'a,
+    {
+        self.passes.push(Node {
+            name,
+            inputs: inputs.to_vec(),
+            output,
+            op,
+            draw: Box::new(draw),
+        });
+        self
+    }
+
+    /// The names of the passes, in the order they would run.
+    pub fn order(&self) -> Result<Vec<&'static str>, Error> {
+        Ok(self
+            .schedule()?
+            .into_iter()
+            .map(|i| self.passes[i].name)
+            .collect())
+    }
+
+    /// Run the passes in dependency order, recording them into the frame.
+    /// Nothing is recorded if the graph is invalid.
+    pub fn execute(self, frame: &mut Frame) -> Result<(), Error> {
+        let plan = self.plan()?;
+        let attachments = self.attachments;
+        let mut passes: Vec<Option<Node<'a>>> = self.passes.into_iter().map(Some).collect();
+
+        for (i, clears) in plan {
+            let mut node = passes[i].take().expect("passes are scheduled once");
+            let target = attachments
+                .iter()
+                .find(|(n, _)| *n == node.output)
+                .map(|(_, t)| *t)
+                .expect("attachments are validated");
+
+            let op = if clears { node.op } else { PassOp::Load() };
+            let mut pass = frame.pass(op, target);
+            (node.draw)(&mut pass);
+        }
+        Ok(())
+    }
+
+    /// Describe the scheduled passes and the attachments they use in the
+    /// Graphviz DOT language. Passes are boxes, numbered in the order they
+    /// run, and attachments are ellipses.
+    pub fn to_dot(&self) -> Result<String, Error> {
+        let plan = self.plan()?;
+        let mut out = String::from("digraph frame {\n    rankdir=LR;\n");
+
+        for (name, _) in self.attachments.iter() {
+            let id = quote(&format!("@{}", name));
+            writeln!(out, "    {} [shape=ellipse, label={}];", id, quote(name)).ok();
+        }
+        for (n, (i, clears)) in plan.iter().enumerate() {
+            let node = &self.passes[*i];
+            let op = if *clears { "clear" } else { "load" };
+            let label = format!("{}. {} ({})", n, node.name, op);
+
+            writeln!(
+                out,
+                "    {} [shape=box, label={}];",
+                quote(node.name),
+                quote(&label)
+            )
+            .ok();
+            for input in node.inputs.iter() {
+                let id = quote(&format!("@{}", input));
+                writeln!(out, "    {} -> {};", id, quote(node.name)).ok();
+            }
+            let id = quote(&format!("@{}", node.output));
+            writeln!(out, "    {} -> {};", quote(node.name), id).ok();
+        }
+        out.push_str("}\n");
+
+        Ok(out)
+    }
+
+    /// Describe the scheduled passes and the attachments they use as JSON,
+    /// with the passes in the order they run.
+    pub fn to_json(&self) -> Result<String, Error> {
+        let plan = self.plan()?;
+        let list = |names: &mut dyn Iterator<Item = &&'static str>| {
+            names.map(|n| quote(n)).collect::<Vec<_>>().join(", ")
+        };
+        let mut out = String::from("{\n");
+
+        writeln!(
+            out,
+            "  \"attachments\": [{}],",
+            list(&mut self.attachments.iter().map(|(n, _)| n))
+        )
+        .ok();
+        out.push_str("  \"passes\": [\n");
+
+        for (n, (i, clears)) in plan.iter().enumerate() {
+            let node = &self.passes[*i];
+
+            write!(
+                out,
+                "    {{\"name\": {}, \"inputs\": [{}], \"output\": {}, \"op\": {}}}",
+                quote(node.name),
+                list(&mut node.inputs.iter()),
+                quote(node.output),
+                quote(if *clears { "clear" } else { "load" }),
+            )
+            .ok();
+            out.push_str(if n + 1 < plan.len() { ",\n" } else { "\n" });
+        }
+        out.push_str("  ]\n}\n");
+
+        Ok(out)
+    }
+
+    /// The passes in the order they run, along with whether they clear their
+    /// output. Only the first pass writing an attachment may clear it.
+    fn plan(&self) -> Result<Vec<(usize, bool)>, Error> {
+        let mut written: Vec<&'static str> = Vec::new();
+
+        Ok(self
+            .schedule()?
+            .into_iter()
+            .map(|i| {
+                let node = &self.passes[i];
+                let first = !written.contains(&node.output);
+
+                if first {
+                    written.push(node.output);
+                }
+                (i, first && matches!(node.op, PassOp::Clear(_)))
+            })
+            .collect())
+    }
+
+    /// Sort the passes topologically, preferring declaration order.
+    fn schedule(&self) -> Result<Vec<usize>, Error> {
+        let n = self.passes.len();
+
+        for node in self.passes.iter() {
+            for name in node.inputs.iter().chain(Some(&node.output)) {
+                if !self.attachments.iter().any(|(a, _)| a == name) {
+                    return Err(Error::UnknownAttachment(name));
+                }
+            }
+            if node.inputs.contains(&node.output) {
+                return Err(Error::GraphCycle(node.name));
+            }
+        }
+
+        // Passes each pass depends on.
+        let mut deps: Vec<Vec<usize>> = vec![Vec::new(); n];
+        for (j, b) in self.passes.iter().enumerate() {
+            for (i, a) in self.passes.iter().enumerate() {
+                let reads = b.inputs.contains(&a.output);
+                let overwrites = i < j && a.output == b.output;
+
+                if i != j && (reads || overwrites) {
+                    deps[j].push(i);
+                }
+            }
+        }
+
+        let mut order = Vec::with_capacity(n);
+        let mut done = vec![false; n];
+
+        while order.len() < n {
+            let next = (0..n).find(|j| !done[*j] && deps[*j].iter().all(|i| done[*i]));
+
+            match next {
+                Some(j) => {
+                    done[j] = true;
+                    order.push(j);
+                }
+                None => {
+                    // Every pass left waits on another pass left, so walking
+                    // back through them ends up going around a cycle.
+                    let mut seen = Vec::new();
+                    let mut j = (0..n).find(|j| !done[*j]).unwrap_or_default();
+
+                    while !seen.contains(&j) {
+                        seen.push(j);
+                        j = deps[j].iter().copied().find(|i| !done[*i]).unwrap_or(j);
+                    }
+                    return Err(Error::GraphCycle(self.passes[j].name));
+                }
+            }
+        }
+        Ok(order)
+    }
+}
+
+/// Quote a string for JSON, which is also a valid quoted ID in DOT.
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                write!(out, "\\u{:04x}", c as u32).ok();
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
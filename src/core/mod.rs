@@ -1,12 +1,39 @@
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
 use std::fmt;
 use std::ops::Range;
+use std::rc::{Rc, Weak};
 use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 use raw_window_handle::HasRawWindowHandle;
 
 pub use crate::error::Error;
 pub use crate::rect::Rect;
 
+pub mod bindings;
+pub mod graph;
+#[cfg(feature = "trace")]
+pub mod trace;
+
+#[cfg(feature = "trace")]
+use trace::Event;
+
+/// Record a call in the trace being recorded, if any. Expands to nothing
+/// without the `trace` feature.
+#[cfg(feature = "trace")]
+macro_rules! trace {
+    ($event:expr) => {
+        trace::record(|| $event)
+    };
+}
+
+#[cfg(not(feature = "trace"))]
+macro_rules! trace {
+    ($event:expr) => {};
+}
+
 ///////////////////////////////////////////////////////////////////////////
 // Rgba8
 ///////////////////////////////////////////////////////////////////////////
@@ -191,6 +218,7 @@ pub trait Draw {
 
 #[repr(C)]
 #[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rgba {
     pub r: f32,
     pub g: f32,
@@ -252,7 +280,8 @@ pub struct Shader {
     module: wgpu::ShaderModule,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ShaderStage {
     Vertex,
     Fragment,
@@ -260,7 +289,7 @@ pub enum ShaderStage {
 }
 
 impl ShaderStage {
-    fn to_wgpu(&self) -> wgpu::ShaderStage {
+    fn to_wgpu(self) -> wgpu::ShaderStage {
         match self {
             ShaderStage::Vertex => wgpu::ShaderStage::VERTEX,
             ShaderStage::Fragment => wgpu::ShaderStage::FRAGMENT,
@@ -297,13 +326,24 @@ pub trait Canvas {
 /// A group of bindings.
 #[derive(Debug)]
 pub struct BindingGroup {
-    wgpu: wgpu::BindGroup,
+    wgpu: Deferred<wgpu::BindGroup>,
     set_index: u32,
+    #[cfg(feature = "trace")]
+    id: trace::Id,
+    #[cfg(feature = "validation")]
+    slots: Vec<BindingType>,
 }
 
 impl BindingGroup {
-    fn new(set_index: u32, wgpu: wgpu::BindGroup) -> Self {
-        Self { set_index, wgpu }
+    fn new(layout: &BindingGroupLayout, wgpu: Deferred<wgpu::BindGroup>) -> Self {
+        Self {
+            set_index: layout.set_index,
+            wgpu,
+            #[cfg(feature = "trace")]
+            id: trace::id(),
+            #[cfg(feature = "validation")]
+            slots: layout.slots.clone(),
+        }
     }
 }
 
@@ -313,14 +353,20 @@ pub struct BindingGroupLayout {
     wgpu: wgpu::BindGroupLayout,
     size: usize,
     set_index: u32,
+    #[cfg(feature = "trace")]
+    id: trace::Id,
+    slots: Vec<BindingType>,
 }
 
 impl BindingGroupLayout {
-    fn new(set_index: u32, layout: wgpu::BindGroupLayout, size: usize) -> Self {
+    fn new(set_index: u32, layout: wgpu::BindGroupLayout, slots: &[Binding]) -> Self {
         Self {
             wgpu: layout,
-            size,
+            size: slots.len(),
             set_index,
+            #[cfg(feature = "trace")]
+            id: trace::id(),
+            slots: slots.iter().map(|s| s.binding).collect(),
         }
     }
 }
@@ -328,6 +374,13 @@ impl BindingGroupLayout {
 /// A trait representing a resource that can be bound.
 pub trait Bind {
     fn binding(&self, index: u32) -> wgpu::Binding;
+
+    /// The traced id of the resource, or zero if it isn't traced.
+    #[cfg(feature = "trace")]
+    #[doc(hidden)]
+    fn trace_id(&self) -> trace::Id {
+        0
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -337,9 +390,11 @@ pub trait Bind {
 /// A uniform buffer that can be bound in a 'BindingGroup'.
 #[derive(Debug)]
 pub struct UniformBuffer {
-    wgpu: wgpu::Buffer,
+    wgpu: Rc<Deferred<wgpu::Buffer>>,
     size: usize,
     count: usize,
+    #[cfg(feature = "trace")]
+    id: trace::Id,
 }
 
 impl Bind for UniformBuffer {
@@ -352,6 +407,45 @@ impl Bind for UniformBuffer {
             },
         }
     }
+
+    #[cfg(feature = "trace")]
+    fn trace_id(&self) -> trace::Id {
+        self.id
+    }
+}
+
+/// A storage buffer that can be bound in a 'BindingGroup', to be read, and
+/// written, by shaders. It can also be set as a vertex buffer with
+/// `Pass::set_storage_vertex_buffer`, eg. to draw instances simulated in a
+/// compute pass.
+#[derive(Debug)]
+pub struct StorageBuffer {
+    wgpu: Deferred<wgpu::Buffer>,
+    size: usize,
+    #[cfg(feature = "trace")]
+    id: trace::Id,
+    /// Size of an element in bytes, or zero if unknown.
+    #[cfg(feature = "validation")]
+    stride: usize,
+}
+
+impl StorageBuffer {
+    /// Size of the buffer, in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+impl Bind for StorageBuffer {
+    fn binding(&self, index: u32) -> wgpu::Binding<'_> {
+        wgpu::Binding {
+            binding: index,
+            resource: wgpu::BindingResource::Buffer {
+                buffer: &self.wgpu,
+                range: 0..(self.size as wgpu::BufferAddress),
+            },
+        }
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -364,17 +458,26 @@ pub struct ZBuffer {
 }
 
 impl ZBuffer {
-    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24PlusStencil8;
 }
 
 ///////////////////////////////////////////////////////////////////////////////
-/// Framebuffer
+// Framebuffer
 ///////////////////////////////////////////////////////////////////////////////
 
+/// An off-screen render target, with its own depth buffer.
+///
+/// A `Framebuffer` can be the target of a `Pass`, instead of the swap chain,
+/// and its `texture` can then be bound and sampled in a later pass, eg. for
+/// post-processing or to cache static layers.
 #[derive(Debug)]
 pub struct Framebuffer {
     pub texture: Texture,
     pub depth: ZBuffer,
+
+    /// Multisampled color target, resolved into `texture` at the end of
+    /// each pass.
+    msaa: Option<Texture>,
 }
 
 impl Framebuffer {
@@ -393,12 +496,47 @@ impl Framebuffer {
 
 impl RenderTarget for Framebuffer {
     fn color_target(&self) -> &wgpu::TextureView {
-        &self.texture.view
+        match &self.msaa {
+            Some(msaa) => &msaa.view,
+            None => &self.texture.view,
+        }
     }
 
     fn zdepth_target(&self) -> &wgpu::TextureView {
         &self.depth.texture.view
     }
+
+    fn resolve_target(&self) -> Option<&wgpu::TextureView> {
+        self.msaa.as_ref().map(|_| &*self.texture.view)
+    }
+
+    #[cfg(feature = "trace")]
+    fn trace_id(&self) -> Option<trace::Id> {
+        Some(self.texture.id)
+    }
+}
+
+/// A snapshot of a region of a framebuffer, for effects that sample what
+/// they draw over, eg. blur or smudge brushes. A pass can't sample its own
+/// target, so the region is copied with `Frame::backdrop` before the pass
+/// drawing the effect is begun, and the backdrop is bound in its place.
+#[derive(Debug)]
+pub struct Backdrop {
+    pub texture: Texture,
+    /// The region last copied, in texels of the framebuffer, with the first
+    /// row at the top. It is copied to the top-left of the texture.
+    pub region: Rect<u32>,
+}
+
+impl Bind for Backdrop {
+    fn binding(&self, index: u32) -> wgpu::Binding<'_> {
+        self.texture.binding(index)
+    }
+
+    #[cfg(feature = "trace")]
+    fn trace_id(&self) -> trace::Id {
+        self.texture.trace_id()
+    }
 }
 
 impl Bind for Framebuffer {
@@ -408,6 +546,11 @@ impl Bind for Framebuffer {
             resource: wgpu::BindingResource::TextureView(&self.texture.view),
         }
     }
+
+    #[cfg(feature = "trace")]
+    fn trace_id(&self) -> trace::Id {
+        self.texture.id
+    }
 }
 
 impl Canvas for Framebuffer {
@@ -445,17 +588,86 @@ impl Canvas for Framebuffer {
 
 #[derive(Debug)]
 pub struct Texture {
-    wgpu: wgpu::Texture,
-    view: wgpu::TextureView,
+    wgpu: Deferred<wgpu::Texture>,
+    view: Deferred<wgpu::TextureView>,
     extent: wgpu::Extent3d,
     format: wgpu::TextureFormat,
+    mipmaps: Option<Deferred<Mipmaps>>,
+    #[cfg(feature = "trace")]
+    id: trace::Id,
 
     pub w: u32,
     pub h: u32,
 }
 
+/// The mip chain of a texture, and the pipeline rendering each level from
+/// the level above it.
+#[derive(Debug)]
+struct Mipmaps {
+    pipeline: wgpu::RenderPipeline,
+    /// Views of each level, starting with the full size texture.
+    views: Vec<wgpu::TextureView>,
+    /// Bindings sampling each level, except the last one.
+    bindings: Vec<wgpu::BindGroup>,
+}
+
 impl Texture {
     pub const COLOR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+    /// Alignment of texel rows copied from a buffer, in bytes.
+    const ROW_ALIGNMENT: u32 = 256;
+
+    /// Upload texels to a region of the texture, eg. to add glyphs to an
+    /// atlas or to stream video frames. The region is in texels, with rows
+    /// going down from the top of the texture, in the same order as the
+    /// texels. The copy is submitted right away, ahead of the next frame.
+    pub fn upload<T>(&self, region: Rect<u32>, texels: &[T], r: &mut Renderer)
+    where
+        T: 'static + Copy,
+    {
+        let (w, h) = (region.x2 - region.x1, region.y2 - region.y1);
+
+        assert!(
+            region.x2 <= self.w && region.y2 <= self.h,
+            "fatal: upload region must be within the texture"
+        );
+        assert_eq!(
+            std::mem::size_of_val(texels) as u32,
+            w * h * texel_size(self.format),
+            "fatal: incorrect length for texel buffer"
+        );
+        if texels.is_empty() {
+            return;
+        }
+        trace!(Event::WriteTexture {
+            id: self.id,
+            x: region.x1,
+            y: region.y1,
+            w,
+            h,
+            row_pitch: w * texel_size(self.format),
+            data: trace::bytes(texels),
+        });
+
+        let extent = wgpu::Extent3d {
+            width: w,
+            height: h,
+            depth: 1,
+        };
+
+        let mut encoder = r.device.create_command_encoder();
+        Self::copy(
+            &r.device,
+            &self.wgpu,
+            texels,
+            w * texel_size(self.format),
+            h,
+            region.x1 as f32,
+            region.y1 as f32,
+            extent,
+            &mut encoder,
+        );
+        r.device.submit(&[encoder.finish()]);
+    }
 
     pub fn rect(&self) -> Rect<f32> {
         Rect {
@@ -466,6 +678,40 @@ impl Texture {
         }
     }
 
+    /// Number of mip levels, including the full size texture.
+    pub fn levels(&self) -> u32 {
+        self.mipmaps.as_ref().map_or(1, |m| m.views.len() as u32)
+    }
+
+    /// Generate the mip levels of the texture from its full size level, by
+    /// halving it with linear filtering until it is a single texel. Does
+    /// nothing if the texture wasn't created with mipmaps. To be sampled
+    /// with trilinear filtering, create a sampler with a linear mipmap filter,
+    /// eg. with `Renderer::sampler_with_mipmaps`.
+    pub fn generate_mipmaps(&self, f: &mut Frame) {
+        let mipmaps = match &self.mipmaps {
+            Some(mipmaps) => mipmaps,
+            None => return,
+        };
+        trace!(Event::GenerateMipmaps { id: self.id });
+
+        for (view, binding) in mipmaps.views[1..].iter().zip(mipmaps.bindings.iter()) {
+            let mut pass = f.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: view,
+                    load_op: wgpu::LoadOp::Clear,
+                    store_op: wgpu::StoreOp::Store,
+                    clear_color: Rgba::TRANSPARENT.to_wgpu(),
+                    resolve_target: None,
+                }],
+                depth_stencil_attachment: None,
+            });
+            pass.set_pipeline(&mipmaps.pipeline);
+            pass.set_bind_group(0, binding, &[]);
+            pass.draw(0..3, 0..1);
+        }
+    }
+
     fn clear<T>(
         texture: &Texture,
         value: T,
@@ -497,20 +743,25 @@ impl Texture {
             texture.w * texture.h,
             "fatal: incorrect length for texel buffer"
         );
-
-        let buf = device
-            .device
-            .create_buffer_mapped(texels.len(), wgpu::BufferUsage::COPY_SRC)
-            .fill_from_slice(&texels);
+        trace!(Event::WriteTexture {
+            id: texture.id,
+            x: 0,
+            y: 0,
+            w: texture.w,
+            h: texture.h,
+            row_pitch: texture.w * std::mem::size_of::<T>() as u32,
+            data: trace::bytes(texels),
+        });
 
         Self::copy(
+            device,
             &texture.wgpu,
-            texture.w,
+            texels,
+            texture.w * std::mem::size_of::<T>() as u32,
             texture.h,
             0.,
             0.,
             texture.extent,
-            &buf,
             encoder,
         );
     }
@@ -546,11 +797,15 @@ impl Texture {
             tx_w * tx_h <= texture.w * texture.h,
             "fatal: transfer size must be <= texture size"
         );
-
-        let buf = device
-            .device
-            .create_buffer_mapped(texels.len(), wgpu::BufferUsage::COPY_SRC)
-            .fill_from_slice(&texels);
+        trace!(Event::WriteTexture {
+            id: texture.id,
+            x: dst_x as u32,
+            y: dst_y as u32,
+            w: tx_w,
+            h: tx_h,
+            row_pitch: width * std::mem::size_of::<T>() as u32,
+            data: trace::bytes(texels),
+        });
 
         let extent = wgpu::Extent3d {
             width: tx_w,
@@ -558,13 +813,14 @@ impl Texture {
             depth: 1,
         };
         Self::copy(
+            device,
             &texture.wgpu,
-            width,
+            texels,
+            width * std::mem::size_of::<T>() as u32,
             height,
             dst_x,
             dst_y,
             extent,
-            &buf,
             encoder,
         );
     }
@@ -580,6 +836,11 @@ impl Texture {
             dst.height(),
             "source and destination rectangles must be of the same size"
         );
+        trace!(Event::Blit {
+            id: self.id,
+            src,
+            dst
+        });
 
         encoder.copy_texture_to_texture(
             wgpu::TextureCopyView {
@@ -610,22 +871,52 @@ impl Texture {
         );
     }
 
-    fn copy(
+    /// Copy `rows` rows of `row_pitch` bytes of texels to the texture. Rows
+    /// are padded to `ROW_ALIGNMENT` bytes on the way, so callers
+    /// can pass tightly packed texels of any width.
+    #[allow(clippy::too_many_arguments)]
+    fn copy<T: Copy>(
+        device: &Device,
         texture: &wgpu::Texture,
-        w: u32,
-        h: u32,
+        texels: &[T],
+        row_pitch: u32,
+        rows: u32,
         x: f32,
         y: f32,
         extent: wgpu::Extent3d,
-        buffer: &wgpu::Buffer,
         encoder: &mut wgpu::CommandEncoder,
     ) {
+        // Safe, since `T` is `Copy`, and the bytes are only copied to the GPU.
+        let bytes = unsafe {
+            std::slice::from_raw_parts(texels.as_ptr() as *const u8, std::mem::size_of_val(texels))
+        };
+        let aligned = row_pitch.div_ceil(Self::ROW_ALIGNMENT) * Self::ROW_ALIGNMENT;
+        let buffer = if aligned == row_pitch {
+            device
+                .device
+                .create_buffer_mapped(bytes.len(), wgpu::BufferUsage::COPY_SRC)
+                .fill_from_slice(bytes)
+        } else {
+            let mut padded = vec![0u8; (aligned * rows) as usize];
+
+            for (src, dst) in bytes
+                .chunks(row_pitch as usize)
+                .zip(padded.chunks_mut(aligned as usize))
+            {
+                dst[..src.len()].copy_from_slice(src);
+            }
+            device
+                .device
+                .create_buffer_mapped(padded.len(), wgpu::BufferUsage::COPY_SRC)
+                .fill_from_slice(&padded)
+        };
+
         encoder.copy_buffer_to_texture(
             wgpu::BufferCopyView {
-                buffer,
+                buffer: &buffer,
                 offset: 0,
-                row_pitch: 4 * w,
-                image_height: h,
+                row_pitch: aligned,
+                image_height: rows,
             },
             wgpu::TextureCopyView {
                 texture,
@@ -638,6 +929,48 @@ impl Texture {
     }
 }
 
+/// Size of a texel, in bytes.
+fn texel_size(format: wgpu::TextureFormat) -> u32 {
+    match format {
+        wgpu::TextureFormat::R8Unorm => 1,
+        wgpu::TextureFormat::Rg8Unorm => 2,
+        wgpu::TextureFormat::Rgba16Float => 8,
+        _ => 4,
+    }
+}
+
+/// The texel format of a texture.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TextureFormat {
+    /// 8-bit RGBA, in sRGB. Texels are converted to linear when sampled.
+    #[default]
+    Rgba8Srgb,
+    /// 8-bit RGBA.
+    Rgba8,
+    /// 8-bit BGRA, eg. for video frames.
+    Bgra8,
+    /// A single 8-bit channel, eg. for glyph coverage, sampled as red.
+    R8,
+    /// Two 8-bit channels, sampled as red and green.
+    Rg8,
+    /// 16-bit floating point RGBA, eg. for HDR images.
+    Rgba16Float,
+}
+
+impl TextureFormat {
+    fn to_wgpu(self) -> wgpu::TextureFormat {
+        match self {
+            TextureFormat::Rgba8Srgb => Texture::COLOR_FORMAT,
+            TextureFormat::Rgba8 => wgpu::TextureFormat::Rgba8Unorm,
+            TextureFormat::Bgra8 => wgpu::TextureFormat::Bgra8Unorm,
+            TextureFormat::R8 => wgpu::TextureFormat::R8Unorm,
+            TextureFormat::Rg8 => wgpu::TextureFormat::Rg8Unorm,
+            TextureFormat::Rgba16Float => wgpu::TextureFormat::Rgba16Float,
+        }
+    }
+}
+
 impl Bind for Texture {
     fn binding(&self, index: u32) -> wgpu::Binding {
         wgpu::Binding {
@@ -645,6 +978,11 @@ impl Bind for Texture {
             resource: wgpu::BindingResource::TextureView(&self.view),
         }
     }
+
+    #[cfg(feature = "trace")]
+    fn trace_id(&self) -> trace::Id {
+        self.id
+    }
 }
 
 impl Canvas for Texture {
@@ -683,7 +1021,9 @@ impl From<Framebuffer> for Texture {
 
 #[derive(Debug)]
 pub struct Sampler {
-    wgpu: wgpu::Sampler,
+    wgpu: Deferred<wgpu::Sampler>,
+    #[cfg(feature = "trace")]
+    id: trace::Id,
 }
 
 impl Bind for Sampler {
@@ -693,16 +1033,22 @@ impl Bind for Sampler {
             resource: wgpu::BindingResource::Sampler(&self.wgpu),
         }
     }
+
+    #[cfg(feature = "trace")]
+    fn trace_id(&self) -> trace::Id {
+        self.id
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Filter {
     Nearest,
     Linear,
 }
 
 impl Filter {
-    fn to_wgpu(&self) -> wgpu::FilterMode {
+    fn to_wgpu(self) -> wgpu::FilterMode {
         match self {
             Filter::Nearest => wgpu::FilterMode::Nearest,
             Filter::Linear => wgpu::FilterMode::Linear,
@@ -710,6 +1056,80 @@ impl Filter {
     }
 }
 
+/// How texture coordinates outside of the `[0, 1]` range are sampled.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AddressMode {
+    /// The texture is tiled, eg. for repeating sprites.
+    #[default]
+    Repeat,
+    /// The texture is tiled, and mirrored on every other tile.
+    MirrorRepeat,
+    /// Texels on the edge of the texture are extended.
+    ClampToEdge,
+}
+
+impl AddressMode {
+    fn to_wgpu(self) -> wgpu::AddressMode {
+        match self {
+            AddressMode::Repeat => wgpu::AddressMode::Repeat,
+            AddressMode::MirrorRepeat => wgpu::AddressMode::MirrorRepeat,
+            AddressMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+        }
+    }
+}
+
+/// Describes a `Sampler`. The default samples the nearest texel and repeats
+/// the texture, which suits pixel art and tiled sprites.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SamplerDescriptor {
+    pub min_filter: Filter,
+    pub mag_filter: Filter,
+    /// Filter between mip levels. Only applies to textures with mipmaps.
+    pub mipmap_filter: Filter,
+    pub address_mode_u: AddressMode,
+    pub address_mode_v: AddressMode,
+}
+
+impl SamplerDescriptor {
+    /// Nearest filtering, eg. for pixel art.
+    pub fn nearest() -> Self {
+        Self::default()
+    }
+
+    /// Trilinear filtering, eg. for scaled sprites.
+    pub fn linear() -> Self {
+        Self {
+            min_filter: Filter::Linear,
+            mag_filter: Filter::Linear,
+            mipmap_filter: Filter::Linear,
+            ..Self::default()
+        }
+    }
+
+    /// Use the same address mode for both axes.
+    pub fn address_mode(self, mode: AddressMode) -> Self {
+        Self {
+            address_mode_u: mode,
+            address_mode_v: mode,
+            ..self
+        }
+    }
+}
+
+impl Default for SamplerDescriptor {
+    fn default() -> Self {
+        Self {
+            min_filter: Filter::Nearest,
+            mag_filter: Filter::Nearest,
+            mipmap_filter: Filter::Nearest,
+            address_mode_u: AddressMode::Repeat,
+            address_mode_v: AddressMode::Repeat,
+        }
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 /// Vertex/Index Buffers
 ///////////////////////////////////////////////////////////////////////////////
@@ -717,7 +1137,12 @@ impl Filter {
 #[derive(Debug)]
 pub struct VertexBuffer {
     pub size: u32,
-    wgpu: wgpu::Buffer,
+    wgpu: Deferred<wgpu::Buffer>,
+    #[cfg(feature = "trace")]
+    id: trace::Id,
+    /// Size of a vertex in bytes, or zero if unknown.
+    #[cfg(feature = "validation")]
+    stride: usize,
 }
 
 impl Draw for VertexBuffer {
@@ -735,16 +1160,329 @@ impl Draw for VertexBuffer {
 
 #[derive(Debug)]
 pub struct IndexBuffer {
-    wgpu: wgpu::Buffer,
+    pub size: u32,
+    wgpu: Deferred<wgpu::Buffer>,
+    #[cfg(feature = "trace")]
+    id: trace::Id,
+}
+
+/// A vertex buffer which can be updated in place, eg. with a batch that
+/// changes every frame. GPU memory is re-used, and only re-allocated when
+/// the vertices no longer fit.
+#[derive(Debug)]
+pub struct DynamicVertexBuffer {
+    buffer: VertexBuffer,
+    capacity: u32,
+}
+
+impl DynamicVertexBuffer {
+    /// The underlying vertex buffer, with its `size` set to the number of
+    /// vertices of the last update.
+    pub fn buffer(&self) -> &VertexBuffer {
+        &self.buffer
+    }
+
+    /// Number of vertices the buffer can hold without re-allocating.
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Replace the buffer contents with the given vertices. The copy is
+    /// submitted right away, ahead of the next frame.
+    pub fn update<T>(&mut self, vertices: &[T], r: &mut Renderer)
+    where
+        T: 'static + Copy,
+    {
+        let mut encoder = r.device.create_command_encoder();
+        r.device.update_dynamic_buffer(vertices, self, &mut encoder);
+        r.device.submit(&[encoder.finish()]);
+    }
+
+    /// Like `update`, when the vertices before `from` are the same as in the
+    /// last update: only the vertices starting at `from` are copied.
+    pub fn update_from<T>(&mut self, vertices: &[T], from: usize, r: &mut Renderer)
+    where
+        T: 'static + Copy,
+    {
+        let mut encoder = r.device.create_command_encoder();
+        r.device
+            .update_dynamic_buffer_from(vertices, from, self, &mut encoder);
+        r.device.submit(&[encoder.finish()]);
+    }
+}
+
+impl Draw for DynamicVertexBuffer {
+    fn draw(&self, binding: &BindingGroup, pass: &mut Pass) {
+        self.buffer.draw(binding, pass);
+    }
+}
+
+/// A buffer shared by small batches, eg. of UI elements, which are written
+/// into it every frame instead of each getting their own buffers. Writes
+/// are staged on the CPU and copied to the GPU together by `upload`.
+///
+/// Space is allocated one write after the other, and wraps around to the
+/// start of the buffer when the end is reached, so slices should be drawn
+/// in the frame they were written in.
+#[derive(Debug)]
+pub struct RingBuffer {
+    wgpu: Deferred<wgpu::Buffer>,
+    capacity: wgpu::BufferAddress,
+    /// Offset of the next write.
+    head: wgpu::BufferAddress,
+    /// Bytes written since the last upload.
+    used: wgpu::BufferAddress,
+    /// Data to upload, and the offset in the buffer of each of its regions.
+    staged: Vec<u8>,
+    regions: Vec<(wgpu::BufferAddress, Range<usize>)>,
+    #[cfg(feature = "trace")]
+    id: trace::Id,
 }
 
+/// The state of a `RingBuffer` before a write, to undo it with
+/// `RingBuffer::rollback`.
 #[derive(Debug, Clone, Copy)]
+pub(crate) struct RingMark {
+    head: wgpu::BufferAddress,
+    used: wgpu::BufferAddress,
+    staged: usize,
+    regions: usize,
+    /// End of the last region, which writes may have extended.
+    end: Option<usize>,
+}
+
+/// The vertices and indices of a batch written into a `RingBuffer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RingSlice {
+    /// Number of vertices.
+    pub size: u32,
+    offset: wgpu::BufferAddress,
+    /// Offset and count of the indices, if any.
+    indices: Option<(wgpu::BufferAddress, u32)>,
+    /// Size of a vertex in bytes.
+    #[cfg(feature = "validation")]
+    stride: usize,
+}
+
+impl RingBuffer {
+    /// Alignment of writes, in bytes.
+    const ALIGNMENT: wgpu::BufferAddress = 4;
+
+    /// Size of the buffer, in bytes.
+    pub fn capacity(&self) -> wgpu::BufferAddress {
+        self.capacity
+    }
+
+    /// Number of bytes written since the last upload.
+    pub fn used(&self) -> wgpu::BufferAddress {
+        self.used
+    }
+
+    /// Write vertices into the buffer. Returns `None` if they don't fit in
+    /// the space left until the next upload, in which case the batch should
+    /// get its own buffer instead.
+    pub fn write<T>(&mut self, vertices: &[T]) -> Option<RingSlice>
+    where
+        T: 'static + Copy,
+    {
+        let offset = self.alloc(vertices)?;
+
+        Some(RingSlice {
+            size: vertices.len() as u32,
+            offset,
+            indices: None,
+            #[cfg(feature = "validation")]
+            stride: std::mem::size_of::<T>(),
+        })
+    }
+
+    /// Write vertices and the indices they are drawn with into the buffer.
+    /// Returns `None` if they don't fit, as with `write`, in which case
+    /// neither is written.
+    pub fn write_indexed<T>(&mut self, vertices: &[T], indices: &[u32]) -> Option<RingSlice>
+    where
+        T: 'static + Copy,
+    {
+        let mark = self.mark();
+        let offset = self.alloc(vertices)?;
+        let indices = match self.alloc(indices) {
+            Some(offset) => (offset, indices.len() as u32),
+            None => {
+                self.rollback(mark);
+                return None;
+            }
+        };
+
+        Some(RingSlice {
+            size: vertices.len() as u32,
+            offset,
+            indices: Some(indices),
+            #[cfg(feature = "validation")]
+            stride: std::mem::size_of::<T>(),
+        })
+    }
+
+    /// The state of the buffer, to undo the writes made after it with
+    /// `rollback`.
+    pub(crate) fn mark(&self) -> RingMark {
+        RingMark {
+            head: self.head,
+            used: self.used,
+            staged: self.staged.len(),
+            regions: self.regions.len(),
+            end: self.regions.last().map(|(_, range)| range.end),
+        }
+    }
+
+    /// Undo the writes made since the given mark, if there was no upload
+    /// in between.
+    pub(crate) fn rollback(&mut self, mark: RingMark) {
+        self.head = mark.head;
+        self.used = mark.used;
+        self.staged.truncate(mark.staged);
+        self.regions.truncate(mark.regions);
+
+        if let (Some((_, range)), Some(end)) = (self.regions.last_mut(), mark.end) {
+            range.end = end;
+        }
+    }
+
+    /// Copy the data written since the last upload to the GPU. The copy is
+    /// submitted right away, ahead of the next frame.
+    pub fn upload(&mut self, r: &mut Renderer) {
+        self.used = 0;
+
+        if self.staged.is_empty() {
+            return;
+        }
+        let src = r
+            .device
+            .device
+            .create_buffer_mapped::<u8>(self.staged.len(), wgpu::BufferUsage::COPY_SRC)
+            .fill_from_slice(&self.staged);
+        let mut encoder = r.device.create_command_encoder();
+
+        for (offset, range) in std::mem::take(&mut self.regions) {
+            trace!(Event::WriteBuffer {
+                id: self.id,
+                offset,
+                data: self.staged[range.clone()].to_vec(),
+            });
+            encoder.copy_buffer_to_buffer(
+                &src,
+                range.start as wgpu::BufferAddress,
+                &self.wgpu,
+                offset,
+                range.len() as wgpu::BufferAddress,
+            );
+        }
+        self.staged.clear();
+        r.device.submit(&[encoder.finish()]);
+    }
+
+    fn aligned<T>(data: &[T]) -> wgpu::BufferAddress {
+        let size = std::mem::size_of_val(data) as wgpu::BufferAddress;
+        size.div_ceil(Self::ALIGNMENT) * Self::ALIGNMENT
+    }
+
+    /// Allocate space for data, and stage it. Returns its offset in the
+    /// buffer.
+    fn alloc<T: Copy>(&mut self, data: &[T]) -> Option<wgpu::BufferAddress> {
+        let size = Self::aligned(data);
+        // Space skipped at the end of the buffer, when wrapping around.
+        let skip = if self.head + size > self.capacity {
+            self.capacity - self.head
+        } else {
+            0
+        };
+        if self.used + skip + size > self.capacity {
+            return None;
+        }
+        if skip > 0 {
+            self.head = 0;
+        }
+        let offset = self.head;
+        let start = self.staged.len();
+
+        // Safe, since `T` is `Copy`, and the bytes are only copied to the GPU.
+        self.staged.extend_from_slice(unsafe {
+            std::slice::from_raw_parts(data.as_ptr() as *const u8, std::mem::size_of_val(data))
+        });
+        self.staged.resize(start + size as usize, 0);
+
+        match self.regions.last_mut() {
+            Some((o, range)) if *o + range.len() as wgpu::BufferAddress == offset => {
+                range.end = self.staged.len();
+            }
+            _ => self.regions.push((offset, start..self.staged.len())),
+        }
+        self.head += size;
+        self.used += skip + size;
+
+        Some(offset)
+    }
+}
+
+/// A vertex buffer drawn through an index buffer.
+#[derive(Debug)]
+pub struct IndexedBuffer {
+    pub vertices: VertexBuffer,
+    pub indices: IndexBuffer,
+}
+
+impl Draw for IndexedBuffer {
+    fn draw(&self, binding: &BindingGroup, pass: &mut Pass) {
+        pass.set_binding(binding, &[]);
+        pass.draw_indexed_buffer(self);
+    }
+}
+
+/// A vertex buffer written by the GPU, eg. by a compute pass, along with the
+/// arguments of the draw call that renders it. The vertices and the draw
+/// arguments are bound as storage buffers, and since the GPU reads the
+/// number of vertices or instances to draw from the arguments, they never
+/// have to be read back.
+#[derive(Debug)]
+pub struct IndirectBuffer {
+    /// Number of vertices the buffer can hold.
+    pub capacity: u32,
+    vertices: StorageBuffer,
+    args: StorageBuffer,
+    initial: Deferred<wgpu::Buffer>,
+}
+
+impl IndirectBuffer {
+    /// Size of the draw arguments: vertex count, instance count, first
+    /// vertex and first instance, as `u32`.
+    const ARGS_SIZE: wgpu::BufferAddress = 16;
+
+    /// The vertices, to bind as a storage buffer.
+    pub fn vertices(&self) -> &StorageBuffer {
+        &self.vertices
+    }
+
+    /// The draw arguments, to bind as a storage buffer.
+    pub fn args(&self) -> &StorageBuffer {
+        &self.args
+    }
+
+    /// Reset the draw arguments to the ones the buffer was created with,
+    /// eg. before the vertices are written again.
+    pub fn reset(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.copy_buffer_to_buffer(&self.initial, 0, &self.args.wgpu, 0, Self::ARGS_SIZE);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VertexFormat {
     Float,
     Float2,
     Float3,
     Float4,
     UByte4,
+    /// An unsigned integer, eg. an index into a uniform array.
+    Uint,
 }
 
 impl VertexFormat {
@@ -756,6 +1494,7 @@ impl VertexFormat {
             VertexFormat::Float3 => 12,
             VertexFormat::Float4 => 16,
             VertexFormat::UByte4 => 4,
+            VertexFormat::Uint => 4,
         }
     }
     // TODO: Use `const fn`
@@ -766,15 +1505,38 @@ impl VertexFormat {
             VertexFormat::Float3 => wgpu::VertexFormat::Float3,
             VertexFormat::Float4 => wgpu::VertexFormat::Float4,
             VertexFormat::UByte4 => wgpu::VertexFormat::Uchar4Norm,
+            VertexFormat::Uint => wgpu::VertexFormat::Uint,
         }
     }
 }
 
-/// Describes a 'VertexBuffer' layout.
-#[derive(Default, Debug)]
-pub struct VertexLayout {
-    wgpu_attrs: Vec<wgpu::VertexAttributeDescriptor>,
+/// How often the vertex buffer of a pipeline advances.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StepMode {
+    /// Once per vertex.
+    #[default]
+    Vertex,
+    /// Once per instance. Vertices of an instance are generated by the
+    /// vertex shader, from `gl_VertexIndex`.
+    Instance,
+}
+
+impl StepMode {
+    fn to_wgpu(self) -> wgpu::InputStepMode {
+        match self {
+            StepMode::Vertex => wgpu::InputStepMode::Vertex,
+            StepMode::Instance => wgpu::InputStepMode::Instance,
+        }
+    }
+}
+
+/// Describes a 'VertexBuffer' layout.
+#[derive(Default, Debug)]
+pub struct VertexLayout {
+    wgpu_attrs: Vec<wgpu::VertexAttributeDescriptor>,
     size: usize,
+    step_mode: StepMode,
 }
 
 impl VertexLayout {
@@ -794,7 +1556,7 @@ impl VertexLayout {
     fn to_wgpu(&self) -> wgpu::VertexBufferDescriptor {
         wgpu::VertexBufferDescriptor {
             stride: self.size as wgpu::BufferAddress,
-            step_mode: wgpu::InputStepMode::Vertex,
+            step_mode: self.step_mode.to_wgpu(),
             attributes: self.wgpu_attrs.as_slice(),
         }
     }
@@ -805,16 +1567,23 @@ impl VertexLayout {
 ///////////////////////////////////////////////////////////////////////////////
 
 /// A binding type.
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BindingType {
     UniformBuffer,
     UniformBufferDynamic,
     Sampler,
     SampledTexture,
+    /// A buffer read and written by compute shaders.
+    StorageBuffer,
+    /// A buffer only read by shaders.
+    StorageBufferReadOnly,
+    /// A texture read and written by compute shaders, texel by texel.
+    StorageTexture,
 }
 
 impl BindingType {
-    fn to_wgpu(&self) -> wgpu::BindingType {
+    fn to_wgpu(self) -> wgpu::BindingType {
         match self {
             BindingType::UniformBuffer => wgpu::BindingType::UniformBuffer { dynamic: false },
             BindingType::UniformBufferDynamic => wgpu::BindingType::UniformBuffer { dynamic: true },
@@ -823,6 +1592,17 @@ impl BindingType {
                 dimension: wgpu::TextureViewDimension::D2,
             },
             BindingType::Sampler => wgpu::BindingType::Sampler,
+            BindingType::StorageBuffer => wgpu::BindingType::StorageBuffer {
+                dynamic: false,
+                readonly: false,
+            },
+            BindingType::StorageBufferReadOnly => wgpu::BindingType::StorageBuffer {
+                dynamic: false,
+                readonly: true,
+            },
+            BindingType::StorageTexture => wgpu::BindingType::StorageTexture {
+                dimension: wgpu::TextureViewDimension::D2,
+            },
         }
     }
 }
@@ -840,6 +1620,8 @@ pub struct Binding {
 #[derive(Debug)]
 pub struct Pipeline {
     wgpu: wgpu::RenderPipeline,
+    #[cfg(feature = "trace")]
+    id: trace::Id,
 
     pub layout: PipelineLayout,
     pub vertex_layout: VertexLayout,
@@ -863,6 +1645,10 @@ impl<'a> AbstractPipeline<'a> for Pipeline {
     }
 
     fn apply(&self, pass: &mut Pass) {
+        trace!(Event::SetPipeline(self.id));
+        #[cfg(feature = "validation")]
+        pass.validation.pipeline(self);
+
         pass.wgpu.set_pipeline(&self.wgpu);
     }
 
@@ -871,77 +1657,252 @@ impl<'a> AbstractPipeline<'a> for Pipeline {
     }
 }
 
+/// How fragments are blended with the render target. Color and alpha are
+/// blended separately, each with its own factors and operation.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Blending {
     src_factor: BlendFactor,
     dst_factor: BlendFactor,
     operation: BlendOp,
+
+    alpha_src_factor: BlendFactor,
+    alpha_dst_factor: BlendFactor,
+    alpha_operation: BlendOp,
 }
 
 impl Blending {
+    /// Blend color and alpha with the same factors and operation.
     pub fn new(src_factor: BlendFactor, dst_factor: BlendFactor, operation: BlendOp) -> Self {
         Blending {
             src_factor,
             dst_factor,
             operation,
+            alpha_src_factor: src_factor,
+            alpha_dst_factor: dst_factor,
+            alpha_operation: operation,
         }
     }
 
+    /// Use different factors and operation for the alpha channel.
+    pub fn alpha(
+        mut self,
+        src_factor: BlendFactor,
+        dst_factor: BlendFactor,
+        operation: BlendOp,
+    ) -> Self {
+        self.alpha_src_factor = src_factor;
+        self.alpha_dst_factor = dst_factor;
+        self.alpha_operation = operation;
+        self
+    }
+
+    /// No blending: fragments replace the target.
     pub fn constant() -> Self {
-        Blending {
-            src_factor: BlendFactor::One,
-            dst_factor: BlendFactor::Zero,
-            operation: BlendOp::Add,
-        }
+        Blending::new(BlendFactor::One, BlendFactor::Zero, BlendOp::Add)
+    }
+
+    /// Add fragments to the target, weighted by their alpha. Useful for
+    /// particles and lights.
+    pub fn additive() -> Self {
+        Blending::new(BlendFactor::SrcAlpha, BlendFactor::One, BlendOp::Add).alpha(
+            BlendFactor::One,
+            BlendFactor::One,
+            BlendOp::Add,
+        )
+    }
+
+    /// Multiply the target by the fragment color, darkening it. Fragment
+    /// alpha is ignored.
+    pub fn multiply() -> Self {
+        Blending::new(BlendFactor::DstColor, BlendFactor::Zero, BlendOp::Add).alpha(
+            BlendFactor::Zero,
+            BlendFactor::One,
+            BlendOp::Add,
+        )
+    }
+
+    /// The inverse of multiply: lightens the target. Fragment alpha is
+    /// ignored.
+    pub fn screen() -> Self {
+        Blending::new(
+            BlendFactor::One,
+            BlendFactor::OneMinusSrcColor,
+            BlendOp::Add,
+        )
+        .alpha(BlendFactor::Zero, BlendFactor::One, BlendOp::Add)
+    }
+
+    /// Alpha blending, for fragments whose color is already multiplied by
+    /// their alpha.
+    pub fn premultiplied() -> Self {
+        Blending::new(
+            BlendFactor::One,
+            BlendFactor::OneMinusSrcAlpha,
+            BlendOp::Add,
+        )
     }
 
-    fn to_wgpu(&self) -> (wgpu::BlendFactor, wgpu::BlendFactor, wgpu::BlendOperation) {
+    fn to_wgpu(&self) -> (wgpu::BlendDescriptor, wgpu::BlendDescriptor) {
         (
-            self.src_factor.to_wgpu(),
-            self.dst_factor.to_wgpu(),
-            self.operation.to_wgpu(),
+            wgpu::BlendDescriptor {
+                src_factor: self.src_factor.to_wgpu(),
+                dst_factor: self.dst_factor.to_wgpu(),
+                operation: self.operation.to_wgpu(),
+            },
+            wgpu::BlendDescriptor {
+                src_factor: self.alpha_src_factor.to_wgpu(),
+                dst_factor: self.alpha_dst_factor.to_wgpu(),
+                operation: self.alpha_operation.to_wgpu(),
+            },
         )
     }
 }
 
 impl Default for Blending {
+    /// Regular alpha blending.
     fn default() -> Self {
-        Blending {
-            src_factor: BlendFactor::SrcAlpha,
-            dst_factor: BlendFactor::OneMinusSrcAlpha,
-            operation: BlendOp::Add,
+        Blending::new(
+            BlendFactor::SrcAlpha,
+            BlendFactor::OneMinusSrcAlpha,
+            BlendOp::Add,
+        )
+    }
+}
+
+/// Whether a pipeline tests and writes depth. Shapes and sprites are drawn
+/// with their `ZDepth`, so with depth testing enabled, overlapping geometry
+/// is ordered by depth rather than by draw order.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DepthTest {
+    /// Fragments behind already drawn fragments are discarded, and drawn
+    /// fragments write their depth.
+    #[default]
+    Enabled,
+    /// Fragments are drawn in order, regardless of depth, and don't write
+    /// to the depth buffer.
+    Disabled,
+}
+
+impl DepthTest {
+    fn to_wgpu(self) -> (bool, wgpu::CompareFunction) {
+        match self {
+            DepthTest::Enabled => (true, wgpu::CompareFunction::LessEqual),
+            DepthTest::Disabled => (false, wgpu::CompareFunction::Always),
+        }
+    }
+}
+
+/// Stencil operations of a pipeline, for masking draws with arbitrary
+/// shapes.
+///
+/// To mask, draw the mask with a `Stencil::Write` pipeline, which sets the
+/// stencil buffer to the pass' stencil reference wherever it draws, then
+/// draw the masked content with a `Stencil::Equal` pipeline. The stencil
+/// reference is set with `Pass::set_stencil_reference`, and the stencil
+/// buffer is cleared to zero with the depth buffer. To draw the mask
+/// without it being visible, use a blending that keeps the target, eg.
+/// `Blending::new(BlendFactor::Zero, BlendFactor::One, BlendOp::Add)`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Stencil {
+    /// The stencil buffer is ignored.
+    #[default]
+    Disabled,
+    /// Fragments set the stencil buffer to the reference value.
+    Write,
+    /// Fragments are only drawn where the stencil buffer equals the
+    /// reference value.
+    Equal,
+    /// Fragments are only drawn where the stencil buffer differs from the
+    /// reference value.
+    NotEqual,
+}
+
+impl Stencil {
+    fn to_wgpu(self) -> (wgpu::StencilStateFaceDescriptor, u32, u32) {
+        let test = |compare| wgpu::StencilStateFaceDescriptor {
+            compare,
+            fail_op: wgpu::StencilOperation::Keep,
+            depth_fail_op: wgpu::StencilOperation::Keep,
+            pass_op: wgpu::StencilOperation::Keep,
+        };
+        match self {
+            Stencil::Disabled => (wgpu::StencilStateFaceDescriptor::IGNORE, 0, 0),
+            Stencil::Write => (
+                wgpu::StencilStateFaceDescriptor {
+                    compare: wgpu::CompareFunction::Always,
+                    fail_op: wgpu::StencilOperation::Keep,
+                    depth_fail_op: wgpu::StencilOperation::Keep,
+                    pass_op: wgpu::StencilOperation::Replace,
+                },
+                !0,
+                !0,
+            ),
+            Stencil::Equal => (test(wgpu::CompareFunction::Equal), !0, 0),
+            Stencil::NotEqual => (test(wgpu::CompareFunction::NotEqual), !0, 0),
         }
     }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BlendFactor {
     One,
     Zero,
+    SrcColor,
+    OneMinusSrcColor,
     SrcAlpha,
     OneMinusSrcAlpha,
+    DstColor,
+    OneMinusDstColor,
+    DstAlpha,
+    OneMinusDstAlpha,
+    SrcAlphaSaturated,
+    /// The constant set with `Pass::set_blend_color`.
+    BlendColor,
+    OneMinusBlendColor,
 }
 
 impl BlendFactor {
     fn to_wgpu(&self) -> wgpu::BlendFactor {
         match self {
-            BlendFactor::SrcAlpha => wgpu::BlendFactor::SrcAlpha,
-            BlendFactor::OneMinusSrcAlpha => wgpu::BlendFactor::OneMinusSrcAlpha,
             BlendFactor::One => wgpu::BlendFactor::One,
             BlendFactor::Zero => wgpu::BlendFactor::Zero,
+            BlendFactor::SrcColor => wgpu::BlendFactor::SrcColor,
+            BlendFactor::OneMinusSrcColor => wgpu::BlendFactor::OneMinusSrcColor,
+            BlendFactor::SrcAlpha => wgpu::BlendFactor::SrcAlpha,
+            BlendFactor::OneMinusSrcAlpha => wgpu::BlendFactor::OneMinusSrcAlpha,
+            BlendFactor::DstColor => wgpu::BlendFactor::DstColor,
+            BlendFactor::OneMinusDstColor => wgpu::BlendFactor::OneMinusDstColor,
+            BlendFactor::DstAlpha => wgpu::BlendFactor::DstAlpha,
+            BlendFactor::OneMinusDstAlpha => wgpu::BlendFactor::OneMinusDstAlpha,
+            BlendFactor::SrcAlphaSaturated => wgpu::BlendFactor::SrcAlphaSaturated,
+            BlendFactor::BlendColor => wgpu::BlendFactor::BlendColor,
+            BlendFactor::OneMinusBlendColor => wgpu::BlendFactor::OneMinusBlendColor,
         }
     }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BlendOp {
     Add,
+    Subtract,
+    ReverseSubtract,
+    Min,
+    Max,
 }
 
 impl BlendOp {
     fn to_wgpu(&self) -> wgpu::BlendOperation {
         match self {
             BlendOp::Add => wgpu::BlendOperation::Add,
+            BlendOp::Subtract => wgpu::BlendOperation::Subtract,
+            BlendOp::ReverseSubtract => wgpu::BlendOperation::ReverseSubtract,
+            BlendOp::Min => wgpu::BlendOperation::Min,
+            BlendOp::Max => wgpu::BlendOperation::Max,
         }
     }
 }
@@ -954,13 +1915,33 @@ pub struct PipelineLayout {
     pub sets: Vec<BindingGroupLayout>,
 }
 
+/// A pipeline running a compute shader, created with
+/// `Renderer::compute_pipeline`, and set on a `ComputePass`.
+#[derive(Debug)]
+pub struct ComputePipeline {
+    wgpu: wgpu::ComputePipeline,
+
+    pub layout: PipelineLayout,
+}
+
 pub trait AbstractPipeline<'a> {
     type PrepareContext;
     type Uniforms: Copy + 'static;
 
     fn description() -> PipelineDescription<'a>;
     fn setup(pip: Pipeline, dev: &Device) -> Self;
+    /// How often the vertex buffer advances. Pipelines drawing instances
+    /// should return `StepMode::Instance`.
+    fn step_mode() -> StepMode {
+        StepMode::Vertex
+    }
     fn apply(&self, pass: &mut Pass);
+    /// The uniform buffer holding the pipeline's orthographic projection, as
+    /// its first member. The renderer keeps it up to date with the size of
+    /// the swap chain once tracked, see `Renderer::track_projection`.
+    fn projection(&self) -> Option<&UniformBuffer> {
+        None
+    }
     fn prepare(
         &'a self,
         t: Self::PrepareContext,
@@ -975,6 +1956,113 @@ pub struct PipelineDescription<'a> {
     pub fragment_shader: &'static [u8],
 }
 
+/// A pipeline whose shaders are loaded from files on disk, and reloaded with
+/// `Renderer::reload` when the files change. Files with the `.spv`
+/// extension are loaded as SPIR-V, eg. after recompiling them with `glslc`.
+/// Other files are compiled as GLSL with `Device::compile_glsl`, which needs
+/// the `glslc` feature. Dereferences to the pipeline.
+#[cfg(feature = "hot-reload")]
+#[derive(Debug)]
+pub struct Watched<T> {
+    pipeline: T,
+    blending: Blending,
+    samples: u32,
+    depth: DepthTest,
+    stencil: Stencil,
+    vertex: Option<WatchedShader>,
+    fragment: Option<WatchedShader>,
+}
+
+#[cfg(feature = "hot-reload")]
+impl<T> std::ops::Deref for Watched<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.pipeline
+    }
+}
+
+#[cfg(feature = "hot-reload")]
+impl<T> std::ops::DerefMut for Watched<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.pipeline
+    }
+}
+
+/// A shader file, along with the modification time it was last loaded at.
+#[cfg(feature = "hot-reload")]
+#[derive(Debug)]
+struct WatchedShader {
+    path: std::path::PathBuf,
+    stage: ShaderStage,
+    modified: Option<std::time::SystemTime>,
+}
+
+#[cfg(feature = "hot-reload")]
+impl WatchedShader {
+    fn new(path: &std::path::Path, stage: ShaderStage) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            stage,
+            modified: None,
+        }
+    }
+
+    fn modified(&self) -> Option<std::time::SystemTime> {
+        std::fs::metadata(&self.path)
+            .and_then(|m| m.modified())
+            .ok()
+    }
+
+    fn is_stale(&self) -> bool {
+        self.modified() != self.modified
+    }
+
+    /// Read the shader, compiling it if it is GLSL, and falling back to the
+    /// built-in one if there is no file to load. Errors name the file.
+    fn read(&mut self, builtin: &'static [u8], device: &Device) -> std::io::Result<Vec<u8>> {
+        let modified = self.modified();
+        let source = match std::fs::read(&self.path) {
+            Ok(source) if self.is_glsl() => self.compile(&source, device),
+            Ok(source) => Ok(source),
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => Ok(builtin.to_vec()),
+            Err(e) => Err(e),
+        }
+        .and_then(|spv| {
+            // Validate the SPIR-V header before handing it to the device.
+            wgpu::read_spirv(std::io::Cursor::new(&spv))?;
+            Ok(spv)
+        })
+        .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", self.path.display(), e)))?;
+
+        self.modified = modified;
+        Ok(source)
+    }
+
+    fn is_glsl(&self) -> bool {
+        self.path.extension() != Some(std::ffi::OsStr::new("spv"))
+    }
+
+    #[cfg(feature = "glslc")]
+    fn compile(&self, source: &[u8], device: &Device) -> std::io::Result<Vec<u8>> {
+        let source = std::str::from_utf8(source)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+        device.compile_glsl(source, self.stage)
+    }
+
+    #[cfg(not(feature = "glslc"))]
+    fn compile(&self, _source: &[u8], _device: &Device) -> std::io::Result<Vec<u8>> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "{:?} shader isn't SPIR-V, and GLSL is only compiled with the `glslc` feature",
+                self.stage
+            ),
+        ))
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 /// Frame
 ///////////////////////////////////////////////////////////////////////////////
@@ -989,16 +2077,37 @@ impl Frame {
         Self { encoder }
     }
 
-    pub fn pass<T: RenderTarget>(&mut self, op: PassOp, view: &T) -> Pass {
-        Pass::begin(
+    /// Begin a render pass on the given target. If the target is
+    /// multisampled, it is resolved when the pass ends.
+    pub fn pass<T: RenderTarget + ?Sized>(&mut self, op: PassOp, view: &T) -> Pass<'_> {
+        trace!(Event::BeginPass {
+            target: view.trace_id(),
+            clear: match op {
+                PassOp::Clear(color) => Some(color),
+                PassOp::Load() => None,
+            },
+        });
+        Pass::begin_resolve(
             &mut self.encoder,
             &view.color_target(),
+            view.resolve_target(),
             &view.zdepth_target(),
             op,
         )
     }
 
+    /// Begin a compute pass. Passes are run in the order they are begun,
+    /// so that render passes can use the results of compute passes begun
+    /// before them.
+    pub fn compute_pass(&mut self) -> ComputePass<'_> {
+        ComputePass::begin(&mut self.encoder)
+    }
+
     pub fn copy(&mut self, src: &UniformBuffer, dst: &UniformBuffer) {
+        trace!(Event::CopyBuffer {
+            src: src.id,
+            dst: dst.id
+        });
         self.encoder.copy_buffer_to_buffer(
             &src.wgpu,
             0,
@@ -1008,6 +2117,51 @@ impl Frame {
         );
     }
 
+    /// Copy a region of a framebuffer into a backdrop, to be sampled while
+    /// drawing over the framebuffer in a later pass. The region is clipped
+    /// to the framebuffer and to the backdrop size.
+    pub fn backdrop(&mut self, src: &Framebuffer, region: Rect<u32>, backdrop: &mut Backdrop) {
+        let region = region.intersection(Rect::new(0, 0, src.width(), src.height()));
+        let region = Rect::new(
+            region.x1,
+            region.y1,
+            region.x1 + region.width().min(backdrop.texture.w),
+            region.y1 + region.height().min(backdrop.texture.h),
+        );
+        backdrop.region = region;
+
+        if region.width() == 0 || region.height() == 0 {
+            return;
+        }
+        self.encoder.copy_texture_to_texture(
+            wgpu::TextureCopyView {
+                texture: &src.texture.wgpu,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d {
+                    x: region.x1 as f32,
+                    y: region.y1 as f32,
+                    z: 0.,
+                },
+            },
+            wgpu::TextureCopyView {
+                texture: &backdrop.texture.wgpu,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d {
+                    x: 0.,
+                    y: 0.,
+                    z: 0.,
+                },
+            },
+            wgpu::Extent3d {
+                width: region.width(),
+                height: region.height(),
+                depth: 1,
+            },
+        );
+    }
+
     pub fn encoder(&self) -> &wgpu::CommandEncoder {
         &self.encoder
     }
@@ -1024,6 +2178,8 @@ impl Frame {
 #[derive(Debug)]
 pub struct Pass<'a> {
     wgpu: wgpu::RenderPass<'a>,
+    #[cfg(feature = "validation")]
+    validation: Validation,
 }
 
 impl<'a> Pass<'a> {
@@ -1032,6 +2188,17 @@ impl<'a> Pass<'a> {
         view: &wgpu::TextureView,
         depth: &wgpu::TextureView,
         op: PassOp,
+    ) -> Self {
+        Self::begin_resolve(encoder, view, None, depth, op)
+    }
+    /// Begin a pass on a multisampled `view`, which is resolved into
+    /// `resolve` at the end of the pass.
+    pub fn begin_resolve(
+        encoder: &'a mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        resolve: Option<&wgpu::TextureView>,
+        depth: &wgpu::TextureView,
+        op: PassOp,
     ) -> Self {
         let pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
@@ -1042,501 +2209,2741 @@ impl<'a> Pass<'a> {
                     PassOp::Clear(color) => color.to_wgpu(),
                     PassOp::Load() => Rgba::TRANSPARENT.to_wgpu(),
                 },
-                resolve_target: None,
+                resolve_target: resolve,
             }],
             depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
                 attachment: depth,
                 depth_load_op: op.to_wgpu(),
                 depth_store_op: wgpu::StoreOp::Store,
-                stencil_load_op: wgpu::LoadOp::Clear,
+                stencil_load_op: op.to_wgpu(),
                 stencil_store_op: wgpu::StoreOp::Store,
                 clear_depth: 1.0,
                 clear_stencil: 0,
             }),
         });
-        Pass { wgpu: pass }
+        Pass {
+            wgpu: pass,
+            #[cfg(feature = "validation")]
+            validation: Validation::default(),
+        }
     }
     pub fn set_pipeline<T>(&mut self, pipeline: &T)
     where
         T: AbstractPipeline<'a>,
     {
+        #[cfg(feature = "validation")]
+        self.validation.enter(std::any::type_name::<T>());
+
         pipeline.apply(self);
+
+        #[cfg(feature = "validation")]
+        self.validation.exit();
     }
     pub fn set_binding(&mut self, group: &BindingGroup, offsets: &[u64]) {
+        #[cfg(feature = "validation")]
+        self.validation.binding(group);
+
+        trace!(Event::SetBinding {
+            id: group.id,
+            offsets: offsets.to_vec(),
+        });
         self.wgpu
             .set_bind_group(group.set_index, &group.wgpu, offsets);
     }
     pub fn set_index_buffer(&mut self, index_buf: &IndexBuffer) {
+        trace!(Event::SetIndexBuffer(index_buf.id));
         self.wgpu.set_index_buffer(&index_buf.wgpu, 0)
     }
     pub fn set_vertex_buffer(&mut self, vertex_buf: &VertexBuffer) {
+        #[cfg(feature = "validation")]
+        self.validation.vertex_buffer(vertex_buf);
+        trace!(Event::SetVertexBuffer(vertex_buf.id));
         self.wgpu.set_vertex_buffers(0, &[(&vertex_buf.wgpu, 0)])
     }
+    /// Set a storage buffer as the vertex buffer, eg. to draw instances
+    /// simulated in a compute pass. Traces replay it with the data it was
+    /// created with, since compute passes aren't recorded.
+    pub fn set_storage_vertex_buffer(&mut self, buf: &StorageBuffer) {
+        #[cfg(feature = "validation")]
+        self.validation.vertices(
+            "set_storage_vertex_buffer",
+            (buf.size / buf.stride.max(1)) as u32,
+            buf.stride,
+        );
+        trace!(Event::SetVertexBuffer(buf.id));
+        self.wgpu.set_vertex_buffers(0, &[(&buf.wgpu, 0)])
+    }
+    /// Set the reference value used by `Stencil` operations.
+    pub fn set_stencil_reference(&mut self, reference: u32) {
+        trace!(Event::SetStencilReference(reference));
+        self.wgpu.set_stencil_reference(reference);
+    }
+    /// Set the constant used by `BlendFactor::BlendColor`.
+    pub fn set_blend_color(&mut self, color: Rgba) {
+        trace!(Event::SetBlendColor(color));
+        self.wgpu.set_blend_color(color.to_wgpu());
+    }
     pub fn draw<T: Draw>(&mut self, drawable: &T, binding: &BindingGroup) {
         drawable.draw(binding, self);
     }
     pub fn draw_buffer(&mut self, buf: &VertexBuffer) {
         self.set_vertex_buffer(buf);
-        self.wgpu.draw(0..buf.size, 0..1);
+        self.draw_range(0..buf.size, 0..1);
+    }
+    /// Draw `vertices` once for each instance in the buffer. The pipeline
+    /// must use `StepMode::Instance`.
+    pub fn draw_instances(&mut self, buf: &VertexBuffer, vertices: Range<u32>) {
+        self.set_vertex_buffer(buf);
+        self.draw_range(vertices, 0..buf.size);
     }
     pub fn draw_buffer_range(&mut self, buf: &VertexBuffer, range: Range<u32>) {
         self.set_vertex_buffer(buf);
-        self.wgpu.draw(range, 0..1);
+        self.draw_range(range, 0..1);
     }
     pub fn draw_indexed(&mut self, indices: Range<u32>, instances: Range<u32>) {
+        #[cfg(feature = "validation")]
+        self.validation.draw("indices", &indices, &instances);
+        trace!(Event::DrawIndexed {
+            indices: indices.clone(),
+            instances: instances.clone(),
+        });
         self.wgpu.draw_indexed(indices, 0, instances)
     }
-}
-
-#[derive(Debug)]
-pub enum PassOp {
-    Clear(Rgba),
-    Load(),
-}
+    pub fn draw_indexed_buffer(&mut self, buf: &IndexedBuffer) {
+        self.set_vertex_buffer(&buf.vertices);
+        self.set_index_buffer(&buf.indices);
+        self.draw_indexed(0..buf.indices.size, 0..1);
+    }
+    /// Draw a slice of a ring buffer, through its indices if it has any.
+    pub fn draw_ring(&mut self, ring: &RingBuffer, slice: &RingSlice) {
+        #[cfg(feature = "validation")]
+        self.validation
+            .vertices("draw_ring", slice.size, slice.stride);
+        self.set_ring_buffer(ring, slice.offset, slice.indices.map(|(offset, _)| offset));
+
+        match slice.indices {
+            Some((_, count)) => self.draw_indexed(0..count, 0..1),
+            None => self.draw_range(0..slice.size, 0..1),
+        }
+    }
+    /// Draw an indirect buffer, with the arguments written by the GPU.
+    /// Indirect draws aren't traced, see `trace`.
+    pub fn draw_indirect(&mut self, buf: &IndirectBuffer) {
+        #[cfg(feature = "validation")]
+        self.validation
+            .vertices("draw_indirect", buf.capacity, buf.vertices.stride);
+        self.wgpu.set_vertex_buffers(0, &[(&buf.vertices.wgpu, 0)]);
+        self.wgpu.draw_indirect(&buf.args.wgpu, 0);
+    }
+    /// Set a ring buffer as the vertex buffer at the given offset, and as
+    /// the index buffer at the given index offset, if any.
+    fn set_ring_buffer(
+        &mut self,
+        ring: &RingBuffer,
+        vertices: wgpu::BufferAddress,
+        indices: Option<wgpu::BufferAddress>,
+    ) {
+        trace!(Event::SetRingBuffer {
+            id: ring.id,
+            vertices,
+            indices,
+        });
+        self.wgpu.set_vertex_buffers(0, &[(&ring.wgpu, vertices)]);
 
-impl PassOp {
-    fn to_wgpu(&self) -> wgpu::LoadOp {
-        match self {
-            PassOp::Clear(_) => wgpu::LoadOp::Clear,
-            PassOp::Load() => wgpu::LoadOp::Load,
+        if let Some(offset) = indices {
+            self.wgpu.set_index_buffer(&ring.wgpu, offset);
         }
     }
+    fn draw_range(&mut self, vertices: Range<u32>, instances: Range<u32>) {
+        #[cfg(feature = "validation")]
+        self.validation.draw("vertices", &vertices, &instances);
+        trace!(Event::Draw {
+            vertices: vertices.clone(),
+            instances: instances.clone(),
+        });
+        self.wgpu.draw(vertices, instances);
+    }
 }
 
-///////////////////////////////////////////////////////////////////////////////
-/// SwapChain & RenderTarget
-///////////////////////////////////////////////////////////////////////////////
-
-pub trait RenderTarget {
-    fn color_target(&self) -> &wgpu::TextureView;
-    fn zdepth_target(&self) -> &wgpu::TextureView;
+#[cfg(feature = "trace")]
+impl Drop for Pass<'_> {
+    fn drop(&mut self) {
+        trace::record(|| Event::EndPass);
+    }
 }
 
+/// A compute pass, begun with `Frame::compute_pass`. Work is dispatched in
+/// workgroups, with the bindings set on the pass.
 #[derive(Debug)]
-pub struct SwapChainTexture<'a> {
-    pub width: u32,
-    pub height: u32,
-
-    wgpu: wgpu::SwapChainOutput<'a>,
-    depth: &'a ZBuffer,
+pub struct ComputePass<'a> {
+    wgpu: wgpu::ComputePass<'a>,
+    #[cfg(feature = "validation")]
+    validation: Validation,
 }
 
-impl RenderTarget for SwapChainTexture<'_> {
-    fn color_target(&self) -> &wgpu::TextureView {
-        &self.wgpu.view
+impl<'a> ComputePass<'a> {
+    pub fn begin(encoder: &'a mut wgpu::CommandEncoder) -> Self {
+        ComputePass {
+            wgpu: encoder.begin_compute_pass(),
+            #[cfg(feature = "validation")]
+            validation: Validation::default(),
+        }
     }
+    pub fn set_pipeline(&mut self, pipeline: &ComputePipeline) {
+        #[cfg(feature = "validation")]
+        self.validation.compute_pipeline(pipeline);
 
-    fn zdepth_target(&self) -> &wgpu::TextureView {
-        &self.depth.texture.view
+        self.wgpu.set_pipeline(&pipeline.wgpu);
     }
-}
+    pub fn set_binding(&mut self, group: &BindingGroup, offsets: &[u64]) {
+        #[cfg(feature = "validation")]
+        self.validation.binding(group);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum PresentMode {
-    Vsync,
-    NoVsync,
-}
+        self.wgpu
+            .set_bind_group(group.set_index, &group.wgpu, offsets);
+    }
+    /// Dispatch `x * y * z` workgroups. The size of a workgroup is given by
+    /// the compute shader.
+    pub fn dispatch(&mut self, x: u32, y: u32, z: u32) {
+        #[cfg(feature = "validation")]
+        self.validation.current("dispatch");
 
-impl PresentMode {
-    fn to_wgpu(&self) -> wgpu::PresentMode {
-        match self {
-            PresentMode::Vsync => wgpu::PresentMode::Vsync,
-            PresentMode::NoVsync => wgpu::PresentMode::NoVsync,
-        }
+        self.wgpu.dispatch(x, y, z);
     }
 }
 
-impl Default for PresentMode {
-    fn default() -> Self {
-        PresentMode::Vsync
-    }
+/// The state of a pass, checked before calls reach `wgpu` with the
+/// `validation` feature. Invalid calls panic with a description of the
+/// mistake, naming the pipeline they were made with.
+#[cfg(feature = "validation")]
+#[derive(Debug, Default)]
+struct Validation {
+    /// Type name of the pipeline set on the pass, if any.
+    pipeline: Option<&'static str>,
+    /// Size of a vertex of the pipeline, or zero if it has no vertex inputs.
+    stride: usize,
+    /// The bindings of each set of the pipeline.
+    sets: Vec<Vec<BindingType>>,
+    /// Type names of the pipelines being applied. Pipelines usually apply
+    /// the pipeline they wrap, which shouldn't replace their name.
+    applying: Vec<&'static str>,
 }
 
-/// A handle to a swap chain.
-///
-/// A `SwapChain` represents the image or series of images that will be presented to a [`Renderer`].
-/// A `SwapChain` may be created with [`Renderer::swap_chain`].
-#[derive(Debug)]
-pub struct SwapChain {
-    pub width: u32,
-    pub height: u32,
+#[cfg(feature = "validation")]
+impl Validation {
+    fn enter(&mut self, name: &'static str) {
+        self.applying.push(name);
+    }
 
-    depth: ZBuffer,
-    wgpu: wgpu::SwapChain,
-}
+    fn exit(&mut self) {
+        self.applying.pop();
+    }
 
-impl SwapChain {
-    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
+    fn pipeline(&mut self, pipeline: &Pipeline) {
+        self.pipeline = self
+            .applying
+            .first()
+            .copied()
+            .or(Some("rgx::core::Pipeline"));
+        self.stride = pipeline.vertex_layout.size;
+        self.sets = pipeline
+            .layout
+            .sets
+            .iter()
+            .map(|s| s.slots.clone())
+            .collect();
+    }
 
-    /// Convenience method to retrieve `(width, height)`
-    #[inline]
-    pub fn size(&self) -> (u32, u32) {
-        (self.width, self.height)
+    fn compute_pipeline(&mut self, pipeline: &ComputePipeline) {
+        self.pipeline = Some("rgx::core::ComputePipeline");
+        self.stride = 0;
+        self.sets = pipeline
+            .layout
+            .sets
+            .iter()
+            .map(|s| s.slots.clone())
+            .collect();
     }
 
-    /// Returns the next texture to be presented by the swapchain for drawing.
-    ///
-    /// When the [`SwapChainTexture`] returned by this method is dropped, the
-    /// swapchain will present the texture to the associated [`Renderer`].
-    pub fn next(&mut self) -> SwapChainTexture {
-        SwapChainTexture {
-            depth: &self.depth,
-            wgpu: self.wgpu.get_next_texture(),
-            width: self.width,
-            height: self.height,
+    fn current(&self, call: &str) -> &'static str {
+        match self.pipeline {
+            Some(name) => name,
+            None => panic!(
+                "validation: `Pass::{}` was called before a pipeline was set, \
+                 set one with `Pass::set_pipeline` first",
+                call
+            ),
         }
     }
 
-    /// Get the texture format in use
-    pub fn format(&self) -> wgpu::TextureFormat {
-        Self::FORMAT
+    fn binding(&self, group: &BindingGroup) {
+        let name = self.current("set_binding");
+        let set = group.set_index as usize;
+
+        match self.sets.get(set) {
+            None => panic!(
+                "validation: a binding group for set {} was bound, but pipeline `{}` \
+                 only has {} set(s)",
+                set,
+                name,
+                self.sets.len()
+            ),
+            Some(expected) if *expected != group.slots => panic!(
+                "validation: the binding group for set {} has bindings {:?}, but \
+                 pipeline `{}` expects {:?}; create it from `pipeline.layout.sets[{}]`",
+                set, group.slots, name, expected, set
+            ),
+            Some(_) => {}
+        }
     }
 
-    fn descriptor(width: u32, height: u32, mode: PresentMode) -> wgpu::SwapChainDescriptor {
-        wgpu::SwapChainDescriptor {
-            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
-            format: Self::FORMAT,
-            present_mode: mode.to_wgpu(),
-            width,
-            height,
+    fn vertex_buffer(&self, buf: &VertexBuffer) {
+        self.vertices("set_vertex_buffer", buf.size, buf.stride);
+    }
+
+    /// Check the vertex size of a buffer of `size` vertices, set by `call`.
+    fn vertices(&self, call: &str, size: u32, stride: usize) {
+        let name = self.current(call);
+
+        if stride != 0 && self.stride != 0 && stride != self.stride {
+            panic!(
+                "validation: a vertex buffer of {} vertices of {} bytes was set, but \
+                 pipeline `{}` expects vertices of {} bytes",
+                size, stride, name, self.stride
+            );
+        }
+    }
+
+    fn draw(&self, what: &str, elements: &Range<u32>, instances: &Range<u32>) {
+        let name = self.current("draw");
+
+        if elements.start >= elements.end || instances.start >= instances.end {
+            panic!(
+                "validation: zero-sized draw of {} {:?} and instances {:?} with \
+                 pipeline `{}`; skip empty buffers, eg. with `Batch::is_empty`",
+                what, elements, instances, name
+            );
         }
     }
 }
 
 ///////////////////////////////////////////////////////////////////////////////
-/// Renderer
+// DrawList
 ///////////////////////////////////////////////////////////////////////////////
 
-#[derive(Debug)]
-pub struct Renderer {
-    pub device: Device,
+/// Handle to a vertex buffer registered with `DrawResources`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BufferHandle(usize);
+
+/// Handle to an index buffer registered with `DrawResources`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct IndexHandle(usize);
+
+/// Handle to a binding group registered with `DrawResources`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BindingHandle(usize);
+
+/// The resources referenced by a `DrawList`. Handles are only valid for
+/// the resources they were created from, but a list may be replayed with
+/// any set of resources registered in the same order.
+#[derive(Default)]
+pub struct DrawResources<'a> {
+    buffers: Vec<&'a VertexBuffer>,
+    indices: Vec<&'a IndexBuffer>,
+    bindings: Vec<&'a BindingGroup>,
 }
 
-impl Renderer {
-    pub fn new<W: HasRawWindowHandle>(window: &W) -> Result<Self, Error> {
-        let adapter = wgpu::Adapter::request(&wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::LowPower,
-            backends: wgpu::BackendBit::METAL | wgpu::BackendBit::VULKAN,
-        })
-        .ok_or(Error::NoAdaptersFound)?;
-
-        Ok(Self {
-            device: Device::new(&adapter, window),
-        })
+impl<'a> DrawResources<'a> {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    pub fn swap_chain(&self, w: u32, h: u32, mode: PresentMode) -> SwapChain {
-        SwapChain {
-            depth: self.device.create_zbuffer(w, h),
-            wgpu: self.device.create_swap_chain(w, h, mode),
-            width: w,
-            height: h,
-        }
+    pub fn buffer(&mut self, buf: &'a VertexBuffer) -> BufferHandle {
+        self.buffers.push(buf);
+        BufferHandle(self.buffers.len() - 1)
     }
 
-    pub fn texture(&self, w: u32, h: u32) -> Texture {
-        self.device.create_texture(w, h)
+    pub fn index(&mut self, buf: &'a IndexBuffer) -> IndexHandle {
+        self.indices.push(buf);
+        IndexHandle(self.indices.len() - 1)
     }
 
-    pub fn framebuffer(&self, w: u32, h: u32) -> Framebuffer {
-        self.device.create_framebuffer(w, h)
+    pub fn binding(&mut self, group: &'a BindingGroup) -> BindingHandle {
+        self.bindings.push(group);
+        BindingHandle(self.bindings.len() - 1)
     }
+}
 
-    pub fn zbuffer(&self, w: u32, h: u32) -> ZBuffer {
-        self.device.create_zbuffer(w, h)
+/// A recorded draw command.
+#[derive(Clone, PartialEq, Debug)]
+pub enum DrawCommand {
+    SetBinding(BindingHandle, Vec<u64>),
+    SetIndexBuffer(IndexHandle),
+    DrawBuffer(BufferHandle),
+    DrawBufferRange(BufferHandle, Range<u32>),
+    DrawIndexed(BufferHandle, Range<u32>, Range<u32>),
+}
+
+/// A list of draw commands, independent of any device or pass. Lists
+/// can be recorded once and replayed on every frame, or into different
+/// render targets.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct DrawList {
+    commands: Vec<DrawCommand>,
+}
+
+impl DrawList {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    pub fn vertex_buffer<T>(&self, verts: &[T]) -> VertexBuffer
-    where
-        T: 'static + Copy,
-    {
-        self.device.create_buffer(verts)
+    pub fn set_binding(&mut self, group: BindingHandle, offsets: &[u64]) {
+        self.commands
+            .push(DrawCommand::SetBinding(group, offsets.to_vec()));
     }
 
-    pub fn uniform_buffer<T>(&self, buf: &[T]) -> UniformBuffer
-    where
-        T: 'static + Copy,
-    {
-        self.device.create_uniform_buffer(buf)
+    pub fn set_index_buffer(&mut self, buf: IndexHandle) {
+        self.commands.push(DrawCommand::SetIndexBuffer(buf));
     }
 
-    pub fn binding_group(&self, layout: &BindingGroupLayout, binds: &[&dyn Bind]) -> BindingGroup {
-        self.device.create_binding_group(layout, binds)
+    pub fn draw(&mut self, buf: BufferHandle, binding: BindingHandle) {
+        self.set_binding(binding, &[]);
+        self.draw_buffer(buf);
     }
 
-    pub fn sampler(&self, min_filter: Filter, mag_filter: Filter) -> Sampler {
-        self.device.create_sampler(min_filter, mag_filter)
+    pub fn draw_buffer(&mut self, buf: BufferHandle) {
+        self.commands.push(DrawCommand::DrawBuffer(buf));
     }
 
-    pub fn pipeline<T>(&self, blending: Blending) -> T
-    where
-        T: AbstractPipeline<'static>,
-    {
-        let desc = T::description();
-        let pip_layout = self.device.create_pipeline_layout(desc.pipeline_layout);
-        let vertex_layout = VertexLayout::from(desc.vertex_layout);
-        let vs =
-            self.device
-                .create_shader("vertex shader", desc.vertex_shader, ShaderStage::Vertex);
-        let fs = self.device.create_shader(
-            "fragment shader",
-            desc.fragment_shader,
-            ShaderStage::Fragment,
-        );
+    pub fn draw_buffer_range(&mut self, buf: BufferHandle, range: Range<u32>) {
+        self.commands.push(DrawCommand::DrawBufferRange(buf, range));
+    }
 
-        T::setup(
-            self.device
-                .create_pipeline(pip_layout, vertex_layout, blending, &vs, &fs),
-            &self.device,
-        )
+    pub fn draw_indexed(&mut self, buf: BufferHandle, indices: Range<u32>, instances: Range<u32>) {
+        self.commands
+            .push(DrawCommand::DrawIndexed(buf, indices, instances));
     }
 
-    pub fn read<F>(&mut self, fb: &Framebuffer, f: F)
-    where
-        F: 'static + FnOnce(&[Bgra8]),
-    {
-        let mut encoder = self.device.create_command_encoder();
+    pub fn commands(&self) -> &[DrawCommand] {
+        &self.commands
+    }
 
-        let bytesize = 4 * fb.size();
-        let dst = self.device.device.create_buffer(&wgpu::BufferDescriptor {
-            size: bytesize as u64,
-            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
-        });
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
 
-        encoder.copy_texture_to_buffer(
-            wgpu::TextureCopyView {
-                texture: &fb.texture.wgpu,
-                mip_level: 0,
-                array_layer: 0,
-                origin: wgpu::Origin3d {
-                    x: 0.0,
-                    y: 0.0,
-                    z: 0.0,
-                },
-            },
-            wgpu::BufferCopyView {
-                buffer: &dst,
-                offset: 0,
-                // TODO: Must be a multiple of 256
-                row_pitch: 4 * fb.texture.w,
-                image_height: fb.texture.h,
-            },
-            fb.texture.extent,
-        );
-        self.device.submit(&[encoder.finish()]);
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
 
-        let mut buffer: Vec<u8> = Vec::with_capacity(bytesize);
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
 
-        dst.map_read_async(
-            0,
-            bytesize as u64,
-            move |result: wgpu::BufferMapAsyncResult<&[u8]>| match result {
-                Ok(ref mapping) => {
-                    buffer.extend_from_slice(mapping.data);
-                    if buffer.len() == bytesize {
-                        let (head, body, tail) = unsafe { buffer.align_to::<Bgra8>() };
-                        if !(head.is_empty() && tail.is_empty()) {
-                            panic!("Renderer::read: framebuffer is not a valid Bgra8 buffer");
-                        }
-                        f(body);
-                    }
+    /// Replay the commands into a pass. The pipeline should already be set.
+    ///
+    /// Panics if a handle doesn't refer to a resource in `res`.
+    pub fn replay<'b>(&self, res: &DrawResources<'b>, pass: &mut Pass<'b>) {
+        for cmd in self.commands.iter() {
+            match cmd {
+                DrawCommand::SetBinding(BindingHandle(ix), offsets) => {
+                    pass.set_binding(res.bindings[*ix], offsets);
                 }
-                Err(ref err) => panic!("{:?}", err),
-            },
-        );
+                DrawCommand::SetIndexBuffer(IndexHandle(ix)) => {
+                    pass.set_index_buffer(res.indices[*ix]);
+                }
+                DrawCommand::DrawBuffer(BufferHandle(ix)) => {
+                    pass.draw_buffer(res.buffers[*ix]);
+                }
+                DrawCommand::DrawBufferRange(BufferHandle(ix), range) => {
+                    pass.draw_buffer_range(res.buffers[*ix], range.clone());
+                }
+                DrawCommand::DrawIndexed(BufferHandle(ix), indices, instances) => {
+                    pass.set_vertex_buffer(res.buffers[*ix]);
+                    pass.draw_indexed(indices.clone(), instances.clone());
+                }
+            }
+        }
     }
+}
 
-    // MUTABLE API ////////////////////////////////////////////////////////////
+#[derive(Debug)]
+pub enum PassOp {
+    Clear(Rgba),
+    Load(),
+}
 
-    pub fn update_pipeline<'a, T>(&mut self, pip: &'a T, p: T::PrepareContext, f: &mut Frame)
-    where
-        T: AbstractPipeline<'a>,
-    {
-        if let Some((buf, unifs)) = pip.prepare(p) {
-            self.device
-                .update_uniform_buffer::<T::Uniforms>(unifs.as_slice(), buf, &mut f.encoder);
+impl PassOp {
+    fn to_wgpu(&self) -> wgpu::LoadOp {
+        match self {
+            PassOp::Clear(_) => wgpu::LoadOp::Clear,
+            PassOp::Load() => wgpu::LoadOp::Load,
         }
     }
+}
 
-    pub fn frame(&mut self) -> Frame {
-        let encoder = self.device.create_command_encoder();
-        Frame::new(encoder)
+///////////////////////////////////////////////////////////////////////////////
+/// SwapChain & RenderTarget
+///////////////////////////////////////////////////////////////////////////////
+
+pub trait RenderTarget {
+    fn color_target(&self) -> &wgpu::TextureView;
+    fn zdepth_target(&self) -> &wgpu::TextureView;
+
+    /// The view a multisampled color target is resolved into, if any.
+    fn resolve_target(&self) -> Option<&wgpu::TextureView> {
+        None
     }
 
-    pub fn present(&mut self, frame: Frame) {
-        self.device.submit(&[frame.encoder.finish()]);
+    /// The traced id of the target, or `None` for the screen.
+    #[cfg(feature = "trace")]
+    #[doc(hidden)]
+    fn trace_id(&self) -> Option<trace::Id> {
+        None
     }
+}
 
-    pub fn submit<T: Copy>(&mut self, commands: &[Op<T>]) {
-        let mut encoder = self.device.create_command_encoder();
-        for c in commands.iter() {
-            c.encode(&mut self.device, &mut encoder);
+#[derive(Debug)]
+pub struct SwapChainTexture<'a> {
+    pub width: u32,
+    pub height: u32,
+
+    wgpu: wgpu::SwapChainOutput<'a>,
+    depth: &'a ZBuffer,
+    msaa: Option<&'a Texture>,
+}
+
+impl RenderTarget for SwapChainTexture<'_> {
+    fn color_target(&self) -> &wgpu::TextureView {
+        match self.msaa {
+            Some(msaa) => &msaa.view,
+            None => &self.wgpu.view,
         }
-        self.device.submit(&[encoder.finish()]);
+    }
+
+    fn zdepth_target(&self) -> &wgpu::TextureView {
+        &self.depth.texture.view
+    }
+
+    fn resolve_target(&self) -> Option<&wgpu::TextureView> {
+        self.msaa.map(|_| &self.wgpu.view)
     }
 }
 
-pub enum Op<'a, T> {
-    Clear(&'a dyn Canvas<Color = T>, T),
-    Fill(&'a dyn Canvas<Color = T>, &'a [T]),
-    Transfer(&'a dyn Canvas<Color = T>, &'a [T], u32, u32, Rect<i32>),
-    Blit(&'a dyn Canvas<Color = T>, Rect<f32>, Rect<f32>),
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresentMode {
+    Vsync,
+    NoVsync,
 }
 
-impl<'a, T> Op<'a, T>
-where
-    T: Copy,
-{
-    fn encode(&self, dev: &mut Device, encoder: &mut wgpu::CommandEncoder) {
-        match *self {
-            Op::Clear(f, color) => {
-                f.clear(color, dev, encoder);
-            }
-            Op::Fill(f, buf) => {
-                f.fill(buf, dev, encoder);
-            }
-            Op::Transfer(f, buf, w, h, rect) => {
-                f.transfer(buf, w, h, rect, dev, encoder);
-            }
-            Op::Blit(f, src, dst) => {
-                f.blit(src, dst, encoder);
-            }
+impl PresentMode {
+    fn to_wgpu(&self) -> wgpu::PresentMode {
+        match self {
+            PresentMode::Vsync => wgpu::PresentMode::Vsync,
+            PresentMode::NoVsync => wgpu::PresentMode::NoVsync,
         }
     }
 }
 
-///////////////////////////////////////////////////////////////////////////////
-/// Device
-///////////////////////////////////////////////////////////////////////////////
+impl Default for PresentMode {
+    fn default() -> Self {
+        PresentMode::Vsync
+    }
+}
 
+/// A handle to a swap chain.
+///
+/// A `SwapChain` represents the image or series of images that will be presented to a [`Renderer`].
+/// A `SwapChain` may be created with [`Renderer::swap_chain`].
 #[derive(Debug)]
-pub struct Device {
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    surface: wgpu::Surface,
+pub struct SwapChain {
+    pub width: u32,
+    pub height: u32,
+
+    mode: PresentMode,
+    samples: u32,
+    depth: ZBuffer,
+    msaa: Option<Texture>,
+    wgpu: wgpu::SwapChain,
 }
 
-impl Device {
-    pub fn new<W: HasRawWindowHandle>(adapter: &wgpu::Adapter, window: &W) -> Self {
-        let surface = wgpu::Surface::create(window);
-        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor {
-            extensions: wgpu::Extensions {
-                anisotropic_filtering: false,
-            },
-            limits: wgpu::Limits::default(),
-        });
+impl SwapChain {
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
 
-        Self {
-            device,
-            queue,
-            surface,
-        }
+    /// Convenience method to retrieve `(width, height)`
+    #[inline]
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
     }
 
-    pub fn device(&self) -> &wgpu::Device {
-        &self.device
+    /// Returns the next texture to be presented by the swapchain for drawing.
+    ///
+    /// When the [`SwapChainTexture`] returned by this method is dropped, the
+    /// swapchain will present the texture to the associated [`Renderer`].
+    pub fn next(&mut self) -> SwapChainTexture {
+        SwapChainTexture {
+            depth: &self.depth,
+            msaa: self.msaa.as_ref(),
+            wgpu: self.wgpu.get_next_texture(),
+            width: self.width,
+            height: self.height,
+        }
     }
 
-    pub fn device_mut(&mut self) -> &mut wgpu::Device {
+    /// Get the texture format in use
+    pub fn format(&self) -> wgpu::TextureFormat {
+        Self::FORMAT
+    }
+
+    /// Get the present mode in use
+    pub fn mode(&self) -> PresentMode {
+        self.mode
+    }
+
+    /// Get the number of samples per pixel. Pipelines drawing to the
+    /// swap chain must be created with the same sample count.
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    fn descriptor(width: u32, height: u32, mode: PresentMode) -> wgpu::SwapChainDescriptor {
+        wgpu::SwapChainDescriptor {
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+            format: Self::FORMAT,
+            present_mode: mode.to_wgpu(),
+            width,
+            height,
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// Renderer
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub struct Renderer {
+    pub device: Device,
+    projections: RefCell<Projections>,
+    timing: Timing,
+}
+
+/// The projection buffers of the pipelines tracked by a renderer, and the
+/// size of its swap chain.
+#[derive(Debug, Default)]
+struct Projections {
+    size: Option<(u32, u32)>,
+    buffers: Vec<Projection>,
+}
+
+/// A projection, given the size of the swap chain.
+type ProjectionFn = Box<dyn Fn(u32, u32) -> crate::math::Matrix4<f32>>;
+
+/// A projection buffer, along with the size it was last updated with.
+struct Projection {
+    buffer: Weak<Deferred<wgpu::Buffer>>,
+    size: Option<(u32, u32)>,
+    /// The projection for a given size.
+    matrix: ProjectionFn,
+    #[cfg(feature = "trace")]
+    id: trace::Id,
+}
+
+impl fmt::Debug for Projection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Projection")
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+/// Timings of the last frame presented by a `Renderer`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Timestamps {
+    /// CPU time spent between `Renderer::frame` and `Renderer::present`,
+    /// encoding the frame.
+    pub encode: Duration,
+    /// Time between submitting the frame and the GPU completing it, if
+    /// profiling is enabled with `Renderer::set_profiling`.
+    ///
+    /// Since wgpu has no timestamp queries, this is measured by waiting for
+    /// the device after submitting, which stalls the CPU and includes queue
+    /// latency. It is an upper bound on the GPU time of the frame.
+    pub gpu: Option<Duration>,
+}
+
+/// Frame timings recorded by the renderer.
+#[derive(Debug, Default)]
+struct Timing {
+    profiling: bool,
+    started: Option<Instant>,
+    last: Timestamps,
+}
+
+/// The timings of a frame, as recorded by a `FrameProfiler`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FrameProfile {
+    pub timestamps: Timestamps,
+    /// CPU time of each scope, in the order they were timed.
+    pub scopes: Vec<(&'static str, Duration)>,
+}
+
+/// Records per-pass CPU encode times over the last frames, along with the
+/// renderer's frame timestamps, eg. to tune batch sizes.
+///
+/// ```ignore
+/// let mut profiler = FrameProfiler::new();
+/// let mut frame = r.frame();
+///
+/// profiler.time("sprites", || {
+///     let mut pass = frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &textures);
+///     ...
+/// });
+/// r.present(frame);
+/// profiler.finish(&r);
+/// ```
+#[derive(Debug, Default)]
+pub struct FrameProfiler {
+    scopes: Vec<(&'static str, Duration)>,
+    frames: VecDeque<FrameProfile>,
+}
+
+impl FrameProfiler {
+    /// Number of frames kept.
+    pub const HISTORY: usize = 120;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Time a scope of the current frame, eg. the encoding of a pass.
+    pub fn time<T, F: FnOnce() -> T>(&mut self, label: &'static str, f: F) -> T {
+        let started = Instant::now();
+        let result = f();
+
+        self.scopes.push((label, started.elapsed()));
+        result
+    }
+
+    /// Finish the current frame, once it was presented, and return its
+    /// profile.
+    pub fn finish(&mut self, r: &Renderer) -> &FrameProfile {
+        if self.frames.len() == Self::HISTORY {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(FrameProfile {
+            timestamps: r.timestamps(),
+            scopes: std::mem::take(&mut self.scopes),
+        });
+        self.frames.back().unwrap()
+    }
+
+    /// The profiles of the last frames, oldest first.
+    pub fn frames(&self) -> impl Iterator<Item = &FrameProfile> {
+        self.frames.iter()
+    }
+
+    /// The average timestamps over the last frames. The GPU time is only
+    /// averaged over the frames it was measured for.
+    pub fn average(&self) -> Timestamps {
+        let n = self.frames.len().max(1) as u32;
+        let encode = self
+            .frames
+            .iter()
+            .map(|f| f.timestamps.encode)
+            .sum::<Duration>()
+            / n;
+        let gpu: Vec<Duration> = self
+            .frames
+            .iter()
+            .filter_map(|f| f.timestamps.gpu)
+            .collect();
+
+        Timestamps {
+            encode,
+            gpu: if gpu.is_empty() {
+                None
+            } else {
+                Some(gpu.iter().sum::<Duration>() / gpu.len() as u32)
+            },
+        }
+    }
+
+    /// The average CPU time of each scope over the last frames, in the
+    /// order they were first timed.
+    pub fn scopes(&self) -> Vec<(&'static str, Duration)> {
+        let mut totals: Vec<(&'static str, Duration, u32)> = Vec::new();
+
+        for (label, duration) in self.frames.iter().flat_map(|f| f.scopes.iter()) {
+            match totals.iter_mut().find(|(l, _, _)| l == label) {
+                Some((_, total, count)) => {
+                    *total += *duration;
+                    *count += 1;
+                }
+                None => totals.push((label, *duration, 1)),
+            }
+        }
+        totals
+            .into_iter()
+            .map(|(label, total, count)| (label, total / count))
+            .collect()
+    }
+}
+
+impl Renderer {
+    pub fn new<W: HasRawWindowHandle>(window: &W) -> Result<Self, Error> {
+        let adapter = wgpu::Adapter::request(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::LowPower,
+            backends: wgpu::BackendBit::METAL | wgpu::BackendBit::VULKAN,
+        })
+        .ok_or(Error::NoAdaptersFound)?;
+
+        Ok(Self {
+            device: Device::new(&adapter, window),
+            projections: RefCell::default(),
+            timing: Timing::default(),
+        })
+    }
+
+    /// Create a renderer without a window, along with a framebuffer of the
+    /// given size to render into, eg. for command-line image generation or
+    /// rendering tests. The result can be read back with `read_pixels`.
+    pub fn headless(w: u32, h: u32) -> Result<(Self, Framebuffer), Error> {
+        let adapter = wgpu::Adapter::request(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::LowPower,
+            backends: wgpu::BackendBit::METAL | wgpu::BackendBit::VULKAN,
+        })
+        .ok_or(Error::NoAdaptersFound)?;
+
+        let renderer = Self {
+            device: Device::headless(&adapter),
+            projections: RefCell::default(),
+            timing: Timing::default(),
+        };
+        let framebuffer = renderer.framebuffer(w, h);
+        renderer.projections.borrow_mut().size = Some((w, h));
+
+        Ok((renderer, framebuffer))
+    }
+
+    pub fn swap_chain(&self, w: u32, h: u32, mode: PresentMode) -> SwapChain {
+        self.swap_chain_msaa(w, h, mode, 1)
+    }
+
+    /// Create a swap chain rendered with `samples` samples per pixel, eg. `4`.
+    /// Frames are drawn to a multisampled texture, which is resolved to the
+    /// swap chain by `Frame::pass`. Pipelines drawing to it must be created
+    /// with `Renderer::pipeline_with_samples`.
+    pub fn swap_chain_msaa(&self, w: u32, h: u32, mode: PresentMode, samples: u32) -> SwapChain {
+        self.projections.borrow_mut().size = Some((w, h));
+        trace!(Event::Screen { w, h, samples });
+
+        let msaa = if samples > 1 {
+            Some(
+                self.device
+                    .create_msaa_texture(w, h, SwapChain::FORMAT, samples),
+            )
+        } else {
+            None
+        };
+
+        SwapChain {
+            depth: self.device.create_zbuffer_samples(w, h, samples),
+            wgpu: self.device.create_swap_chain(w, h, mode),
+            msaa,
+            mode,
+            samples,
+            width: w,
+            height: h,
+        }
+    }
+
+    /// Resize the swap chain and its depth buffer, keeping its present mode
+    /// and sample count. Does nothing if the size is unchanged. The
+    /// orthographic projection of the pipelines created by the renderer is
+    /// updated with the new size on the next frame.
+    pub fn resize(&self, chain: &mut SwapChain, w: u32, h: u32) {
+        if chain.size() != (w, h) {
+            *chain = self.swap_chain_msaa(w, h, chain.mode, chain.samples);
+        }
+    }
+
+    pub fn texture(&self, w: u32, h: u32) -> Texture {
+        self.device.create_texture(w, h)
+    }
+
+    pub fn texture_with_format(&self, w: u32, h: u32, format: TextureFormat) -> Texture {
+        self.device.create_texture_with_format(w, h, format)
+    }
+
+    pub fn texture_with_mipmaps(&self, w: u32, h: u32, format: TextureFormat) -> Texture {
+        self.device.create_texture_with_mipmaps(w, h, format)
+    }
+
+    pub fn backdrop(&self, w: u32, h: u32) -> Backdrop {
+        self.device.create_backdrop(w, h)
+    }
+
+    pub fn storage_texture(&self, w: u32, h: u32, format: TextureFormat) -> Texture {
+        self.device.create_storage_texture(w, h, format)
+    }
+
+    /// Create a texture from an encoded PNG, JPEG or BMP image.
+    #[cfg(feature = "image")]
+    pub fn texture_from_bytes(&mut self, bytes: &[u8]) -> Result<Texture, Error> {
+        let image = image::load_from_memory(bytes)
+            .map_err(|_| Error::InvalidImage)?
+            .to_rgba();
+        let (w, h) = image.dimensions();
+        let pixels = image.into_raw();
+        let texture = self.device.create_texture(w, h);
+
+        let mut encoder = self.device.create_command_encoder();
+        Texture::fill(
+            &texture,
+            Rgba8::align(&pixels),
+            &mut self.device,
+            &mut encoder,
+        );
+        self.device.submit(&[encoder.finish()]);
+
+        Ok(texture)
+    }
+
+    /// Create a texture from a PNG, JPEG or BMP image file.
+    #[cfg(feature = "image")]
+    pub fn texture_from_path<P: AsRef<std::path::Path>>(
+        &mut self,
+        path: P,
+    ) -> std::io::Result<Texture> {
+        let bytes = std::fs::read(path)?;
+
+        self.texture_from_bytes(&bytes)
+            .map_err(std::io::Error::from)
+    }
+
+    pub fn framebuffer(&self, w: u32, h: u32) -> Framebuffer {
+        self.device.create_framebuffer(w, h)
+    }
+
+    /// Create a framebuffer rendered with `samples` samples per pixel. Its
+    /// `texture` holds the resolved image, and can be sampled or read back
+    /// like any other framebuffer. Since only the resolved image is kept
+    /// up to date by clears and fills, passes on it should use `PassOp::Clear`.
+    pub fn framebuffer_msaa(&self, w: u32, h: u32, samples: u32) -> Framebuffer {
+        self.device.create_framebuffer_msaa(w, h, samples)
+    }
+
+    pub fn zbuffer(&self, w: u32, h: u32) -> ZBuffer {
+        self.device.create_zbuffer(w, h)
+    }
+
+    pub fn vertex_buffer<T>(&self, verts: &[T]) -> VertexBuffer
+    where
+        T: 'static + Copy,
+    {
+        self.device.create_buffer(verts)
+    }
+
+    pub fn dynamic_vertex_buffer<T>(&self, verts: &[T]) -> DynamicVertexBuffer
+    where
+        T: 'static + Copy,
+    {
+        self.device.create_dynamic_buffer(verts)
+    }
+
+    pub fn index_buffer(&self, indices: &[u32]) -> IndexBuffer {
+        self.device.create_index_buffer(indices)
+    }
+
+    /// Create a compute pipeline from a SPIR-V compute shader, with the
+    /// given binding sets. Storage buffers are bound with
+    /// `BindingType::StorageBuffer`.
+    pub fn compute_pipeline(&self, sets: &[Set], shader: &[u8]) -> ComputePipeline {
+        let layout = self.device.create_pipeline_layout(sets);
+        let cs = self
+            .device
+            .create_shader("compute shader", shader, ShaderStage::Compute);
+
+        self.device.create_compute_pipeline(layout, &cs)
+    }
+
+    /// Create a ring buffer of `capacity` bytes, shared by small batches.
+    pub fn ring_buffer(&self, capacity: u64) -> RingBuffer {
+        self.device.create_ring_buffer(capacity)
+    }
+
+    pub fn indirect_buffer<T>(
+        &self,
+        capacity: u32,
+        vertices: Range<u32>,
+        instances: Range<u32>,
+    ) -> IndirectBuffer
+    where
+        T: 'static + Copy,
+    {
+        self.device
+            .create_indirect_buffer::<T>(capacity, vertices, instances)
+    }
+
+    pub fn uniform_buffer<T>(&self, buf: &[T]) -> UniformBuffer
+    where
+        T: 'static + Copy,
+    {
+        self.device.create_uniform_buffer(buf)
+    }
+
+    pub fn storage_buffer<T>(&self, buf: &[T]) -> StorageBuffer
+    where
+        T: 'static + Copy,
+    {
+        self.device.create_storage_buffer(buf)
+    }
+
+    pub fn uniform_array<T>(&self, buf: &[T]) -> UniformBuffer
+    where
+        T: 'static + Copy,
+    {
+        self.device.create_uniform_array(buf)
+    }
+
+    pub fn binding_group(&self, layout: &BindingGroupLayout, binds: &[&dyn Bind]) -> BindingGroup {
+        self.device.create_binding_group(layout, binds)
+    }
+
+    pub fn sampler(&self, min_filter: Filter, mag_filter: Filter) -> Sampler {
+        self.device.create_sampler(min_filter, mag_filter)
+    }
+
+    pub fn sampler_with_mipmaps(
+        &self,
+        min_filter: Filter,
+        mag_filter: Filter,
+        mipmap_filter: Filter,
+    ) -> Sampler {
+        self.device
+            .create_sampler_with_mipmaps(min_filter, mag_filter, mipmap_filter)
+    }
+
+    /// Create a sampler from a descriptor, eg. to clamp texture coordinates
+    /// to the edge instead of repeating the texture.
+    pub fn sampler_with(&self, desc: SamplerDescriptor) -> Sampler {
+        self.device.create_sampler_with(desc)
+    }
+
+    pub fn pipeline<T>(&self, blending: Blending) -> T
+    where
+        T: AbstractPipeline<'static>,
+    {
+        self.pipeline_with_samples(blending, 1)
+    }
+
+    /// Create a pipeline for targets with `samples` samples per pixel, as
+    /// created by `Renderer::swap_chain_msaa` or `Renderer::framebuffer_msaa`.
+    pub fn pipeline_with_samples<T>(&self, blending: Blending, samples: u32) -> T
+    where
+        T: AbstractPipeline<'static>,
+    {
+        self.pipeline_with(blending, samples, DepthTest::default(), Stencil::default())
+    }
+
+    /// Create a pipeline with the given blending, sample count, depth test
+    /// and stencil operations. Pipelines created with `Renderer::pipeline`
+    /// have depth testing enabled, and ignore the stencil buffer.
+    pub fn pipeline_with<T>(
+        &self,
+        blending: Blending,
+        samples: u32,
+        depth: DepthTest,
+        stencil: Stencil,
+    ) -> T
+    where
+        T: AbstractPipeline<'static>,
+    {
+        let desc = T::description();
+
+        self.build_pipeline(
+            blending,
+            samples,
+            (depth, stencil),
+            desc.vertex_shader,
+            desc.fragment_shader,
+        )
+    }
+
+    /// Create a pipeline with its vertex and fragment shaders loaded from
+    /// files, to be reloaded with `Renderer::reload` as they change. See
+    /// `Watched`. Shaders without a file fall back to the pipeline's
+    /// built-in ones. The pipeline is created as with `Renderer::pipeline`.
+    #[cfg(feature = "hot-reload")]
+    pub fn watch_shaders<T, P>(
+        &self,
+        blending: Blending,
+        vertex: Option<P>,
+        fragment: Option<P>,
+    ) -> std::io::Result<Watched<T>>
+    where
+        T: AbstractPipeline<'static>,
+        P: AsRef<std::path::Path>,
+    {
+        self.watch_shaders_with(
+            blending,
+            1,
+            DepthTest::default(),
+            Stencil::default(),
+            vertex,
+            fragment,
+        )
+    }
+
+    /// Like `watch_shaders`, with the given sample count, depth test and
+    /// stencil operations, as with `Renderer::pipeline_with`. They are kept
+    /// when the pipeline is reloaded.
+    #[cfg(feature = "hot-reload")]
+    pub fn watch_shaders_with<T, P>(
+        &self,
+        blending: Blending,
+        samples: u32,
+        depth: DepthTest,
+        stencil: Stencil,
+        vertex: Option<P>,
+        fragment: Option<P>,
+    ) -> std::io::Result<Watched<T>>
+    where
+        T: AbstractPipeline<'static>,
+        P: AsRef<std::path::Path>,
+    {
+        let mut vertex = vertex.map(|p| WatchedShader::new(p.as_ref(), ShaderStage::Vertex));
+        let mut fragment = fragment.map(|p| WatchedShader::new(p.as_ref(), ShaderStage::Fragment));
+        let (vs, fs) = self.read_shaders::<T>(&mut vertex, &mut fragment)?;
+
+        Ok(Watched {
+            pipeline: self.build_pipeline(blending.clone(), samples, (depth, stencil), &vs, &fs),
+            blending,
+            samples,
+            depth,
+            stencil,
+            vertex,
+            fragment,
+        })
+    }
+
+    /// Reload the shaders of a watched pipeline if their files changed, and
+    /// rebuild it. Returns whether the pipeline was rebuilt, in which case
+    /// its bindings and uniforms must be recreated. If a shader can't be
+    /// read or compiled, the pipeline is left as is, and the error names the
+    /// shader's file.
+    #[cfg(feature = "hot-reload")]
+    pub fn reload<T>(&self, watched: &mut Watched<T>) -> std::io::Result<bool>
+    where
+        T: AbstractPipeline<'static>,
+    {
+        let stale = |s: &Option<WatchedShader>| s.as_ref().is_some_and(|s| s.is_stale());
+
+        if !stale(&watched.vertex) && !stale(&watched.fragment) {
+            return Ok(false);
+        }
+        let (vs, fs) = self.read_shaders::<T>(&mut watched.vertex, &mut watched.fragment)?;
+
+        watched.pipeline = self.build_pipeline(
+            watched.blending.clone(),
+            watched.samples,
+            (watched.depth, watched.stencil),
+            &vs,
+            &fs,
+        );
+        Ok(true)
+    }
+
+    /// Read the vertex and fragment shaders of a watched pipeline, falling
+    /// back to the built-in ones of `T` for shaders without a file.
+    #[cfg(feature = "hot-reload")]
+    fn read_shaders<T>(
+        &self,
+        vertex: &mut Option<WatchedShader>,
+        fragment: &mut Option<WatchedShader>,
+    ) -> std::io::Result<(Vec<u8>, Vec<u8>)>
+    where
+        T: AbstractPipeline<'static>,
+    {
+        let desc = T::description();
+        let vs = match vertex {
+            Some(s) => s.read(desc.vertex_shader, &self.device)?,
+            None => desc.vertex_shader.to_vec(),
+        };
+        let fs = match fragment {
+            Some(s) => s.read(desc.fragment_shader, &self.device)?,
+            None => desc.fragment_shader.to_vec(),
+        };
+        Ok((vs, fs))
+    }
+
+    /// Create a pipeline from GLSL sources, in place of the SPIR-V shaders of
+    /// its description. See `Device::compile_glsl`.
+    #[cfg(feature = "glslc")]
+    pub fn pipeline_from_glsl<T>(
+        &self,
+        blending: Blending,
+        vertex_shader: &str,
+        fragment_shader: &str,
+    ) -> std::io::Result<T>
+    where
+        T: AbstractPipeline<'static>,
+    {
+        let vs = self
+            .device
+            .compile_glsl(vertex_shader, ShaderStage::Vertex)?;
+        let fs = self
+            .device
+            .compile_glsl(fragment_shader, ShaderStage::Fragment)?;
+
+        Ok(self.build_pipeline(
+            blending,
+            1,
+            (DepthTest::default(), Stencil::default()),
+            &vs,
+            &fs,
+        ))
+    }
+
+    fn build_pipeline<T>(
+        &self,
+        blending: Blending,
+        samples: u32,
+        (depth, stencil): (DepthTest, Stencil),
+        vertex_shader: &[u8],
+        fragment_shader: &[u8],
+    ) -> T
+    where
+        T: AbstractPipeline<'static>,
+    {
+        let desc = T::description();
+        let pip_layout = self.device.create_pipeline_layout(desc.pipeline_layout);
+        let mut vertex_layout = VertexLayout::from(desc.vertex_layout);
+        vertex_layout.step_mode = T::step_mode();
+        let vs = self
+            .device
+            .create_shader("vertex shader", vertex_shader, ShaderStage::Vertex);
+        let fs =
+            self.device
+                .create_shader("fragment shader", fragment_shader, ShaderStage::Fragment);
+
+        let pipeline = self.device.create_pipeline(
+            pip_layout,
+            vertex_layout,
+            blending.clone(),
+            samples,
+            (depth, stencil),
+            (&vs, &fs),
+        );
+        trace!(Event::Pipeline {
+            id: pipeline.id,
+            layouts: pipeline.layout.sets.iter().map(|s| s.id).collect(),
+            vertex_layout: desc.vertex_layout.to_vec(),
+            step_mode: T::step_mode(),
+            blending,
+            samples,
+            depth,
+            stencil,
+            vertex_shader: vertex_shader.to_vec(),
+            fragment_shader: fragment_shader.to_vec(),
+        });
+        T::setup(pipeline, &self.device)
+    }
+
+    pub fn read<F>(&mut self, fb: &Framebuffer, f: F)
+    where
+        F: 'static + FnOnce(&[Bgra8]),
+    {
+        let mut encoder = self.device.create_command_encoder();
+
+        let bytesize = 4 * fb.size();
+        let dst = self.device.device.create_buffer(&wgpu::BufferDescriptor {
+            size: bytesize as u64,
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+        });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::TextureCopyView {
+                texture: &fb.texture.wgpu,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+            },
+            wgpu::BufferCopyView {
+                buffer: &dst,
+                offset: 0,
+                // TODO: Must be a multiple of 256
+                row_pitch: 4 * fb.texture.w,
+                image_height: fb.texture.h,
+            },
+            fb.texture.extent,
+        );
+        self.device.submit(&[encoder.finish()]);
+
+        let mut buffer: Vec<u8> = Vec::with_capacity(bytesize);
+
+        dst.map_read_async(
+            0,
+            bytesize as u64,
+            move |result: wgpu::BufferMapAsyncResult<&[u8]>| match result {
+                Ok(ref mapping) => {
+                    buffer.extend_from_slice(mapping.data);
+                    if buffer.len() == bytesize {
+                        let (head, body, tail) = unsafe { buffer.align_to::<Bgra8>() };
+                        if !(head.is_empty() && tail.is_empty()) {
+                            panic!("Renderer::read: framebuffer is not a valid Bgra8 buffer");
+                        }
+                        f(body);
+                    }
+                }
+                Err(ref err) => panic!("{:?}", err),
+            },
+        );
+    }
+
+    /// Read the color of a single pixel of a framebuffer, with the origin
+    /// at the top-left. Blocks until the pixel is read back from the GPU.
+    /// Returns `None` if the pixel is out of bounds, and `Error::ReadFailed`
+    /// if the pixel couldn't be mapped.
+    ///
+    /// Since framebuffers are stored in sRGB, the returned color is
+    /// sRGB-encoded, ie. as it appears on screen.
+    ///
+    /// Swap chains are out of scope: their textures can only be rendered
+    /// to, and are gone once presented. To pick colors from the screen,
+    /// render to a framebuffer, and draw it to the swap chain.
+    pub fn pick_color(&mut self, fb: &Framebuffer, x: u32, y: u32) -> Result<Option<Rgba8>, Error> {
+        if x >= fb.width() || y >= fb.height() {
+            return Ok(None);
+        }
+        let mut encoder = self.device.create_command_encoder();
+
+        // Rows of a buffer copy must be aligned to 256 bytes.
+        let row_pitch = 256;
+        let dst = self.device.device.create_buffer(&wgpu::BufferDescriptor {
+            size: row_pitch as u64,
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+        });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::TextureCopyView {
+                texture: &fb.texture.wgpu,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d {
+                    x: x as f32,
+                    y: y as f32,
+                    z: 0.0,
+                },
+            },
+            wgpu::BufferCopyView {
+                buffer: &dst,
+                offset: 0,
+                row_pitch,
+                image_height: 1,
+            },
+            wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth: 1,
+            },
+        );
+        self.device.submit(&[encoder.finish()]);
+
+        let pixel = Rc::new(Cell::new(Err(Error::ReadFailed)));
+        let result = pixel.clone();
+
+        dst.map_read_async(0, 4, move |result: wgpu::BufferMapAsyncResult<&[u8]>| {
+            // Framebuffers are stored as BGRA.
+            if let Ok(&[b, g, r, a]) = result.as_ref().map(|m| m.data) {
+                pixel.set(Ok(Some(Rgba8::new(r, g, b, a))));
+            }
+        });
+        self.device.device.poll(true);
+
+        result.get()
+    }
+
+    /// Read the pixels of a framebuffer, as rows from top to bottom. Blocks
+    /// until the pixels are read back from the GPU, and returns
+    /// `Error::ReadFailed` if they couldn't be mapped.
+    ///
+    /// Like `pick_color`, colors are sRGB-encoded, and swap chains are out
+    /// of scope: render to a framebuffer first, eg. to take a screenshot.
+    pub fn read_pixels(&mut self, fb: &Framebuffer) -> Result<Vec<Rgba8>, Error> {
+        let pixels = Rc::new(RefCell::new(Err(Error::ReadFailed)));
+        let result = pixels.clone();
+
+        self.read_pixels_async(fb, move |data| {
+            *pixels.borrow_mut() = data;
+        });
+        self.device.device.poll(true);
+
+        result.replace(Err(Error::ReadFailed))
+    }
+
+    /// Like `read_pixels`, but without blocking. The callback is invoked
+    /// with the pixels once they are mapped, or with `Error::ReadFailed`,
+    /// the next time the device is polled, eg. when the next frame is
+    /// presented.
+    pub fn read_pixels_async<F>(&mut self, fb: &Framebuffer, f: F)
+    where
+        F: 'static + FnOnce(Result<Vec<Rgba8>, Error>),
+    {
+        let (w, h) = (fb.width(), fb.height());
+        let mut encoder = self.device.create_command_encoder();
+
+        // Rows of a buffer copy must be aligned to 256 bytes.
+        let row_pitch = (4 * w).div_ceil(256) * 256;
+        let bytesize = (row_pitch * h) as u64;
+        let dst = self.device.device.create_buffer(&wgpu::BufferDescriptor {
+            size: bytesize,
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+        });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::TextureCopyView {
+                texture: &fb.texture.wgpu,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+            },
+            wgpu::BufferCopyView {
+                buffer: &dst,
+                offset: 0,
+                row_pitch,
+                image_height: h,
+            },
+            fb.texture.extent,
+        );
+        self.device.submit(&[encoder.finish()]);
+
+        dst.map_read_async(
+            0,
+            bytesize,
+            move |result: wgpu::BufferMapAsyncResult<&[u8]>| {
+                let pixels = result.map_err(|()| Error::ReadFailed).map(|mapping| {
+                    mapping
+                        .data
+                        .chunks(row_pitch as usize)
+                        .flat_map(|row| row[..4 * w as usize].chunks(4))
+                        // Framebuffers are stored as BGRA.
+                        .map(|p| Rgba8::new(p[2], p[1], p[0], p[3]))
+                        .collect()
+                });
+                f(pixels);
+            },
+        );
+    }
+
+    /// Read back the contents of a storage buffer, eg. written by a compute
+    /// pass, as 32-bit words. Like `read_pixels_async`, the callback is
+    /// invoked once the buffer is mapped, or with `Error::ReadFailed`.
+    pub fn read_storage_buffer_async<F>(&mut self, buf: &StorageBuffer, f: F)
+    where
+        F: 'static + FnOnce(Result<&[u32], Error>),
+    {
+        let bytesize = buf.size as wgpu::BufferAddress;
+        let mut encoder = self.device.create_command_encoder();
+        let dst = self.device.device.create_buffer(&wgpu::BufferDescriptor {
+            size: bytesize,
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+        });
+
+        encoder.copy_buffer_to_buffer(&buf.wgpu, 0, &dst, 0, bytesize);
+        self.device.submit(&[encoder.finish()]);
+
+        dst.map_read_async(
+            0,
+            bytesize,
+            move |result: wgpu::BufferMapAsyncResult<&[u32]>| {
+                f(result.map_err(|()| Error::ReadFailed).map(|m| m.data));
+            },
+        );
+    }
+
+    // MUTABLE API ////////////////////////////////////////////////////////////
+
+    /// Update the uniforms of a pipeline, eg. its projection. Kit pipelines
+    /// take a projection matrix, or a `kit::Camera2D`.
+    pub fn update_pipeline<'a, T, P>(&mut self, pip: &'a T, p: P, f: &mut Frame)
+    where
+        T: AbstractPipeline<'a>,
+        P: Into<T::PrepareContext>,
+    {
+        if let Some((buf, unifs)) = pip.prepare(p.into()) {
+            self.device
+                .update_uniform_buffer::<T::Uniforms>(unifs.as_slice(), buf, &mut f.encoder);
+        }
+    }
+
+    /// Start a new frame. If the swap chain was resized, the projection of
+    /// the tracked pipelines is updated first, so that it can still be
+    /// overridden with `Renderer::update_pipeline`.
+    pub fn frame(&mut self) -> Frame {
+        self.timing.started = Some(Instant::now());
+
+        let mut encoder = self.device.create_command_encoder();
+        self.update_projections(&mut encoder);
+
+        Frame::new(encoder)
+    }
+
+    fn update_projections(&self, encoder: &mut wgpu::CommandEncoder) {
+        let mut projections = self.projections.borrow_mut();
+        let Projections { size, buffers } = &mut *projections;
+        let size = match size {
+            Some(size) => *size,
+            None => return,
+        };
+        buffers.retain(|p| p.buffer.strong_count() > 0);
+
+        let bytesize = std::mem::size_of::<crate::math::Matrix4<f32>>() as wgpu::BufferAddress;
+        // Source buffers of each distinct projection, shared by pipelines
+        // with the same projection.
+        let mut sources: Vec<(crate::math::Matrix4<f32>, wgpu::Buffer)> = Vec::new();
+
+        for p in buffers.iter_mut() {
+            if p.size == Some(size) {
+                continue;
+            }
+            if let Some(dst) = p.buffer.upgrade() {
+                let matrix = (p.matrix)(size.0, size.1);
+                let ix = match sources.iter().position(|(m, _)| *m == matrix) {
+                    Some(ix) => ix,
+                    None => {
+                        let src = self
+                            .device
+                            .device
+                            .create_buffer_mapped(1, wgpu::BufferUsage::COPY_SRC)
+                            .fill_from_slice(&[matrix]);
+                        sources.push((matrix, src));
+                        sources.len() - 1
+                    }
+                };
+                encoder.copy_buffer_to_buffer(&sources[ix].1, 0, &dst, 0, bytesize);
+                trace!(Event::WriteBuffer {
+                    id: p.id,
+                    offset: 0,
+                    data: trace::bytes(&[matrix]),
+                });
+            }
+            p.size = Some(size);
+        }
+    }
+
+    /// Keep the projection of a pipeline up to date with the size of the
+    /// swap chain, starting with the next frame, so that it doesn't have to
+    /// be updated on every resize. The projection is given the size of the
+    /// swap chain, eg. `kit::ortho`, or a perspective or oblique projection.
+    /// Tracking a pipeline again replaces its projection.
+    ///
+    /// Pipelines drawing to framebuffers of another size shouldn't be
+    /// tracked, and are updated with `Renderer::update_pipeline` instead.
+    ///
+    /// ```ignore
+    /// r.track_projection(&sprites, kit::ortho);
+    /// r.track_projection(&shapes, |w, h| kit::perspective(w, h, 0.8));
+    /// ```
+    pub fn track_projection<'a, T, F>(&self, pipeline: &T, projection: F)
+    where
+        T: AbstractPipeline<'a>,
+        F: Fn(u32, u32) -> crate::math::Matrix4<f32> + 'static,
+    {
+        let buf = pipeline
+            .projection()
+            .expect("fatal: the pipeline has no projection to track");
+        let mut projections = self.projections.borrow_mut();
+        let target = Rc::downgrade(&buf.wgpu);
+        let matrix: ProjectionFn = Box::new(projection);
+
+        match projections
+            .buffers
+            .iter_mut()
+            .find(|p| p.buffer.ptr_eq(&target))
+        {
+            Some(p) => {
+                p.matrix = matrix;
+                p.size = None;
+            }
+            None => projections.buffers.push(Projection {
+                buffer: target,
+                size: None,
+                matrix,
+                #[cfg(feature = "trace")]
+                id: buf.id,
+            }),
+        }
+    }
+
+    /// Stop keeping the projection of a pipeline up to date, see
+    /// `Renderer::track_projection`.
+    pub fn untrack_projection<'a, T>(&self, pipeline: &T)
+    where
+        T: AbstractPipeline<'a>,
+    {
+        if let Some(buf) = pipeline.projection() {
+            let target = Rc::downgrade(&buf.wgpu);
+
+            self.projections
+                .borrow_mut()
+                .buffers
+                .retain(|p| !p.buffer.ptr_eq(&target));
+        }
+    }
+
+    pub fn present(&mut self, frame: Frame) {
+        let commands = frame.encoder.finish();
+        let encode = self
+            .timing
+            .started
+            .take()
+            .map_or(Duration::default(), |t| t.elapsed());
+
+        let submitted = Instant::now();
+        self.device.submit(&[commands]);
+
+        let gpu = if self.timing.profiling {
+            self.device.device.poll(true);
+            Some(submitted.elapsed())
+        } else {
+            None
+        };
+        self.timing.last = Timestamps { encode, gpu };
+    }
+
+    /// Enable or disable GPU frame timing. While enabled, `present` waits
+    /// for the GPU to complete each frame.
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.timing.profiling = enabled;
+    }
+
+    /// The timings of the last presented frame.
+    pub fn timestamps(&self) -> Timestamps {
+        self.timing.last
+    }
+
+    pub fn submit<T: Copy>(&mut self, commands: &[Op<T>]) {
+        let mut encoder = self.device.create_command_encoder();
+        for c in commands.iter() {
+            c.encode(&mut self.device, &mut encoder);
+        }
+        self.device.submit(&[encoder.finish()]);
+    }
+}
+
+pub enum Op<'a, T> {
+    Clear(&'a dyn Canvas<Color = T>, T),
+    Fill(&'a dyn Canvas<Color = T>, &'a [T]),
+    Transfer(&'a dyn Canvas<Color = T>, &'a [T], u32, u32, Rect<i32>),
+    Blit(&'a dyn Canvas<Color = T>, Rect<f32>, Rect<f32>),
+}
+
+impl<'a, T> Op<'a, T>
+where
+    T: Copy,
+{
+    fn encode(&self, dev: &mut Device, encoder: &mut wgpu::CommandEncoder) {
+        match *self {
+            Op::Clear(f, color) => {
+                f.clear(color, dev, encoder);
+            }
+            Op::Fill(f, buf) => {
+                f.fill(buf, dev, encoder);
+            }
+            Op::Transfer(f, buf, w, h, rect) => {
+                f.transfer(buf, w, h, rect, dev, encoder);
+            }
+            Op::Blit(f, src, dst) => {
+                f.blit(src, dst, encoder);
+            }
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// Device
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub struct Device {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    surface: Option<wgpu::Surface>,
+    garbage: Rc<RefCell<Garbage>>,
+}
+
+/// A GPU resource owned by a `Device`. When dropped, the resource is handed
+/// back to the device, which keeps it alive until the GPU has completed the
+/// commands submitted before it was dropped. This makes it safe to drop a
+/// resource mid-frame, while a pass recorded earlier still uses it.
+struct Deferred<T: 'static> {
+    resource: Option<T>,
+    garbage: Weak<RefCell<Garbage>>,
+}
+
+impl<T: 'static> std::ops::Deref for Deferred<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.resource
+            .as_ref()
+            .expect("fatal: resource used after being dropped")
+    }
+}
+
+impl<T: 'static> Drop for Deferred<T> {
+    fn drop(&mut self) {
+        if let (Some(resource), Some(garbage)) = (self.resource.take(), self.garbage.upgrade()) {
+            garbage.borrow_mut().dropped.push(Box::new(resource));
+        }
+    }
+}
+
+impl<T: fmt::Debug + 'static> fmt::Debug for Deferred<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.resource {
+            Some(resource) => resource.fmt(f),
+            None => write!(f, "<dropped>"),
+        }
+    }
+}
+
+/// Resources dropped while the GPU may still be using them.
+struct Garbage {
+    /// Resources dropped since the last submission.
+    dropped: Vec<Box<dyn Any>>,
+    /// Resources dropped before a submission which hasn't completed yet,
+    /// oldest first.
+    fences: VecDeque<Fence>,
+    /// Source of the copies which signal fences.
+    source: wgpu::Buffer,
+}
+
+/// A set of resources which can be destroyed once `buffer` is mapped, ie.
+/// once the GPU has completed the submission which copies into it.
+struct Fence {
+    buffer: wgpu::Buffer,
+    signaled: Rc<Cell<bool>>,
+    resources: Vec<Box<dyn Any>>,
+}
+
+impl Garbage {
+    /// Size of fence buffers.
+    const FENCE_SIZE: wgpu::BufferAddress = 4;
+
+    fn new(device: &wgpu::Device) -> Self {
+        Self {
+            dropped: Vec::new(),
+            fences: VecDeque::new(),
+            source: device
+                .create_buffer_mapped(1, wgpu::BufferUsage::COPY_SRC)
+                .fill_from_slice(&[0u32]),
+        }
+    }
+}
+
+impl fmt::Debug for Garbage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Garbage")
+            .field("dropped", &self.dropped.len())
+            .field("fences", &self.fences.len())
+            .finish()
+    }
+}
+
+impl Device {
+    pub fn new<W: HasRawWindowHandle>(adapter: &wgpu::Adapter, window: &W) -> Self {
+        let surface = wgpu::Surface::create(window);
+
+        Self {
+            surface: Some(surface),
+            ..Self::headless(adapter)
+        }
+    }
+
+    /// Create a device without a surface. Swap chains can't be created on
+    /// such a device: render to a `Framebuffer` instead.
+    pub fn headless(adapter: &wgpu::Adapter) -> Self {
+        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor {
+            extensions: wgpu::Extensions {
+                anisotropic_filtering: false,
+            },
+            limits: wgpu::Limits::default(),
+        });
+
+        let garbage = Rc::new(RefCell::new(Garbage::new(&device)));
+
+        Self {
+            device,
+            queue,
+            surface: None,
+            garbage,
+        }
+    }
+
+    pub fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    pub fn device_mut(&mut self) -> &mut wgpu::Device {
         &mut self.device
     }
 
-    pub fn create_command_encoder(&self) -> wgpu::CommandEncoder {
+    pub fn create_command_encoder(&self) -> wgpu::CommandEncoder {
+        self.device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { todo: 0 })
+    }
+
+    pub fn create_swap_chain(&self, w: u32, h: u32, mode: PresentMode) -> wgpu::SwapChain {
+        let desc = SwapChain::descriptor(w, h, mode);
+        let surface = self
+            .surface
+            .as_ref()
+            .expect("Device::create_swap_chain: headless devices have no surface");
+
+        self.device.create_swap_chain(surface, &desc)
+    }
+
+    pub fn create_pipeline_layout(&self, ss: &[Set]) -> PipelineLayout {
+        let mut sets = Vec::new();
+        for (i, s) in ss.iter().enumerate() {
+            sets.push(self.create_binding_group_layout(i as u32, s.0))
+        }
+        PipelineLayout { sets }
+    }
+
+    /// Compile a GLSL shader to SPIR-V, with the `glslc` compiler found in
+    /// the `PATH`. Compilation errors are returned with the compiler's
+    /// diagnostics.
+    #[cfg(feature = "glslc")]
+    pub fn compile_glsl(&self, source: &str, stage: ShaderStage) -> std::io::Result<Vec<u8>> {
+        use std::io::{Error, ErrorKind, Write};
+        use std::process::{Command, Stdio};
+
+        let stage = match stage {
+            ShaderStage::Vertex => "vertex",
+            ShaderStage::Fragment => "fragment",
+            ShaderStage::Compute => "compute",
+        };
+        let mut child = Command::new("glslc")
+            .arg("--target-env=vulkan")
+            .arg(format!("-fshader-stage={}", stage))
+            .args(["-", "-o", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(source.as_bytes())?;
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                String::from_utf8_lossy(&output.stderr).into_owned(),
+            ));
+        }
+        Ok(output.stdout)
+    }
+
+    /// Compile a GLSL shader and create a shader module from it. See
+    /// `Device::compile_glsl`.
+    #[cfg(feature = "glslc")]
+    pub fn create_shader_from_glsl(
+        &self,
+        source: &str,
+        stage: ShaderStage,
+    ) -> std::io::Result<Shader> {
+        let spv = self.compile_glsl(source, stage)?;
+        let spv = wgpu::read_spirv(std::io::Cursor::new(&spv))?;
+
+        Ok(Shader {
+            module: self.device.create_shader_module(spv.as_slice()),
+        })
+    }
+
+    pub fn create_shader(&self, _name: &str, source: &[u8], _stage: ShaderStage) -> Shader {
+        let buf = std::io::Cursor::new(source);
+        let spv = wgpu::read_spirv(buf).unwrap();
+
+        Shader {
+            module: self.device.create_shader_module(spv.as_slice()),
+        }
+    }
+
+    pub fn create_encoder(&self) -> wgpu::CommandEncoder {
         self.device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor { todo: 0 })
     }
 
-    pub fn create_swap_chain(&self, w: u32, h: u32, mode: PresentMode) -> wgpu::SwapChain {
-        let desc = SwapChain::descriptor(w, h, mode);
-        self.device.create_swap_chain(&self.surface, &desc)
+    pub fn create_texture(&self, w: u32, h: u32) -> Texture {
+        self.create_texture_with_format(w, h, TextureFormat::default())
+    }
+
+    /// Create a texture with the given texel format. Texels can be uploaded
+    /// with `Texture::upload`.
+    pub fn create_texture_with_format(&self, w: u32, h: u32, format: TextureFormat) -> Texture {
+        self.create_texture_with_levels(w, h, format, 1)
+    }
+
+    /// Create a texture with a full mip chain, to be generated with
+    /// `Texture::generate_mipmaps` once its texels are uploaded.
+    pub fn create_texture_with_mipmaps(&self, w: u32, h: u32, format: TextureFormat) -> Texture {
+        let levels = 32 - w.max(h).max(1).leading_zeros();
+        self.create_texture_with_levels(w, h, format, levels)
+    }
+
+    /// Create a texture which can be bound as a storage texture, to be
+    /// written by compute shaders, as well as sampled. The format must
+    /// support storage, which `TextureFormat::Rgba8Srgb` doesn't.
+    pub fn create_storage_texture(&self, w: u32, h: u32, format: TextureFormat) -> Texture {
+        self.create_texture_with_usage(w, h, format, 1, wgpu::TextureUsage::STORAGE)
+    }
+
+    /// Create a backdrop of the given size, which is the largest region it
+    /// can hold.
+    pub fn create_backdrop(&self, w: u32, h: u32) -> Backdrop {
+        let format = SwapChain::FORMAT;
+        let extent = wgpu::Extent3d {
+            width: w,
+            height: h,
+            depth: 1,
+        };
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            size: extent,
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+        });
+        let view = texture.create_default_view();
+
+        Backdrop {
+            texture: Texture {
+                wgpu: self.defer(texture),
+                view: self.defer(view),
+                extent,
+                format,
+                mipmaps: None,
+                #[cfg(feature = "trace")]
+                id: trace::id(),
+                w,
+                h,
+            },
+            region: Rect::new(0, 0, 0, 0),
+        }
+    }
+
+    pub fn create_framebuffer(&self, w: u32, h: u32) -> Framebuffer {
+        self.create_framebuffer_msaa(w, h, 1)
+    }
+
+    pub fn create_framebuffer_msaa(&self, w: u32, h: u32, samples: u32) -> Framebuffer {
+        let format = SwapChain::FORMAT;
+        let extent = wgpu::Extent3d {
+            width: w,
+            height: h,
+            depth: 1,
+        };
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            size: extent,
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::SAMPLED
+                | wgpu::TextureUsage::COPY_DST
+                | wgpu::TextureUsage::COPY_SRC
+                | wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+        });
+        let view = texture.create_default_view();
+
+        let texture = Texture {
+            wgpu: self.defer(texture),
+            view: self.defer(view),
+            extent,
+            format,
+            mipmaps: None,
+            #[cfg(feature = "trace")]
+            id: trace::id(),
+            w,
+            h,
+        };
+        trace!(Event::Framebuffer {
+            id: texture.id,
+            w,
+            h,
+            samples
+        });
+
+        Framebuffer {
+            texture,
+            depth: self.create_zbuffer_samples(w, h, samples),
+            msaa: if samples > 1 {
+                Some(self.create_msaa_texture(w, h, format, samples))
+            } else {
+                None
+            },
+        }
+    }
+
+    pub fn create_zbuffer(&self, w: u32, h: u32) -> ZBuffer {
+        self.create_zbuffer_samples(w, h, 1)
+    }
+
+    pub fn create_binding_group(
+        &self,
+        layout: &BindingGroupLayout,
+        binds: &[&dyn Bind],
+    ) -> BindingGroup {
+        assert_eq!(
+            binds.len(),
+            layout.size,
+            "layout slot count does not match bindings"
+        );
+
+        let mut bindings = Vec::new();
+
+        for (i, b) in binds.iter().enumerate() {
+            bindings.push(b.binding(i as u32));
+        }
+
+        let group = BindingGroup::new(
+            layout,
+            self.defer(self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &layout.wgpu,
+                bindings: bindings.as_slice(),
+            })),
+        );
+        trace!(Event::BindingGroup {
+            id: group.id,
+            layout: layout.id,
+            resources: binds.iter().map(|b| b.trace_id()).collect(),
+        });
+        group
+    }
+
+    pub fn create_buffer<T>(&self, vertices: &[T]) -> VertexBuffer
+    where
+        T: 'static + Copy,
+    {
+        let buf = VertexBuffer {
+            wgpu: self.defer(
+                self.device
+                    .create_buffer_mapped(vertices.len(), wgpu::BufferUsage::VERTEX)
+                    .fill_from_slice(vertices),
+            ),
+            size: vertices.len() as u32,
+            #[cfg(feature = "trace")]
+            id: trace::id(),
+            #[cfg(feature = "validation")]
+            stride: std::mem::size_of::<T>(),
+        };
+        trace!(Event::VertexBuffer {
+            id: buf.id,
+            data: trace::bytes(vertices),
+        });
+        buf
+    }
+
+    pub fn create_dynamic_buffer<T>(&self, vertices: &[T]) -> DynamicVertexBuffer
+    where
+        T: 'static + Copy,
+    {
+        self.create_dynamic_buffer_with_capacity(vertices, vertices.len() as u32)
+    }
+
+    pub fn create_uniform_buffer<T>(&self, buf: &[T]) -> UniformBuffer
+    where
+        T: 'static + Copy,
+    {
+        self.create_uniform_buffer_sized(buf, std::mem::size_of::<T>(), buf.len())
+    }
+
+    /// Create a uniform buffer holding an array, bound as a whole, eg. to be
+    /// indexed per object by a shader. Elements are laid out with the array
+    /// stride of uniform blocks, so their size must be a multiple of 16 bytes.
+    pub fn create_uniform_array<T>(&self, buf: &[T]) -> UniformBuffer
+    where
+        T: 'static + Copy,
+    {
+        assert!(
+            std::mem::size_of::<T>() % 16 == 0,
+            "fatal: uniform array elements must be a multiple of 16 bytes"
+        );
+        self.create_uniform_buffer_sized(buf, std::mem::size_of_val(buf), 1)
+    }
+
+    fn create_uniform_buffer_sized<T>(&self, buf: &[T], size: usize, count: usize) -> UniformBuffer
+    where
+        T: 'static + Copy,
+    {
+        let uniforms = UniformBuffer {
+            size,
+            count,
+            #[cfg(feature = "trace")]
+            id: trace::id(),
+
+            wgpu: Rc::new(
+                self.defer(
+                    self.device
+                        .create_buffer_mapped::<T>(
+                            buf.len(),
+                            wgpu::BufferUsage::UNIFORM
+                                | wgpu::BufferUsage::COPY_DST
+                                | wgpu::BufferUsage::COPY_SRC,
+                        )
+                        .fill_from_slice(buf),
+                ),
+            ),
+        };
+        trace!(Event::UniformBuffer {
+            id: uniforms.id,
+            size: uniforms.size,
+            data: trace::bytes(buf),
+        });
+        uniforms
+    }
+
+    /// Create a storage buffer holding an array, eg. of particles to be
+    /// simulated in a compute pass. Elements are laid out with the array
+    /// stride of the `std430` layout.
+    pub fn create_storage_buffer<T>(&self, buf: &[T]) -> StorageBuffer
+    where
+        T: 'static + Copy,
+    {
+        let usage = wgpu::BufferUsage::STORAGE
+            | wgpu::BufferUsage::VERTEX
+            | wgpu::BufferUsage::COPY_DST
+            | wgpu::BufferUsage::COPY_SRC;
+        // Empty buffers can't be bound, so they are given a single word.
+        let size = std::mem::size_of_val(buf).max(4);
+        let wgpu = if buf.is_empty() {
+            self.device.create_buffer(&wgpu::BufferDescriptor {
+                size: size as wgpu::BufferAddress,
+                usage,
+            })
+        } else {
+            self.device
+                .create_buffer_mapped::<T>(buf.len(), usage)
+                .fill_from_slice(buf)
+        };
+
+        let storage = StorageBuffer {
+            wgpu: self.defer(wgpu),
+            size,
+            #[cfg(feature = "trace")]
+            id: trace::id(),
+            #[cfg(feature = "validation")]
+            stride: std::mem::size_of::<T>(),
+        };
+        // Traced as a vertex buffer, for it to be drawn when replayed. What
+        // compute passes write to it isn't recorded.
+        trace!(Event::VertexBuffer {
+            id: storage.id,
+            data: {
+                let mut data = trace::bytes(buf);
+                data.resize(size, 0);
+                data
+            },
+        });
+        storage
+    }
+
+    /// Create an indirect buffer of `capacity` vertices, to be drawn with
+    /// the given vertices and instances until its arguments are written.
+    pub fn create_indirect_buffer<T>(
+        &self,
+        capacity: u32,
+        vertices: Range<u32>,
+        instances: Range<u32>,
+    ) -> IndirectBuffer
+    where
+        T: 'static + Copy,
+    {
+        let bytesize = (std::mem::size_of::<T>() * capacity.max(1) as usize) as wgpu::BufferAddress;
+        let args = [
+            vertices.end - vertices.start,
+            instances.end - instances.start,
+            vertices.start,
+            instances.start,
+        ];
+
+        IndirectBuffer {
+            capacity,
+            vertices: StorageBuffer {
+                wgpu: self.defer(self.device.create_buffer(&wgpu::BufferDescriptor {
+                    size: bytesize,
+                    usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::STORAGE,
+                })),
+                size: bytesize as usize,
+                #[cfg(feature = "trace")]
+                id: 0,
+                #[cfg(feature = "validation")]
+                stride: std::mem::size_of::<T>(),
+            },
+            args: StorageBuffer {
+                wgpu: self.defer(
+                    self.device
+                        .create_buffer_mapped(
+                            args.len(),
+                            wgpu::BufferUsage::INDIRECT
+                                | wgpu::BufferUsage::STORAGE
+                                | wgpu::BufferUsage::COPY_DST,
+                        )
+                        .fill_from_slice(&args),
+                ),
+                size: IndirectBuffer::ARGS_SIZE as usize,
+                #[cfg(feature = "trace")]
+                id: 0,
+                #[cfg(feature = "validation")]
+                stride: 0,
+            },
+            initial: self.defer(
+                self.device
+                    .create_buffer_mapped(args.len(), wgpu::BufferUsage::COPY_SRC)
+                    .fill_from_slice(&args),
+            ),
+        }
+    }
+
+    pub fn create_index_buffer(&self, indices: &[u32]) -> IndexBuffer {
+        let index_buf = self
+            .device
+            .create_buffer_mapped(indices.len(), wgpu::BufferUsage::INDEX)
+            .fill_from_slice(indices);
+        let buf = IndexBuffer {
+            size: indices.len() as u32,
+            wgpu: self.defer(index_buf),
+            #[cfg(feature = "trace")]
+            id: trace::id(),
+        };
+        trace!(Event::IndexBuffer {
+            id: buf.id,
+            data: indices.to_vec(),
+        });
+        buf
+    }
+
+    /// Create an index buffer from 16-bit indices. Index buffers hold 32-bit
+    /// indices, so these are widened.
+    #[deprecated(note = "use `create_index_buffer`, with `u32` indices")]
+    pub fn create_index(&self, indices: &[u16]) -> IndexBuffer {
+        let indices = indices.iter().map(|&i| i as u32).collect::<Vec<_>>();
+        self.create_index_buffer(&indices)
+    }
+
+    pub fn create_ring_buffer(&self, capacity: u64) -> RingBuffer {
+        // Empty buffers aren't valid, and writes are aligned to four bytes.
+        let capacity = capacity.max(RingBuffer::ALIGNMENT);
+        let ring = RingBuffer {
+            wgpu: self.defer(self.device.create_buffer(&wgpu::BufferDescriptor {
+                size: capacity,
+                usage: wgpu::BufferUsage::VERTEX
+                    | wgpu::BufferUsage::INDEX
+                    | wgpu::BufferUsage::COPY_DST,
+            })),
+            capacity,
+            head: 0,
+            used: 0,
+            staged: Vec::new(),
+            regions: Vec::new(),
+            #[cfg(feature = "trace")]
+            id: trace::id(),
+        };
+        trace!(Event::RingBuffer {
+            id: ring.id,
+            capacity,
+        });
+        ring
+    }
+
+    pub fn create_sampler(&self, min_filter: Filter, mag_filter: Filter) -> Sampler {
+        self.create_sampler_with_mipmaps(min_filter, mag_filter, Filter::Nearest)
+    }
+
+    /// Create a sampler which filters between mip levels with `mipmap_filter`.
+    /// Trilinear filtering uses `Filter::Linear` for all three filters.
+    pub fn create_sampler_with_mipmaps(
+        &self,
+        min_filter: Filter,
+        mag_filter: Filter,
+        mipmap_filter: Filter,
+    ) -> Sampler {
+        self.create_sampler_with(SamplerDescriptor {
+            min_filter,
+            mag_filter,
+            mipmap_filter,
+            ..SamplerDescriptor::default()
+        })
+    }
+
+    pub fn create_sampler_with(&self, desc: SamplerDescriptor) -> Sampler {
+        let sampler = Sampler {
+            wgpu: self.defer(self.device.create_sampler(&wgpu::SamplerDescriptor {
+                address_mode_u: desc.address_mode_u.to_wgpu(),
+                address_mode_v: desc.address_mode_v.to_wgpu(),
+                address_mode_w: wgpu::AddressMode::Repeat,
+                mag_filter: desc.mag_filter.to_wgpu(),
+                min_filter: desc.min_filter.to_wgpu(),
+                mipmap_filter: desc.mipmap_filter.to_wgpu(),
+                lod_min_clamp: -100.0,
+                lod_max_clamp: 100.0,
+                compare_function: wgpu::CompareFunction::Always,
+            })),
+            #[cfg(feature = "trace")]
+            id: trace::id(),
+        };
+        trace!(Event::Sampler {
+            id: sampler.id,
+            desc
+        });
+        sampler
+    }
+
+    pub fn create_binding_group_layout(&self, index: u32, slots: &[Binding]) -> BindingGroupLayout {
+        let mut bindings = Vec::new();
+
+        for s in slots {
+            bindings.push(wgpu::BindGroupLayoutBinding {
+                binding: bindings.len() as u32,
+                visibility: s.stage.to_wgpu(),
+                ty: s.binding.to_wgpu(),
+            });
+        }
+        let layout = self
+            .device
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                bindings: bindings.as_slice(),
+            });
+        let layout = BindingGroupLayout::new(index, layout, slots);
+        trace!(Event::Layout {
+            id: layout.id,
+            set: index,
+            bindings: slots.iter().map(|s| (s.binding, s.stage)).collect(),
+        });
+        layout
+    }
+
+    pub fn update_uniform_buffer<T: Copy + 'static>(
+        &self,
+        slice: &[T],
+        buf: &UniformBuffer,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let src = self
+            .device
+            .create_buffer_mapped::<T>(
+                slice.len(),
+                wgpu::BufferUsage::UNIFORM
+                    | wgpu::BufferUsage::COPY_SRC
+                    | wgpu::BufferUsage::MAP_WRITE,
+            )
+            .fill_from_slice(slice);
+        trace!(Event::WriteBuffer {
+            id: buf.id,
+            offset: 0,
+            data: trace::bytes(slice),
+        });
+
+        encoder.copy_buffer_to_buffer(
+            &src,
+            0,
+            &buf.wgpu,
+            0,
+            (std::mem::size_of::<T>() * slice.len()) as wgpu::BufferAddress,
+        );
+    }
+
+    /// Replace the contents of a dynamic vertex buffer. If the vertices don't
+    /// fit, the buffer is re-allocated with at least twice its capacity.
+    pub fn update_dynamic_buffer<T: Copy + 'static>(
+        &self,
+        slice: &[T],
+        buf: &mut DynamicVertexBuffer,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        self.update_dynamic_buffer_from(slice, 0, buf, encoder);
+    }
+
+    /// Replace the contents of a dynamic vertex buffer, only copying the
+    /// vertices starting at `from`, since the ones before it are unchanged.
+    /// If the vertices don't fit, the buffer is re-allocated with at least
+    /// twice its capacity, and all of them are copied.
+    pub fn update_dynamic_buffer_from<T: Copy + 'static>(
+        &self,
+        slice: &[T],
+        from: usize,
+        buf: &mut DynamicVertexBuffer,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let len = slice.len() as u32;
+
+        if len > buf.capacity {
+            *buf = self.create_dynamic_buffer_with_capacity(slice, len.max(buf.capacity * 2));
+            return;
+        }
+        buf.buffer.size = len;
+
+        let changed = &slice[from.min(slice.len())..];
+        if changed.is_empty() {
+            return;
+        }
+        let offset = (from * std::mem::size_of::<T>()) as wgpu::BufferAddress;
+        let src = self
+            .device
+            .create_buffer_mapped::<T>(changed.len(), wgpu::BufferUsage::COPY_SRC)
+            .fill_from_slice(changed);
+        trace!(Event::WriteBuffer {
+            id: buf.buffer.id,
+            offset,
+            data: trace::bytes(changed),
+        });
+
+        encoder.copy_buffer_to_buffer(
+            &src,
+            0,
+            &buf.buffer.wgpu,
+            offset,
+            std::mem::size_of_val(changed) as wgpu::BufferAddress,
+        );
+    }
+
+    // MUTABLE API ////////////////////////////////////////////////////////////
+
+    /// Submit commands to the GPU, and destroy the resources dropped before
+    /// earlier submissions which have since completed.
+    pub fn submit(&mut self, cmds: &[wgpu::CommandBuffer]) {
+        trace!(Event::Submit);
+        self.queue.submit(cmds);
+
+        if !self.garbage.borrow().dropped.is_empty() {
+            self.fence();
+        }
+        self.collect();
     }
 
-    pub fn create_pipeline_layout(&self, ss: &[Set]) -> PipelineLayout {
-        let mut sets = Vec::new();
-        for (i, s) in ss.iter().enumerate() {
-            sets.push(self.create_binding_group_layout(i as u32, s.0))
+    // PRIVATE API ////////////////////////////////////////////////////////////
+
+    /// Wrap a resource to defer its destruction when dropped.
+    fn defer<T: 'static>(&self, resource: T) -> Deferred<T> {
+        Deferred {
+            resource: Some(resource),
+            garbage: Rc::downgrade(&self.garbage),
         }
-        PipelineLayout { sets }
     }
 
-    pub fn create_shader(&self, _name: &str, source: &[u8], _stage: ShaderStage) -> Shader {
-        let buf = std::io::Cursor::new(source);
-        let spv = wgpu::read_spirv(buf).unwrap();
+    /// Submit a fence after the last submission, guarding the resources
+    /// dropped until now.
+    fn fence(&mut self) {
+        let mut garbage = self.garbage.borrow_mut();
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            size: Garbage::FENCE_SIZE,
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+        });
+        let mut encoder = self.create_command_encoder();
+        encoder.copy_buffer_to_buffer(&garbage.source, 0, &buffer, 0, Garbage::FENCE_SIZE);
+        self.queue.submit(&[encoder.finish()]);
+
+        let signaled = Rc::new(Cell::new(false));
+        buffer.map_read_async(0, Garbage::FENCE_SIZE, {
+            let signaled = signaled.clone();
+            move |_: wgpu::BufferMapAsyncResult<&[u8]>| signaled.set(true)
+        });
+        let resources = std::mem::take(&mut garbage.dropped);
 
-        Shader {
-            module: self.device.create_shader_module(spv.as_slice()),
+        garbage.fences.push_back(Fence {
+            buffer,
+            signaled,
+            resources,
+        });
+    }
+
+    /// Destroy the resources guarded by fences which have been signaled.
+    fn collect(&self) {
+        self.device.poll(false);
+
+        let mut resources = Vec::new();
+        {
+            let mut garbage = self.garbage.borrow_mut();
+            while garbage.fences.front().is_some_and(|f| f.signaled.get()) {
+                if let Some(fence) = garbage.fences.pop_front() {
+                    fence.buffer.unmap();
+                    resources.extend(fence.resources);
+                }
+            }
         }
+        // Resources are destroyed here, once the garbage is no longer borrowed.
+        drop(resources);
     }
 
-    pub fn create_encoder(&self) -> wgpu::CommandEncoder {
-        self.device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor { todo: 0 })
+    fn create_texture_with_levels(
+        &self,
+        w: u32,
+        h: u32,
+        format: TextureFormat,
+        levels: u32,
+    ) -> Texture {
+        self.create_texture_with_usage(w, h, format, levels, wgpu::TextureUsage::empty())
     }
 
-    pub fn create_texture(&self, w: u32, h: u32) -> Texture {
-        let format = Texture::COLOR_FORMAT;
+    fn create_texture_with_usage(
+        &self,
+        w: u32,
+        h: u32,
+        format: TextureFormat,
+        levels: u32,
+        usage: wgpu::TextureUsage,
+    ) -> Texture {
+        let wgpu_format = format.to_wgpu();
         let texture_extent = wgpu::Extent3d {
             width: w,
             height: h,
             depth: 1,
         };
+        let mut usage = usage | wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST;
+        if levels > 1 {
+            usage |= wgpu::TextureUsage::OUTPUT_ATTACHMENT;
+        }
         let texture = self.device.create_texture(&wgpu::TextureDescriptor {
             size: texture_extent,
             array_layer_count: 1,
-            mip_level_count: 1,
+            mip_level_count: levels,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format,
-            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+            format: wgpu_format,
+            usage,
         });
         let texture_view = texture.create_default_view();
+        let mipmaps = if levels > 1 {
+            Some(self.create_mipmaps(&texture, wgpu_format, levels))
+        } else {
+            None
+        };
 
-        Texture {
-            wgpu: texture,
-            view: texture_view,
+        let texture = Texture {
+            wgpu: self.defer(texture),
+            view: self.defer(texture_view),
             extent: texture_extent,
-            format,
+            format: wgpu_format,
+            mipmaps: mipmaps.map(|m| self.defer(m)),
+            #[cfg(feature = "trace")]
+            id: trace::id(),
+            w,
+            h,
+        };
+        trace!(Event::Texture {
+            id: texture.id,
             w,
             h,
+            format,
+            levels,
+        });
+        texture
+    }
+
+    fn create_mipmaps(
+        &self,
+        texture: &wgpu::Texture,
+        format: wgpu::TextureFormat,
+        levels: u32,
+    ) -> Mipmaps {
+        let views = (0..levels)
+            .map(|level| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    format,
+                    dimension: wgpu::TextureViewDimension::D2,
+                    aspect: wgpu::TextureAspect::All,
+                    base_mip_level: level,
+                    level_count: 1,
+                    base_array_layer: 0,
+                    array_layer_count: 1,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let layout = self
+            .device
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                bindings: &[
+                    wgpu::BindGroupLayoutBinding {
+                        binding: 0,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: BindingType::SampledTexture.to_wgpu(),
+                    },
+                    wgpu::BindGroupLayoutBinding {
+                        binding: 1,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: BindingType::Sampler.to_wgpu(),
+                    },
+                ],
+            });
+        let sampler = self.create_sampler(Filter::Linear, Filter::Linear);
+        let bindings = views[..views.len() - 1]
+            .iter()
+            .map(|view| {
+                self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    layout: &layout,
+                    bindings: &[
+                        wgpu::Binding {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(view),
+                        },
+                        sampler.binding(1),
+                    ],
+                })
+            })
+            .collect();
+
+        let vs = self.create_shader(
+            "mipmap.vert",
+            include_bytes!("data/mipmap.vert.spv"),
+            ShaderStage::Vertex,
+        );
+        let fs = self.create_shader(
+            "mipmap.frag",
+            include_bytes!("data/mipmap.frag.spv"),
+            ShaderStage::Fragment,
+        );
+        let pipeline_layout = self
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                bind_group_layouts: &[&layout],
+            });
+        let pipeline = self
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                layout: &pipeline_layout,
+                vertex_stage: wgpu::ProgrammableStageDescriptor {
+                    module: &vs.module,
+                    entry_point: "main",
+                },
+                fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
+                    module: &fs.module,
+                    entry_point: "main",
+                }),
+                rasterization_state: Some(wgpu::RasterizationStateDescriptor {
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: wgpu::CullMode::None,
+                    depth_bias: 0,
+                    depth_bias_slope_scale: 0.0,
+                    depth_bias_clamp: 0.0,
+                }),
+                primitive_topology: wgpu::PrimitiveTopology::TriangleList,
+                color_states: &[wgpu::ColorStateDescriptor {
+                    format,
+                    color_blend: wgpu::BlendDescriptor::REPLACE,
+                    alpha_blend: wgpu::BlendDescriptor::REPLACE,
+                    write_mask: wgpu::ColorWrite::ALL,
+                }],
+                depth_stencil_state: None,
+                index_format: wgpu::IndexFormat::Uint32,
+                vertex_buffers: &[],
+                sample_count: 1,
+                sample_mask: !0,
+                alpha_to_coverage_enabled: false,
+            });
+
+        Mipmaps {
+            pipeline,
+            views,
+            bindings,
         }
     }
 
-    pub fn create_framebuffer(&self, w: u32, h: u32) -> Framebuffer {
-        let format = SwapChain::FORMAT;
+    fn create_dynamic_buffer_with_capacity<T>(
+        &self,
+        vertices: &[T],
+        capacity: u32,
+    ) -> DynamicVertexBuffer
+    where
+        T: 'static + Copy,
+    {
+        // Empty buffers aren't valid, so always allocate at least one vertex.
+        let capacity = capacity.max(1);
+        let mapped = self.device.create_buffer_mapped(
+            capacity as usize,
+            wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+        );
+        mapped.data[..vertices.len()].copy_from_slice(vertices);
+
+        let buffer = VertexBuffer {
+            wgpu: self.defer(mapped.finish()),
+            size: vertices.len() as u32,
+            #[cfg(feature = "trace")]
+            id: trace::id(),
+            #[cfg(feature = "validation")]
+            stride: std::mem::size_of::<T>(),
+        };
+        trace!(Event::VertexBuffer {
+            id: buffer.id,
+            data: {
+                let mut data = trace::bytes(vertices);
+                data.resize(capacity as usize * std::mem::size_of::<T>(), 0);
+                data
+            },
+        });
+
+        DynamicVertexBuffer { buffer, capacity }
+    }
+
+    fn create_zbuffer_samples(&self, w: u32, h: u32, samples: u32) -> ZBuffer {
+        let format = ZBuffer::FORMAT;
         let extent = wgpu::Extent3d {
             width: w,
             height: h,
             depth: 1,
         };
-        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+        let wgpu = self.device.create_texture(&wgpu::TextureDescriptor {
             size: extent,
             array_layer_count: 1,
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count: samples,
             dimension: wgpu::TextureDimension::D2,
             format,
-            usage: wgpu::TextureUsage::SAMPLED
-                | wgpu::TextureUsage::COPY_DST
-                | wgpu::TextureUsage::COPY_SRC
-                | wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+            usage: wgpu::TextureUsage::COPY_DST | wgpu::TextureUsage::OUTPUT_ATTACHMENT,
         });
-        let view = texture.create_default_view();
+        let view = wgpu.create_default_view();
 
-        Framebuffer {
+        ZBuffer {
             texture: Texture {
-                wgpu: texture,
-                view,
+                wgpu: self.defer(wgpu),
                 extent,
+                view: self.defer(view),
                 format,
+                mipmaps: None,
+                #[cfg(feature = "trace")]
+                id: trace::id(),
                 w,
                 h,
             },
-            depth: self.create_zbuffer(w, h),
         }
     }
 
-    pub fn create_zbuffer(&self, w: u32, h: u32) -> ZBuffer {
-        let format = ZBuffer::FORMAT;
+    fn create_msaa_texture(
+        &self,
+        w: u32,
+        h: u32,
+        format: wgpu::TextureFormat,
+        samples: u32,
+    ) -> Texture {
         let extent = wgpu::Extent3d {
             width: w,
             height: h,
@@ -1546,165 +4953,65 @@ impl Device {
             size: extent,
             array_layer_count: 1,
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count: samples,
             dimension: wgpu::TextureDimension::D2,
             format,
-            usage: wgpu::TextureUsage::COPY_DST | wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
         });
         let view = wgpu.create_default_view();
 
-        ZBuffer {
-            texture: Texture {
-                wgpu,
-                extent,
-                view,
-                format,
-                w,
-                h,
-            },
+        Texture {
+            wgpu: self.defer(wgpu),
+            extent,
+            view: self.defer(view),
+            format,
+            mipmaps: None,
+            #[cfg(feature = "trace")]
+            id: trace::id(),
+            w,
+            h,
         }
     }
 
-    pub fn create_binding_group(
+    pub fn create_compute_pipeline(
         &self,
-        layout: &BindingGroupLayout,
-        binds: &[&dyn Bind],
-    ) -> BindingGroup {
-        assert_eq!(
-            binds.len(),
-            layout.size,
-            "layout slot count does not match bindings"
-        );
-
-        let mut bindings = Vec::new();
-
-        for (i, b) in binds.iter().enumerate() {
-            bindings.push(b.binding(i as u32));
-        }
-
-        BindingGroup::new(
-            layout.set_index,
-            self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-                layout: &layout.wgpu,
-                bindings: bindings.as_slice(),
-            }),
-        )
-    }
-
-    pub fn create_buffer<T>(&self, vertices: &[T]) -> VertexBuffer
-    where
-        T: 'static + Copy,
-    {
-        VertexBuffer {
-            wgpu: self
-                .device
-                .create_buffer_mapped(vertices.len(), wgpu::BufferUsage::VERTEX)
-                .fill_from_slice(vertices),
-            size: vertices.len() as u32,
-        }
-    }
-
-    pub fn create_uniform_buffer<T>(&self, buf: &[T]) -> UniformBuffer
-    where
-        T: 'static + Copy,
-    {
-        UniformBuffer {
-            size: std::mem::size_of::<T>(),
-            count: buf.len(),
-            wgpu: self
-                .device
-                .create_buffer_mapped::<T>(
-                    buf.len(),
-                    wgpu::BufferUsage::UNIFORM
-                        | wgpu::BufferUsage::COPY_DST
-                        | wgpu::BufferUsage::COPY_SRC,
-                )
-                .fill_from_slice(buf),
-        }
-    }
-
-    pub fn create_index(&self, indices: &[u16]) -> IndexBuffer {
-        let index_buf = self
-            .device
-            .create_buffer_mapped(indices.len(), wgpu::BufferUsage::INDEX)
-            .fill_from_slice(indices);
-        IndexBuffer { wgpu: index_buf }
-    }
-
-    pub fn create_sampler(&self, min_filter: Filter, mag_filter: Filter) -> Sampler {
-        Sampler {
-            wgpu: self.device.create_sampler(&wgpu::SamplerDescriptor {
-                address_mode_u: wgpu::AddressMode::Repeat,
-                address_mode_v: wgpu::AddressMode::Repeat,
-                address_mode_w: wgpu::AddressMode::Repeat,
-                mag_filter: mag_filter.to_wgpu(),
-                min_filter: min_filter.to_wgpu(),
-                mipmap_filter: wgpu::FilterMode::Nearest,
-                lod_min_clamp: -100.0,
-                lod_max_clamp: 100.0,
-                compare_function: wgpu::CompareFunction::Always,
-            }),
-        }
-    }
-
-    pub fn create_binding_group_layout(&self, index: u32, slots: &[Binding]) -> BindingGroupLayout {
-        let mut bindings = Vec::new();
-
-        for s in slots {
-            bindings.push(wgpu::BindGroupLayoutBinding {
-                binding: bindings.len() as u32,
-                visibility: s.stage.to_wgpu(),
-                ty: s.binding.to_wgpu(),
-            });
+        pipeline_layout: PipelineLayout,
+        cs: &Shader,
+    ) -> ComputePipeline {
+        let mut sets = Vec::new();
+        for s in pipeline_layout.sets.iter() {
+            sets.push(&s.wgpu);
         }
-        let layout = self
+        let layout = &self
             .device
-            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                bindings: bindings.as_slice(),
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                bind_group_layouts: sets.as_slice(),
             });
-        BindingGroupLayout::new(index, layout, bindings.len())
-    }
 
-    pub fn update_uniform_buffer<T: Copy + 'static>(
-        &self,
-        slice: &[T],
-        buf: &UniformBuffer,
-        encoder: &mut wgpu::CommandEncoder,
-    ) {
-        let src = self
+        let wgpu = self
             .device
-            .create_buffer_mapped::<T>(
-                slice.len(),
-                wgpu::BufferUsage::UNIFORM
-                    | wgpu::BufferUsage::COPY_SRC
-                    | wgpu::BufferUsage::MAP_WRITE,
-            )
-            .fill_from_slice(slice);
-
-        encoder.copy_buffer_to_buffer(
-            &src,
-            0,
-            &buf.wgpu,
-            0,
-            (std::mem::size_of::<T>() * slice.len()) as wgpu::BufferAddress,
-        );
-    }
-
-    // MUTABLE API ////////////////////////////////////////////////////////////
+            .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                layout,
+                compute_stage: wgpu::ProgrammableStageDescriptor {
+                    module: &cs.module,
+                    entry_point: "main",
+                },
+            });
 
-    pub fn submit(&mut self, cmds: &[wgpu::CommandBuffer]) {
-        self.queue.submit(cmds);
+        ComputePipeline {
+            layout: pipeline_layout,
+            wgpu,
+        }
     }
 
-    // PRIVATE API ////////////////////////////////////////////////////////////
-
     fn create_pipeline(
         &self,
         pipeline_layout: PipelineLayout,
         vertex_layout: VertexLayout,
         blending: Blending,
-        vs: &Shader,
-        fs: &Shader,
+        samples: u32,
+        (depth, stencil): (DepthTest, Stencil),
+        (vs, fs): (&Shader, &Shader),
     ) -> Pipeline {
         let vertex_attrs = vertex_layout.to_wgpu();
 
@@ -1718,7 +5025,9 @@ impl Device {
                 bind_group_layouts: sets.as_slice(),
             });
 
-        let (src_factor, dst_factor, operation) = blending.to_wgpu();
+        let (color_blend, alpha_blend) = blending.to_wgpu();
+        let (depth_write_enabled, depth_compare) = depth.to_wgpu();
+        let (stencil_face, stencil_read_mask, stencil_write_mask) = stencil.to_wgpu();
 
         let wgpu = self
             .device
@@ -1742,30 +5051,22 @@ impl Device {
                 primitive_topology: wgpu::PrimitiveTopology::TriangleList,
                 color_states: &[wgpu::ColorStateDescriptor {
                     format: SwapChain::FORMAT,
-                    color_blend: wgpu::BlendDescriptor {
-                        src_factor,
-                        dst_factor,
-                        operation,
-                    },
-                    alpha_blend: wgpu::BlendDescriptor {
-                        src_factor,
-                        dst_factor,
-                        operation,
-                    },
+                    color_blend,
+                    alpha_blend,
                     write_mask: wgpu::ColorWrite::ALL,
                 }],
                 depth_stencil_state: Some(wgpu::DepthStencilStateDescriptor {
                     format: ZBuffer::FORMAT,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::LessEqual,
-                    stencil_front: wgpu::StencilStateFaceDescriptor::IGNORE,
-                    stencil_back: wgpu::StencilStateFaceDescriptor::IGNORE,
-                    stencil_read_mask: 0,
-                    stencil_write_mask: 0,
+                    depth_write_enabled,
+                    depth_compare,
+                    stencil_front: stencil_face.clone(),
+                    stencil_back: stencil_face,
+                    stencil_read_mask,
+                    stencil_write_mask,
                 }),
-                index_format: wgpu::IndexFormat::Uint16,
+                index_format: wgpu::IndexFormat::Uint32,
                 vertex_buffers: &[vertex_attrs],
-                sample_count: 1,
+                sample_count: samples,
                 sample_mask: !0,
                 alpha_to_coverage_enabled: false,
             });
@@ -1774,6 +5075,8 @@ impl Device {
             layout: pipeline_layout,
             vertex_layout,
             wgpu,
+            #[cfg(feature = "trace")]
+            id: trace::id(),
         }
     }
 }
@@ -0,0 +1,612 @@
+//! Recording of the calls made to the core API, and a player replaying them
+//! without a window, eg. to attach a minimal reproduction to a bug report.
+//!
+//! While a trace is recorded, resource creation, uploads, submissions and
+//! the passes of each frame are recorded along with their data. Only calls
+//! made on the recording thread are recorded, so the trace should be
+//! started before any resource is created, eg. right after the renderer.
+//!
+//! Compute passes aren't recorded, eg. those of `kit::cull` or
+//! `kit::histogram`, and neither are indirect draws. Passes must be started
+//! with `Frame::pass` to be recorded.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use rgx::core::trace::{self, Trace};
+//!
+//! trace::start();
+//! // Create resources and render a few frames.
+//! trace::finish().unwrap().save("bug.json")?;
+//!
+//! // Later, without a window.
+//! let trace = Trace::load("bug.json")?;
+//! let pixels = trace::replay(&trace)?;
+//! ```
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::core::{
+    Bind, Binding, BindingGroup, BindingGroupLayout, BindingType, Blending, DepthTest, Device,
+    DynamicVertexBuffer, Error, Frame, Framebuffer, IndexBuffer, Pass, PassOp, Pipeline,
+    PipelineLayout, Rect, RenderTarget, Renderer, Rgba, Rgba8, RingBuffer, Sampler,
+    SamplerDescriptor, ShaderStage, Stencil, StepMode, Texture, TextureFormat, UniformBuffer,
+    VertexBuffer, VertexFormat, VertexLayout,
+};
+
+/// Identifies a resource in a trace. Resources which are never traced have
+/// the id zero.
+pub type Id = u32;
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+
+thread_local! {
+    static RECORDER: RefCell<Option<Trace>> = const { RefCell::new(None) };
+}
+
+/// A recorded call.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Event {
+    /// A swap chain was created, or resized. The screen is replayed as a
+    /// framebuffer.
+    Screen {
+        w: u32,
+        h: u32,
+        samples: u32,
+    },
+    Layout {
+        id: Id,
+        set: u32,
+        bindings: Vec<(BindingType, ShaderStage)>,
+    },
+    Pipeline {
+        id: Id,
+        layouts: Vec<Id>,
+        vertex_layout: Vec<VertexFormat>,
+        step_mode: StepMode,
+        blending: Blending,
+        samples: u32,
+        depth: DepthTest,
+        stencil: Stencil,
+        vertex_shader: Vec<u8>,
+        fragment_shader: Vec<u8>,
+    },
+    Texture {
+        id: Id,
+        w: u32,
+        h: u32,
+        format: TextureFormat,
+        levels: u32,
+    },
+    Framebuffer {
+        id: Id,
+        w: u32,
+        h: u32,
+        samples: u32,
+    },
+    Sampler {
+        id: Id,
+        desc: SamplerDescriptor,
+    },
+    VertexBuffer {
+        id: Id,
+        data: Vec<u8>,
+    },
+    IndexBuffer {
+        id: Id,
+        data: Vec<u32>,
+    },
+    /// A ring buffer of `capacity` bytes, written to on upload.
+    RingBuffer {
+        id: Id,
+        capacity: u64,
+    },
+    /// A uniform buffer, with `size` the size of a single uniform.
+    UniformBuffer {
+        id: Id,
+        size: usize,
+        data: Vec<u8>,
+    },
+    BindingGroup {
+        id: Id,
+        layout: Id,
+        resources: Vec<Id>,
+    },
+    /// Data was copied to a buffer, at an offset in bytes.
+    WriteBuffer {
+        id: Id,
+        #[serde(default)]
+        offset: u64,
+        data: Vec<u8>,
+    },
+    /// Texels were copied to a region of a texture, with rows going down.
+    WriteTexture {
+        id: Id,
+        x: u32,
+        y: u32,
+        w: u32,
+        h: u32,
+        row_pitch: u32,
+        data: Vec<u8>,
+    },
+    CopyBuffer {
+        src: Id,
+        dst: Id,
+    },
+    Blit {
+        id: Id,
+        src: Rect<f32>,
+        dst: Rect<f32>,
+    },
+    GenerateMipmaps {
+        id: Id,
+    },
+    /// Commands were submitted.
+    Submit,
+    /// A pass was started on a framebuffer, or on the screen if `target` is
+    /// `None`.
+    BeginPass {
+        target: Option<Id>,
+        clear: Option<Rgba>,
+    },
+    SetPipeline(Id),
+    SetBinding {
+        id: Id,
+        offsets: Vec<u64>,
+    },
+    SetVertexBuffer(Id),
+    SetIndexBuffer(Id),
+    /// A ring buffer was set as the vertex buffer, and as the index buffer
+    /// if `indices` is set, at the given offsets in bytes.
+    SetRingBuffer {
+        id: Id,
+        vertices: u64,
+        indices: Option<u64>,
+    },
+    SetStencilReference(u32),
+    SetBlendColor(Rgba),
+    Draw {
+        vertices: Range<u32>,
+        instances: Range<u32>,
+    },
+    DrawIndexed {
+        indices: Range<u32>,
+        instances: Range<u32>,
+    },
+    EndPass,
+}
+
+/// A recorded sequence of calls.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Trace {
+    pub events: Vec<Event>,
+}
+
+impl Trace {
+    /// Save the trace as JSON.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let file = io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer(file, self).map_err(io::Error::from)
+    }
+
+    /// Load a trace saved with `Trace::save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = io::BufReader::new(std::fs::File::open(path)?);
+        serde_json::from_reader(file).map_err(io::Error::from)
+    }
+}
+
+/// Start recording the calls made on the current thread. A trace already
+/// being recorded is discarded.
+pub fn start() {
+    RECORDER.with(|r| *r.borrow_mut() = Some(Trace::default()));
+}
+
+/// Stop recording, and return the recorded trace, if any.
+pub fn finish() -> Option<Trace> {
+    RECORDER.with(|r| r.borrow_mut().take())
+}
+
+/// Whether the calls made on the current thread are being recorded.
+pub fn is_recording() -> bool {
+    RECORDER.with(|r| r.borrow().is_some())
+}
+
+pub(crate) fn id() -> Id {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Record an event, if a trace is being recorded. The event is only built
+/// if it is recorded.
+pub(crate) fn record<F: FnOnce() -> Event>(event: F) {
+    RECORDER.with(|r| {
+        if let Some(trace) = r.borrow_mut().as_mut() {
+            trace.events.push(event());
+        }
+    });
+}
+
+pub(crate) fn bytes<T: Copy>(slice: &[T]) -> Vec<u8> {
+    let len = std::mem::size_of_val(slice);
+    unsafe { std::slice::from_raw_parts(slice.as_ptr() as *const u8, len) }.to_vec()
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// Replay
+///////////////////////////////////////////////////////////////////////////////
+
+/// Replay a trace on a headless renderer, and read back the screen, or the
+/// target of the last pass if the trace has no screen.
+///
+/// Calls are replayed in the order they were made, on a single encoder
+/// which is submitted wherever commands were submitted.
+pub fn replay(trace: &Trace) -> Result<Vec<Rgba8>, Error> {
+    // Don't record the replay itself.
+    let recording = finish();
+    let result = Player::new().and_then(|p| p.play(&trace.events));
+
+    RECORDER.with(|r| *r.borrow_mut() = recording);
+    result
+}
+
+/// The resources created by a replay, by traced id.
+#[derive(Default)]
+struct Resources {
+    layouts: HashMap<Id, BindingGroupLayout>,
+    /// Layouts moved into a pipeline, with the pipeline and their index.
+    owned: HashMap<Id, (Id, usize)>,
+    pipelines: HashMap<Id, Pipeline>,
+    textures: HashMap<Id, Texture>,
+    framebuffers: HashMap<Id, Framebuffer>,
+    samplers: HashMap<Id, Sampler>,
+    vertices: HashMap<Id, DynamicVertexBuffer>,
+    indices: HashMap<Id, IndexBuffer>,
+    rings: HashMap<Id, RingBuffer>,
+    uniforms: HashMap<Id, UniformBuffer>,
+    groups: HashMap<Id, BindingGroup>,
+}
+
+fn get<T>(map: &HashMap<Id, T>, id: Id) -> Result<&T, Error> {
+    map.get(&id).ok_or(Error::InvalidTrace)
+}
+
+impl Resources {
+    fn layout(&self, id: Id) -> Result<&BindingGroupLayout, Error> {
+        if let Some(layout) = self.layouts.get(&id) {
+            return Ok(layout);
+        }
+        let (pipeline, index) = get(&self.owned, id)?;
+
+        Ok(&get(&self.pipelines, *pipeline)?.layout.sets[*index])
+    }
+
+    fn texture(&self, id: Id) -> Result<&Texture, Error> {
+        match self.framebuffers.get(&id) {
+            Some(fb) => Ok(&fb.texture),
+            None => get(&self.textures, id),
+        }
+    }
+
+    fn vertices(&self, id: Id) -> Result<&VertexBuffer, Error> {
+        get(&self.vertices, id).map(|v| v.buffer())
+    }
+
+    fn buffer(&self, id: Id) -> Result<&wgpu::Buffer, Error> {
+        if let Some(u) = self.uniforms.get(&id) {
+            Ok(&u.wgpu)
+        } else if let Some(ring) = self.rings.get(&id) {
+            Ok(&ring.wgpu)
+        } else {
+            self.vertices(id).map(|v| &*v.wgpu)
+        }
+    }
+
+    fn bind(&self, id: Id) -> Result<&dyn Bind, Error> {
+        if let Some(t) = self.textures.get(&id) {
+            Ok(t)
+        } else if let Some(fb) = self.framebuffers.get(&id) {
+            Ok(fb)
+        } else if let Some(s) = self.samplers.get(&id) {
+            Ok(s)
+        } else {
+            Ok(get(&self.uniforms, id)?)
+        }
+    }
+}
+
+struct Player {
+    r: Renderer,
+    resources: Resources,
+    screen: Option<Framebuffer>,
+    /// The target of the last pass on a framebuffer.
+    last: Option<Id>,
+    encoder: wgpu::CommandEncoder,
+}
+
+impl Player {
+    fn new() -> Result<Self, Error> {
+        let (r, _) = Renderer::headless(1, 1)?;
+        let encoder = r.device.create_command_encoder();
+
+        Ok(Self {
+            r,
+            resources: Resources::default(),
+            screen: None,
+            last: None,
+            encoder,
+        })
+    }
+
+    fn play(mut self, events: &[Event]) -> Result<Vec<Rgba8>, Error> {
+        let mut events = events.iter();
+
+        while let Some(event) = events.next() {
+            if let Event::BeginPass { target, clear } = event {
+                let pass = events.by_ref().take_while(|e| **e != Event::EndPass);
+                self.pass(*target, *clear, pass)?;
+            } else {
+                self.event(event)?;
+            }
+        }
+        self.submit();
+
+        let Self {
+            mut r,
+            resources,
+            screen,
+            last,
+            ..
+        } = self;
+        let fb = match (&screen, last) {
+            (Some(screen), _) => screen,
+            (None, Some(id)) => get(&resources.framebuffers, id)?,
+            (None, None) => return Err(Error::InvalidTrace),
+        };
+        r.read_pixels(fb)
+    }
+
+    fn submit(&mut self) {
+        let encoder = std::mem::replace(&mut self.encoder, self.r.device.create_command_encoder());
+        self.r.device.submit(&[encoder.finish()]);
+    }
+
+    fn event(&mut self, event: &Event) -> Result<(), Error> {
+        let dev = &self.r.device;
+        let res = &mut self.resources;
+        let encoder = &mut self.encoder;
+
+        match event.clone() {
+            Event::Screen { w, h, samples } => {
+                self.screen = Some(dev.create_framebuffer_msaa(w, h, samples));
+            }
+            Event::Layout { id, set, bindings } => {
+                let bindings = bindings
+                    .into_iter()
+                    .map(|(binding, stage)| Binding { binding, stage })
+                    .collect::<Vec<_>>();
+                res.layouts
+                    .insert(id, dev.create_binding_group_layout(set, &bindings));
+            }
+            Event::Pipeline {
+                id,
+                layouts,
+                vertex_layout,
+                step_mode,
+                blending,
+                samples,
+                depth,
+                stencil,
+                vertex_shader,
+                fragment_shader,
+            } => {
+                let mut sets = Vec::with_capacity(layouts.len());
+                for (i, layout) in layouts.iter().enumerate() {
+                    sets.push(res.layouts.remove(layout).ok_or(Error::InvalidTrace)?);
+                    res.owned.insert(*layout, (id, i));
+                }
+                let mut vertex_layout = VertexLayout::from(&vertex_layout);
+                vertex_layout.step_mode = step_mode;
+
+                let vs = dev.create_shader("vertex shader", &vertex_shader, ShaderStage::Vertex);
+                let fs =
+                    dev.create_shader("fragment shader", &fragment_shader, ShaderStage::Fragment);
+                let pipeline = dev.create_pipeline(
+                    PipelineLayout { sets },
+                    vertex_layout,
+                    blending,
+                    samples,
+                    (depth, stencil),
+                    (&vs, &fs),
+                );
+                res.pipelines.insert(id, pipeline);
+            }
+            Event::Texture {
+                id,
+                w,
+                h,
+                format,
+                levels,
+            } => {
+                let texture = dev.create_texture_with_levels(w, h, format, levels);
+                res.textures.insert(id, texture);
+            }
+            Event::Framebuffer { id, w, h, samples } => {
+                res.framebuffers
+                    .insert(id, dev.create_framebuffer_msaa(w, h, samples));
+            }
+            Event::Sampler { id, desc } => {
+                res.samplers.insert(id, dev.create_sampler_with(desc));
+            }
+            Event::VertexBuffer { id, data } => {
+                // Created as dynamic buffers, since they may be written to.
+                let len = data.len() as u32;
+                #[allow(unused_mut)]
+                let mut buf = dev.create_dynamic_buffer_with_capacity(&data, len);
+
+                // The size of the traced vertices isn't known.
+                #[cfg(feature = "validation")]
+                {
+                    buf.buffer.stride = 0;
+                }
+                res.vertices.insert(id, buf);
+            }
+            Event::IndexBuffer { id, data } => {
+                res.indices.insert(id, dev.create_index_buffer(&data));
+            }
+            Event::RingBuffer { id, capacity } => {
+                res.rings.insert(id, dev.create_ring_buffer(capacity));
+            }
+            Event::UniformBuffer { id, size, data } => {
+                let mut buf = dev.create_uniform_buffer(&data);
+                buf.size = size;
+                buf.count = data.len() / size.max(1);
+
+                res.uniforms.insert(id, buf);
+            }
+            Event::BindingGroup {
+                id,
+                layout,
+                resources,
+            } => {
+                let binds = resources
+                    .iter()
+                    .map(|r| res.bind(*r))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let group = dev.create_binding_group(res.layout(layout)?, &binds);
+
+                res.groups.insert(id, group);
+            }
+            Event::WriteBuffer { id, offset, data } if !data.is_empty() => {
+                let src = staging(dev, &data);
+                let size = data.len() as wgpu::BufferAddress;
+
+                encoder.copy_buffer_to_buffer(&src, 0, res.buffer(id)?, offset, size);
+            }
+            Event::WriteTexture {
+                id,
+                x,
+                y,
+                w,
+                h,
+                row_pitch,
+                data,
+            } if !data.is_empty() => {
+                let extent = wgpu::Extent3d {
+                    width: w,
+                    height: h,
+                    depth: 1,
+                };
+                let texture = &res.texture(id)?.wgpu;
+                let rows = data.len() as u32 / row_pitch;
+
+                Texture::copy(
+                    dev, texture, &data, row_pitch, rows, x as f32, y as f32, extent, encoder,
+                );
+            }
+            Event::CopyBuffer { src, dst } => {
+                let (src, dst) = (get(&res.uniforms, src)?, get(&res.uniforms, dst)?);
+                let size = (src.size * src.count) as wgpu::BufferAddress;
+
+                encoder.copy_buffer_to_buffer(&src.wgpu, 0, &dst.wgpu, 0, size);
+            }
+            Event::Blit { id, src, dst } => {
+                res.texture(id)?.blit(src, dst, encoder);
+            }
+            Event::GenerateMipmaps { id } => {
+                let texture = res.texture(id)?;
+                let mut frame =
+                    Frame::new(std::mem::replace(encoder, dev.create_command_encoder()));
+
+                texture.generate_mipmaps(&mut frame);
+                *encoder = frame.encoder;
+            }
+            Event::Submit => {
+                self.submit();
+            }
+            // Empty writes are skipped, and pass calls are only replayed
+            // within a pass.
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn pass<'a, I>(
+        &mut self,
+        target: Option<Id>,
+        clear: Option<Rgba>,
+        events: I,
+    ) -> Result<(), Error>
+    where
+        I: Iterator<Item = &'a Event>,
+    {
+        let res = &self.resources;
+        let view: &dyn RenderTarget = match target {
+            Some(id) => get(&res.framebuffers, id)?,
+            None => self.screen.as_ref().ok_or(Error::InvalidTrace)?,
+        };
+        let op = match clear {
+            Some(color) => PassOp::Clear(color),
+            None => PassOp::Load(),
+        };
+        self.last = target.or(self.last);
+
+        let mut pass = Pass::begin_resolve(
+            &mut self.encoder,
+            view.color_target(),
+            view.resolve_target(),
+            view.zdepth_target(),
+            op,
+        );
+
+        for event in events {
+            match event {
+                Event::SetPipeline(id) => {
+                    pass.set_pipeline(get(&res.pipelines, *id)?);
+                }
+                Event::SetBinding { id, offsets } => {
+                    pass.set_binding(get(&res.groups, *id)?, offsets);
+                }
+                Event::SetVertexBuffer(id) => {
+                    pass.set_vertex_buffer(res.vertices(*id)?);
+                }
+                Event::SetIndexBuffer(id) => {
+                    pass.set_index_buffer(get(&res.indices, *id)?);
+                }
+                Event::SetRingBuffer {
+                    id,
+                    vertices,
+                    indices,
+                } => {
+                    pass.set_ring_buffer(get(&res.rings, *id)?, *vertices, *indices);
+                }
+                Event::SetStencilReference(reference) => {
+                    pass.set_stencil_reference(*reference);
+                }
+                Event::SetBlendColor(color) => {
+                    pass.set_blend_color(*color);
+                }
+                Event::Draw {
+                    vertices,
+                    instances,
+                } => {
+                    pass.draw_range(vertices.clone(), instances.clone());
+                }
+                Event::DrawIndexed { indices, instances } => {
+                    pass.draw_indexed(indices.clone(), instances.clone());
+                }
+                _ => return Err(Error::InvalidTrace),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn staging(dev: &Device, data: &[u8]) -> wgpu::Buffer {
+    dev.device
+        .create_buffer_mapped(data.len(), wgpu::BufferUsage::COPY_SRC)
+        .fill_from_slice(data)
+}
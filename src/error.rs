@@ -5,6 +5,17 @@ use std::io;
 #[derive(Debug, Copy, Clone)]
 pub enum Error {
     NoAdaptersFound,
+    InvalidAtlas(usize),
+    InvalidFont,
+    InvalidImage,
+    UnknownAttachment(&'static str),
+    GraphCycle(&'static str),
+    InvalidTrace,
+    LayoutMismatch(u32),
+    InvalidMap,
+    InvalidSvg,
+    TileFailed(u32, u32),
+    ReadFailed,
 }
 
 impl From<Error> for io::Error {
@@ -17,6 +28,27 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::NoAdaptersFound => write!(f, "a suitable graphics adapter was not found"),
+            Self::InvalidAtlas(line) => write!(f, "invalid texture atlas region on line {}", line),
+            Self::InvalidFont => write!(f, "invalid or unsupported font data"),
+            Self::InvalidImage => write!(f, "invalid or unsupported image data"),
+            Self::UnknownAttachment(name) => {
+                write!(f, "unknown render graph attachment {:?}", name)
+            }
+            Self::GraphCycle(name) => {
+                write!(
+                    f,
+                    "render graph pass {:?} is part of a dependency cycle",
+                    name
+                )
+            }
+            Self::InvalidTrace => write!(f, "invalid trace: unknown resource or unexpected call"),
+            Self::LayoutMismatch(set) => {
+                write!(f, "binding types don't match the layout of set {}", set)
+            }
+            Self::InvalidMap => write!(f, "invalid or unsupported map data"),
+            Self::InvalidSvg => write!(f, "invalid or unsupported SVG data"),
+            Self::TileFailed(x, y) => write!(f, "tile ({}, {}) failed to load", x, y),
+            Self::ReadFailed => write!(f, "failed to read back pixels from the GPU"),
         }
     }
 }
@@ -25,6 +57,17 @@ impl error::Error for Error {
     fn description(&self) -> &str {
         match self {
             Self::NoAdaptersFound => "a suitable graphics adapter was not found",
+            Self::InvalidAtlas(_) => "invalid texture atlas region",
+            Self::InvalidFont => "invalid or unsupported font data",
+            Self::InvalidImage => "invalid or unsupported image data",
+            Self::UnknownAttachment(_) => "unknown render graph attachment",
+            Self::GraphCycle(_) => "render graph has a dependency cycle",
+            Self::InvalidTrace => "invalid trace",
+            Self::LayoutMismatch(_) => "binding types don't match the layout",
+            Self::InvalidMap => "invalid or unsupported map data",
+            Self::InvalidSvg => "invalid or unsupported SVG data",
+            Self::TileFailed(..) => "tile failed to load",
+            Self::ReadFailed => "failed to read back pixels from the GPU",
         }
     }
 
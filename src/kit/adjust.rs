@@ -0,0 +1,127 @@
+use crate::core;
+use crate::core::{Binding, BindingType, Set, ShaderStage};
+use crate::kit::effects;
+
+///////////////////////////////////////////////////////////////////////////
+// Adjustments
+///////////////////////////////////////////////////////////////////////////
+
+/// Color adjustments applied to a texture or framebuffer when it is drawn.
+/// The source itself is left untouched.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Adjustments {
+    /// Hue shift, in radians.
+    pub hue: f32,
+    /// Saturation multiplier. `0.0` is grayscale.
+    pub saturation: f32,
+    /// Brightness offset, added to each color channel.
+    pub brightness: f32,
+    /// Contrast multiplier, around mid-gray.
+    pub contrast: f32,
+    /// Color temperature. Positive values are warmer, negative values cooler.
+    pub temperature: f32,
+}
+
+impl Adjustments {
+    /// Adjustments which leave the source unchanged.
+    pub const IDENTITY: Self = Self {
+        hue: 0.,
+        saturation: 1.,
+        brightness: 0.,
+        contrast: 1.,
+        temperature: 0.,
+    };
+}
+
+impl Default for Adjustments {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Pipeline
+///////////////////////////////////////////////////////////////////////////
+
+/// Draws a texture or framebuffer over the whole render target, with
+/// color adjustments applied.
+pub struct Pipeline {
+    pipeline: core::Pipeline,
+    bindings: core::BindingGroup,
+    buf: core::UniformBuffer,
+    vertices: core::VertexBuffer,
+}
+
+impl Pipeline {
+    /// Create a binding for the source to adjust, eg. a `Texture` or `Framebuffer`.
+    pub fn binding(
+        &self,
+        renderer: &core::Renderer,
+        source: &dyn core::Bind,
+        sampler: &core::Sampler,
+    ) -> core::BindingGroup {
+        renderer
+            .device
+            .create_binding_group(&self.pipeline.layout.sets[1], &[source, sampler])
+    }
+
+    pub fn draw(&self, binding: &core::BindingGroup, pass: &mut core::Pass) {
+        pass.draw(&self.vertices, binding);
+    }
+}
+
+impl<'a> core::AbstractPipeline<'a> for Pipeline {
+    type PrepareContext = Adjustments;
+    type Uniforms = Adjustments;
+
+    fn description() -> core::PipelineDescription<'a> {
+        core::PipelineDescription {
+            vertex_layout: &[core::VertexFormat::Float2, core::VertexFormat::Float2],
+            pipeline_layout: &[
+                Set(&[Binding {
+                    binding: BindingType::UniformBuffer,
+                    stage: ShaderStage::Fragment,
+                }]),
+                Set(&[
+                    Binding {
+                        binding: BindingType::SampledTexture,
+                        stage: ShaderStage::Fragment,
+                    },
+                    Binding {
+                        binding: BindingType::Sampler,
+                        stage: ShaderStage::Fragment,
+                    },
+                ]),
+            ],
+            // TODO: Use `env("CARGO_MANIFEST_DIR")`
+            vertex_shader: include_bytes!("data/screen.vert.spv"),
+            fragment_shader: include_bytes!("data/adjust.frag.spv"),
+        }
+    }
+
+    fn setup(pipeline: core::Pipeline, dev: &core::Device) -> Self {
+        let vertices = effects::screen_quad(dev);
+        let buf = dev.create_uniform_buffer(&[Adjustments::IDENTITY]);
+        let bindings = dev.create_binding_group(&pipeline.layout.sets[0], &[&buf]);
+
+        Self {
+            pipeline,
+            bindings,
+            buf,
+            vertices,
+        }
+    }
+
+    fn apply(&self, pass: &mut core::Pass) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_binding(&self.bindings, &[]);
+    }
+
+    fn prepare(
+        &'a self,
+        adjustments: Adjustments,
+    ) -> Option<(&'a core::UniformBuffer, Vec<Adjustments>)> {
+        Some((&self.buf, vec![adjustments]))
+    }
+}
@@ -0,0 +1,149 @@
+//! Bloom of emissive shapes and sprites only, rather than of everything
+//! bright in the scene.
+//!
+//! Shapes and sprites are tagged as emissive when they are added to their
+//! batch, with `add_emissive`. They are drawn with the rest of the scene,
+//! and their batch's `emissive` copy is also drawn into the target of a
+//! `Bloom`, which is blurred and added over the scene.
+//!
+//! Blurring uses an effects pipeline, which is prepared by `Bloom::blur`.
+//! Compositing uses another one, created with `Blending::additive` and
+//! prepared with `Effect::NONE`.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use rgx::kit::bloom::Bloom;
+//! use rgx::kit::effects::{self, Effect};
+//!
+//! let blur: effects::Pipeline = r.pipeline(Blending::default());
+//! let composite: effects::Pipeline = r.pipeline(Blending::additive());
+//! let sampler = r.sampler(Filter::Linear, Filter::Linear);
+//! let bloom = Bloom::new(&r, &blur, &composite, &sampler, w, h, 0.5);
+//!
+//! // Every frame.
+//! let glow = batch.emissive().buffer(&r);
+//!
+//! r.update_pipeline(&composite, (Effect::NONE, w, h), &mut frame);
+//! {
+//!     let mut pass = frame.pass(PassOp::Clear(Rgba::TRANSPARENT), bloom.target());
+//!
+//!     pass.set_pipeline(&shapes);
+//!     pass.draw_indexed_buffer(&glow);
+//! }
+//! bloom.blur(&mut r, &blur, &mut frame);
+//! {
+//!     let mut pass = frame.pass(PassOp::Clear(Rgba::BLACK), &output);
+//!
+//!     pass.set_pipeline(&shapes);
+//!     pass.draw_indexed_buffer(&scene);
+//!
+//!     bloom.draw(&composite, &mut pass);
+//! }
+//! ```
+use crate::core;
+use crate::core::{PassOp, Rgba};
+use crate::kit::effects::{self, Axis, Effect};
+
+///////////////////////////////////////////////////////////////////////////
+// Bloom
+///////////////////////////////////////////////////////////////////////////
+
+/// A glow target, blurred and added over the output.
+pub struct Bloom {
+    /// Blur radius, in pixels of the output.
+    pub radius: f32,
+
+    /// Target of the emissive draws, and of the blurred result.
+    glow: core::Framebuffer,
+    /// Target of the horizontal blur.
+    blurred: core::Framebuffer,
+    /// Bindings of the glow and the horizontally blurred glow, as sources
+    /// of the blur pipeline.
+    sources: [core::BindingGroup; 2],
+    /// Binding of the glow, as source of the composite pipeline.
+    result: core::BindingGroup,
+    quad: core::VertexBuffer,
+    scale: f32,
+}
+
+impl Bloom {
+    /// Create a bloom for an output of the given size, blurred at the given
+    /// scale of it, eg. `0.5` for half resolution, which is cheaper and
+    /// spreads further with the same radius.
+    pub fn new(
+        r: &core::Renderer,
+        blur: &effects::Pipeline,
+        composite: &effects::Pipeline,
+        sampler: &core::Sampler,
+        w: u32,
+        h: u32,
+        scale: f32,
+    ) -> Self {
+        assert!(scale > 0., "fatal: bloom scale must be positive");
+
+        let (gw, gh) = (
+            (w as f32 * scale).round().max(1.) as u32,
+            (h as f32 * scale).round().max(1.) as u32,
+        );
+        let glow = r.framebuffer(gw, gh);
+        let blurred = r.framebuffer(gw, gh);
+        let sources = [
+            blur.binding(r, &glow, sampler),
+            blur.binding(r, &blurred, sampler),
+        ];
+        let result = composite.binding(r, &glow, sampler);
+
+        Self {
+            radius: 8.,
+            glow,
+            blurred,
+            sources,
+            result,
+            quad: effects::screen_quad(&r.device),
+            scale,
+        }
+    }
+
+    /// The target to draw emissive shapes and sprites to, cleared with
+    /// `Rgba::TRANSPARENT`.
+    pub fn target(&self) -> &core::Framebuffer {
+        &self.glow
+    }
+
+    /// Blur the glow, once the emissive draws are done, horizontally and
+    /// then vertically. This prepares the blur pipeline twice.
+    pub fn blur(&self, r: &mut core::Renderer, blur: &effects::Pipeline, frame: &mut core::Frame) {
+        let (w, h) = (self.glow.width(), self.glow.height());
+        let radius = self.radius * self.scale;
+        let passes = [
+            (Axis::Horizontal, &self.blurred, &self.sources[0]),
+            (Axis::Vertical, &self.glow, &self.sources[1]),
+        ];
+
+        for (axis, target, source) in passes.iter() {
+            r.update_pipeline(
+                blur,
+                (
+                    Effect::Blur {
+                        radius,
+                        axis: *axis,
+                    },
+                    w,
+                    h,
+                ),
+                frame,
+            );
+
+            let mut pass = frame.pass(PassOp::Clear(Rgba::TRANSPARENT), *target);
+            pass.set_pipeline(blur);
+            pass.draw(&self.quad, source);
+        }
+    }
+
+    /// Add the blurred glow over the target of the pass.
+    pub fn draw(&self, composite: &effects::Pipeline, pass: &mut core::Pass) {
+        pass.set_pipeline(composite);
+        pass.draw(&self.quad, &self.result);
+    }
+}
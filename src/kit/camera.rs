@@ -0,0 +1,187 @@
+//! A 2D camera, to pan, zoom and rotate the view of a scene.
+//!
+//! The camera converts to the projection of the kit pipelines, so it can be
+//! passed to `Renderer::update_pipeline` in place of `kit::ortho`. A camera
+//! that wasn't moved gives the same projection as `kit::ortho`.
+//!
+//! Screen positions are in pixels from the bottom-left of the viewport, as
+//! with the rest of the kit. Cursor positions reported from the top-left
+//! must be flipped first, eg. with `h - y`.
+//!
+//! # Examples
+//!
+//! ```
+//! use rgx::kit::Camera2D;
+//! use rgx::math::Point2;
+//!
+//! let mut camera = Camera2D::new(800, 600);
+//!
+//! // Zoom in on the bottom-left corner of the screen.
+//! camera.zoom_at(2., Point2::new(0., 0.));
+//! assert_eq!(camera.screen_to_world(Point2::new(0., 0.)), Point2::new(0., 0.));
+//! assert_eq!(camera.screen_to_world(Point2::new(800., 600.)), Point2::new(400., 300.));
+//!
+//! // Drag the view 100 pixels to the right.
+//! camera.pan(100., 0.);
+//! assert_eq!(camera.world_to_screen(Point2::new(0., 0.)), Point2::new(100., 0.));
+//! ```
+use crate::kit;
+use crate::math::{Matrix4, Point2, Vector3};
+use crate::rect::Rect;
+
+/// A view of a 2D scene, centered on a world position.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Camera2D {
+    /// Width of the viewport, in pixels.
+    pub w: u32,
+    /// Height of the viewport, in pixels.
+    pub h: u32,
+    /// World position at the center of the viewport.
+    pub position: Point2<f32>,
+    /// Screen pixels per world unit.
+    pub zoom: f32,
+    /// Rotation of the camera, counter-clockwise, in radians. The scene
+    /// appears rotated the other way.
+    pub rotation: f32,
+}
+
+impl Camera2D {
+    /// A camera over a viewport, with the world origin at its bottom-left.
+    pub fn new(w: u32, h: u32) -> Self {
+        Self {
+            w,
+            h,
+            position: Point2::new(w as f32 / 2., h as f32 / 2.),
+            zoom: 1.,
+            rotation: 0.,
+        }
+    }
+
+    /// Resize the viewport, keeping the same world position at its center.
+    pub fn resize(&mut self, w: u32, h: u32) {
+        self.w = w;
+        self.h = h;
+    }
+
+    /// Move the view by a distance in pixels, eg. of a mouse drag. The scene
+    /// follows the movement.
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        let (x, y) = rotate(dx / self.zoom, dy / self.zoom, self.rotation);
+
+        self.position = Point2::new(self.position.x - x, self.position.y - y);
+    }
+
+    /// Center the view on a world position.
+    pub fn look_at(&mut self, p: Point2<f32>) {
+        self.position = p;
+    }
+
+    /// Multiply the zoom by a factor, keeping the world position under a
+    /// screen position in place, eg. under the cursor.
+    pub fn zoom_at(&mut self, factor: f32, screen: Point2<f32>) {
+        let before = self.screen_to_world(screen);
+        self.zoom *= factor;
+        let after = self.screen_to_world(screen);
+
+        self.position = Point2::new(
+            self.position.x + before.x - after.x,
+            self.position.y + before.y - after.y,
+        );
+    }
+
+    /// Rotate the camera by an angle, in radians, around the center of the
+    /// viewport.
+    pub fn rotate(&mut self, angle: f32) {
+        self.rotation += angle;
+    }
+
+    /// The transform from world coordinates to screen pixels.
+    #[rustfmt::skip]
+    pub fn view(&self) -> Matrix4<f32> {
+        let (sin, cos) = (-self.rotation).sin_cos();
+        let (s, c) = (sin * self.zoom, cos * self.zoom);
+        let (cx, cy) = (self.w as f32 / 2., self.h as f32 / 2.);
+        let (px, py) = (self.position.x, self.position.y);
+
+        let rotation = Matrix4::new(
+            c,  s,  0., 0.,
+            -s, c,  0., 0.,
+            0., 0., 1., 0.,
+            0., 0., 0., 1.,
+        );
+        Matrix4::from_translation(Vector3::new(cx, cy, 0.))
+            * rotation
+            * Matrix4::from_translation(Vector3::new(-px, -py, 0.))
+    }
+
+    /// The projection of the kit pipelines, ie. `kit::ortho` combined with
+    /// the view.
+    pub fn matrix(&self) -> Matrix4<f32> {
+        self.matrix_with(kit::ortho(self.w, self.h))
+    }
+
+    /// The view combined with another projection than `kit::ortho`, eg.
+    /// `kit::perspective`, for the same size of viewport.
+    pub fn matrix_with(&self, projection: Matrix4<f32>) -> Matrix4<f32> {
+        projection * self.view()
+    }
+
+    /// The screen position of a world position.
+    pub fn world_to_screen(&self, p: Point2<f32>) -> Point2<f32> {
+        let (x, y) = rotate(p.x - self.position.x, p.y - self.position.y, -self.rotation);
+
+        Point2::new(
+            self.w as f32 / 2. + x * self.zoom,
+            self.h as f32 / 2. + y * self.zoom,
+        )
+    }
+
+    /// The world position under a screen position, eg. of the cursor.
+    pub fn screen_to_world(&self, p: Point2<f32>) -> Point2<f32> {
+        let dx = (p.x - self.w as f32 / 2.) / self.zoom;
+        let dy = (p.y - self.h as f32 / 2.) / self.zoom;
+        let (x, y) = rotate(dx, dy, self.rotation);
+
+        Point2::new(self.position.x + x, self.position.y + y)
+    }
+
+    /// The bounds of the world area visible in the viewport, eg. to cull
+    /// what isn't in view.
+    pub fn visible(&self) -> Rect<f32> {
+        let corners = [
+            self.screen_to_world(Point2::new(0., 0.)),
+            self.screen_to_world(Point2::new(self.w as f32, 0.)),
+            self.screen_to_world(Point2::new(0., self.h as f32)),
+            self.screen_to_world(Point2::new(self.w as f32, self.h as f32)),
+        ];
+        let mut rect = Rect::new(corners[0].x, corners[0].y, corners[0].x, corners[0].y);
+
+        for p in corners.iter() {
+            rect = Rect::new(
+                rect.x1.min(p.x),
+                rect.y1.min(p.y),
+                rect.x2.max(p.x),
+                rect.y2.max(p.y),
+            );
+        }
+        rect
+    }
+}
+
+impl From<Camera2D> for Matrix4<f32> {
+    fn from(camera: Camera2D) -> Self {
+        camera.matrix()
+    }
+}
+
+impl From<&Camera2D> for Matrix4<f32> {
+    fn from(camera: &Camera2D) -> Self {
+        camera.matrix()
+    }
+}
+
+/// Rotate a vector counter-clockwise by an angle, in radians.
+fn rotate(x: f32, y: f32, angle: f32) -> (f32, f32) {
+    let (sin, cos) = angle.sin_cos();
+    (x * cos - y * sin, x * sin + y * cos)
+}
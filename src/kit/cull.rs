@@ -0,0 +1,136 @@
+use crate::core;
+use crate::core::{Binding, BindingType, Set, ShaderStage};
+use crate::kit::sprite2d::{Instance, InstanceBatch};
+use crate::rect::Rect;
+
+///////////////////////////////////////////////////////////////////////////
+// Scene
+///////////////////////////////////////////////////////////////////////////
+
+/// Instances uploaded to the GPU once, and culled against the view on
+/// every frame by a compute pass. The instances that survive are drawn
+/// with an indirect draw call, so that huge scenes stay on the GPU.
+pub struct Scene {
+    visible: core::IndirectBuffer,
+    view: core::UniformBuffer,
+    binding: core::BindingGroup,
+    len: u32,
+}
+
+impl Scene {
+    /// Draw the instances that survived the last culling pass. The pass
+    /// must have an `InstancePipeline` applied.
+    pub fn draw(&self, binding: &core::BindingGroup, pass: &mut core::Pass) {
+        pass.set_binding(binding, &[]);
+        pass.draw_indirect(&self.visible);
+    }
+
+    /// Total number of instances, visible or not.
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// The view uniforms of the culling shader.
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct View {
+    bounds: [f32; 4],
+    count: u32,
+    _padding: [u32; 3],
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Pipeline
+///////////////////////////////////////////////////////////////////////////
+
+/// Culls the instances of a `Scene` with a compute pass.
+///
+/// Instances are culled by their bounding circle, so rotated sprites are
+/// never culled while they are partially visible.
+pub struct Pipeline {
+    pipeline: core::ComputePipeline,
+}
+
+impl Pipeline {
+    /// Size of a compute workgroup, in instances.
+    const WORKGROUP: u32 = 64;
+
+    pub fn new(r: &core::Renderer) -> Self {
+        let binding = |binding| Binding {
+            binding,
+            stage: ShaderStage::Compute,
+        };
+        let pipeline = r.compute_pipeline(
+            &[Set(&[
+                binding(BindingType::UniformBuffer),
+                binding(BindingType::StorageBufferReadOnly),
+                binding(BindingType::StorageBuffer),
+                binding(BindingType::StorageBuffer),
+            ])],
+            include_bytes!("data/cull.comp.spv"),
+        );
+
+        Self { pipeline }
+    }
+
+    /// Upload the instances of a batch, to be culled and drawn. Until the
+    /// first culling pass, no instances are drawn.
+    pub fn scene(&self, r: &core::Renderer, batch: &InstanceBatch) -> Scene {
+        let len = batch.len() as u32;
+        let instances = r.storage_buffer(batch.instances());
+        let visible = r.indirect_buffer::<Instance>(len, 0..6, 0..0);
+        let view = r.uniform_buffer(&[View {
+            bounds: [0.; 4],
+            count: len,
+            _padding: [0; 3],
+        }]);
+        let binding = r.binding_group(
+            &self.pipeline.layout.sets[0],
+            &[&view, &instances, visible.vertices(), visible.args()],
+        );
+
+        Scene {
+            visible,
+            view,
+            binding,
+            len,
+        }
+    }
+
+    /// Cull the instances of a scene against the view, given in the same
+    /// coordinates as the instances. The compute pass is recorded into the
+    /// frame, and must come before the scene is drawn.
+    pub fn cull(&self, r: &core::Renderer, scene: &Scene, view: Rect<f32>, f: &mut core::Frame) {
+        let encoder = f.encoder_mut();
+
+        r.device.update_uniform_buffer(
+            &[View {
+                bounds: [
+                    view.x1.min(view.x2),
+                    view.y1.min(view.y2),
+                    view.x1.max(view.x2),
+                    view.y1.max(view.y2),
+                ],
+                count: scene.len,
+                _padding: [0; 3],
+            }],
+            &scene.view,
+            encoder,
+        );
+        scene.visible.reset(encoder);
+
+        if scene.is_empty() {
+            return;
+        }
+        let mut pass = f.compute_pass();
+
+        pass.set_pipeline(&self.pipeline);
+        pass.set_binding(&scene.binding, &[]);
+        pass.dispatch(scene.len.div_ceil(Self::WORKGROUP), 1, 1);
+    }
+}
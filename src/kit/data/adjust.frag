@@ -0,0 +1,37 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform Adjustments {
+	float hue;
+	float saturation;
+	float brightness;
+	float contrast;
+	float temperature;
+} adj;
+
+layout(set = 1, binding = 0) uniform texture2D tex;
+layout(set = 1, binding = 1) uniform sampler   sam;
+
+layout(location = 0) in  vec2 f_uv;
+layout(location = 0) out vec4 fragColor;
+
+const vec3 LUMA = vec3(0.2126, 0.7152, 0.0722);
+
+// Rotate a color around the gray axis of the RGB cube.
+vec3 hueShift(vec3 color, float angle) {
+	const vec3 k = vec3(0.57735);
+	float c = cos(angle);
+
+	return color * c + cross(k, color) * sin(angle) + k * dot(k, color) * (1.0 - c);
+}
+
+void main() {
+	vec4 texel = texture(sampler2D(tex, sam), f_uv);
+	vec3 color = hueShift(texel.rgb, adj.hue);
+
+	color = mix(vec3(dot(color, LUMA)), color, adj.saturation);
+	color = color + vec3(adj.brightness);
+	color = (color - vec3(0.5)) * adj.contrast + vec3(0.5);
+	color = color + vec3(0.1, 0.0, -0.1) * adj.temperature;
+
+	fragColor = vec4(clamp(color, 0.0, 1.0), texel.a);
+}
@@ -0,0 +1,19 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform texture2D scene;
+layout(set = 0, binding = 1) uniform sampler   sam;
+layout(set = 0, binding = 2) uniform texture2D map;
+layout(set = 0, binding = 3) uniform sampler   mapSam;
+
+layout(location = 0) in  vec2 f_uv;
+layout(location = 1) in  vec2 f_map;
+layout(location = 2) in  vec2 f_scale;
+layout(location = 0) out vec4 fragColor;
+
+void main() {
+	// Offsets are stored around `128`, and scaled by alpha.
+	vec4 d = texture(sampler2D(map, mapSam), f_map);
+	vec2 offset = (d.rg * 255.0 - 128.0) / 128.0 * d.a;
+
+	fragColor = texture(sampler2D(scene, sam), f_uv + offset * f_scale);
+}
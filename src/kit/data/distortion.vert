@@ -0,0 +1,17 @@
+#version 450
+
+layout(location = 0) in vec2 position;
+layout(location = 1) in vec2 uv;
+layout(location = 2) in vec2 map;
+layout(location = 3) in vec2 scale;
+
+layout(location = 0) out vec2 f_uv;
+layout(location = 1) out vec2 f_map;
+layout(location = 2) out vec2 f_scale;
+
+void main() {
+	f_uv = uv;
+	f_map = map;
+	f_scale = scale;
+	gl_Position = vec4(position, 0.0, 1.0);
+}
@@ -0,0 +1,78 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform Effect {
+	vec4  params;
+	vec2  texel;
+	uint  kind;
+} effect;
+
+layout(set = 1, binding = 0) uniform texture2D tex;
+layout(set = 1, binding = 1) uniform sampler   sam;
+layout(set = 1, binding = 2) uniform texture2D lut;
+
+layout(location = 0) in  vec2 f_uv;
+layout(location = 0) out vec4 fragColor;
+
+const uint BLUR        = 0;
+const uint VIGNETTE    = 1;
+const uint COLOR_GRADE = 2;
+const uint PIXELATE    = 3;
+
+// Weights of a 9-tap gaussian kernel, with a standard deviation of 2 taps.
+const float WEIGHTS[5] = float[](0.2042, 0.1802, 0.1238, 0.0663, 0.0276);
+
+vec4 blur(vec2 direction, float radius) {
+	vec2 step = direction * effect.texel * radius / 4.0;
+	vec4 color = texture(sampler2D(tex, sam), f_uv) * WEIGHTS[0];
+
+	for (int i = 1; i < 5; i++) {
+		color += texture(sampler2D(tex, sam), f_uv + step * float(i)) * WEIGHTS[i];
+		color += texture(sampler2D(tex, sam), f_uv - step * float(i)) * WEIGHTS[i];
+	}
+	return color;
+}
+
+vec4 vignette(float intensity, float radius, float softness) {
+	vec4 color = texture(sampler2D(tex, sam), f_uv);
+	float d = distance(f_uv, vec2(0.5)) * 1.41421;
+	float v = 1.0 - smoothstep(radius - softness, radius, d);
+
+	return vec4(color.rgb * mix(1.0, v, intensity), color.a);
+}
+
+// The LUT is a 256x16 strip of 16 slices along blue, with red increasing
+// to the right and green downwards in each slice.
+vec4 colorGrade(float intensity) {
+	vec4 color = texture(sampler2D(tex, sam), f_uv);
+	vec3 c = clamp(color.rgb, 0.0, 1.0) * 15.0;
+	float b0 = floor(c.b);
+	float b1 = min(b0 + 1.0, 15.0);
+
+	vec2 uv0 = vec2((b0 * 16.0 + c.r + 0.5) / 256.0, (c.g + 0.5) / 16.0);
+	vec2 uv1 = vec2((b1 * 16.0 + c.r + 0.5) / 256.0, (c.g + 0.5) / 16.0);
+	vec3 graded = mix(
+		texture(sampler2D(lut, sam), uv0).rgb,
+		texture(sampler2D(lut, sam), uv1).rgb,
+		c.b - b0
+	);
+	return vec4(mix(color.rgb, graded, intensity), color.a);
+}
+
+vec4 pixelate(float size) {
+	vec2 cell = effect.texel * size;
+	return texture(sampler2D(tex, sam), (floor(f_uv / cell) + 0.5) * cell);
+}
+
+void main() {
+	vec4 p = effect.params;
+
+	if (effect.kind == BLUR) {
+		fragColor = blur(p.xy, p.z);
+	} else if (effect.kind == VIGNETTE) {
+		fragColor = vignette(p.x, p.y, p.z);
+	} else if (effect.kind == COLOR_GRADE) {
+		fragColor = colorGrade(p.x);
+	} else {
+		fragColor = pixelate(p.x);
+	}
+}
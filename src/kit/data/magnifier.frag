@@ -0,0 +1,31 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform Magnifier {
+	vec4 region;
+	vec4 crosshair;
+	uint circular;
+} magnifier;
+
+layout(set = 1, binding = 0) uniform texture2D tex;
+layout(set = 1, binding = 1) uniform sampler sam;
+
+layout(location = 0) in  vec2 f_uv;
+layout(location = 0) out vec4 fragColor;
+
+void main() {
+	vec4 region = magnifier.region;
+
+	// Position within the magnified region, in the [-1, 1] range.
+	vec2 local = (f_uv - (region.xy + region.zw) * 0.5) / ((region.zw - region.xy) * 0.5);
+	vec4 texel = texture(sampler2D(tex, sam), f_uv);
+
+	// One pixel wide crosshair, through the center of the region.
+	vec2 fw = max(fwidth(local), vec2(0.0001));
+	float line = clamp(1.0 - min(abs(local.x) / fw.x, abs(local.y) / fw.y), 0.0, 1.0);
+	vec3 color = mix(texel.rgb, magnifier.crosshair.rgb, magnifier.crosshair.a * line);
+
+	float r = length(local);
+	float edge = clamp((1.0 - r) / max(fwidth(r), 0.0001) + 0.5, 0.0, 1.0);
+
+	fragColor = vec4(color, magnifier.circular != 0 ? edge : 1.0);
+}
@@ -0,0 +1,38 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform Outline {
+	vec4 color;
+	float thickness;
+} outline;
+
+layout(set = 1, binding = 0) uniform texture2D ids;
+layout(set = 1, binding = 1) uniform sampler sam;
+
+layout(location = 0) in  vec2 f_uv;
+layout(location = 0) out vec4 fragColor;
+
+// Same as `Outline::MAX_THICKNESS`.
+const float MAX_THICKNESS = 16.0;
+
+void main() {
+	vec2 texel = 1.0 / vec2(textureSize(sampler2D(ids, sam), 0));
+	vec4 center = textureLod(sampler2D(ids, sam), f_uv, 0.0);
+	float t = clamp(outline.thickness, 0.0, MAX_THICKNESS);
+	int r = int(ceil(t));
+	float coverage = 0.0;
+
+	for (int y = -r; y < r + 1; y++) {
+		for (int x = -r; x < r + 1; x++) {
+			vec2 offset = vec2(x, y);
+			vec4 s = textureLod(sampler2D(ids, sam), f_uv + offset * texel, 0.0);
+
+			// The neighbour is an object other than the one under this pixel.
+			bool other = center.a < 0.5 || distance(s.rgb, center.rgb) > 0.0001;
+			bool hit = s.a >= 0.5 && other;
+			float weight = clamp(t + 0.5 - length(offset), 0.0, 1.0);
+
+			coverage = max(coverage, hit ? weight : 0.0);
+		}
+	}
+	fragColor = vec4(outline.color.rgb, outline.color.a * coverage);
+}
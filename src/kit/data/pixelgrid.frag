@@ -0,0 +1,28 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform Grid {
+	vec4  color;
+	vec2  offset;
+	vec2  size;
+	float zoom;
+	float threshold;
+} grid;
+
+layout(location = 0) out vec4 fragColor;
+
+void main() {
+	float zoom = max(grid.zoom, 0.0001);
+	vec2 p = (gl_FragCoord.xy - grid.offset) / zoom;
+
+	// Distance to the nearest pixel boundary, in screen pixels.
+	vec2 d = abs(p - round(p)) * zoom;
+	float line = 1.0 - clamp(min(d.x, d.y), 0.0, 1.0);
+
+	// Only draw inside the canvas, including its far edges.
+	vec2 inside = step(vec2(-0.5), p * zoom) * step(p * zoom, grid.size * zoom + 0.5);
+
+	// Fade the grid in as the zoom passes the threshold.
+	float fade = clamp(grid.zoom - grid.threshold, 0.0, 1.0);
+
+	fragColor = vec4(grid.color.rgb, grid.color.a * line * inside.x * inside.y * fade);
+}
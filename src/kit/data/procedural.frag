@@ -0,0 +1,138 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform Generator {
+	vec4  a;
+	vec4  b;
+	float scale;
+	uint  kind;
+	uint  seed;
+	uint  octaves;
+} gen;
+
+layout(location = 0) out vec4 fragColor;
+
+const uint VALUE        = 0;
+const uint PERLIN       = 1;
+const uint SIMPLEX      = 2;
+const uint CHECKERBOARD = 3;
+const uint STRIPES      = 4;
+
+// Must match `kit::procedural::hash`, so that the same seeds generate the
+// same images on the CPU and the GPU.
+uint hash(ivec2 p, uint seed) {
+	uint h = (seed * 0x27d4eb2du) ^ (uint(p.x) * 0x85ebca6bu) ^ (uint(p.y) * 0xc2b2ae35u);
+
+	h ^= h >> 15;
+	h *= 0x2c1b3c6du;
+	h ^= h >> 12;
+	h *= 0x297a2d39u;
+	h ^= h >> 15;
+
+	return h;
+}
+
+// A unit gradient, picked from eight directions.
+vec2 gradient(ivec2 p, uint seed) {
+	float a = float(hash(p, seed) & 7u) * 0.78539816;
+	return vec2(cos(a), sin(a));
+}
+
+float fade(float t) {
+	return t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+}
+
+float value(vec2 p, uint seed) {
+	vec2 p0 = floor(p);
+	ivec2 i = ivec2(p0);
+	vec2 f = vec2(fade(p.x - p0.x), fade(p.y - p0.y));
+
+	float a = float(hash(i, seed)) / 4294967295.0;
+	float b = float(hash(i + ivec2(1, 0), seed)) / 4294967295.0;
+	float c = float(hash(i + ivec2(0, 1), seed)) / 4294967295.0;
+	float d = float(hash(i + ivec2(1, 1), seed)) / 4294967295.0;
+
+	return mix(mix(a, b, f.x), mix(c, d, f.x), f.y);
+}
+
+float perlin(vec2 p, uint seed) {
+	vec2 p0 = floor(p);
+	ivec2 i = ivec2(p0);
+	vec2 f = p - p0;
+
+	float a = dot(gradient(i, seed), f);
+	float b = dot(gradient(i + ivec2(1, 0), seed), f - vec2(1.0, 0.0));
+	float c = dot(gradient(i + ivec2(0, 1), seed), f - vec2(0.0, 1.0));
+	float d = dot(gradient(i + ivec2(1, 1), seed), f - vec2(1.0, 1.0));
+	float n = mix(mix(a, b, fade(f.x)), mix(c, d, fade(f.x)), fade(f.y));
+
+	return clamp(n * 0.70710678 + 0.5, 0.0, 1.0);
+}
+
+float simplex(vec2 p, uint seed) {
+	// Skewing factors between the square and triangular grids.
+	const float F2 = 0.36602540;
+	const float G2 = 0.21132487;
+
+	vec2 s = floor(p + (p.x + p.y) * F2);
+	vec2 p0 = p - (s - (s.x + s.y) * G2);
+	// The triangle the point is in, lower or upper.
+	vec2 o = p0.x > p0.y ? vec2(1.0, 0.0) : vec2(0.0, 1.0);
+	ivec2 i = ivec2(s);
+
+	vec2 corners[3] = vec2[](p0, p0 - o + G2, p0 - 1.0 + 2.0 * G2);
+	ivec2 offsets[3] = ivec2[](ivec2(0), ivec2(o), ivec2(1));
+	float n = 0.0;
+
+	for (int k = 0; k < 3; k++) {
+		vec2 c = corners[k];
+		float t = 0.5 - dot(c, c);
+
+		if (t >= 0.0) {
+			n += t * t * t * t * dot(gradient(i + offsets[k], seed), c);
+		}
+	}
+	return clamp(n * 35.0 + 0.5, 0.0, 1.0);
+}
+
+// Several octaves of noise, each with twice the frequency and half the
+// amplitude of the previous one.
+float fbm(vec2 p) {
+	float sum = 0.0, total = 0.0;
+	float frequency = 1.0, amplitude = 1.0;
+
+	for (int octave = 0; octave < max(int(gen.octaves), 1); octave++) {
+		vec2 q = p * frequency;
+		uint seed = gen.seed + uint(octave) * 0x9e3779b9u;
+		float n;
+
+		if (gen.kind == VALUE) {
+			n = value(q, seed);
+		} else if (gen.kind == PERLIN) {
+			n = perlin(q, seed);
+		} else {
+			n = simplex(q, seed);
+		}
+		sum += n * amplitude;
+		total += amplitude;
+		frequency *= 2.0;
+		amplitude *= 0.5;
+	}
+	return sum / total;
+}
+
+void main() {
+	// Pixel centers, with the origin at the top-left.
+	vec2 p = gl_FragCoord.xy / gen.scale;
+
+	if (gen.kind < CHECKERBOARD) {
+		float v = fbm(p);
+		// Framebuffers are sRGB: decode the value, so that it's stored as is.
+		float c = v <= 0.04045 ? v / 12.92 : pow((v + 0.055) / 1.055, 2.4);
+
+		fragColor = vec4(c, c, c, 1.0);
+	} else if (gen.kind == CHECKERBOARD) {
+		fragColor = mod(floor(p.x) + floor(p.y), 2.0) < 0.5 ? gen.a : gen.b;
+	} else {
+		fragColor = mod(floor(p.x), 2.0) < 0.5 ? gen.a : gen.b;
+	}
+}
@@ -0,0 +1,32 @@
+#version 450
+
+layout(location = 0) in  vec4 f_fill;
+layout(location = 1) in  vec4 f_stroke;
+layout(location = 2) in  vec2 f_local;
+layout(location = 3) in  vec4 f_bounds;
+layout(location = 0) out vec4 fragColor;
+
+// Signed distance to the edge of a rounded box centered on the origin,
+// with the given half size and corner radius. Circles are boxes whose
+// corner radius is their half size.
+float roundedBox(vec2 p, vec2 halfSize, float radius) {
+	vec2 q = abs(p) - halfSize + radius;
+	return length(max(q, 0.0)) + min(max(q.x, q.y), 0.0) - radius;
+}
+
+void main() {
+	float d = roundedBox(f_local, f_bounds.xy, f_bounds.z);
+	float w = max(fwidth(d), 0.0001);
+
+	// Coverage of the whole shape, and of its inside, within the stroke,
+	// whose width is in `f_bounds.w`.
+	float coverage = clamp(0.5 - d / w, 0.0, 1.0);
+	float inner = clamp(0.5 - (d + f_bounds.w) / w, 0.0, 1.0);
+
+	float strokeAlpha = f_stroke.a * (1.0 - inner);
+	float fillAlpha = f_fill.a * inner;
+	float alpha = strokeAlpha + fillAlpha;
+	vec3 color = (f_stroke.rgb * strokeAlpha + f_fill.rgb * fillAlpha) / max(alpha, 0.0001);
+
+	fragColor = vec4(color, alpha * coverage);
+}
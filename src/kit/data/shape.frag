@@ -1,8 +1,70 @@
 #version 450
 
 layout(location = 0) in  vec4 f_color;
+layout(location = 1) in  vec2 f_data;
+layout(location = 2) in  vec4 f_edge;
+layout(location = 3) in  vec4 f_pattern;
+layout(location = 4) in  vec2 f_local;
+layout(location = 5) in  vec4 f_shimmer;
 layout(location = 0) out vec4 fragColor;
 
+// Signed distance to the edge of the shape, given the position relative
+// to the shape center in `xy`. For boxes, `zw` holds the half size, for
+// circles, `z` holds the radius and `w` is zero. When `z` is zero, edges
+// are not smoothed.
+float edgeDistance(vec4 edge) {
+	vec2 p = edge.xy;
+	float box = max(abs(p.x) - edge.z, abs(p.y) - edge.w);
+	float circle = length(p) - edge.z;
+
+	return edge.w > 0.0 ? box : circle;
+}
+
+// Pattern coverage at the given position. `pattern` holds the pattern
+// kind in `x` (none, stripes, hatching or dots), the angle in `y`, the
+// spacing in `z` and the line thickness or dot diameter in `w`.
+float patternMask(vec4 pattern, vec2 p) {
+	float s = sin(pattern.y);
+	float c = cos(pattern.y);
+	vec2 uv = vec2(c * p.x + s * p.y, c * p.y - s * p.x);
+
+	float spacing = max(pattern.z, 1.0);
+	float halfWidth = pattern.w * 0.5;
+	vec2 cell = (fract(uv / spacing + 0.5) - 0.5) * spacing;
+	float w = max(length(fwidth(uv)), 0.0001);
+
+	float stripes = clamp((halfWidth - abs(cell.y)) / w + 0.5, 0.0, 1.0);
+	float hatching = max(stripes, clamp((halfWidth - abs(cell.x)) / w + 0.5, 0.0, 1.0));
+	float dots = clamp((halfWidth - length(cell)) / w + 0.5, 0.0, 1.0);
+
+	if (pattern.x < 0.5) {
+		return 1.0;
+	} else if (pattern.x < 1.5) {
+		return stripes;
+	} else if (pattern.x < 2.5) {
+		return hatching;
+	}
+	return dots;
+}
+
+// Light of a band sweeping across the shape, from left to right. `shimmer`
+// holds the phase of the sweep in `x`, its width in `y`, relative to the
+// shape, and its intensity in `z`.
+float shimmerLight(vec4 shimmer, float x) {
+	if (shimmer.x < 0.0) {
+		return 0.0;
+	}
+	// The band starts and ends outside of the shape.
+	float center = shimmer.x * (1.0 + 2.0 * shimmer.y) - shimmer.y;
+	return shimmer.z * max(1.0 - abs(x - center) / shimmer.y, 0.0);
+}
+
 void main() {
-	fragColor = f_color;
+	float d = edgeDistance(f_edge);
+	float coverage = clamp(0.5 - d / max(fwidth(d), 0.0001), 0.0, 1.0);
+	float mask = patternMask(f_pattern, f_local);
+
+	vec3 color = mix(f_color.rgb, vec3(1.0), shimmerLight(f_shimmer, f_data.x));
+
+	fragColor = vec4(color, f_color.a * (f_edge.z > 0.0 ? coverage : 1.0) * mask);
 }
@@ -3,6 +3,7 @@
 layout(set = 0, binding = 0) uniform Globals {
 	mat4 ortho;
 	mat4 transform;
+	float time;
 } global;
 
 layout(set = 1, binding = 0) uniform Model {
@@ -13,8 +14,24 @@ layout(location = 0) in vec3 position;
 layout(location = 1) in float angle;
 layout(location = 2) in vec2 center;
 layout(location = 3) in vec4 color;
+layout(location = 4) in float opacity;
+layout(location = 5) in vec2 data;
+layout(location = 6) in vec4 edge;
+layout(location = 7) in vec4 pattern;
+layout(location = 8) in vec4 motion;
+layout(location = 9) in vec2 offset;
 
 layout(location = 0) out vec4 f_color;
+layout(location = 1) out vec2 f_data;
+layout(location = 2) out vec4 f_edge;
+layout(location = 3) out vec4 f_pattern;
+layout(location = 4) out vec2 f_local;
+layout(location = 5) out vec4 f_shimmer;
+
+const float PULSE   = 1.0;
+const float BLINK   = 2.0;
+const float SHIMMER = 3.0;
+const float PI      = 3.14159265;
 
 // Convert an sRGB color to linear space.
 vec3 linearize(vec3 srgb) {
@@ -38,8 +55,20 @@ vec2 rotate(vec2 position, vec2 around, float angle) {
 }
 
 void main() {
-	vec2 r = rotate(position.xy, center, angle);
+	// Position in the period of the motion, and a wave going from zero to
+	// one and back over it.
+	float phase = motion.y > 0.0 ? fract(global.time / motion.y) : 0.0;
+	float wave = 0.5 - 0.5 * cos(phase * 2.0 * PI);
+	float blink = motion.x == BLINK ? 1.0 - (1.0 - motion.z) * wave : 1.0;
+
+	// The offset is only set on pulsing vertices.
+	vec2 r = rotate(position.xy + offset * wave, center, angle);
 
-	f_color = vec4(linearize(color.rgb), color.a);
+	f_color = vec4(linearize(color.rgb), color.a * opacity * blink);
+	f_shimmer = vec4(motion.x == SHIMMER ? phase : -1.0, motion.zw, 0.0);
+	f_data = data;
+	f_edge = edge;
+	f_pattern = pattern;
+	f_local = position.xy;
 	gl_Position = global.ortho * global.transform * model.transform * vec4(r, position.z, 1.0);
 }
@@ -0,0 +1,49 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform Globals {
+	mat4 ortho;
+	mat4 transform;
+} global;
+
+layout(set = 1, binding = 0) uniform Model {
+	mat4 transform;
+} model;
+
+// Per-instance attributes.
+layout(location = 0) in vec3  position;
+layout(location = 1) in vec2  scale;
+layout(location = 2) in float angle;
+layout(location = 3) in vec4  uv;
+layout(location = 4) in vec4  color;
+layout(location = 5) in float opacity;
+
+layout(location = 0) out vec2  f_uv;
+layout(location = 1) out vec4  f_color;
+layout(location = 2) out float f_opacity;
+
+// Convert an sRGB color to linear space.
+vec3 linearize(vec3 srgb) {
+    bvec3 cutoff = lessThan(srgb, vec3(0.04045));
+    vec3 higher = pow((srgb + vec3(0.055)) / vec3(1.055), vec3(2.4));
+    vec3 lower = srgb / vec3(12.92);
+
+    return mix(higher, lower, cutoff);
+}
+
+void main() {
+	// Quad corners for vertices 0 to 5, as bit masks:
+	// (0, 0), (1, 0), (1, 1), (0, 0), (0, 1), (1, 1).
+	float cx = float((0x26 >> gl_VertexIndex) & 1);
+	float cy = float((0x34 >> gl_VertexIndex) & 1);
+
+	float s = sin(angle);
+	float c = cos(angle);
+	vec2 d = (vec2(cx, cy) - 0.5) * scale;
+	vec2 p = vec2(c * d.x + s * d.y, c * d.y - s * d.x) + position.xy;
+
+	f_uv = vec2(mix(uv.x, uv.z, cx), mix(uv.w, uv.y, cy));
+	f_color = vec4(linearize(color.rgb), color.a);
+	f_opacity = opacity;
+
+	gl_Position = global.ortho * global.transform * model.transform * vec4(p, position.z, 1.0);
+}
@@ -0,0 +1,27 @@
+#version 450
+
+layout(set = 2, binding = 0) uniform texture2D tex;
+layout(set = 2, binding = 1) uniform sampler   sam;
+layout(set = 2, binding = 2) uniform texture2D mask;
+layout(set = 2, binding = 3) uniform sampler   maskSam;
+
+layout(location = 0) in  vec2  f_uv;
+layout(location = 1) in  vec4  f_color;
+layout(location = 2) in  float f_opacity;
+layout(location = 3) in  vec2  f_mask;
+layout(location = 4) in  vec4  f_region;
+
+layout(location = 0) out vec4 fragColor;
+
+void main() {
+	vec4 texel = texture(sampler2D(tex, sam), f_uv);
+
+	// Fragments outside of the mask are clipped.
+	float coverage = texture(sampler2D(mask, maskSam), mix(f_region.xy, f_region.zw, f_mask)).r;
+	bool inside = all(greaterThanEqual(f_mask, vec2(0.0))) && all(lessThanEqual(f_mask, vec2(1.0)));
+
+	fragColor = vec4(
+		mix(texel.rgb, f_color.rgb, f_color.a),
+		texel.a * f_opacity * (inside ? coverage : 0.0)
+	);
+}
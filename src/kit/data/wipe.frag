@@ -0,0 +1,24 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform Wipe {
+	float progress;
+} wipe;
+
+layout(set = 1, binding = 0) uniform texture2D scene;
+layout(set = 1, binding = 1) uniform sampler   sam;
+layout(set = 1, binding = 2) uniform texture2D mask;
+layout(set = 1, binding = 3) uniform sampler   maskSam;
+
+layout(location = 0) in  vec2 f_uv;
+layout(location = 0) out vec4 fragColor;
+
+void main() {
+	// The scene is revealed where the mask is at most the progress, and
+	// nowhere before the transition starts.
+	float m = texture(sampler2D(mask, maskSam), f_uv).r;
+
+	if (wipe.progress <= 0.0 || m > wipe.progress) {
+		discard;
+	}
+	fragColor = texture(sampler2D(scene, sam), f_uv);
+}
@@ -0,0 +1,122 @@
+use crate::core::Rgba;
+use crate::kit::shape2d::{Batch, Fill, Rotation, Shape, Stroke};
+use crate::kit::ZDepth;
+use crate::math::*;
+use crate::rect::Rect;
+
+/// Seven-segment digit renderer, for quick numeric overlays without a font.
+///
+/// Supports the digits `0` to `9`, as well as `-`, `.`, `:` and spaces.
+/// Other characters are skipped.
+#[derive(Clone, Debug)]
+pub struct Digits {
+    /// Height of a digit.
+    pub height: f32,
+    /// Thickness of a segment.
+    pub thickness: f32,
+    /// Space between characters.
+    pub spacing: f32,
+    pub color: Rgba,
+    pub zdepth: ZDepth,
+}
+
+// Segments, in bit order: top, top-right, bottom-right, bottom,
+// bottom-left, top-left, middle.
+const SEGMENTS: [u8; 10] = [
+    0b011_1111, // 0
+    0b000_0110, // 1
+    0b101_1011, // 2
+    0b100_1111, // 3
+    0b110_0110, // 4
+    0b110_1101, // 5
+    0b111_1101, // 6
+    0b000_0111, // 7
+    0b111_1111, // 8
+    0b110_1111, // 9
+];
+const MINUS: u8 = 0b100_0000;
+
+impl Digits {
+    pub fn new(height: f32, color: Rgba) -> Self {
+        Self {
+            height,
+            thickness: (height / 8.).max(1.),
+            spacing: height / 4.,
+            color,
+            zdepth: ZDepth::default(),
+        }
+    }
+
+    /// Width of a single digit.
+    pub fn width(&self) -> f32 {
+        self.height / 2.
+    }
+
+    /// Add the given text to the batch, with its bottom-left corner at `pos`.
+    /// Returns the width of the rendered text.
+    pub fn add(&self, batch: &mut Batch, text: &str, pos: Point2<f32>) -> f32 {
+        let mut x = pos.x;
+
+        for c in text.chars() {
+            let advance = match c {
+                '0'..='9' => {
+                    let ix = c as usize - '0' as usize;
+                    self.segments(batch, SEGMENTS[ix], x, pos.y);
+                    self.width()
+                }
+                '-' => {
+                    self.segments(batch, MINUS, x, pos.y);
+                    self.width()
+                }
+                '.' => {
+                    self.dot(batch, x, pos.y + self.thickness / 2.);
+                    self.thickness
+                }
+                ':' => {
+                    self.dot(batch, x, pos.y + self.height / 4.);
+                    self.dot(batch, x, pos.y + self.height * 3. / 4.);
+                    self.thickness
+                }
+                ' ' => self.width(),
+                _ => continue,
+            };
+            x += advance + self.spacing;
+        }
+        (x - pos.x - self.spacing).max(0.)
+    }
+
+    fn segments(&self, batch: &mut Batch, mask: u8, x: f32, y: f32) {
+        let (w, h, t) = (self.width(), self.height, self.thickness);
+        let m = y + h / 2.;
+
+        let rects = [
+            Rect::new(x, y + h - t, x + w, y + h),
+            Rect::new(x + w - t, m, x + w, y + h),
+            Rect::new(x + w - t, y, x + w, m),
+            Rect::new(x, y, x + w, y + t),
+            Rect::new(x, y, x + t, m),
+            Rect::new(x, m, x + t, y + h),
+            Rect::new(x, m - t / 2., x + w, m + t / 2.),
+        ];
+        for (i, r) in rects.iter().enumerate() {
+            if mask & (1 << i) != 0 {
+                self.rect(batch, *r);
+            }
+        }
+    }
+
+    fn dot(&self, batch: &mut Batch, x: f32, y: f32) {
+        let t = self.thickness;
+        self.rect(batch, Rect::new(x, y - t / 2., x + t, y + t / 2.));
+    }
+
+    fn rect(&self, batch: &mut Batch, r: Rect<f32>) {
+        batch.add(Shape::Rectangle(
+            r,
+            self.zdepth,
+            Rotation::ZERO,
+            Stroke::NONE,
+            Fill::Solid(self.color),
+        ));
+    }
+}
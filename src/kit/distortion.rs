@@ -0,0 +1,267 @@
+//! Distortion of regions of the rendered scene, eg. for heat haze, water
+//! and shockwaves.
+//!
+//! The scene is rendered to a framebuffer and drawn to the output with the
+//! effects pipeline, prepared with `Effect::NONE`. Each `Distortion` is then
+//! drawn over it with the distortion pipeline, as a quad covering its region,
+//! whose fragment shader offsets the scene's texture coordinates by a
+//! distortion map.
+//!
+//! Distortion maps are textures, eg. images from `kit::procedural`, with the
+//! horizontal offset in red and the vertical offset in green, where `128`
+//! means no offset, scaled by alpha. They should be created with
+//! `TextureFormat::Rgba8`, for offsets not to be converted from sRGB, and
+//! sampled with `AddressMode::Repeat`, so that they wrap around, and
+//! scrolling `src` animates them.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use rgx::kit::distortion::{self, Distortion};
+//! use rgx::kit::effects::{self, Effect};
+//!
+//! let effects: effects::Pipeline = r.pipeline(Blending::default());
+//! let distort: distortion::Pipeline = r.pipeline(Blending::default());
+//! let scene = effects.binding(&r, &framebuffer, &sampler);
+//!
+//! let wave = distortion::shockwave(128, 16.);
+//! let map = r.texture_with_format(wave.w, wave.h, TextureFormat::Rgba8);
+//! let repeat = r.sampler_with(SamplerDescriptor::linear().address_mode(AddressMode::Repeat));
+//! let maps = distort.binding(&r, &framebuffer, &sampler, &map, &repeat);
+//!
+//! r.submit(&[Op::Fill(&map, wave.pixels.as_slice())]);
+//!
+//! // Every frame.
+//! let haze = Distortion::new(Rect::new(0., 0., 128., 128.), Rect::new(x, y, x + 128., y + 128.))
+//!     .strength(6.)
+//!     .finish(&r, &map, w, h);
+//!
+//! r.update_pipeline(&effects, (Effect::NONE, w, h), &mut frame);
+//! {
+//!     let mut pass = frame.pass(PassOp::Clear(Rgba::BLACK), &output);
+//!
+//!     pass.set_pipeline(&effects);
+//!     effects.draw(&scene, &mut pass);
+//!     pass.set_pipeline(&distort);
+//!     pass.draw(&haze, &maps);
+//! }
+//! ```
+use crate::core;
+use crate::core::{Binding, BindingType, Rgba8, Set, ShaderStage};
+use crate::kit::procedural::Image;
+use crate::math::*;
+use crate::rect::Rect;
+
+///////////////////////////////////////////////////////////////////////////
+// Distortion
+///////////////////////////////////////////////////////////////////////////
+
+/// A region of the scene, drawn with its sampling offset by a region of a
+/// distortion map.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Distortion {
+    /// Region of the distortion map, in map pixels.
+    pub src: Rect<f32>,
+    /// Region of the scene to distort, in target pixels, with the origin at
+    /// the top-left, as with `kit::ortho`.
+    pub dst: Rect<f32>,
+    /// Offset of a full intensity map pixel, in target pixels.
+    pub strength: f32,
+}
+
+impl Distortion {
+    pub fn new(src: Rect<f32>, dst: Rect<f32>) -> Self {
+        Self {
+            src,
+            dst,
+            strength: 4.,
+        }
+    }
+
+    pub fn strength(mut self, strength: f32) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    /// Returns the vertices of the distorted region, for a map of size
+    /// `map_w` by `map_h`, and a target of size `w` by `h`, as a quad of two
+    /// triangles.
+    ///
+    /// ```
+    /// use rgx::kit::distortion::Distortion;
+    /// use rgx::rect::Rect;
+    ///
+    /// let d = Distortion::new(Rect::new(0., 0., 4., 4.), Rect::new(0., 0., 64., 32.)).strength(8.);
+    /// let vertices = d.vertices(4, 4, 128, 128);
+    ///
+    /// assert_eq!(vertices.len(), 6);
+    /// // The whole map is sampled, and offsets are scaled to the target.
+    /// assert_eq!(vertices[2].map, rgx::math::Vector2::new(1., 1.));
+    /// assert_eq!(vertices[2].scale, rgx::math::Vector2::new(8. / 128., 8. / 128.));
+    /// ```
+    pub fn vertices(&self, map_w: u32, map_h: u32, w: u32, h: u32) -> Vec<Vertex> {
+        let (w, h) = (w as f32, h as f32);
+        let (mw, mh) = (map_w.max(1) as f32, map_h.max(1) as f32);
+        let Rect { x1, y1, x2, y2 } = self.dst;
+        let src = self.src;
+        let scale = Vector2::new(self.strength / w, self.strength / h);
+        let v = |x: f32, y: f32, s: f32, t: f32| Vertex {
+            position: Vector2::new(x / w * 2. - 1., 1. - y / h * 2.),
+            uv: Vector2::new(x / w, y / h),
+            map: Vector2::new(s / mw, t / mh),
+            scale,
+        };
+        let (a, b) = (v(x1, y1, src.x1, src.y1), v(x2, y1, src.x2, src.y1));
+        let (c, d) = (v(x2, y2, src.x2, src.y2), v(x1, y2, src.x1, src.y2));
+
+        vec![a, b, c, a, d, c]
+    }
+
+    /// Create the vertex buffer of the distorted region, for the given map.
+    pub fn finish(
+        &self,
+        r: &core::Renderer,
+        map: &core::Texture,
+        w: u32,
+        h: u32,
+    ) -> core::VertexBuffer {
+        let buf = self.vertices(map.w, map.h, w, h);
+        r.vertex_buffer(buf.as_slice())
+    }
+}
+
+/// A vertex of a distorted region.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Vertex {
+    /// Position, in clip space.
+    pub position: Vector2<f32>,
+    /// Texture coordinates of the scene.
+    pub uv: Vector2<f32>,
+    /// Texture coordinates of the distortion map.
+    pub map: Vector2<f32>,
+    /// Offset of a full intensity map pixel, in scene texture coordinates.
+    pub scale: Vector2<f32>,
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Pipeline
+///////////////////////////////////////////////////////////////////////////
+
+/// Draws distorted regions of a scene, sampling their distortion map per
+/// fragment.
+pub struct Pipeline {
+    pipeline: core::Pipeline,
+}
+
+impl Pipeline {
+    /// Create a binding for the scene, eg. a `Framebuffer`, and the
+    /// distortion map to offset it with.
+    pub fn binding(
+        &self,
+        renderer: &core::Renderer,
+        scene: &dyn core::Bind,
+        sampler: &core::Sampler,
+        map: &core::Texture,
+        map_sampler: &core::Sampler,
+    ) -> core::BindingGroup {
+        renderer.device.create_binding_group(
+            &self.pipeline.layout.sets[0],
+            &[scene, sampler, map, map_sampler],
+        )
+    }
+}
+
+impl<'a> core::AbstractPipeline<'a> for Pipeline {
+    type PrepareContext = ();
+    type Uniforms = ();
+
+    fn description() -> core::PipelineDescription<'a> {
+        core::PipelineDescription {
+            vertex_layout: &[
+                core::VertexFormat::Float2,
+                core::VertexFormat::Float2,
+                core::VertexFormat::Float2,
+                core::VertexFormat::Float2,
+            ],
+            pipeline_layout: &[Set(&[
+                Binding {
+                    binding: BindingType::SampledTexture,
+                    stage: ShaderStage::Fragment,
+                },
+                Binding {
+                    binding: BindingType::Sampler,
+                    stage: ShaderStage::Fragment,
+                },
+                Binding {
+                    binding: BindingType::SampledTexture,
+                    stage: ShaderStage::Fragment,
+                },
+                Binding {
+                    binding: BindingType::Sampler,
+                    stage: ShaderStage::Fragment,
+                },
+            ])],
+            // TODO: Use `env("CARGO_MANIFEST_DIR")`
+            vertex_shader: include_bytes!("data/distortion.vert.spv"),
+            fragment_shader: include_bytes!("data/distortion.frag.spv"),
+        }
+    }
+
+    fn setup(pipeline: core::Pipeline, _dev: &core::Device) -> Self {
+        Self { pipeline }
+    }
+
+    fn apply(&self, pass: &mut core::Pass) {
+        pass.set_pipeline(&self.pipeline);
+    }
+
+    fn prepare(&'a self, _: ()) -> Option<(&'a core::UniformBuffer, Vec<()>)> {
+        None
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Maps
+///////////////////////////////////////////////////////////////////////////
+
+/// A distortion map of a ring of the given thickness, inscribed in a square
+/// of the given size, pushing outwards, eg. for shockwaves. Growing the
+/// distorted region over time expands the ring.
+///
+/// ```
+/// use rgx::kit::distortion;
+///
+/// let wave = distortion::shockwave(64, 8.);
+/// let center = wave.pixels[32 * 64 + 32];
+/// let ring = wave.pixels[32 * 64 + 60];
+///
+/// assert_eq!(center.a, 0);
+/// assert!(ring.r > 128 && ring.a > 0);
+/// ```
+pub fn shockwave(size: u32, thickness: f32) -> Image {
+    let radius = size as f32 / 2.;
+    let ring = radius - thickness / 2.;
+
+    Image::from_fn(size, size, |x, y| {
+        let (dx, dy) = (x as f32 + 0.5 - radius, y as f32 + 0.5 - radius);
+        let d = (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+        // Intensity peaks in the middle of the ring, and fades to its edges.
+        let t = 1. - ((d - ring).abs() / (thickness / 2.)).min(1.);
+        let t = t * t * (3. - 2. * t);
+
+        encode(dx / d, dy / d, t)
+    })
+}
+
+/// Encode an offset direction and intensity into a distortion map pixel.
+fn encode(x: f32, y: f32, intensity: f32) -> Rgba8 {
+    let channel = |v: f32| (128. + v.clamp(-1., 1.) * 127.).round() as u8;
+
+    Rgba8::new(
+        channel(x),
+        channel(y),
+        0,
+        (intensity.clamp(0., 1.) * 255.).round() as u8,
+    )
+}
@@ -0,0 +1,228 @@
+//! Full-screen post-processing effects, drawn from a source texture or
+//! framebuffer into the target of a pass.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use rgx::kit::effects::{self, Axis, Effect};
+//!
+//! let effects: effects::Pipeline = r.pipeline(Blending::default());
+//! let binding = effects.binding(&r, &scene, &sampler);
+//! let blur = Effect::Blur { radius: 8., axis: Axis::Horizontal };
+//!
+//! r.update_pipeline(&effects, (blur, scene.width(), scene.height()), &mut frame);
+//! {
+//!     let mut pass = frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &blurred);
+//!
+//!     pass.set_pipeline(&effects);
+//!     effects.draw(&binding, &mut pass);
+//! }
+//! ```
+use crate::core;
+use crate::core::{Binding, BindingType, Set, ShaderStage};
+
+///////////////////////////////////////////////////////////////////////////
+// Effect
+///////////////////////////////////////////////////////////////////////////
+
+/// An axis of the screen.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+/// A post-processing effect.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Effect {
+    /// Gaussian blur along one axis, with a radius in source pixels. Blurring
+    /// in both directions takes two passes, through an intermediate
+    /// framebuffer.
+    Blur { radius: f32, axis: Axis },
+    /// Darkening of the corners. The radius and softness are relative to
+    /// the distance from the center to the corners.
+    Vignette {
+        intensity: f32,
+        radius: f32,
+        softness: f32,
+    },
+    /// Color grading with a lookup table, bound with
+    /// `Pipeline::binding_with_lut`. The table is a 256x16 texture of 16
+    /// slices along blue, with red increasing to the right and green
+    /// downwards in each slice, and should be sampled linearly.
+    ColorGrade { intensity: f32 },
+    /// Pixelation into square cells, with a size in source pixels.
+    Pixelate { size: f32 },
+}
+
+impl Effect {
+    /// No effect: the source is drawn as is, sampled with the filtering of
+    /// its sampler, eg. to scale it to the size of the target.
+    pub const NONE: Effect = Effect::Blur {
+        radius: 0.,
+        axis: Axis::Horizontal,
+    };
+}
+
+/// The uniforms of the effect shader.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct Uniforms {
+    params: [f32; 4],
+    texel: [f32; 2],
+    kind: u32,
+    _padding: u32,
+}
+
+impl Uniforms {
+    fn new(effect: Effect, w: u32, h: u32) -> Self {
+        let (kind, params) = match effect {
+            Effect::Blur { radius, axis } => match axis {
+                Axis::Horizontal => (0, [1., 0., radius, 0.]),
+                Axis::Vertical => (0, [0., 1., radius, 0.]),
+            },
+            Effect::Vignette {
+                intensity,
+                radius,
+                softness,
+            } => (1, [intensity, radius, softness, 0.]),
+            Effect::ColorGrade { intensity } => (2, [intensity, 0., 0., 0.]),
+            Effect::Pixelate { size } => (3, [size.max(1.), 0., 0., 0.]),
+        };
+
+        Self {
+            params,
+            texel: [1. / w.max(1) as f32, 1. / h.max(1) as f32],
+            kind,
+            _padding: 0,
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Quad
+///////////////////////////////////////////////////////////////////////////
+
+/// Create a quad covering the whole render target, with `Float2` positions
+/// and texture coordinates, as expected by `data/screen.vert`. Texture
+/// coordinates have their origin at the top-left, so that the source is
+/// sampled upright.
+pub fn screen_quad(dev: &core::Device) -> core::VertexBuffer {
+    #[rustfmt::skip]
+    let vertices: &[(f32, f32, f32, f32)] = &[
+        (-1.0, -1.0, 0.0, 1.0),
+        ( 1.0, -1.0, 1.0, 1.0),
+        ( 1.0,  1.0, 1.0, 0.0),
+        (-1.0, -1.0, 0.0, 1.0),
+        (-1.0,  1.0, 0.0, 0.0),
+        ( 1.0,  1.0, 1.0, 0.0),
+    ];
+    dev.create_buffer(vertices)
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Pipeline
+///////////////////////////////////////////////////////////////////////////
+
+/// Draws a texture or framebuffer over the whole render target, with a
+/// post-processing effect applied.
+pub struct Pipeline {
+    pipeline: core::Pipeline,
+    bindings: core::BindingGroup,
+    buf: core::UniformBuffer,
+    vertices: core::VertexBuffer,
+    /// Bound in place of a lookup table, for effects which don't use one.
+    lut: core::Texture,
+}
+
+impl Pipeline {
+    /// Create a binding for the source, eg. a `Texture` or `Framebuffer`.
+    pub fn binding(
+        &self,
+        renderer: &core::Renderer,
+        source: &dyn core::Bind,
+        sampler: &core::Sampler,
+    ) -> core::BindingGroup {
+        self.binding_with_lut(renderer, source, sampler, &self.lut)
+    }
+
+    /// Create a binding for the source, with a lookup table for
+    /// `Effect::ColorGrade`.
+    pub fn binding_with_lut(
+        &self,
+        renderer: &core::Renderer,
+        source: &dyn core::Bind,
+        sampler: &core::Sampler,
+        lut: &core::Texture,
+    ) -> core::BindingGroup {
+        renderer
+            .device
+            .create_binding_group(&self.pipeline.layout.sets[1], &[source, sampler, lut])
+    }
+
+    pub fn draw(&self, binding: &core::BindingGroup, pass: &mut core::Pass) {
+        pass.draw(&self.vertices, binding);
+    }
+}
+
+impl<'a> core::AbstractPipeline<'a> for Pipeline {
+    /// The effect, and the size of the source in pixels.
+    type PrepareContext = (Effect, u32, u32);
+    type Uniforms = Uniforms;
+
+    fn description() -> core::PipelineDescription<'a> {
+        core::PipelineDescription {
+            vertex_layout: &[core::VertexFormat::Float2, core::VertexFormat::Float2],
+            pipeline_layout: &[
+                Set(&[Binding {
+                    binding: BindingType::UniformBuffer,
+                    stage: ShaderStage::Fragment,
+                }]),
+                Set(&[
+                    Binding {
+                        binding: BindingType::SampledTexture,
+                        stage: ShaderStage::Fragment,
+                    },
+                    Binding {
+                        binding: BindingType::Sampler,
+                        stage: ShaderStage::Fragment,
+                    },
+                    Binding {
+                        binding: BindingType::SampledTexture,
+                        stage: ShaderStage::Fragment,
+                    },
+                ]),
+            ],
+            // TODO: Use `env("CARGO_MANIFEST_DIR")`
+            vertex_shader: include_bytes!("data/screen.vert.spv"),
+            fragment_shader: include_bytes!("data/effect.frag.spv"),
+        }
+    }
+
+    fn setup(pipeline: core::Pipeline, dev: &core::Device) -> Self {
+        let vertices = screen_quad(dev);
+        let buf = dev.create_uniform_buffer(&[Uniforms::new(Effect::Pixelate { size: 1. }, 1, 1)]);
+        let bindings = dev.create_binding_group(&pipeline.layout.sets[0], &[&buf]);
+        let lut = dev.create_texture(1, 1);
+
+        Self {
+            pipeline,
+            bindings,
+            buf,
+            vertices,
+            lut,
+        }
+    }
+
+    fn apply(&self, pass: &mut core::Pass) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_binding(&self.bindings, &[]);
+    }
+
+    fn prepare(
+        &'a self,
+        (effect, w, h): (Effect, u32, u32),
+    ) -> Option<(&'a core::UniformBuffer, Vec<Uniforms>)> {
+        Some((&self.buf, vec![Uniforms::new(effect, w, h)]))
+    }
+}
@@ -0,0 +1,251 @@
+use crate::core::Rgba;
+use crate::kit::shape2d::{Batch, Fill, Line, Rotation, Shape, Stroke};
+use crate::kit::ZDepth;
+use crate::math::*;
+use crate::rect::Rect;
+
+///////////////////////////////////////////////////////////////////////////
+// Handle
+///////////////////////////////////////////////////////////////////////////
+
+/// A part of a selection gizmo, as returned by hit-testing.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Handle {
+    BottomLeft,
+    Bottom,
+    BottomRight,
+    Right,
+    TopRight,
+    Top,
+    TopLeft,
+    Left,
+    /// The rotation handle, above the top edge.
+    Rotate,
+    /// The inside of the selection, eg. to move it.
+    Body,
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Selection
+///////////////////////////////////////////////////////////////////////////
+
+/// A selection marquee, with resize handles on its corners and edges, and
+/// a rotation handle above it.
+///
+/// Gizmos are drawn as shapes in screen space, so that they keep the same
+/// size at any zoom level: add them to a batch drawn without the camera
+/// transform, and hit-test them with screen coordinates.
+#[derive(Clone, Debug)]
+pub struct Selection {
+    pub rect: Rect<f32>,
+    /// Width and height of the handles.
+    pub handle: f32,
+    /// Distance from the top edge to the rotation handle. If `0.0`, the
+    /// rotation handle isn't shown.
+    pub rotate: f32,
+    pub thickness: f32,
+    pub color: Rgba,
+    /// Color inside the handles.
+    pub fill: Rgba,
+    pub zdepth: ZDepth,
+}
+
+impl Selection {
+    pub fn new(rect: Rect<f32>, color: Rgba) -> Self {
+        Self {
+            rect,
+            handle: 8.,
+            rotate: 24.,
+            thickness: 1.,
+            color,
+            fill: Rgba::WHITE,
+            zdepth: ZDepth::default(),
+        }
+    }
+
+    /// The handles and their bounds. Resize handles are listed first,
+    /// counter-clockwise from the bottom-left corner.
+    pub fn handles(&self) -> Vec<(Handle, Rect<f32>)> {
+        let r = self.rect;
+        let (x1, y1) = (r.x1.min(r.x2), r.y1.min(r.y2));
+        let (x2, y2) = (r.x1.max(r.x2), r.y1.max(r.y2));
+        let (mx, my) = ((x1 + x2) / 2., (y1 + y2) / 2.);
+
+        let mut handles = vec![
+            (Handle::BottomLeft, x1, y1),
+            (Handle::Bottom, mx, y1),
+            (Handle::BottomRight, x2, y1),
+            (Handle::Right, x2, my),
+            (Handle::TopRight, x2, y2),
+            (Handle::Top, mx, y2),
+            (Handle::TopLeft, x1, y2),
+            (Handle::Left, x1, my),
+        ];
+        if self.rotate > 0. {
+            handles.push((Handle::Rotate, mx, y2 + self.rotate));
+        }
+        handles
+            .into_iter()
+            .map(|(h, x, y)| (h, self.square(x, y)))
+            .collect()
+    }
+
+    /// The part of the gizmo under the given point, if any. Handles take
+    /// precedence over the body.
+    pub fn hit(&self, p: Point2<f32>) -> Option<Handle> {
+        for (handle, rect) in self.handles() {
+            if contains(rect, p) {
+                return Some(handle);
+            }
+        }
+        if contains(self.rect, p) {
+            return Some(Handle::Body);
+        }
+        None
+    }
+
+    /// Add the gizmo shapes to the batch.
+    pub fn add(&self, batch: &mut Batch) {
+        let stroke = Stroke::new(self.thickness, self.color);
+
+        batch.add(Shape::Rectangle(
+            self.rect,
+            self.zdepth,
+            Rotation::ZERO,
+            stroke.clone(),
+            Fill::Empty(),
+        ));
+        if self.rotate > 0. {
+            let mx = (self.rect.x1 + self.rect.x2) / 2.;
+            let top = self.rect.y1.max(self.rect.y2);
+
+            batch.add(Shape::Line(
+                Line::new(mx, top, mx, top + self.rotate),
+                self.zdepth,
+                Rotation::ZERO,
+                stroke.clone(),
+            ));
+        }
+        for (handle, rect) in self.handles() {
+            if handle == Handle::Rotate {
+                batch.add(Shape::Circle(
+                    Point2::new((rect.x1 + rect.x2) / 2., (rect.y1 + rect.y2) / 2.),
+                    self.zdepth,
+                    self.handle / 2.,
+                    16,
+                    stroke.clone(),
+                    Fill::Solid(self.fill),
+                ));
+            } else {
+                batch.add(Shape::Rectangle(
+                    rect,
+                    self.zdepth,
+                    Rotation::ZERO,
+                    stroke.clone(),
+                    Fill::Solid(self.fill),
+                ));
+            }
+        }
+    }
+
+    fn square(&self, x: f32, y: f32) -> Rect<f32> {
+        let h = self.handle / 2.;
+        Rect::new(x - h, y - h, x + h, y + h)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Crosshair
+///////////////////////////////////////////////////////////////////////////
+
+/// A crosshair centered on a point, eg. to mark a pivot or the cursor.
+#[derive(Clone, Debug)]
+pub struct Crosshair {
+    pub center: Point2<f32>,
+    /// Length of each arm, from the center.
+    pub size: f32,
+    /// Gap around the center, left empty.
+    pub gap: f32,
+    pub thickness: f32,
+    pub color: Rgba,
+    pub zdepth: ZDepth,
+}
+
+impl Crosshair {
+    pub fn new(center: Point2<f32>, size: f32, color: Rgba) -> Self {
+        Self {
+            center,
+            size,
+            gap: 0.,
+            thickness: 1.,
+            color,
+            zdepth: ZDepth::default(),
+        }
+    }
+
+    /// Whether the point is within the crosshair's extent.
+    pub fn hit(&self, p: Point2<f32>) -> bool {
+        (p.x - self.center.x).abs() <= self.size && (p.y - self.center.y).abs() <= self.size
+    }
+
+    pub fn add(&self, batch: &mut Batch) {
+        let Point2 { x, y } = self.center;
+        let (s, g) = (self.size, self.gap);
+        let arms = [
+            Line::new(x - s, y, x - g, y),
+            Line::new(x + g, y, x + s, y),
+            Line::new(x, y - s, x, y - g),
+            Line::new(x, y + g, x, y + s),
+        ];
+        for arm in arms.iter() {
+            batch.add(Shape::Line(
+                *arm,
+                self.zdepth,
+                Rotation::ZERO,
+                Stroke::new(self.thickness, self.color),
+            ));
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Guide
+///////////////////////////////////////////////////////////////////////////
+
+/// A horizontal or vertical guide line, spanning the whole viewport.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Guide {
+    /// A horizontal guide at the given Y coordinate.
+    Horizontal(f32),
+    /// A vertical guide at the given X coordinate.
+    Vertical(f32),
+}
+
+impl Guide {
+    /// Whether the point is within `tolerance` of the guide.
+    pub fn hit(&self, p: Point2<f32>, tolerance: f32) -> bool {
+        match *self {
+            Guide::Horizontal(y) => (p.y - y).abs() <= tolerance,
+            Guide::Vertical(x) => (p.x - x).abs() <= tolerance,
+        }
+    }
+
+    pub fn add(&self, batch: &mut Batch, viewport: Rect<f32>, color: Rgba, zdepth: ZDepth) {
+        let line = match *self {
+            Guide::Horizontal(y) => Line::new(viewport.x1, y, viewport.x2, y),
+            Guide::Vertical(x) => Line::new(x, viewport.y1, x, viewport.y2),
+        };
+        batch.add(Shape::Line(
+            line,
+            zdepth,
+            Rotation::ZERO,
+            Stroke::new(1., color),
+        ));
+    }
+}
+
+/// Whether the rectangle contains the point, including its edges, whatever
+/// the orientation of the rectangle.
+fn contains(r: Rect<f32>, p: Point2<f32>) -> bool {
+    p.x >= r.x1.min(r.x2) && p.x <= r.x1.max(r.x2) && p.y >= r.y1.min(r.y2) && p.y <= r.y1.max(r.y2)
+}
@@ -0,0 +1,111 @@
+use crate::core;
+use crate::core::{Binding, BindingType, Set, ShaderStage};
+use crate::error::Error;
+
+///////////////////////////////////////////////////////////////////////////
+// Histogram
+///////////////////////////////////////////////////////////////////////////
+
+/// Per-channel pixel counts of a texture, with one bin per 8-bit value.
+///
+/// Values are binned in sRGB, ie. as they are stored in the texture and
+/// appear on screen. Luma is computed with the Rec. 709 coefficients.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    pub red: Vec<u32>,
+    pub green: Vec<u32>,
+    pub blue: Vec<u32>,
+    pub luma: Vec<u32>,
+}
+
+impl Histogram {
+    /// Number of bins per channel.
+    pub const BINS: usize = 256;
+
+    fn from_bins(bins: &[u32]) -> Self {
+        let n = Self::BINS;
+
+        Self {
+            red: bins[0..n].to_vec(),
+            green: bins[n..n * 2].to_vec(),
+            blue: bins[n * 2..n * 3].to_vec(),
+            luma: bins[n * 3..n * 4].to_vec(),
+        }
+    }
+
+    /// The highest count across the color channels, useful to normalize
+    /// the histogram for display.
+    pub fn max(&self) -> u32 {
+        self.red
+            .iter()
+            .chain(self.green.iter())
+            .chain(self.blue.iter())
+            .cloned()
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Pipeline
+///////////////////////////////////////////////////////////////////////////
+
+/// Computes the histogram of a texture with a compute pass. Only the bins
+/// are read back from the GPU, not the texture itself.
+///
+/// To compute the histogram of a framebuffer, pass its `texture`.
+pub struct Pipeline {
+    pipeline: core::ComputePipeline,
+}
+
+impl Pipeline {
+    /// Size of a compute workgroup, in pixels along each axis.
+    const WORKGROUP: u32 = 16;
+
+    pub fn new(r: &core::Renderer) -> Self {
+        let pipeline = r.compute_pipeline(
+            &[Set(&[
+                Binding {
+                    binding: BindingType::SampledTexture,
+                    stage: ShaderStage::Compute,
+                },
+                Binding {
+                    binding: BindingType::StorageBuffer,
+                    stage: ShaderStage::Compute,
+                },
+            ])],
+            include_bytes!("data/histogram.comp.spv"),
+        );
+
+        Self { pipeline }
+    }
+
+    /// Compute the histogram of the given texture. Like
+    /// `Renderer::read_pixels_async`, the callback is invoked once the bins
+    /// are mapped, which happens asynchronously, or with
+    /// `Error::ReadFailed`.
+    pub fn compute<F>(&self, r: &mut core::Renderer, texture: &core::Texture, f: F)
+    where
+        F: 'static + FnOnce(Result<Histogram, Error>),
+    {
+        let bins = r.storage_buffer(&vec![0u32; Histogram::BINS * 4]);
+        let binding = r.binding_group(&self.pipeline.layout.sets[0], &[texture, &bins]);
+
+        let mut encoder = r.device.create_command_encoder();
+        {
+            let mut pass = core::ComputePass::begin(&mut encoder);
+
+            pass.set_pipeline(&self.pipeline);
+            pass.set_binding(&binding, &[]);
+            pass.dispatch(
+                texture.w.div_ceil(Self::WORKGROUP),
+                texture.h.div_ceil(Self::WORKGROUP),
+                1,
+            );
+        }
+        r.device.submit(&[encoder.finish()]);
+        r.read_storage_buffer_async(&bins, move |result| {
+            f(result.map(Histogram::from_bins));
+        });
+    }
+}
@@ -0,0 +1,143 @@
+//! Layers rendered at a different resolution than the output, and
+//! composited over it with filtering.
+//!
+//! Supersampled layers, eg. of thin line art, are rendered at a multiple of
+//! the output size, and halved until they reach it, averaging each block of
+//! pixels. Low resolution layers, eg. of particles or blurry backgrounds,
+//! are rendered at a fraction of the output size, which saves fill rate, and
+//! are scaled up with the filtering of the sampler they were created with:
+//! `Filter::Linear` for a smooth result, `Filter::Nearest` for a pixelated
+//! one. Supersampled layers should always use `Filter::Linear`.
+//!
+//! Since pipelines created by the renderer project their vertices with the
+//! size of the swap chain, and not the size of the target, the same batches
+//! can be drawn in a layer as on the output, whatever its scale.
+//!
+//! Layers are composited with the effects pipeline, which must be prepared
+//! with `Effect::NONE`.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use rgx::kit::effects::{self, Effect};
+//! use rgx::kit::layer::Layer;
+//!
+//! let effects: effects::Pipeline = r.pipeline(Blending::default());
+//! let sampler = r.sampler(Filter::Linear, Filter::Linear);
+//!
+//! // Line art, at twice the resolution of the output.
+//! let lines = Layer::new(&r, &effects, &sampler, w, h, 2.);
+//!
+//! r.update_pipeline(&effects, (Effect::NONE, w, h), &mut frame);
+//! {
+//!     let mut pass = frame.pass(PassOp::Clear(Rgba::TRANSPARENT), lines.target());
+//!
+//!     pass.set_pipeline(&shapes);
+//!     pass.draw_buffer(&buffer);
+//! }
+//! lines.resolve(&effects, &mut frame);
+//! {
+//!     let mut pass = frame.pass(PassOp::Load(), &output);
+//!
+//!     lines.draw(&effects, &mut pass);
+//! }
+//! ```
+use crate::core;
+use crate::core::{PassOp, Rgba};
+use crate::kit::effects;
+
+///////////////////////////////////////////////////////////////////////////
+// Layer
+///////////////////////////////////////////////////////////////////////////
+
+/// A layer rendered at a scale of the output size.
+pub struct Layer {
+    /// The target the layer is rendered to.
+    framebuffer: core::Framebuffer,
+    /// Intermediate targets, each half the size of the previous one, which
+    /// supersampled layers are resolved through.
+    downsamples: Vec<core::Framebuffer>,
+    /// Bindings of the target and each intermediate target, as sources.
+    bindings: Vec<core::BindingGroup>,
+    /// Quad covering the render target, sampling the source upright.
+    quad: core::VertexBuffer,
+    scale: f32,
+}
+
+impl Layer {
+    /// Create a layer for an output of the given size, rendered at the given
+    /// scale of it, eg. `2.0` for supersampled, or `0.25` for quarter
+    /// resolution layers.
+    pub fn new(
+        r: &core::Renderer,
+        effects: &effects::Pipeline,
+        sampler: &core::Sampler,
+        w: u32,
+        h: u32,
+        scale: f32,
+    ) -> Self {
+        assert!(scale > 0., "fatal: layer scale must be positive");
+
+        let size = |s: f32| {
+            (
+                (w as f32 * s).round().max(1.) as u32,
+                (h as f32 * s).round().max(1.) as u32,
+            )
+        };
+        let (fw, fh) = size(scale);
+        let framebuffer = r.framebuffer(fw, fh);
+
+        let mut downsamples = Vec::new();
+        let mut s = scale / 2.;
+        while s > 1. {
+            let (dw, dh) = size(s);
+            downsamples.push(r.framebuffer(dw, dh));
+            s /= 2.;
+        }
+        let bindings = std::iter::once(&framebuffer)
+            .chain(downsamples.iter())
+            .map(|fb| effects.binding(r, fb, sampler))
+            .collect();
+
+        Self {
+            framebuffer,
+            downsamples,
+            bindings,
+            quad: effects::screen_quad(&r.device),
+            scale,
+        }
+    }
+
+    /// The target to render the layer to.
+    pub fn target(&self) -> &core::Framebuffer {
+        &self.framebuffer
+    }
+
+    /// The scale of the layer, relative to the output.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Resolve a supersampled layer, once it's rendered, by halving it until
+    /// it's at most twice the output size. Does nothing for other layers.
+    pub fn resolve(&self, effects: &effects::Pipeline, frame: &mut core::Frame) {
+        for (target, binding) in self.downsamples.iter().zip(self.bindings.iter()) {
+            let mut pass = frame.pass(PassOp::Clear(Rgba::TRANSPARENT), target);
+
+            pass.set_pipeline(effects);
+            pass.draw(&self.quad, binding);
+        }
+    }
+
+    /// Composite the layer over the target of the pass, scaling it to the
+    /// size of the target.
+    pub fn draw(&self, effects: &effects::Pipeline, pass: &mut core::Pass) {
+        let binding = self
+            .bindings
+            .last()
+            .expect("a layer always has a binding for its target");
+
+        pass.set_pipeline(effects);
+        pass.draw(&self.quad, binding);
+    }
+}
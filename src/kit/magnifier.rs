@@ -0,0 +1,219 @@
+use crate::core;
+use crate::core::{Binding, BindingType, Rgba, Set, ShaderStage};
+use crate::math::*;
+use crate::rect::Rect;
+
+///////////////////////////////////////////////////////////////////////////
+// Uniforms
+///////////////////////////////////////////////////////////////////////////
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct Uniforms {
+    pub region: Vector4<f32>,
+    pub crosshair: Rgba,
+    pub circular: u32,
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Magnifier
+///////////////////////////////////////////////////////////////////////////
+
+/// A magnified view of a region of a texture or framebuffer, eg. the
+/// current canvas, for color pickers and detail inspection.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Magnifier {
+    /// Region of the source to magnify, in texture coordinates, with the
+    /// origin at the top-left.
+    pub src: Rect<f32>,
+    /// Where to draw the magnified region, in target pixels, with the origin
+    /// at the bottom-left, as with the rest of the kit.
+    pub dst: Rect<f32>,
+    /// Whether the view is clipped to the circle inscribed in `dst`.
+    pub circular: bool,
+    /// Color of the crosshair drawn through the center of the view, if any.
+    pub crosshair: Option<Rgba>,
+}
+
+impl Magnifier {
+    pub fn new(src: Rect<f32>, dst: Rect<f32>) -> Self {
+        Self {
+            src,
+            dst,
+            circular: false,
+            crosshair: None,
+        }
+    }
+
+    /// Magnify the area around `center`, in pixels from the top-left of a
+    /// source of size `w` by `h`, like `src`, by the given zoom factor, into
+    /// `dst`.
+    pub fn around(center: Point2<f32>, w: u32, h: u32, zoom: f32, dst: Rect<f32>) -> Self {
+        let (w, h) = (w as f32, h as f32);
+        let (rx, ry) = (
+            dst.width().abs() / zoom / 2.,
+            dst.height().abs() / zoom / 2.,
+        );
+
+        Self::new(
+            Rect::new(
+                (center.x - rx) / w,
+                (center.y - ry) / h,
+                (center.x + rx) / w,
+                (center.y + ry) / h,
+            ),
+            dst,
+        )
+    }
+
+    pub fn circular(mut self, circular: bool) -> Self {
+        self.circular = circular;
+        self
+    }
+
+    pub fn crosshair(mut self, color: Rgba) -> Self {
+        self.crosshair = Some(color);
+        self
+    }
+
+    /// Returns the vertices of the view, for a target of size `w` by `h`, as
+    /// positions in clip space and texture coordinates. The bottom of `dst`
+    /// shows the bottom of `src`, so the view is upright.
+    ///
+    /// ```
+    /// use rgx::kit::magnifier::Magnifier;
+    /// use rgx::rect::Rect;
+    ///
+    /// let m = Magnifier::new(Rect::new(0.25, 0.25, 0.5, 0.5), Rect::new(0., 0., 50., 25.));
+    /// let vertices = m.vertices(100, 100);
+    ///
+    /// // The bottom-left corner of the view is at the bottom-left of the
+    /// // target, and samples the bottom-left of the region.
+    /// assert_eq!(vertices[0], (-1., -1., 0.25, 0.5));
+    /// // The top-right corner samples the top-right of the region.
+    /// assert_eq!(vertices[2], (0., -0.5, 0.5, 0.25));
+    /// ```
+    pub fn vertices(&self, w: u32, h: u32) -> Vec<(f32, f32, f32, f32)> {
+        let (w, h) = (w as f32, h as f32);
+        let Rect { x1, y1, x2, y2 } = self.dst;
+        let (x1, x2) = (x1 / w * 2. - 1., x2 / w * 2. - 1.);
+        let (y1, y2) = (y1 / h * 2. - 1., y2 / h * 2. - 1.);
+        let src = self.src;
+
+        // Texture coordinates go down from the top, while `dst` goes up.
+        vec![
+            (x1, y1, src.x1, src.y2),
+            (x2, y1, src.x2, src.y2),
+            (x2, y2, src.x2, src.y1),
+            (x1, y1, src.x1, src.y2),
+            (x1, y2, src.x1, src.y1),
+            (x2, y2, src.x2, src.y1),
+        ]
+    }
+
+    pub fn finish(&self, r: &core::Renderer, w: u32, h: u32) -> core::VertexBuffer {
+        let buf = self.vertices(w, h);
+        r.device.create_buffer(buf.as_slice())
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Pipeline
+///////////////////////////////////////////////////////////////////////////
+
+/// Draws a `Magnifier` view of a texture or framebuffer. The source should
+/// be bound with a `Filter::Nearest` sampler, for pixels to stay sharp.
+pub struct Pipeline {
+    pipeline: core::Pipeline,
+    bindings: core::BindingGroup,
+    buf: core::UniformBuffer,
+}
+
+impl Pipeline {
+    /// Create a binding for the source to magnify, eg. a `Texture` or `Framebuffer`.
+    pub fn binding(
+        &self,
+        renderer: &core::Renderer,
+        source: &dyn core::Bind,
+        sampler: &core::Sampler,
+    ) -> core::BindingGroup {
+        renderer
+            .device
+            .create_binding_group(&self.pipeline.layout.sets[1], &[source, sampler])
+    }
+
+    pub fn draw(
+        &self,
+        vertices: &core::VertexBuffer,
+        binding: &core::BindingGroup,
+        pass: &mut core::Pass,
+    ) {
+        pass.draw(vertices, binding);
+    }
+}
+
+impl<'a> core::AbstractPipeline<'a> for Pipeline {
+    type PrepareContext = Magnifier;
+    type Uniforms = self::Uniforms;
+
+    fn description() -> core::PipelineDescription<'a> {
+        core::PipelineDescription {
+            vertex_layout: &[core::VertexFormat::Float2, core::VertexFormat::Float2],
+            pipeline_layout: &[
+                Set(&[Binding {
+                    binding: BindingType::UniformBuffer,
+                    stage: ShaderStage::Fragment,
+                }]),
+                Set(&[
+                    Binding {
+                        binding: BindingType::SampledTexture,
+                        stage: ShaderStage::Fragment,
+                    },
+                    Binding {
+                        binding: BindingType::Sampler,
+                        stage: ShaderStage::Fragment,
+                    },
+                ]),
+            ],
+            // TODO: Use `env("CARGO_MANIFEST_DIR")`
+            vertex_shader: include_bytes!("data/screen.vert.spv"),
+            fragment_shader: include_bytes!("data/magnifier.frag.spv"),
+        }
+    }
+
+    fn setup(pipeline: core::Pipeline, dev: &core::Device) -> Self {
+        let buf = dev.create_uniform_buffer(&[self::Uniforms {
+            region: Vector4::new(0., 0., 1., 1.),
+            crosshair: Rgba::TRANSPARENT,
+            circular: 0,
+        }]);
+        let bindings = dev.create_binding_group(&pipeline.layout.sets[0], &[&buf]);
+
+        Self {
+            pipeline,
+            bindings,
+            buf,
+        }
+    }
+
+    fn apply(&self, pass: &mut core::Pass) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_binding(&self.bindings, &[]);
+    }
+
+    fn prepare(
+        &'a self,
+        magnifier: Magnifier,
+    ) -> Option<(&'a core::UniformBuffer, Vec<self::Uniforms>)> {
+        let src = magnifier.src;
+
+        Some((
+            &self.buf,
+            vec![self::Uniforms {
+                region: Vector4::new(src.x1, src.y1, src.x2, src.y2),
+                crosshair: magnifier.crosshair.unwrap_or(Rgba::TRANSPARENT),
+                circular: magnifier.circular as u32,
+            }],
+        ))
+    }
+}
@@ -0,0 +1,267 @@
+//! Sprites masked by grayscale alpha masks, eg. for reveal effects and
+//! irregular clipping, without stencil buffers.
+//!
+//! Masked sprites are drawn with their own pipeline, a variant of the sprite
+//! pipeline which binds a second texture, the mask, along with the sprite
+//! texture. The mask is placed independently of the sprites, with its own
+//! source and destination rectangles, which the pipeline is prepared with,
+//! and its red channel scales the alpha of the sprites under it. Sprites
+//! outside of the mask are clipped.
+//!
+//! Masks are single channel textures, created with `TextureFormat::R8`, and
+//! filled with the texels of grayscale images from `mask::gray`.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use rgx::kit::mask::{self, Mask};
+//!
+//! let masked: mask::Pipeline = r.pipeline(Blending::default());
+//! let sampler = r.sampler(Filter::Linear, Filter::Linear);
+//!
+//! let texels = mask::gray(&image);
+//! let gradient = r.texture_with_format(image.w, image.h, TextureFormat::R8);
+//! gradient.upload(Rect::origin(image.w, image.h), texels.as_slice(), &mut r);
+//! let binding = masked.binding(&r, &sprite_texture, &sampler, &gradient, &sampler);
+//!
+//! // Every frame, eg. sliding the mask for a reveal.
+//! let mask = Mask::new(image.w, image.h, Rect::new(x, 0., x + 256., h as f32));
+//!
+//! r.update_pipeline(&masked, (kit::ortho(w, h), mask), &mut frame);
+//! {
+//!     let mut pass = frame.pass(PassOp::Load(), &output);
+//!
+//!     pass.set_pipeline(&masked);
+//!     pass.draw(&sprite_buffer, &binding);
+//! }
+//! ```
+use crate::core;
+use crate::core::{Binding, BindingType, Set, ShaderStage};
+use crate::kit::procedural::Image;
+use crate::kit::Model;
+use crate::math::*;
+use crate::rect::Rect;
+
+/// The texels of a mask texture, created with `TextureFormat::R8`, from a
+/// grayscale image, read from its red channel. The mask is opaque where the
+/// image is white.
+///
+/// ```
+/// use rgx::core::Rgba8;
+/// use rgx::kit::mask;
+/// use rgx::kit::procedural::Image;
+///
+/// let gray = Image::from_fn(2, 1, |x, _| {
+///     let v = x as u8 * 0xff;
+///     Rgba8::new(v, v, v, 0xff)
+/// });
+///
+/// assert_eq!(mask::gray(&gray), vec![0, 0xff]);
+/// ```
+pub fn gray(image: &Image) -> Vec<u8> {
+    image.pixels.iter().map(|p| p.r).collect()
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Uniforms
+///////////////////////////////////////////////////////////////////////////
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct Uniforms {
+    pub ortho: Matrix4<f32>,
+    pub transform: Matrix4<f32>,
+    /// Transform from sprite positions to mask coordinates, in the `[0, 1]`
+    /// range over the mask's destination.
+    pub mask: Matrix4<f32>,
+    /// Region of the mask texture, in texture coordinates.
+    pub region: Vector4<f32>,
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Mask
+///////////////////////////////////////////////////////////////////////////
+
+/// Placement of a mask texture over the masked sprites.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mask {
+    /// Size of the mask texture.
+    pub w: u32,
+    pub h: u32,
+    /// Region of the mask texture to use, in texels.
+    pub src: Rect<f32>,
+    /// Where the mask is drawn, in target pixels, with the origin at the
+    /// top-left, as with `kit::ortho`. Sprites outside of it are clipped.
+    pub dst: Rect<f32>,
+}
+
+impl Mask {
+    /// A mask of the whole texture, of size `w` by `h`, drawn over `dst`.
+    pub fn new(w: u32, h: u32, dst: Rect<f32>) -> Self {
+        Self {
+            w,
+            h,
+            src: Rect::origin(w as f32, h as f32),
+            dst,
+        }
+    }
+
+    pub fn src(mut self, src: Rect<f32>) -> Self {
+        self.src = src;
+        self
+    }
+
+    /// The UV transform of the mask: from sprite positions to coordinates
+    /// in the `[0, 1]` range over `dst`.
+    ///
+    /// ```
+    /// use rgx::kit::mask::Mask;
+    /// use rgx::math::{Vector4, Matrix4};
+    /// use rgx::rect::Rect;
+    ///
+    /// let mask = Mask::new(64, 64, Rect::new(10., 20., 50., 40.));
+    /// let m = mask.transform();
+    ///
+    /// assert_eq!(m * Vector4::new(10., 20., 0., 1.), Vector4::new(0., 0., 0., 1.));
+    /// assert_eq!(m * Vector4::new(30., 40., 0., 1.), Vector4::new(0.5, 1., 0., 1.));
+    /// ```
+    pub fn transform(&self) -> Matrix4<f32> {
+        let Rect { x1, y1, x2, y2 } = self.dst;
+        let (w, h) = (x2 - x1, y2 - y1);
+        let (sx, sy) = (
+            if w != 0. { 1. / w } else { 0. },
+            if h != 0. { 1. / h } else { 0. },
+        );
+
+        Matrix4::from_nonuniform_scale(sx, sy, 1.)
+            * Matrix4::from_translation(Vector3::new(-x1, -y1, 0.))
+    }
+
+    /// The region of the mask texture, in texture coordinates.
+    pub fn region(&self) -> Vector4<f32> {
+        let (w, h) = (self.w.max(1) as f32, self.h.max(1) as f32);
+        let src = self.src;
+
+        Vector4::new(src.x1 / w, src.y1 / h, src.x2 / w, src.y2 / h)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Pipeline
+///////////////////////////////////////////////////////////////////////////
+
+/// Draws sprite batches, masked by a mask texture. Prepared with a
+/// projection and the placement of the mask.
+pub struct Pipeline {
+    pipeline: core::Pipeline,
+    bindings: core::BindingGroup,
+    buf: core::UniformBuffer,
+    model: Model,
+}
+
+impl Pipeline {
+    /// Create a binding for the sprite texture, and the mask texture.
+    pub fn binding(
+        &self,
+        renderer: &core::Renderer,
+        texture: &core::Texture,
+        sampler: &core::Sampler,
+        mask: &core::Texture,
+        mask_sampler: &core::Sampler,
+    ) -> core::BindingGroup {
+        renderer.device.create_binding_group(
+            &self.pipeline.layout.sets[2],
+            &[texture, sampler, mask, mask_sampler],
+        )
+    }
+}
+
+impl<'a> core::AbstractPipeline<'a> for Pipeline {
+    type PrepareContext = (Matrix4<f32>, Mask);
+    type Uniforms = self::Uniforms;
+
+    fn description() -> core::PipelineDescription<'a> {
+        core::PipelineDescription {
+            vertex_layout: &[
+                core::VertexFormat::Float3,
+                core::VertexFormat::Float2,
+                core::VertexFormat::UByte4,
+                core::VertexFormat::Float,
+            ],
+            pipeline_layout: &[
+                Set(&[Binding {
+                    binding: BindingType::UniformBuffer,
+                    stage: ShaderStage::Vertex,
+                }]),
+                Set(&[Binding {
+                    binding: BindingType::UniformBuffer,
+                    stage: ShaderStage::Vertex,
+                }]),
+                Set(&[
+                    Binding {
+                        binding: BindingType::SampledTexture,
+                        stage: ShaderStage::Fragment,
+                    },
+                    Binding {
+                        binding: BindingType::Sampler,
+                        stage: ShaderStage::Fragment,
+                    },
+                    Binding {
+                        binding: BindingType::SampledTexture,
+                        stage: ShaderStage::Fragment,
+                    },
+                    Binding {
+                        binding: BindingType::Sampler,
+                        stage: ShaderStage::Fragment,
+                    },
+                ]),
+            ],
+            // TODO: Use `env("CARGO_MANIFEST_DIR")`
+            vertex_shader: include_bytes!("data/sprite_masked.vert.spv"),
+            fragment_shader: include_bytes!("data/sprite_masked.frag.spv"),
+        }
+    }
+
+    fn setup(pipeline: core::Pipeline, dev: &core::Device) -> Self {
+        let model = Model::new(&pipeline.layout.sets[1], &[Matrix4::identity()], dev);
+        let buf = dev.create_uniform_buffer(&[self::Uniforms {
+            ortho: Matrix4::identity(),
+            transform: Matrix4::identity(),
+            mask: Matrix4::identity(),
+            region: Vector4::new(0., 0., 1., 1.),
+        }]);
+        let bindings = dev.create_binding_group(&pipeline.layout.sets[0], &[&buf]);
+
+        Self {
+            pipeline,
+            bindings,
+            buf,
+            model,
+        }
+    }
+
+    fn apply(&self, pass: &mut core::Pass) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_binding(&self.bindings, &[]);
+        pass.set_binding(&self.model.binding, &[]);
+    }
+
+    fn projection(&self) -> Option<&core::UniformBuffer> {
+        Some(&self.buf)
+    }
+
+    fn prepare(
+        &'a self,
+        (ortho, mask): (Matrix4<f32>, Mask),
+    ) -> Option<(&'a core::UniformBuffer, Vec<self::Uniforms>)> {
+        Some((
+            &self.buf,
+            vec![self::Uniforms {
+                ortho,
+                transform: Matrix4::identity(),
+                mask: mask.transform(),
+                region: mask.region(),
+            }],
+        ))
+    }
+}
@@ -2,10 +2,45 @@
 pub use crate::core;
 pub use crate::core::{Bgra8, Rgba, Rgba8};
 
+pub mod adjust;
+pub mod bloom;
+pub mod camera;
+pub mod cull;
+pub mod digits;
+pub mod distortion;
+pub mod effects;
+pub mod gizmo;
+pub mod histogram;
+pub mod layer;
+pub mod magnifier;
+pub mod mask;
+pub mod outline;
+pub mod parallax;
+pub mod particles;
+#[cfg(feature = "pdf")]
+pub mod pdf;
+pub mod pixelgrid;
+pub mod procedural;
+pub mod pyramid;
+pub mod raster;
+pub mod renderer2d;
+pub mod scene;
 pub mod shape2d;
+pub mod snap;
 pub mod sprite2d;
+pub mod svg;
+#[cfg(feature = "text")]
+pub mod text;
+pub mod tilemap;
+pub mod tiles;
+pub mod transform;
+pub mod transition;
+pub mod viewport;
 
-use crate::math::{Matrix4, Ortho};
+pub use camera::Camera2D;
+pub use transform::{Transform2D, TransformStack};
+
+use crate::math::{Matrix4, Ortho, Vector3};
 
 use std::time;
 
@@ -16,6 +51,8 @@ pub enum Origin {
     TopLeft,
 }
 
+/// How many times a texture is tiled across a sprite. Tiling requires the
+/// texture to be sampled with `AddressMode::Repeat`, which is the default.
 #[derive(PartialEq, Clone, Debug)]
 pub struct Repeat {
     pub x: f32,
@@ -35,6 +72,7 @@ impl Default for Repeat {
 }
 
 #[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ZDepth(pub f32);
 
 impl ZDepth {
@@ -175,6 +213,94 @@ pub fn ortho(w: u32, h: u32) -> Matrix4<f32> {
     .into()
 }
 
+/// A perspective projection, for 2.5D views, eg. of extruded shapes. The
+/// camera looks down on the viewport from the Z axis, with the given vertical
+/// field of view, in radians, and from a distance at which anything at depth
+/// zero is projected as with `ortho`. Positive depths are closer to the
+/// camera, and appear larger.
+///
+/// ```
+/// use rgx::kit;
+/// use rgx::math::Vector4;
+///
+/// let (o, p) = (kit::ortho(800, 600), kit::perspective(800, 600, 1.));
+/// let (a, b) = (o * Vector4::new(100., 50., 0., 1.), p * Vector4::new(100., 50., 0., 1.));
+///
+/// assert!((a.x - b.x / b.w).abs() < 1e-5 && (a.y - b.y / b.w).abs() < 1e-5);
+/// ```
+#[rustfmt::skip]
+pub fn perspective(w: u32, h: u32, fovy: f32) -> Matrix4<f32> {
+    let (w, h) = (w as f32, h as f32);
+    // Distance of the camera from the plane at depth zero.
+    let d = h / 2. / (fovy / 2.).tan();
+    let (near, far) = (d / 100., d * 10.);
+    let (sx, sy) = (2. * d / w, 2. * d / h);
+    let a = far / (near - far);
+    let b = near * far / (near - far);
+
+    Matrix4::new(
+        sx,             0.,           0.,         0.,
+        0.,             -sy,          0.,         0.,
+        0.,             0.,           a,          -1.,
+        -sx * w / 2.,   sy * h / 2.,  b - a * d,  d,
+    )
+}
+
+/// A perspective projection for 3D views, with the given vertical field of
+/// view, in radians, aspect ratio and clipping distances. As with `ortho`,
+/// the Y axis points up on screen, and depths are mapped to the `[0, 1]`
+/// range, with the near plane at zero. To be combined with a view, eg. from
+/// `look_at`, as `projection * view`.
+#[rustfmt::skip]
+pub fn perspective_fov(fovy: f32, aspect: f32, near: f32, far: f32) -> Matrix4<f32> {
+    let f = 1. / (fovy / 2.).tan();
+    let a = far / (near - far);
+    let b = near * far / (near - far);
+
+    Matrix4::new(
+        f / aspect, 0., 0., 0.,
+        0.,         -f, 0., 0.,
+        0.,         0., a,  -1.,
+        0.,         0., b,  0.,
+    )
+}
+
+/// A view from an eye position, looking at a target, with the given up
+/// direction, for right-handed coordinates. Points in front of the eye end
+/// up with negative depths, as expected by `perspective_fov`.
+///
+/// ```
+/// use rgx::kit;
+/// use rgx::math::{Vector3, Vector4};
+///
+/// let eye = Vector3::new(0., 0., 10.);
+/// let view = kit::look_at(eye, Vector3::new(0., 0., 0.), Vector3::new(0., 1., 0.));
+///
+/// assert_eq!(view * Vector4::new(0., 0., 0., 1.), Vector4::new(0., 0., -10., 1.));
+/// ```
+#[rustfmt::skip]
+pub fn look_at(eye: Vector3<f32>, target: Vector3<f32>, up: Vector3<f32>) -> Matrix4<f32> {
+    let normalize = |v: Vector3<f32>| {
+        let len = (v.x * v.x + v.y * v.y + v.z * v.z).sqrt();
+        Vector3::new(v.x / len, v.y / len, v.z / len)
+    };
+    let cross = |a: Vector3<f32>, b: Vector3<f32>| {
+        Vector3::new(a.y * b.z - a.z * b.y, a.z * b.x - a.x * b.z, a.x * b.y - a.y * b.x)
+    };
+    let dot = |a: Vector3<f32>, b: Vector3<f32>| a.x * b.x + a.y * b.y + a.z * b.z;
+
+    let f = normalize(Vector3::new(target.x - eye.x, target.y - eye.y, target.z - eye.z));
+    let s = normalize(cross(f, up));
+    let u = cross(s, f);
+
+    Matrix4::new(
+        s.x,           u.x,           -f.x,         0.,
+        s.y,           u.y,           -f.y,         0.,
+        s.z,           u.z,           -f.z,         0.,
+        -dot(s, eye),  -dot(u, eye),  dot(f, eye),  1.,
+    )
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 
 #[derive(Copy, Clone)]
@@ -0,0 +1,214 @@
+//! Screen-space outlines around selected shapes and sprites, eg. to
+//! highlight selected units in games, or selected objects in editors.
+//!
+//! Objects are selected by id, with `Batch::select`, and drawn into the id
+//! target of a `Selection`, each in a flat color encoding its id. The
+//! outline pipeline then draws over the output, as a post effect, every
+//! pixel within the outline thickness of an object other than the one under
+//! it, if any: around the selection, and between touching objects.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use rgx::kit::outline::{self, Outline, Selection};
+//!
+//! let outlines: outline::Pipeline = r.pipeline(Blending::default());
+//! let ids: shape2d::Pipeline = r.pipeline(Blending::constant());
+//! let sampler = r.sampler(Filter::Nearest, Filter::Nearest);
+//! let selection = Selection::new(&r, &outlines, &sampler, w, h);
+//! let style = Outline::new(Rgba::WHITE, 3.);
+//!
+//! // Every frame.
+//! let selected = selection.shapes(&batch.select(&[2, 5])).buffer(&r);
+//!
+//! r.update_pipeline(&ids, kit::ortho(w, h), &mut frame);
+//! r.update_pipeline(&outlines, style, &mut frame);
+//! {
+//!     let mut pass = frame.pass(PassOp::Clear(Rgba::TRANSPARENT), selection.target());
+//!
+//!     pass.set_pipeline(&ids);
+//!     pass.draw_indexed_buffer(&selected);
+//! }
+//! {
+//!     let mut pass = frame.pass(PassOp::Load(), &output);
+//!
+//!     pass.set_pipeline(&outlines);
+//!     outlines.draw(&selection, &mut pass);
+//! }
+//! ```
+use crate::core;
+use crate::core::{Binding, BindingType, Rgba, Set, ShaderStage};
+use crate::kit::{effects, shape2d, sprite2d, Rgba8};
+
+///////////////////////////////////////////////////////////////////////////
+// Outline
+///////////////////////////////////////////////////////////////////////////
+
+/// Color and thickness of outlines.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Outline {
+    /// Color of the outline.
+    pub color: Rgba,
+    /// Thickness of the outline, in pixels, up to `Outline::MAX_THICKNESS`.
+    pub thickness: f32,
+}
+
+impl Outline {
+    /// Thickest outline drawn, in pixels. Thicker outlines are clamped.
+    pub const MAX_THICKNESS: f32 = 16.;
+
+    pub fn new(color: Rgba, thickness: f32) -> Self {
+        Self { color, thickness }
+    }
+}
+
+impl Default for Outline {
+    fn default() -> Self {
+        Self::new(Rgba::WHITE, 2.)
+    }
+}
+
+/// The flat color of the object with the given id, in the id target of a
+/// `Selection`. Ids are distinct up to `2^24`.
+///
+/// ```
+/// use rgx::kit::outline;
+///
+/// assert_ne!(outline::id(1), outline::id(256));
+/// assert_eq!(outline::id(7).a, 255);
+/// ```
+pub fn id(n: usize) -> Rgba8 {
+    Rgba8::new(n as u8, (n >> 8) as u8, (n >> 16) as u8, 255)
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Selection
+///////////////////////////////////////////////////////////////////////////
+
+/// The id target of the selected objects, outlined by the outline pipeline.
+pub struct Selection {
+    framebuffer: core::Framebuffer,
+    binding: core::BindingGroup,
+}
+
+impl Selection {
+    /// Create a selection for an output of the given size. The sampler
+    /// should use `Filter::Nearest`, so that ids aren't blended.
+    pub fn new(
+        r: &core::Renderer,
+        pipeline: &Pipeline,
+        sampler: &core::Sampler,
+        w: u32,
+        h: u32,
+    ) -> Self {
+        let framebuffer = r.framebuffer(w, h);
+        let binding = pipeline.binding(r, &framebuffer, sampler);
+
+        Self {
+            framebuffer,
+            binding,
+        }
+    }
+
+    /// The target to draw the ids of the selected objects to, cleared with
+    /// `Rgba::TRANSPARENT`, with pipelines created with
+    /// `Blending::constant()`.
+    pub fn target(&self) -> &core::Framebuffer {
+        &self.framebuffer
+    }
+
+    /// The given shapes, each in the color of its id: its index in the
+    /// batch. Shapes are tessellated, even in batches with `sdf` set.
+    pub fn shapes(&self, batch: &shape2d::Batch) -> shape2d::Mesh {
+        batch.flat_mesh(id)
+    }
+
+    /// The given sprites, in the color of the given id, keeping their
+    /// texture alpha. Ids of shapes and sprites should be distinct for them
+    /// to be separated by an outline.
+    pub fn sprites(&self, batch: &sprite2d::Batch, n: usize) -> sprite2d::Batch {
+        batch.silhouette(id(n).into())
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Pipeline
+///////////////////////////////////////////////////////////////////////////
+
+/// Draws the outlines of a `Selection` over the whole render target.
+pub struct Pipeline {
+    pipeline: core::Pipeline,
+    bindings: core::BindingGroup,
+    buf: core::UniformBuffer,
+    vertices: core::VertexBuffer,
+}
+
+impl Pipeline {
+    fn binding(
+        &self,
+        renderer: &core::Renderer,
+        ids: &dyn core::Bind,
+        sampler: &core::Sampler,
+    ) -> core::BindingGroup {
+        renderer
+            .device
+            .create_binding_group(&self.pipeline.layout.sets[1], &[ids, sampler])
+    }
+
+    pub fn draw(&self, selection: &Selection, pass: &mut core::Pass) {
+        pass.draw(&self.vertices, &selection.binding);
+    }
+}
+
+impl<'a> core::AbstractPipeline<'a> for Pipeline {
+    type PrepareContext = Outline;
+    type Uniforms = Outline;
+
+    fn description() -> core::PipelineDescription<'a> {
+        core::PipelineDescription {
+            vertex_layout: &[core::VertexFormat::Float2, core::VertexFormat::Float2],
+            pipeline_layout: &[
+                Set(&[Binding {
+                    binding: BindingType::UniformBuffer,
+                    stage: ShaderStage::Fragment,
+                }]),
+                Set(&[
+                    Binding {
+                        binding: BindingType::SampledTexture,
+                        stage: ShaderStage::Fragment,
+                    },
+                    Binding {
+                        binding: BindingType::Sampler,
+                        stage: ShaderStage::Fragment,
+                    },
+                ]),
+            ],
+            // TODO: Use `env("CARGO_MANIFEST_DIR")`
+            vertex_shader: include_bytes!("data/screen.vert.spv"),
+            fragment_shader: include_bytes!("data/outline.frag.spv"),
+        }
+    }
+
+    fn setup(pipeline: core::Pipeline, dev: &core::Device) -> Self {
+        let vertices = effects::screen_quad(dev);
+        let buf = dev.create_uniform_buffer(&[Outline::default()]);
+        let bindings = dev.create_binding_group(&pipeline.layout.sets[0], &[&buf]);
+
+        Self {
+            pipeline,
+            bindings,
+            buf,
+            vertices,
+        }
+    }
+
+    fn apply(&self, pass: &mut core::Pass) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_binding(&self.bindings, &[]);
+    }
+
+    fn prepare(&'a self, outline: Outline) -> Option<(&'a core::UniformBuffer, Vec<Outline>)> {
+        Some((&self.buf, vec![outline]))
+    }
+}
@@ -0,0 +1,153 @@
+use crate::core;
+use crate::kit::sprite2d::Vertex;
+use crate::kit::{Camera2D, Rgba8, ZDepth};
+use crate::math::*;
+use crate::rect::Rect;
+
+///////////////////////////////////////////////////////////////////////////
+// Layer
+///////////////////////////////////////////////////////////////////////////
+
+/// A tiling background layer, scrolled relative to the camera.
+///
+/// Layers are drawn with the sprite pipeline. The texture they are bound
+/// to must be sampled with a repeating address mode, which is what
+/// `Renderer::sampler` creates, so that the layer wraps around seamlessly.
+#[derive(Clone, Debug)]
+pub struct Layer {
+    /// Texture width.
+    pub w: u32,
+    /// Texture height.
+    pub h: u32,
+    /// Scroll factor relative to the camera. A factor of `1.0` scrolls at
+    /// the same speed as the camera, `0.0` keeps the layer fixed.
+    pub factor: Vector2<f32>,
+    /// Constant offset added to the scroll position, in pixels.
+    pub offset: Vector2<f32>,
+    pub depth: ZDepth,
+    pub opacity: f32,
+}
+
+impl Layer {
+    pub fn new(w: u32, h: u32, factor: f32) -> Self {
+        Self {
+            w,
+            h,
+            factor: Vector2::new(factor, factor),
+            offset: Vector2::new(0., 0.),
+            depth: ZDepth::default(),
+            opacity: 1.0,
+        }
+    }
+
+    pub fn factor(mut self, x: f32, y: f32) -> Self {
+        self.factor = Vector2::new(x, y);
+        self
+    }
+
+    pub fn offset(mut self, x: f32, y: f32) -> Self {
+        self.offset = Vector2::new(x, y);
+        self
+    }
+
+    pub fn depth(mut self, depth: ZDepth) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    /// Returns the vertices covering the viewport of the camera, in screen
+    /// pixels, with the texture scrolled by the camera position. Layers are
+    /// drawn with the screen projection, ie. `kit::ortho`, rather than the
+    /// camera's, so that they always fill the viewport.
+    ///
+    /// ```
+    /// use rgx::kit::parallax::Layer;
+    /// use rgx::kit::Camera2D;
+    ///
+    /// let camera = Camera2D::new(640, 480);
+    /// let layer = Layer::new(32, 32, 0.5);
+    ///
+    /// // A single quad, drawn as two triangles.
+    /// assert_eq!(layer.vertices(&camera).len(), 4);
+    /// assert_eq!(layer.indices(), vec![0, 1, 2, 0, 3, 2]);
+    /// ```
+    pub fn vertices(&self, camera: &Camera2D) -> Vec<Vertex> {
+        let w = self.w as f32;
+        let h = self.h as f32;
+
+        let viewport = Rect::origin(camera.w as f32, camera.h as f32);
+        let scroll = Vector2::new(
+            camera.position.x * self.factor.x * camera.zoom,
+            camera.position.y * self.factor.y * camera.zoom,
+        ) + self.offset;
+
+        // Keep the texture coordinates in the `[0, 1)` range at the origin,
+        // to avoid losing precision as the camera moves further away.
+        let u1 = ((viewport.x1 + scroll.x) / w).rem_euclid(1.0);
+        let u2 = u1 + viewport.width() / w;
+        let v2 = (-(viewport.y2 + scroll.y) / h).rem_euclid(1.0);
+        let v1 = v2 + viewport.height() / h;
+
+        let z = *self.depth;
+        let o = self.opacity;
+        let c = Rgba8::TRANSPARENT;
+        let Rect { x1, y1, x2, y2 } = viewport;
+
+        vec![
+            Vertex::new(x1, y1, z, u1, v1, c, o),
+            Vertex::new(x2, y1, z, u2, v1, c, o),
+            Vertex::new(x2, y2, z, u2, v2, c, o),
+            Vertex::new(x1, y2, z, u1, v2, c, o),
+        ]
+    }
+
+    /// Indices into `vertices`, two triangles.
+    pub fn indices(&self) -> Vec<u32> {
+        vec![0, 1, 2, 0, 3, 2]
+    }
+
+    pub fn finish(&self, r: &core::Renderer, camera: &Camera2D) -> core::IndexedBuffer {
+        let vertices = self.vertices(camera);
+        let indices = self.indices();
+
+        core::IndexedBuffer {
+            vertices: r.device.create_buffer(vertices.as_slice()),
+            indices: r.device.create_index_buffer(indices.as_slice()),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Parallax
+///////////////////////////////////////////////////////////////////////////
+
+/// A stack of background layers, ordered from back to front.
+#[derive(Clone, Debug, Default)]
+pub struct Parallax {
+    pub layers: Vec<Layer>,
+}
+
+impl Parallax {
+    pub fn new() -> Self {
+        Self { layers: Vec::new() }
+    }
+
+    pub fn add(&mut self, layer: Layer) {
+        self.layers.push(layer);
+    }
+
+    /// Returns one buffer per layer, in the same order as the layers. Each
+    /// buffer should be drawn with its layer's texture binding.
+    pub fn finish(&self, r: &core::Renderer, camera: &Camera2D) -> Vec<core::IndexedBuffer> {
+        self.layers.iter().map(|l| l.finish(r, camera)).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+}
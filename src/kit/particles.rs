@@ -0,0 +1,362 @@
+//! Particle systems, simulated on the CPU and drawn with the instanced
+//! sprite pipeline.
+//!
+//! An `Emitter` spawns particles at a steady rate, or in bursts, and moves
+//! them every frame. Their size, speed and color follow curves over their
+//! lifetime. Once updated, the living particles are added to an
+//! `InstanceBatch`, one instance each.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use rgx::core::Rgba;
+//! use rgx::kit::particles::{Curve, Emitter};
+//! use rgx::kit::shape2d::Gradient;
+//! use rgx::kit::sprite2d::InstanceBatch;
+//! use rgx::math::Point2;
+//! use rgx::rect::Rect;
+//!
+//! let mut sparks = Emitter::new(Point2::new(100., 100.), Rect::new(0., 0., 8., 8.))
+//!     .rate(100.)
+//!     .lifetime(0.5, 1.5)
+//!     .size(Curve::linear(8., 0.))
+//!     .color(Gradient::new(&[(0., Rgba::WHITE), (1., Rgba::new(1., 0.5, 0., 0.))]));
+//!
+//! sparks.update(Duration::from_millis(100));
+//! assert_eq!(sparks.len(), 10);
+//!
+//! let mut batch = InstanceBatch::new(8, 8);
+//! sparks.fill(&mut batch);
+//! assert_eq!(batch.len(), 10);
+//! ```
+use std::f32;
+use std::time::Duration;
+
+use crate::core::Rgba;
+use crate::kit::shape2d::Gradient;
+use crate::kit::sprite2d::InstanceBatch;
+use crate::kit::ZDepth;
+use crate::math::*;
+use crate::rect::Rect;
+
+///////////////////////////////////////////////////////////////////////////
+// Curve
+///////////////////////////////////////////////////////////////////////////
+
+/// A value over the lifetime of a particle, given by keys at positions in
+/// the `[0, 1]` range, and linearly interpolated between them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Curve {
+    keys: Vec<(f32, f32)>,
+}
+
+impl Curve {
+    /// A curve through the given keys, as `(position, value)` pairs.
+    pub fn new(keys: &[(f32, f32)]) -> Self {
+        let mut keys = keys.to_vec();
+        keys.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        Self { keys }
+    }
+
+    pub fn constant(value: f32) -> Self {
+        Self::new(&[(0., value)])
+    }
+
+    pub fn linear(from: f32, to: f32) -> Self {
+        Self::new(&[(0., from), (1., to)])
+    }
+
+    /// The value at a position along the curve.
+    pub fn at(&self, t: f32) -> f32 {
+        let (first, last) = match (self.keys.first(), self.keys.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return 0.,
+        };
+        if t <= first.0 {
+            return first.1;
+        }
+        if t >= last.0 {
+            return last.1;
+        }
+        for w in self.keys.windows(2) {
+            let ((t1, v1), (t2, v2)) = (w[0], w[1]);
+
+            if t <= t2 {
+                let s = if t2 > t1 { (t - t1) / (t2 - t1) } else { 1. };
+                return v1 + (v2 - v1) * s;
+            }
+        }
+        last.1
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Particle
+///////////////////////////////////////////////////////////////////////////
+
+/// A living particle.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Particle {
+    pub position: Point2<f32>,
+    /// Initial velocity, in pixels per second, scaled by the speed curve.
+    pub velocity: Vector2<f32>,
+    /// Velocity gained from gravity, which isn't scaled.
+    pub drift: Vector2<f32>,
+    /// Rotation, in radians, and rotation speed, in radians per second.
+    pub angle: f32,
+    pub spin: f32,
+    /// Time lived and lifetime, in seconds.
+    pub age: f32,
+    pub lifetime: f32,
+}
+
+impl Particle {
+    /// Position in the lifetime of the particle, in the `[0, 1]` range.
+    pub fn life(&self) -> f32 {
+        (self.age / self.lifetime).min(1.)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Emitter
+///////////////////////////////////////////////////////////////////////////
+
+/// Spawns, moves and retires particles.
+#[derive(Clone, Debug)]
+pub struct Emitter {
+    /// Where particles are spawned.
+    pub position: Point2<f32>,
+    /// Texture region of particles.
+    pub src: Rect<f32>,
+    pub depth: ZDepth,
+    /// Particles spawned per second.
+    pub rate: f32,
+    /// Most particles alive at once. Particles aren't spawned past it.
+    pub max: usize,
+    /// Minimum and maximum lifetime, in seconds.
+    pub lifetime: (f32, f32),
+    /// Direction particles are spawned in, in radians, counter-clockwise
+    /// from the X axis, and the spread around it.
+    pub direction: f32,
+    pub spread: f32,
+    /// Minimum and maximum initial speed, in pixels per second.
+    pub speed: (f32, f32),
+    /// Minimum and maximum rotation speed, in radians per second.
+    pub spin: (f32, f32),
+    /// Acceleration, in pixels per second squared.
+    pub gravity: Vector2<f32>,
+    /// Size over life, in pixels.
+    pub size: Curve,
+    /// Speed over life, as a multiplier of the initial speed.
+    pub velocity: Curve,
+    /// Color over life.
+    pub color: Gradient,
+
+    particles: Vec<Particle>,
+    /// Time since the last particle was spawned, in seconds.
+    elapsed: f32,
+    rng: Rng,
+}
+
+impl Emitter {
+    /// An emitter of white particles, spawned upwards, 10 per second.
+    pub fn new(position: Point2<f32>, src: Rect<f32>) -> Self {
+        Self {
+            position,
+            src,
+            depth: ZDepth::default(),
+            rate: 10.,
+            max: 1024,
+            lifetime: (1., 1.),
+            direction: f32::consts::FRAC_PI_2,
+            spread: f32::consts::FRAC_PI_4,
+            speed: (50., 100.),
+            spin: (0., 0.),
+            gravity: Vector2::new(0., 0.),
+            size: Curve::constant(src.width().abs()),
+            velocity: Curve::constant(1.),
+            color: Gradient::new(&[(0., Rgba::WHITE)]),
+            particles: Vec::new(),
+            elapsed: 0.,
+            rng: Rng::new(0x2545_f491),
+        }
+    }
+
+    pub fn rate(mut self, rate: f32) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    pub fn max(mut self, max: usize) -> Self {
+        self.max = max;
+        self
+    }
+
+    pub fn lifetime(mut self, min: f32, max: f32) -> Self {
+        self.lifetime = (min, max);
+        self
+    }
+
+    pub fn direction(mut self, direction: f32, spread: f32) -> Self {
+        self.direction = direction;
+        self.spread = spread;
+        self
+    }
+
+    pub fn speed(mut self, min: f32, max: f32) -> Self {
+        self.speed = (min, max);
+        self
+    }
+
+    pub fn spin(mut self, min: f32, max: f32) -> Self {
+        self.spin = (min, max);
+        self
+    }
+
+    pub fn gravity(mut self, x: f32, y: f32) -> Self {
+        self.gravity = Vector2::new(x, y);
+        self
+    }
+
+    pub fn size(mut self, size: Curve) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn velocity(mut self, velocity: Curve) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    pub fn color(mut self, color: Gradient) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn depth(mut self, depth: ZDepth) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Seed the random number generator, eg. for particles to be the same
+    /// across runs.
+    pub fn seed(mut self, seed: u32) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    /// Advance the simulation: age and move particles, retire those past
+    /// their lifetime, and spawn new ones at the emitter's rate.
+    pub fn update(&mut self, delta: Duration) {
+        let dt = delta.as_secs_f32();
+
+        for p in self.particles.iter_mut() {
+            p.age += dt;
+            p.drift += self.gravity * dt;
+            p.position += (p.velocity * self.velocity.at(p.life()) + p.drift) * dt;
+            p.angle += p.spin * dt;
+        }
+        self.particles.retain(|p| p.age < p.lifetime);
+
+        if self.rate > 0. {
+            self.elapsed += dt;
+
+            let count = (self.elapsed * self.rate).floor();
+
+            self.elapsed -= count / self.rate;
+            self.burst(count as usize);
+        }
+    }
+
+    /// Spawn particles at once, eg. for an explosion.
+    pub fn burst(&mut self, count: usize) {
+        let count = count.min(self.max.saturating_sub(self.particles.len()));
+
+        for _ in 0..count {
+            let angle = self.direction + self.spread * (self.rng.unit() * 2. - 1.);
+            let speed = self.rng.between(self.speed);
+            let (sin, cos) = angle.sin_cos();
+
+            self.particles.push(Particle {
+                position: self.position,
+                velocity: Vector2::new(cos * speed, sin * speed),
+                drift: Vector2::new(0., 0.),
+                angle: 0.,
+                spin: self.rng.between(self.spin),
+                age: 0.,
+                lifetime: self.rng.between(self.lifetime).max(f32::EPSILON),
+            });
+        }
+    }
+
+    /// Add the living particles to a batch, which should have the size of
+    /// the particle texture.
+    pub fn fill(&self, batch: &mut InstanceBatch) {
+        for p in self.particles.iter() {
+            let life = p.life();
+            let size = self.size.at(life) / 2.;
+            let dst = Rect::new(
+                p.position.x - size,
+                p.position.y - size,
+                p.position.x + size,
+                p.position.y + size,
+            );
+            batch.add(
+                self.src,
+                dst,
+                self.depth,
+                p.angle,
+                self.color.color(life),
+                1.,
+            );
+        }
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    /// Retire all particles.
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.elapsed = 0.;
+    }
+}
+
+/// A xorshift random number generator. Not suitable for anything but
+/// visual effects.
+#[derive(Clone, Debug)]
+struct Rng(u32);
+
+impl Rng {
+    fn new(seed: u32) -> Self {
+        Self(seed.max(1))
+    }
+
+    /// A number in the `[0, 1)` range.
+    fn unit(&mut self) -> f32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+
+        (x >> 8) as f32 / (1 << 24) as f32
+    }
+
+    /// A number between a minimum and a maximum.
+    fn between(&mut self, (min, max): (f32, f32)) -> f32 {
+        min + (max - min) * self.unit()
+    }
+}
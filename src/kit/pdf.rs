@@ -0,0 +1,658 @@
+use std::fmt::Write;
+
+use crate::core::Rgba;
+use crate::kit::shape2d::{Batch, Cap, Fill, Join, Path, PatternStyle, Rotation, Segment, Shape};
+#[cfg(feature = "text")]
+use crate::kit::text::TextBatch;
+use crate::math::*;
+use crate::rect::Rect;
+
+/// Export a shape batch as a single-page PDF document of the given size,
+/// in points. See `Document` for details.
+pub fn export(batch: &Batch, w: u32, h: u32) -> Vec<u8> {
+    let mut doc = Document::new(w, h);
+    doc.add_shapes(batch);
+    doc.finish()
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Document
+///////////////////////////////////////////////////////////////////////////
+
+/// A single-page PDF document, built from shape and text batches.
+///
+/// Batches are drawn in the order they are added, and shapes in the order
+/// they were added to their batch; depth is ignored. Text is exported as
+/// glyph outlines, so the document doesn't depend on the font. PDF shares
+/// the Y-up coordinate system of an orthographic projection with its
+/// origin at the bottom-left, so one unit maps to one point.
+///
+/// Gradients ignore the alpha of their color stops, and gradient strokes
+/// are exported with their first color. Meshes are skipped.
+#[derive(Debug)]
+pub struct Document {
+    pub w: u32,
+    pub h: u32,
+
+    content: String,
+    /// Graphics states, as fill and stroke alpha.
+    states: Vec<(f32, f32)>,
+    /// Shading dictionaries, for gradients.
+    shadings: Vec<String>,
+}
+
+impl Document {
+    pub fn new(w: u32, h: u32) -> Self {
+        Self {
+            w,
+            h,
+            content: String::new(),
+            states: Vec::new(),
+            shadings: Vec::new(),
+        }
+    }
+
+    /// Draw the shapes of the batch.
+    pub fn add_shapes(&mut self, batch: &Batch) {
+        for (shape, opacity, transform) in batch.items.iter() {
+            if let Some(m) = transform {
+                self.op("q");
+                writeln!(
+                    self.content,
+                    "{} {} {} {} {} {} cm",
+                    m.x.x, m.x.y, m.y.x, m.y.y, m.w.x, m.w.y
+                )
+                .ok();
+                self.shape(shape, opacity * batch.opacity);
+                self.op("Q");
+            } else {
+                self.shape(shape, opacity * batch.opacity);
+            }
+        }
+    }
+
+    /// Draw the text of the batch.
+    #[cfg(feature = "text")]
+    pub fn add_text(&mut self, batch: &TextBatch) {
+        for run in batch.runs() {
+            self.begin(&run.rotation(), run.color.a, 1.);
+            self.color("rg", run.color);
+
+            for path in batch.font.run_outlines(run) {
+                self.path(&path);
+            }
+            self.op("f");
+            self.op("Q");
+        }
+    }
+
+    /// Serialize the document.
+    pub fn finish(self) -> Vec<u8> {
+        let mut resources = String::new();
+
+        if !self.states.is_empty() {
+            resources.push_str("/ExtGState <<");
+            for (i, (fill, stroke)) in self.states.iter().enumerate() {
+                write!(resources, " /GS{} << /ca {} /CA {} >>", i, fill, stroke).ok();
+            }
+            resources.push_str(" >> ");
+        }
+        if !self.shadings.is_empty() {
+            resources.push_str("/Shading <<");
+            for (i, shading) in self.shadings.iter().enumerate() {
+                write!(resources, " /Sh{} {}", i, shading).ok();
+            }
+            resources.push_str(" >> ");
+        }
+
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_owned(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_owned(),
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << {}>> /Contents 4 0 R >>",
+                self.w, self.h, resources
+            ),
+            format!(
+                "<< /Length {} >>\nstream\n{}endstream",
+                self.content.len(),
+                self.content
+            ),
+        ];
+
+        let mut out = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::with_capacity(objects.len());
+
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            write!(out, "{} 0 obj\n{}\nendobj\n", i + 1, object).ok();
+        }
+        let xref = out.len();
+
+        write!(out, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).ok();
+        for offset in offsets {
+            writeln!(out, "{:010} 00000 n ", offset).ok();
+        }
+        write!(
+            out,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        )
+        .ok();
+
+        out.into_bytes()
+    }
+
+    fn shape(&mut self, shape: &Shape, opacity: f32) {
+        match shape {
+            Shape::Line(l, _, rot, stroke) => {
+                if stroke.width > 0. {
+                    self.begin(rot, 1., stroke.color.a * opacity);
+                    self.stroke_style(stroke.color, stroke.width, stroke.join, stroke.cap);
+                    self.move_to(Point2::new(l.p1.x, l.p1.y));
+                    self.line_to(Point2::new(l.p2.x, l.p2.y));
+                    self.op("S");
+                    self.op("Q");
+                }
+            }
+            Shape::Rectangle(r, _, rot, stroke, fill) => {
+                let bounds = Rect::new(
+                    r.x1.min(r.x2),
+                    r.y1.min(r.y2),
+                    r.x1.max(r.x2),
+                    r.y1.max(r.y2),
+                );
+                let path = |doc: &mut Self| {
+                    doc.rect(bounds);
+                };
+
+                self.begin(rot, fill_alpha(fill) * opacity, stroke.color.a * opacity);
+                self.fill(fill, bounds, false, path);
+
+                // Strokes are drawn on the inside of the rectangle.
+                if stroke.width > 0. {
+                    let s = stroke.width / 2.;
+
+                    self.stroke_style(stroke.color, stroke.width, Join::Miter, Cap::Butt);
+                    self.rect(Rect::new(
+                        bounds.x1 + s,
+                        bounds.y1 + s,
+                        (bounds.x2 - s).max(bounds.x1 + s),
+                        (bounds.y2 - s).max(bounds.y1 + s),
+                    ));
+                    self.op("S");
+                }
+                self.op("Q");
+            }
+            Shape::Circle(center, _, radius, _, stroke, fill) => {
+                let (center, radius) = (*center, *radius);
+                let bounds = Rect::new(
+                    center.x - radius,
+                    center.y - radius,
+                    center.x + radius,
+                    center.y + radius,
+                );
+
+                self.begin(
+                    &Rotation::ZERO,
+                    fill_alpha(fill) * opacity,
+                    stroke.color.a * opacity,
+                );
+                self.fill(fill, bounds, true, |doc| doc.circle(center, radius));
+
+                // Strokes are drawn on the inside of the circle.
+                if stroke.width > 0. {
+                    self.stroke_style(stroke.color, stroke.width, Join::Miter, Cap::Butt);
+                    self.circle(center, (radius - stroke.width / 2.).max(0.));
+                    self.op("S");
+                }
+                self.op("Q");
+            }
+            Shape::Polygon(points, _, rot, stroke, fill) => {
+                if points.is_empty() {
+                    return;
+                }
+                let bounds = bounds(points);
+                let path = |doc: &mut Self| {
+                    doc.polyline(points);
+                    doc.op("h");
+                };
+
+                self.begin(rot, fill_alpha(fill) * opacity, stroke.color.a * opacity);
+                self.fill(fill, bounds, false, path);
+
+                // Strokes are drawn on the inside of the outline: draw a
+                // stroke twice as wide, clipped to the polygon.
+                if stroke.width > 0. {
+                    self.op("q");
+                    path(self);
+                    self.op("W n");
+                    self.stroke_style(stroke.color, stroke.width * 2., Join::Miter, Cap::Butt);
+                    path(self);
+                    self.op("S");
+                    self.op("Q");
+                }
+                self.op("Q");
+            }
+            Shape::Polyline(points, _, rot, stroke) => {
+                if stroke.width > 0. && !points.is_empty() {
+                    self.begin(rot, 1., stroke.color.a * opacity);
+                    self.stroke_style(stroke.color, stroke.width, stroke.join, stroke.cap);
+                    self.polyline(points);
+                    self.op("S");
+                    self.op("Q");
+                }
+            }
+            Shape::RoundedRectangle(r, radius, z, rot, stroke, fill) => {
+                let polygon = Shape::Polygon(
+                    Shape::rounded_outline(*r, *radius),
+                    *z,
+                    rot.clone(),
+                    stroke.clone(),
+                    fill.clone(),
+                );
+                self.shape(&polygon, opacity);
+            }
+            Shape::Triangle(a, b, c, z, rot, stroke, fill) => {
+                let polygon = Shape::Polygon(
+                    vec![*a, *b, *c],
+                    *z,
+                    rot.clone(),
+                    stroke.clone(),
+                    fill.clone(),
+                );
+                self.shape(&polygon, opacity);
+            }
+            Shape::Mesh(_) => {}
+            Shape::Path(path, _, rot, stroke, fill) => {
+                let mut points = vec![path.start];
+                for segment in path.segments.iter() {
+                    match *segment {
+                        Segment::Line(p) => points.push(p),
+                        Segment::Quadratic(c, p) => points.extend_from_slice(&[c, p]),
+                        Segment::Cubic(c1, c2, p) => points.extend_from_slice(&[c1, c2, p]),
+                    }
+                }
+                let bounds = bounds(&points);
+
+                self.begin(rot, fill_alpha(fill) * opacity, stroke.color.a * opacity);
+                self.fill(fill, bounds, false, |doc| doc.path(path));
+
+                if stroke.width > 0. {
+                    self.stroke_style(stroke.color, stroke.width, stroke.join, stroke.cap);
+                    self.path(path);
+                    self.op("S");
+                }
+                self.op("Q");
+            }
+        }
+    }
+
+    /// Fill the path constructed by `path`, whose bounds are given.
+    /// Circles get radial gradients, other shapes linear gradients.
+    fn fill<F>(&mut self, fill: &Fill, bounds: Rect<f32>, radial: bool, path: F)
+    where
+        F: Fn(&mut Self),
+    {
+        match fill {
+            Fill::Empty() => {}
+            Fill::Solid(color) => {
+                self.color("rg", *color);
+                path(self);
+                self.op("f");
+            }
+            Fill::Gradient(from, to, angle) => {
+                let stops = [(0., *from), (1., *to)];
+                self.gradient(&stops, bounds, radial, *angle, path);
+            }
+            Fill::Stops(gradient) => {
+                if !gradient.is_empty() {
+                    self.gradient(gradient.stops(), bounds, radial, 0., path);
+                }
+            }
+            Fill::Pattern(color, pattern) => {
+                let (s, t) = (pattern.spacing.max(1.), pattern.thickness);
+                let (sin, cos) = pattern.angle.sin_cos();
+
+                self.op("q");
+                path(self);
+                self.op("W n");
+                self.color("rg", *color);
+
+                // Lay the pattern out in its own rotated space, covering
+                // the shape's bounds.
+                writeln!(self.content, "{} {} {} {} 0 0 cm", cos, sin, -sin, cos).ok();
+
+                let corners = [
+                    (bounds.x1, bounds.y1),
+                    (bounds.x2, bounds.y1),
+                    (bounds.x1, bounds.y2),
+                    (bounds.x2, bounds.y2),
+                ];
+                let rotated = corners
+                    .iter()
+                    .map(|(x, y)| Point2::new(x * cos + y * sin, y * cos - x * sin))
+                    .collect::<Vec<_>>();
+                let area = self::bounds(&rotated);
+
+                // Lines and dots are centered on multiples of the spacing.
+                let (x1, x2) = ((area.x1 / s).floor() as i32, (area.x2 / s).ceil() as i32);
+                let (y1, y2) = ((area.y1 / s).floor() as i32, (area.y2 / s).ceil() as i32);
+                let (w, h) = (area.x2 - area.x1, area.y2 - area.y1);
+
+                match pattern.style {
+                    PatternStyle::Stripes | PatternStyle::Hatching => {
+                        for y in y1..=y2 {
+                            self.rect(Rect::new(
+                                area.x1,
+                                y as f32 * s - t / 2.,
+                                area.x1 + w,
+                                y as f32 * s + t / 2.,
+                            ));
+                        }
+                        if pattern.style == PatternStyle::Hatching {
+                            for x in x1..=x2 {
+                                self.rect(Rect::new(
+                                    x as f32 * s - t / 2.,
+                                    area.y1,
+                                    x as f32 * s + t / 2.,
+                                    area.y1 + h,
+                                ));
+                            }
+                        }
+                    }
+                    PatternStyle::Dots => {
+                        for y in y1..=y2 {
+                            for x in x1..=x2 {
+                                self.circle(Point2::new(x as f32 * s, y as f32 * s), t / 2.);
+                            }
+                        }
+                    }
+                }
+                self.op("f");
+                self.op("Q");
+            }
+        }
+    }
+
+    /// Fill the path with a gradient. Linear gradients run in the
+    /// direction of the angle, across the bounds, radial gradients from the
+    /// center outwards.
+    fn gradient<F>(
+        &mut self,
+        stops: &[(f32, Rgba)],
+        bounds: Rect<f32>,
+        radial: bool,
+        angle: f32,
+        path: F,
+    ) where
+        F: Fn(&mut Self),
+    {
+        let (cx, cy) = ((bounds.x1 + bounds.x2) / 2., (bounds.y1 + bounds.y2) / 2.);
+        let (w, h) = (bounds.x2 - bounds.x1, bounds.y2 - bounds.y1);
+
+        let shading = if radial {
+            format!(
+                "<< /ShadingType 3 /ColorSpace /DeviceRGB /Coords [{} {} 0 {} {} {}] /Function {} /Extend [true true] >>",
+                cx,
+                cy,
+                cx,
+                cy,
+                w.max(h) / 2.,
+                function(stops)
+            )
+        } else {
+            let (dx, dy) = (angle.cos() * w / 2., angle.sin() * h / 2.);
+            format!(
+                "<< /ShadingType 2 /ColorSpace /DeviceRGB /Coords [{} {} {} {}] /Function {} /Extend [true true] >>",
+                cx - dx,
+                cy - dy,
+                cx + dx,
+                cy + dy,
+                function(stops)
+            )
+        };
+        self.shadings.push(shading);
+
+        self.op("q");
+        path(self);
+        self.op("W n");
+        writeln!(self.content, "/Sh{} sh", self.shadings.len() - 1).ok();
+        self.op("Q");
+    }
+
+    /// Save the graphics state, and set up the rotation and alpha of a
+    /// shape. Must be matched with a `Q` operator.
+    fn begin(&mut self, rot: &Rotation, fill: f32, stroke: f32) {
+        self.op("q");
+
+        if fill < 1. || stroke < 1. {
+            let state = (fill, stroke);
+            let ix = match self.states.iter().position(|s| *s == state) {
+                Some(ix) => ix,
+                None => {
+                    self.states.push(state);
+                    self.states.len() - 1
+                }
+            };
+            writeln!(self.content, "/GS{} gs", ix).ok();
+        }
+        if rot.angle != 0. {
+            // Shapes are rotated clockwise.
+            let (sin, cos) = (-rot.angle).sin_cos();
+            let Point2 { x, y } = rot.center;
+
+            writeln!(
+                self.content,
+                "{} {} {} {} {} {} cm",
+                cos,
+                sin,
+                -sin,
+                cos,
+                x - cos * x + sin * y,
+                y - sin * x - cos * y
+            )
+            .ok();
+        }
+    }
+
+    fn stroke_style(&mut self, color: Rgba, width: f32, join: Join, cap: Cap) {
+        let join = match join {
+            Join::Miter => 0,
+            Join::Round => 1,
+            Join::Bevel => 2,
+        };
+        let cap = match cap {
+            Cap::Butt => 0,
+            Cap::Round => 1,
+            Cap::Square => 2,
+        };
+        self.color("RG", color);
+        writeln!(self.content, "{} w {} j {} J 4 M", width, join, cap).ok();
+    }
+
+    fn color(&mut self, op: &str, c: Rgba) {
+        writeln!(
+            self.content,
+            "{} {} {} {}",
+            c.r.clamp(0., 1.),
+            c.g.clamp(0., 1.),
+            c.b.clamp(0., 1.),
+            op
+        )
+        .ok();
+    }
+
+    fn rect(&mut self, r: Rect<f32>) {
+        writeln!(
+            self.content,
+            "{} {} {} {} re",
+            r.x1,
+            r.y1,
+            r.x2 - r.x1,
+            r.y2 - r.y1
+        )
+        .ok();
+    }
+
+    /// A circle, approximated with four cubic curves.
+    fn circle(&mut self, c: Point2<f32>, r: f32) {
+        const K: f32 = 0.552_284_8;
+        let k = K * r;
+
+        self.move_to(Point2::new(c.x + r, c.y));
+        self.cubic_to(
+            Point2::new(c.x + r, c.y + k),
+            Point2::new(c.x + k, c.y + r),
+            Point2::new(c.x, c.y + r),
+        );
+        self.cubic_to(
+            Point2::new(c.x - k, c.y + r),
+            Point2::new(c.x - r, c.y + k),
+            Point2::new(c.x - r, c.y),
+        );
+        self.cubic_to(
+            Point2::new(c.x - r, c.y - k),
+            Point2::new(c.x - k, c.y - r),
+            Point2::new(c.x, c.y - r),
+        );
+        self.cubic_to(
+            Point2::new(c.x + k, c.y - r),
+            Point2::new(c.x + r, c.y - k),
+            Point2::new(c.x + r, c.y),
+        );
+        self.op("h");
+    }
+
+    fn polyline(&mut self, points: &[Point2<f32>]) {
+        if let Some((first, rest)) = points.split_first() {
+            self.move_to(*first);
+            for p in rest {
+                self.line_to(*p);
+            }
+        }
+    }
+
+    fn path(&mut self, path: &Path) {
+        let mut current = path.start;
+        self.move_to(current);
+
+        for segment in path.segments.iter() {
+            current = match *segment {
+                Segment::Line(p) => {
+                    self.line_to(p);
+                    p
+                }
+                Segment::Quadratic(c, p) => {
+                    self.quad_to(current, c, p);
+                    p
+                }
+                Segment::Cubic(c1, c2, p) => {
+                    self.cubic_to(c1, c2, p);
+                    p
+                }
+            };
+        }
+        if path.closed {
+            self.op("h");
+        }
+    }
+
+    fn move_to(&mut self, p: Point2<f32>) {
+        writeln!(self.content, "{} {} m", p.x, p.y).ok();
+    }
+
+    fn line_to(&mut self, p: Point2<f32>) {
+        writeln!(self.content, "{} {} l", p.x, p.y).ok();
+    }
+
+    fn cubic_to(&mut self, c1: Point2<f32>, c2: Point2<f32>, p: Point2<f32>) {
+        writeln!(
+            self.content,
+            "{} {} {} {} {} {} c",
+            c1.x, c1.y, c2.x, c2.y, p.x, p.y
+        )
+        .ok();
+    }
+
+    /// A quadratic curve from `p0`, as PDF only has cubic curves.
+    fn quad_to(&mut self, p0: Point2<f32>, c: Point2<f32>, p: Point2<f32>) {
+        let k = 2. / 3.;
+        let c1 = Point2::new(p0.x + (c.x - p0.x) * k, p0.y + (c.y - p0.y) * k);
+        let c2 = Point2::new(p.x + (c.x - p.x) * k, p.y + (c.y - p.y) * k);
+
+        self.cubic_to(c1, c2, p);
+    }
+
+    fn op(&mut self, op: &str) {
+        self.content.push_str(op);
+        self.content.push('\n');
+    }
+}
+
+/// The alpha of a fill, applied to the whole shape. Gradients ignore the
+/// alpha of their stops.
+fn fill_alpha(fill: &Fill) -> f32 {
+    match fill {
+        Fill::Solid(c) | Fill::Pattern(c, _) => c.a,
+        _ => 1.,
+    }
+}
+
+/// A function interpolating between the color stops, for shadings.
+fn function(stops: &[(f32, Rgba)]) -> String {
+    let rgb = |c: Rgba| format!("[{} {} {}]", c.r, c.g, c.b);
+    let interpolate = |a: Rgba, b: Rgba| {
+        format!(
+            "<< /FunctionType 2 /Domain [0 1] /C0 {} /C1 {} /N 1 >>",
+            rgb(a),
+            rgb(b)
+        )
+    };
+
+    // Extend the first and last colors to the ends of the domain.
+    let mut stops = stops.to_vec();
+    if let Some(&(pos, color)) = stops.first() {
+        if pos > 0. {
+            stops.insert(0, (0., color));
+        }
+    }
+    if let Some(&(pos, color)) = stops.last() {
+        if pos < 1. || stops.len() == 1 {
+            stops.push((1., color));
+        }
+    }
+    if stops.len() == 2 {
+        return interpolate(stops[0].1, stops[1].1);
+    }
+
+    let functions = stops
+        .windows(2)
+        .map(|w| interpolate(w[0].1, w[1].1))
+        .collect::<Vec<_>>();
+    let bounds = stops[1..stops.len() - 1]
+        .iter()
+        .map(|(pos, _)| pos.to_string())
+        .collect::<Vec<_>>();
+    let encode = vec!["0 1"; functions.len()];
+
+    format!(
+        "<< /FunctionType 3 /Domain [0 1] /Functions [{}] /Bounds [{}] /Encode [{}] >>",
+        functions.join(" "),
+        bounds.join(" "),
+        encode.join(" ")
+    )
+}
+
+/// The bounds of a non-empty set of points.
+fn bounds(points: &[Point2<f32>]) -> Rect<f32> {
+    let mut r = Rect::new(points[0].x, points[0].y, points[0].x, points[0].y);
+
+    for p in points {
+        r.x1 = r.x1.min(p.x);
+        r.y1 = r.y1.min(p.y);
+        r.x2 = r.x2.max(p.x);
+        r.y2 = r.y2.max(p.y);
+    }
+    r
+}
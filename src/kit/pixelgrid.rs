@@ -0,0 +1,122 @@
+use crate::core;
+use crate::core::{Binding, BindingType, Rgba, Set, ShaderStage};
+use crate::math::*;
+
+///////////////////////////////////////////////////////////////////////////
+// Grid
+///////////////////////////////////////////////////////////////////////////
+
+/// Placement and appearance of a pixel grid. All positions and sizes are
+/// in physical screen pixels, with the origin at the top-left of the target.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Grid {
+    /// Color of the gridlines.
+    pub color: Rgba,
+    /// Screen position of the top-left corner of the canvas.
+    pub offset: Vector2<f32>,
+    /// Size of the canvas, in canvas pixels.
+    pub size: Vector2<f32>,
+    /// Size of a canvas pixel on screen.
+    pub zoom: f32,
+    /// Zoom factor above which the grid is shown. The grid fades in over
+    /// the next zoom level.
+    pub threshold: f32,
+}
+
+impl Grid {
+    pub fn new(offset: Vector2<f32>, size: Vector2<f32>, zoom: f32) -> Self {
+        Self {
+            offset,
+            size,
+            zoom,
+            ..Self::default()
+        }
+    }
+
+    /// Whether the grid is visible at the current zoom factor.
+    pub fn is_visible(&self) -> bool {
+        self.zoom > self.threshold
+    }
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Self {
+            color: Rgba::new(0.5, 0.5, 0.5, 0.5),
+            offset: Vector2::new(0., 0.),
+            size: Vector2::new(0., 0.),
+            zoom: 1.,
+            threshold: 8.,
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Pipeline
+///////////////////////////////////////////////////////////////////////////
+
+/// Draws a line between each pixel of a zoomed canvas, over the whole
+/// render target. Lines are computed per fragment, so the cost doesn't
+/// depend on the zoom factor or canvas size.
+pub struct Pipeline {
+    pipeline: core::Pipeline,
+    bindings: core::BindingGroup,
+    buf: core::UniformBuffer,
+    vertices: core::VertexBuffer,
+}
+
+impl Pipeline {
+    pub fn draw(&self, pass: &mut core::Pass) {
+        pass.draw_buffer(&self.vertices);
+    }
+}
+
+impl<'a> core::AbstractPipeline<'a> for Pipeline {
+    type PrepareContext = Grid;
+    type Uniforms = Grid;
+
+    fn description() -> core::PipelineDescription<'a> {
+        core::PipelineDescription {
+            vertex_layout: &[core::VertexFormat::Float2, core::VertexFormat::Float2],
+            pipeline_layout: &[Set(&[Binding {
+                binding: BindingType::UniformBuffer,
+                stage: ShaderStage::Fragment,
+            }])],
+            // TODO: Use `env("CARGO_MANIFEST_DIR")`
+            vertex_shader: include_bytes!("data/screen.vert.spv"),
+            fragment_shader: include_bytes!("data/pixelgrid.frag.spv"),
+        }
+    }
+
+    fn setup(pipeline: core::Pipeline, dev: &core::Device) -> Self {
+        #[rustfmt::skip]
+        let vertices: &[(f32, f32, f32, f32)] = &[
+            (-1.0, -1.0, 0.0, 0.0),
+            ( 1.0, -1.0, 1.0, 0.0),
+            ( 1.0,  1.0, 1.0, 1.0),
+            (-1.0, -1.0, 0.0, 0.0),
+            (-1.0,  1.0, 0.0, 1.0),
+            ( 1.0,  1.0, 1.0, 1.0),
+        ];
+        let vertices = dev.create_buffer(vertices);
+        let buf = dev.create_uniform_buffer(&[Grid::default()]);
+        let bindings = dev.create_binding_group(&pipeline.layout.sets[0], &[&buf]);
+
+        Self {
+            pipeline,
+            bindings,
+            buf,
+            vertices,
+        }
+    }
+
+    fn apply(&self, pass: &mut core::Pass) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_binding(&self.bindings, &[]);
+    }
+
+    fn prepare(&'a self, grid: Grid) -> Option<(&'a core::UniformBuffer, Vec<Grid>)> {
+        Some((&self.buf, vec![grid]))
+    }
+}
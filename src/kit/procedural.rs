@@ -0,0 +1,432 @@
+//! Noise and procedural patterns, eg. as inputs for effects, brushes and
+//! backgrounds.
+//!
+//! They are generated on the GPU with a `Pipeline`, into the target of a
+//! pass, eg. a framebuffer which is then sampled from. They can also be
+//! generated on the CPU, into images which are uploaded to textures, or
+//! read back, eg. as masks and distortion maps.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use rgx::kit::procedural::{self, Generator, Noise};
+//!
+//! let generator: procedural::Pipeline = r.pipeline(Blending::default());
+//! let clouds = r.framebuffer(256, 256);
+//! let perlin = Generator::Noise {
+//!     noise: Noise::Perlin,
+//!     cell: 32.,
+//!     octaves: 4,
+//!     seed: 7,
+//! };
+//!
+//! r.update_pipeline(&generator, perlin, &mut frame);
+//! {
+//!     let mut pass = frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &clouds);
+//!
+//!     pass.set_pipeline(&generator);
+//!     generator.draw(&mut pass);
+//! }
+//! ```
+//!
+//! The same noise, generated on the CPU:
+//!
+//! ```
+//! use rgx::kit::procedural::{Image, Noise};
+//!
+//! // Four octaves of Perlin noise, with cells of 32 pixels.
+//! let clouds = Image::noise(128, 128, Noise::Perlin, 32., 4, 7);
+//!
+//! assert_eq!(clouds.pixels.len(), 128 * 128);
+//! assert_eq!(clouds, Image::noise(128, 128, Noise::Perlin, 32., 4, 7));
+//! ```
+use crate::core;
+use crate::core::{Binding, BindingType, Rgba, Rgba8, Set, ShaderStage};
+use crate::kit::effects;
+
+///////////////////////////////////////////////////////////////////////////
+// Noise
+///////////////////////////////////////////////////////////////////////////
+
+/// A kind of gradient noise, sampled in cells of unit size.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Noise {
+    /// Random values at each cell corner, interpolated smoothly. Blocky,
+    /// but cheap.
+    Value,
+    /// Random gradients at each cell corner.
+    Perlin,
+    /// Random gradients at the corners of a triangular grid, with fewer
+    /// directional artifacts than `Perlin`.
+    Simplex,
+}
+
+impl Noise {
+    /// Sample the noise at the given position, with the given seed. Returns
+    /// a value in the `[0, 1]` range.
+    ///
+    /// ```
+    /// use rgx::kit::procedural::Noise;
+    ///
+    /// for noise in &[Noise::Value, Noise::Perlin, Noise::Simplex] {
+    ///     let v = noise.sample(1.5, 2.25, 42);
+    ///
+    ///     assert!(v >= 0. && v <= 1.);
+    ///     assert_eq!(v, noise.sample(1.5, 2.25, 42));
+    /// }
+    /// ```
+    pub fn sample(self, x: f32, y: f32, seed: u32) -> f32 {
+        match self {
+            Noise::Value => value(x, y, seed),
+            Noise::Perlin => {
+                (perlin(x, y, seed) * std::f32::consts::SQRT_2 * 0.5 + 0.5).clamp(0., 1.)
+            }
+            Noise::Simplex => (simplex(x, y, seed) * 0.5 + 0.5).clamp(0., 1.),
+        }
+    }
+
+    /// Sample several octaves of the noise, each with twice the frequency
+    /// and half the amplitude of the previous one, for finer detail. Returns
+    /// a value in the `[0, 1]` range.
+    pub fn fbm(self, x: f32, y: f32, seed: u32, octaves: u32) -> f32 {
+        let (mut sum, mut total) = (0., 0.);
+        let (mut frequency, mut amplitude) = (1., 1.);
+
+        for octave in 0..octaves.max(1) {
+            let seed = seed.wrapping_add(octave.wrapping_mul(0x9e37_79b9));
+
+            sum += self.sample(x * frequency, y * frequency, seed) * amplitude;
+            total += amplitude;
+            frequency *= 2.;
+            amplitude *= 0.5;
+        }
+        sum / total
+    }
+}
+
+/// Hash a lattice point and seed into a pseudo-random integer. Must match
+/// `hash` in `data/procedural.frag`.
+fn hash(x: i32, y: i32, seed: u32) -> u32 {
+    let mut h = seed.wrapping_mul(0x27d4_eb2d)
+        ^ (x as u32).wrapping_mul(0x85eb_ca6b)
+        ^ (y as u32).wrapping_mul(0xc2b2_ae35);
+
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2c1b_3c6d);
+    h ^= h >> 12;
+    h = h.wrapping_mul(0x297a_2d39);
+    h ^= h >> 15;
+    h
+}
+
+/// A unit gradient, picked from eight directions.
+fn gradient(x: i32, y: i32, seed: u32) -> (f32, f32) {
+    const D: f32 = std::f32::consts::FRAC_1_SQRT_2;
+    const GRADIENTS: [(f32, f32); 8] = [
+        (1., 0.),
+        (D, D),
+        (0., 1.),
+        (-D, D),
+        (-1., 0.),
+        (-D, -D),
+        (0., -1.),
+        (D, -D),
+    ];
+    GRADIENTS[(hash(x, y, seed) & 7) as usize]
+}
+
+/// Quintic interpolation curve, with zero first and second derivatives at
+/// the cell corners.
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn value(x: f32, y: f32, seed: u32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (i, j) = (x0 as i32, y0 as i32);
+    let (u, v) = (fade(x - x0), fade(y - y0));
+    let corner = |i, j| hash(i, j, seed) as f32 / u32::MAX as f32;
+
+    lerp(
+        lerp(corner(i, j), corner(i + 1, j), u),
+        lerp(corner(i, j + 1), corner(i + 1, j + 1), u),
+        v,
+    )
+}
+
+/// Perlin noise, in the `[-1/√2, 1/√2]` range.
+fn perlin(x: f32, y: f32, seed: u32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (i, j) = (x0 as i32, y0 as i32);
+    let (fx, fy) = (x - x0, y - y0);
+    let corner = |di: i32, dj: i32| {
+        let (gx, gy) = gradient(i + di, j + dj, seed);
+        gx * (fx - di as f32) + gy * (fy - dj as f32)
+    };
+    let (u, v) = (fade(fx), fade(fy));
+
+    lerp(
+        lerp(corner(0, 0), corner(1, 0), u),
+        lerp(corner(0, 1), corner(1, 1), u),
+        v,
+    )
+}
+
+/// Simplex noise, in the `[-1, 1]` range.
+fn simplex(x: f32, y: f32, seed: u32) -> f32 {
+    // Skewing factors between the square and triangular grids.
+    let f2 = 0.5 * (3f32.sqrt() - 1.);
+    let g2 = (3. - 3f32.sqrt()) / 6.;
+
+    let s = (x + y) * f2;
+    let (i, j) = ((x + s).floor(), (y + s).floor());
+    let t = (i + j) * g2;
+    let (x0, y0) = (x - (i - t), y - (j - t));
+
+    // The triangle the point is in, lower or upper.
+    let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+    let corners = [
+        (0, 0, x0, y0),
+        (i1, j1, x0 - i1 as f32 + g2, y0 - j1 as f32 + g2),
+        (1, 1, x0 - 1. + 2. * g2, y0 - 1. + 2. * g2),
+    ];
+    let (i, j) = (i as i32, j as i32);
+
+    70. * corners
+        .iter()
+        .map(|&(di, dj, x, y)| {
+            let t = 0.5 - x * x - y * y;
+            if t < 0. {
+                0.
+            } else {
+                let (gx, gy) = gradient(i + di, j + dj, seed);
+                t * t * t * t * (gx * x + gy * y)
+            }
+        })
+        .sum::<f32>()
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Image
+///////////////////////////////////////////////////////////////////////////
+
+/// A generated image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    pub w: u32,
+    pub h: u32,
+    pub pixels: Vec<Rgba8>,
+}
+
+impl Image {
+    /// Generate an image with the color of each pixel, given its position.
+    pub fn from_fn<F>(w: u32, h: u32, f: F) -> Self
+    where
+        F: Fn(u32, u32) -> Rgba8,
+    {
+        let pixels = (0..h)
+            .flat_map(|y| (0..w).map(move |x| (x, y)))
+            .map(|(x, y)| f(x, y))
+            .collect();
+
+        Self { w, h, pixels }
+    }
+
+    /// Opaque grayscale noise, with cells of the given size in pixels, and
+    /// the given number of octaves, see `Noise::fbm`.
+    pub fn noise(w: u32, h: u32, noise: Noise, cell: f32, octaves: u32, seed: u32) -> Self {
+        Self::from_fn(w, h, |x, y| {
+            let v = noise.fbm(
+                (x as f32 + 0.5) / cell,
+                (y as f32 + 0.5) / cell,
+                seed,
+                octaves,
+            );
+            let v = (v * 255.).round() as u8;
+
+            Rgba8::new(v, v, v, 0xff)
+        })
+    }
+
+    /// A checkerboard of squares of the given size, starting with `a` at the
+    /// top-left.
+    ///
+    /// ```
+    /// use rgx::core::Rgba8;
+    /// use rgx::kit::procedural::Image;
+    ///
+    /// let board = Image::checkerboard(4, 4, 2, Rgba8::WHITE, Rgba8::BLACK);
+    ///
+    /// assert_eq!(board.pixels[0], Rgba8::WHITE);
+    /// assert_eq!(board.pixels[2], Rgba8::BLACK);
+    /// assert_eq!(board.pixels[4 * 2 + 2], Rgba8::WHITE);
+    /// ```
+    pub fn checkerboard(w: u32, h: u32, size: u32, a: Rgba8, b: Rgba8) -> Self {
+        let size = size.max(1);
+
+        Self::from_fn(
+            w,
+            h,
+            |x, y| {
+                if (x / size + y / size) % 2 == 0 {
+                    a
+                } else {
+                    b
+                }
+            },
+        )
+    }
+
+    /// Vertical stripes of the given width, alternating between `a` and `b`.
+    pub fn stripes(w: u32, h: u32, width: u32, a: Rgba8, b: Rgba8) -> Self {
+        let width = width.max(1);
+
+        Self::from_fn(w, h, |x, _| if (x / width) % 2 == 0 { a } else { b })
+    }
+
+    /// Create a texture for the image. The image still needs to be uploaded
+    /// to it.
+    pub fn texture(&self, r: &core::Renderer) -> core::Texture {
+        r.texture(self.w, self.h)
+    }
+
+    /// Upload the image to a texture created with `Image::texture`.
+    pub fn upload(&self, texture: &core::Texture, r: &mut core::Renderer) {
+        r.submit(&[core::Op::Fill(texture, &self.pixels)]);
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Generator
+///////////////////////////////////////////////////////////////////////////
+
+/// Noise or a pattern, generated on the GPU by a `Pipeline`. Pixels are
+/// positioned as in images, with the origin at the top-left of the target.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Generator {
+    /// Opaque grayscale noise, as generated by `Image::noise` with the same
+    /// parameters.
+    Noise {
+        noise: Noise,
+        cell: f32,
+        octaves: u32,
+        seed: u32,
+    },
+    /// A checkerboard of squares of the given size, starting with `a` at the
+    /// top-left.
+    Checkerboard { size: f32, a: Rgba, b: Rgba },
+    /// Vertical stripes of the given width, alternating between `a` and `b`.
+    Stripes { width: f32, a: Rgba, b: Rgba },
+}
+
+/// The uniforms of the generator shader.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct Uniforms {
+    a: [f32; 4],
+    b: [f32; 4],
+    scale: f32,
+    kind: u32,
+    seed: u32,
+    octaves: u32,
+}
+
+impl From<Generator> for Uniforms {
+    fn from(generator: Generator) -> Self {
+        let color = |c: Rgba| [c.r, c.g, c.b, c.a];
+        let (kind, scale, seed, octaves, a, b) = match generator {
+            Generator::Noise {
+                noise,
+                cell,
+                octaves,
+                seed,
+            } => {
+                let kind = match noise {
+                    Noise::Value => 0,
+                    Noise::Perlin => 1,
+                    Noise::Simplex => 2,
+                };
+                (kind, cell, seed, octaves, Rgba::WHITE, Rgba::WHITE)
+            }
+            Generator::Checkerboard { size, a, b } => (3, size.max(1.), 0, 0, a, b),
+            Generator::Stripes { width, a, b } => (4, width.max(1.), 0, 0, a, b),
+        };
+
+        Self {
+            a: color(a),
+            b: color(b),
+            scale,
+            kind,
+            seed,
+            octaves,
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Pipeline
+///////////////////////////////////////////////////////////////////////////
+
+/// Generates noise and patterns over the whole render target.
+pub struct Pipeline {
+    pipeline: core::Pipeline,
+    bindings: core::BindingGroup,
+    buf: core::UniformBuffer,
+    vertices: core::VertexBuffer,
+}
+
+impl Pipeline {
+    /// Generate into the target of the pass, which must have the pipeline
+    /// set.
+    pub fn draw(&self, pass: &mut core::Pass) {
+        pass.draw_buffer(&self.vertices);
+    }
+}
+
+impl<'a> core::AbstractPipeline<'a> for Pipeline {
+    type PrepareContext = Generator;
+    type Uniforms = Uniforms;
+
+    fn description() -> core::PipelineDescription<'a> {
+        core::PipelineDescription {
+            vertex_layout: &[core::VertexFormat::Float2, core::VertexFormat::Float2],
+            pipeline_layout: &[Set(&[Binding {
+                binding: BindingType::UniformBuffer,
+                stage: ShaderStage::Fragment,
+            }])],
+            // TODO: Use `env("CARGO_MANIFEST_DIR")`
+            vertex_shader: include_bytes!("data/screen.vert.spv"),
+            fragment_shader: include_bytes!("data/procedural.frag.spv"),
+        }
+    }
+
+    fn setup(pipeline: core::Pipeline, dev: &core::Device) -> Self {
+        let vertices = effects::screen_quad(dev);
+        let buf = dev.create_uniform_buffer(&[Uniforms::from(Generator::Stripes {
+            width: 1.,
+            a: Rgba::TRANSPARENT,
+            b: Rgba::TRANSPARENT,
+        })]);
+        let bindings = dev.create_binding_group(&pipeline.layout.sets[0], &[&buf]);
+
+        Self {
+            pipeline,
+            bindings,
+            buf,
+            vertices,
+        }
+    }
+
+    fn apply(&self, pass: &mut core::Pass) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_binding(&self.bindings, &[]);
+    }
+
+    fn prepare(&'a self, generator: Generator) -> Option<(&'a core::UniformBuffer, Vec<Uniforms>)> {
+        Some((&self.buf, vec![Uniforms::from(generator)]))
+    }
+}
@@ -0,0 +1,117 @@
+use crate::core;
+use crate::core::Rgba8;
+
+///////////////////////////////////////////////////////////////////////////
+// Level
+///////////////////////////////////////////////////////////////////////////
+
+/// A single level of a pyramid, half the size of the previous one.
+#[derive(Clone, Debug)]
+pub struct Level {
+    pub w: u32,
+    pub h: u32,
+    pub pixels: Vec<Rgba8>,
+}
+
+impl Level {
+    /// Downsample the level by half, with a box filter.
+    fn downsample(&self) -> Self {
+        let w = (self.w / 2).max(1);
+        let h = (self.h / 2).max(1);
+        let mut pixels = Vec::with_capacity((w * h) as usize);
+
+        for y in 0..h {
+            for x in 0..w {
+                let (x0, y0) = (x * 2, y * 2);
+                let (x1, y1) = ((x0 + 1).min(self.w - 1), (y0 + 1).min(self.h - 1));
+                let samples = [
+                    self.pixel(x0, y0),
+                    self.pixel(x1, y0),
+                    self.pixel(x0, y1),
+                    self.pixel(x1, y1),
+                ];
+                let avg = |f: fn(&Rgba8) -> u8| {
+                    let sum: u32 = samples.iter().map(|p| f(p) as u32).sum();
+                    ((sum + 2) / 4) as u8
+                };
+                pixels.push(Rgba8::new(
+                    avg(|p| p.r),
+                    avg(|p| p.g),
+                    avg(|p| p.b),
+                    avg(|p| p.a),
+                ));
+            }
+        }
+        Self { w, h, pixels }
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Rgba8 {
+        self.pixels[(y * self.w + x) as usize]
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Pyramid
+///////////////////////////////////////////////////////////////////////////
+
+/// A level-of-detail pyramid of a large image. Each level is half the
+/// size of the previous one, down to a single pixel.
+///
+/// Drawing a large image zoomed out samples the level matching the zoom,
+/// instead of minifying the full image, which aliases heavily. Each level
+/// is uploaded to its own texture.
+#[derive(Clone, Debug)]
+pub struct Pyramid {
+    pub levels: Vec<Level>,
+}
+
+impl Pyramid {
+    /// Build the pyramid of the given image, with its full size as the
+    /// first level.
+    pub fn new(w: u32, h: u32, pixels: Vec<Rgba8>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            (w * h) as usize,
+            "Pyramid::new: pixel count does not match image size"
+        );
+        let mut levels = vec![Level { w, h, pixels }];
+
+        while let Some(last) = levels.last() {
+            if last.w == 1 && last.h == 1 {
+                break;
+            }
+            levels.push(last.downsample());
+        }
+        Self { levels }
+    }
+
+    /// The level to draw at the given zoom, where `1.0` is the full size.
+    /// Picks the smallest level that is still at least as large as the
+    /// image on screen.
+    pub fn level(&self, zoom: f32) -> usize {
+        if zoom >= 1. || zoom <= 0. {
+            return 0;
+        }
+        let level = (1. / zoom).log2().floor() as usize;
+
+        level.min(self.levels.len() - 1)
+    }
+
+    /// Create one texture per level. The pyramid still needs to be
+    /// uploaded to them.
+    pub fn textures(&self, r: &core::Renderer) -> Vec<core::Texture> {
+        self.levels.iter().map(|l| r.texture(l.w, l.h)).collect()
+    }
+
+    /// Upload all levels to the given textures, as created by `textures`.
+    pub fn upload(&self, textures: &[core::Texture], r: &mut core::Renderer) {
+        let ops = self
+            .levels
+            .iter()
+            .zip(textures.iter())
+            .map(|(l, t)| core::Op::Fill(t, &l.pixels))
+            .collect::<Vec<_>>();
+
+        r.submit(ops.as_slice());
+    }
+}
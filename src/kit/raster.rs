@@ -0,0 +1,412 @@
+use crate::core::{Filter, Rgba, Rgba8};
+use crate::kit::{shape2d, sprite2d};
+use crate::math::*;
+
+///////////////////////////////////////////////////////////////////////////
+// Rasterizer
+///////////////////////////////////////////////////////////////////////////
+
+/// Renders shape and sprite batches on the CPU, without a GPU, eg. for
+/// thumbnails or to compare against reference images.
+///
+/// Batches are tessellated exactly as for the GPU pipelines, and their
+/// fragments shaded and blended the same way: with depth testing, and
+/// alpha blending in linear space. Coordinates are in pixels, with the
+/// origin at the bottom-left, as with `kit::ortho`.
+#[derive(Clone, Debug)]
+pub struct Rasterizer {
+    pub w: u32,
+    pub h: u32,
+    /// Transform applied to vertex positions, like the global transform of
+    /// the GPU pipelines.
+    pub transform: Matrix4<f32>,
+
+    /// Linear, non-premultiplied colors, from the bottom row up.
+    color: Vec<[f32; 4]>,
+    depth: Vec<f32>,
+}
+
+/// A vertex in screen space, with the attributes to interpolate.
+#[derive(Copy, Clone)]
+struct Fragment<const N: usize> {
+    x: f32,
+    y: f32,
+    z: f32,
+    attrs: [f32; N],
+}
+
+impl Rasterizer {
+    pub fn new(w: u32, h: u32) -> Self {
+        let size = (w * h) as usize;
+
+        Self {
+            w,
+            h,
+            transform: Matrix4::identity(),
+            color: vec![[0.; 4]; size],
+            depth: vec![1.; size],
+        }
+    }
+
+    /// Clear the image to the given color, and reset the depth buffer. As
+    /// with `PassOp::Clear`, the color is in linear space.
+    pub fn clear(&mut self, color: Rgba) {
+        for c in self.color.iter_mut() {
+            *c = [color.r, color.g, color.b, color.a];
+        }
+        for d in self.depth.iter_mut() {
+            *d = 1.;
+        }
+    }
+
+    /// Draw a shape batch. Shapes drawn with signed distance edges on the
+    /// GPU, in batches with `sdf` set, are tessellated instead.
+    pub fn draw_shapes(&mut self, batch: &shape2d::Batch) {
+        if batch.sdf {
+            let all = (0..batch.items.len()).collect::<Vec<_>>();
+            let mut batch = batch.select(&all);
+            batch.sdf = false;
+
+            self.draw_mesh(&batch.mesh());
+        } else {
+            self.draw_mesh(&batch.mesh());
+        }
+    }
+
+    /// Draw a shape mesh, as returned by `Batch::mesh`.
+    pub fn draw_mesh(&mut self, mesh: &shape2d::Mesh) {
+        let vertices = mesh
+            .vertices
+            .iter()
+            .map(|v| {
+                // Shapes are rotated clockwise around their center.
+                let (s, c) = v.angle.sin_cos();
+                let (x, y) = (v.position.x - v.center.x, v.position.y - v.center.y);
+                let rotated = Vector3::new(
+                    c * x + s * y + v.center.x,
+                    c * y - s * x + v.center.y,
+                    v.position.z,
+                );
+                let [r, g, b] = linearize(v.color);
+                let alpha = v.color.a as f32 / 255. * v.opacity;
+
+                self.fragment(
+                    rotated,
+                    [
+                        r,
+                        g,
+                        b,
+                        alpha,
+                        v.edge.x,
+                        v.edge.y,
+                        v.edge.z,
+                        v.edge.w,
+                        v.pattern.x,
+                        v.pattern.y,
+                        v.pattern.z,
+                        v.pattern.w,
+                        v.position.x,
+                        v.position.y,
+                    ],
+                )
+            })
+            .collect::<Vec<_>>();
+
+        for tri in mesh.indices.chunks_exact(3) {
+            let tri = [
+                vertices[tri[0] as usize],
+                vertices[tri[1] as usize],
+                vertices[tri[2] as usize],
+            ];
+            self.triangle(tri, |a, dx, dy| {
+                let d = edge_distance(&a[4..8]);
+                let fwidth =
+                    (edge_distance(&dx[4..8]) - d).abs() + (edge_distance(&dy[4..8]) - d).abs();
+                let coverage = (0.5 - d / fwidth.max(0.0001)).clamp(0., 1.);
+                let mask = pattern_mask(
+                    &a[8..12],
+                    [a[12], a[13]],
+                    [dx[12], dx[13]],
+                    [dy[12], dy[13]],
+                );
+                let coverage = if a[6] > 0. { coverage } else { 1. };
+
+                [a[0], a[1], a[2], a[3] * coverage * mask]
+            });
+        }
+    }
+
+    /// Draw a sprite batch, sampling the given texture. The texture must
+    /// have the size of the batch, with rows from top to bottom.
+    pub fn draw_sprites(&mut self, batch: &sprite2d::Batch, texture: &[Rgba8], filter: Filter) {
+        let (tw, th) = (batch.w, batch.h);
+
+        assert_eq!(
+            texture.len(),
+            (tw * th) as usize,
+            "Rasterizer::draw_sprites: texture size does not match batch size"
+        );
+
+        let vertices = batch
+            .vertices()
+            .iter()
+            .map(|v| {
+                let [r, g, b] = linearize(v.color);
+                let alpha = v.color.a as f32 / 255.;
+
+                self.fragment(v.position, [v.uv.x, v.uv.y, r, g, b, alpha, v.opacity])
+            })
+            .collect::<Vec<_>>();
+        let indices = batch.indices();
+
+        for tri in indices.chunks_exact(3) {
+            let tri = [
+                vertices[tri[0] as usize],
+                vertices[tri[1] as usize],
+                vertices[tri[2] as usize],
+            ];
+            self.triangle(tri, |a, _, _| {
+                let texel = sample(texture, tw, th, a[0], a[1], &filter);
+                let alpha = a[5];
+
+                [
+                    texel[0] + (a[2] - texel[0]) * alpha,
+                    texel[1] + (a[3] - texel[1]) * alpha,
+                    texel[2] + (a[4] - texel[2]) * alpha,
+                    texel[3] * a[6],
+                ]
+            });
+        }
+    }
+
+    /// The sRGB-encoded image, as rows from top to bottom, like
+    /// `Renderer::read_pixels`.
+    pub fn pixels(&self) -> Vec<Rgba8> {
+        let w = self.w as usize;
+
+        self.color
+            .chunks(w.max(1))
+            .rev()
+            .flat_map(|row| row.iter())
+            .map(|c| {
+                let encode = |v: f32| (srgb(v.clamp(0., 1.)) * 255.).round() as u8;
+                Rgba8::new(
+                    encode(c[0]),
+                    encode(c[1]),
+                    encode(c[2]),
+                    (c[3].clamp(0., 1.) * 255.).round() as u8,
+                )
+            })
+            .collect()
+    }
+
+    /// Transform a vertex position to screen space.
+    fn fragment<const N: usize>(&self, p: Vector3<f32>, attrs: [f32; N]) -> Fragment<N> {
+        let m = self.transform;
+        let v = m.x * p.x + m.y * p.y + m.z * p.z + m.w;
+
+        // The orthographic projection maps `z` to `-z` in clip space.
+        Fragment {
+            x: v.x / v.w,
+            y: v.y / v.w,
+            z: -v.z / v.w,
+            attrs,
+        }
+    }
+
+    /// Rasterize a triangle. Pixels are covered if their center is inside
+    /// the triangle, with a top-left rule for pixels on shared edges. The
+    /// shader is given the interpolated attributes at the pixel, and one
+    /// pixel to the right and above it, to compute derivatives.
+    fn triangle<const N: usize, F>(&mut self, tri: [Fragment<N>; 3], shader: F)
+    where
+        F: Fn(&[f32; N], &[f32; N], &[f32; N]) -> [f32; 4],
+    {
+        let [a, b, c] = tri;
+        let area = edge(&a, &b, c.x, c.y);
+
+        if area == 0. {
+            return;
+        }
+        let x1 = a.x.min(b.x).min(c.x).floor().max(0.) as u32;
+        let y1 = a.y.min(b.y).min(c.y).floor().max(0.) as u32;
+        let x2 = (a.x.max(b.x).max(c.x).ceil().max(0.) as u32).min(self.w);
+        let y2 = (a.y.max(b.y).max(c.y).ceil().max(0.) as u32).min(self.h);
+
+        // Barycentric coordinates of a point, which may lie outside of the
+        // triangle.
+        let barycentric = |x: f32, y: f32| {
+            [
+                edge(&b, &c, x, y) / area,
+                edge(&c, &a, x, y) / area,
+                edge(&a, &b, x, y) / area,
+            ]
+        };
+        let interpolate = |w: [f32; 3]| {
+            let mut attrs = [0.; N];
+            for (i, attr) in attrs.iter_mut().enumerate() {
+                *attr = a.attrs[i] * w[0] + b.attrs[i] * w[1] + c.attrs[i] * w[2];
+            }
+            attrs
+        };
+        // Whether pixels exactly on the edge from `p` to `q` are covered.
+        let top_left = |p: &Fragment<N>, q: &Fragment<N>| {
+            let (dx, dy) = if area > 0. {
+                (q.x - p.x, q.y - p.y)
+            } else {
+                (p.x - q.x, p.y - q.y)
+            };
+            (dy == 0. && dx < 0.) || dy < 0.
+        };
+        let edges = [top_left(&b, &c), top_left(&c, &a), top_left(&a, &b)];
+
+        for y in y1..y2 {
+            for x in x1..x2 {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let w = barycentric(px, py);
+
+                let inside = w
+                    .iter()
+                    .zip(edges.iter())
+                    .all(|(w, top_left)| *w > 0. || (*w == 0. && *top_left));
+                if !inside {
+                    continue;
+                }
+
+                let z = a.z * w[0] + b.z * w[1] + c.z * w[2];
+                let ix = (y * self.w + x) as usize;
+
+                if !(0. ..=1.).contains(&z) || z > self.depth[ix] {
+                    continue;
+                }
+                let src = shader(
+                    &interpolate(w),
+                    &interpolate(barycentric(px + 1., py)),
+                    &interpolate(barycentric(px, py + 1.)),
+                );
+                let dst = &mut self.color[ix];
+                let alpha = src[3];
+
+                for i in 0..4 {
+                    dst[i] = src[i] * alpha + dst[i] * (1. - alpha);
+                }
+                self.depth[ix] = z;
+            }
+        }
+    }
+}
+
+/// Twice the signed area of the triangle `(p, q, (x, y))`. The result is
+/// exactly negated when `p` and `q` are swapped, so that pixels on an edge
+/// shared by two triangles are covered by exactly one of them.
+fn edge<const N: usize>(p: &Fragment<N>, q: &Fragment<N>, x: f32, y: f32) -> f32 {
+    if (p.x, p.y) > (q.x, q.y) {
+        return -edge(q, p, x, y);
+    }
+    (q.x - p.x) * (y - p.y) - (q.y - p.y) * (x - p.x)
+}
+
+/// Signed distance to the edge of a shape, as in the shape shader.
+fn edge_distance(edge: &[f32]) -> f32 {
+    let (x, y) = (edge[0], edge[1]);
+
+    if edge[3] > 0. {
+        (x.abs() - edge[2]).max(y.abs() - edge[3])
+    } else {
+        (x * x + y * y).sqrt() - edge[2]
+    }
+}
+
+/// Pattern coverage at the given position, as in the shape shader. The
+/// positions one pixel to the right and above are used for anti-aliasing.
+fn pattern_mask(pattern: &[f32], p: [f32; 2], dx: [f32; 2], dy: [f32; 2]) -> f32 {
+    if pattern[0] < 0.5 {
+        return 1.;
+    }
+    let (s, c) = pattern[1].sin_cos();
+    let uv = |p: [f32; 2]| [c * p[0] + s * p[1], c * p[1] - s * p[0]];
+    let (p, dx, dy) = (uv(p), uv(dx), uv(dy));
+
+    let spacing = pattern[2].max(1.);
+    let half_width = pattern[3] * 0.5;
+    let cell = |v: f32| {
+        let v = v / spacing + 0.5;
+        (v - v.floor() - 0.5) * spacing
+    };
+    let (cx, cy) = (cell(p[0]), cell(p[1]));
+    let fwidth = [
+        (dx[0] - p[0]).abs() + (dy[0] - p[0]).abs(),
+        (dx[1] - p[1]).abs() + (dy[1] - p[1]).abs(),
+    ];
+    let w = (fwidth[0] * fwidth[0] + fwidth[1] * fwidth[1])
+        .sqrt()
+        .max(0.0001);
+    let line = |d: f32| ((half_width - d) / w + 0.5).clamp(0., 1.);
+
+    let stripes = line(cy.abs());
+
+    if pattern[0] < 1.5 {
+        stripes
+    } else if pattern[0] < 2.5 {
+        stripes.max(line(cx.abs()))
+    } else {
+        line((cx * cx + cy * cy).sqrt())
+    }
+}
+
+/// Sample a texture at the given coordinates, repeating it. Returns a
+/// linear color.
+fn sample(texture: &[Rgba8], w: u32, h: u32, u: f32, v: f32, filter: &Filter) -> [f32; 4] {
+    let texel = |x: i64, y: i64| {
+        let x = x.rem_euclid(w as i64) as u32;
+        let y = y.rem_euclid(h as i64) as u32;
+        let t = texture[(y * w + x) as usize];
+        let [r, g, b] = linearize(t);
+
+        [r, g, b, t.a as f32 / 255.]
+    };
+    let (x, y) = (u * w as f32, v * h as f32);
+
+    match filter {
+        Filter::Nearest => texel(x.floor() as i64, y.floor() as i64),
+        Filter::Linear => {
+            let (x, y) = (x - 0.5, y - 0.5);
+            let (x0, y0) = (x.floor(), y.floor());
+            let (fx, fy) = (x - x0, y - y0);
+            let (x0, y0) = (x0 as i64, y0 as i64);
+
+            let (a, b) = (texel(x0, y0), texel(x0 + 1, y0));
+            let (c, d) = (texel(x0, y0 + 1), texel(x0 + 1, y0 + 1));
+            let mut out = [0.; 4];
+
+            for i in 0..4 {
+                let top = a[i] + (b[i] - a[i]) * fx;
+                let bottom = c[i] + (d[i] - c[i]) * fx;
+                out[i] = top + (bottom - top) * fy;
+            }
+            out
+        }
+    }
+}
+
+/// Convert an sRGB color to linear space.
+fn linearize(c: Rgba8) -> [f32; 3] {
+    let linear = |v: u8| {
+        let v = v as f32 / 255.;
+        if v < 0.04045 {
+            v / 12.92
+        } else {
+            ((v + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    [linear(c.r), linear(c.g), linear(c.b)]
+}
+
+/// Convert a linear color component to sRGB.
+fn srgb(v: f32) -> f32 {
+    if v < 0.003_130_8 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1. / 2.4) - 0.055
+    }
+}
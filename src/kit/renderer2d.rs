@@ -0,0 +1,373 @@
+//! Retained submission of sprites, shapes and text, batched and sorted
+//! automatically.
+//!
+//! Instead of managing batches by hand, draws are submitted to a
+//! `Renderer2D` on a layer, every frame. When the frame is finished, draws
+//! are sorted by layer, pipeline and texture, and compatible draws are
+//! merged into shared buffers. The resulting `Frame2D` is drawn in a single
+//! pass, switching pipelines and bindings only when they change.
+//!
+//! Layers are drawn in increasing order, each over the layers before it.
+//! Within a layer, overlapping draws are ordered by their depth, which is
+//! remapped into the depth range of the layer.
+//!
+//! # Examples
+//!
+//! ```
+//! use rgx::core::Rgba;
+//! use rgx::kit::renderer2d::Renderer2D;
+//! use rgx::kit::shape2d::{Fill, Shape, Stroke};
+//! use rgx::kit::ZDepth;
+//! use rgx::rect::Rect;
+//!
+//! let mut r2d = Renderer2D::new();
+//! let tiles = r2d.texture(256, 256);
+//!
+//! // The background is drawn first, whatever the order of submission.
+//! r2d.draw_shape(
+//!     1,
+//!     Shape::Rectangle(
+//!         Rect::new(8., 8., 24., 24.),
+//!         ZDepth::ZERO,
+//!         Default::default(),
+//!         Stroke::NONE,
+//!         Fill::Solid(Rgba::new(1., 0., 0., 1.)),
+//!     ),
+//! );
+//! r2d.draw_sprite(0, tiles, Rect::origin(16., 16.), Rect::origin(32., 32.), ZDepth::ZERO, Rgba::WHITE);
+//!
+//! assert_eq!(r2d.layers(), vec![0, 1]);
+//! assert_eq!(r2d.len(), 2);
+//! ```
+use std::collections::BTreeMap;
+
+use crate::core;
+use crate::core::Rgba;
+use crate::kit::shape2d::{self, Shape};
+use crate::kit::sprite2d::{self, MultiBatch, MultiBuffer, TextureId};
+#[cfg(feature = "text")]
+use crate::kit::text::{Orientation, Run, TextBatch};
+use crate::kit::{Repeat, ZDepth};
+#[cfg(feature = "text")]
+use crate::math::*;
+use crate::rect::Rect;
+
+///////////////////////////////////////////////////////////////////////////
+// Renderer2D
+///////////////////////////////////////////////////////////////////////////
+
+/// A sprite, as submitted.
+#[derive(Copy, Clone, Debug)]
+struct Sprite {
+    texture: TextureId,
+    src: Rect<f32>,
+    dst: Rect<f32>,
+    depth: ZDepth,
+    color: Rgba,
+}
+
+/// The draws of a layer, as submitted.
+#[derive(Debug, Default)]
+struct Layer {
+    shapes: Vec<Shape>,
+    sprites: Vec<Sprite>,
+    #[cfg(feature = "text")]
+    text: Vec<(ZDepth, Run)>,
+}
+
+/// Collects the draws of a frame, to be sorted and batched when the frame is
+/// finished.
+#[derive(Debug, Default)]
+pub struct Renderer2D {
+    /// The textures draws can use, as an empty batch of each.
+    textures: MultiBatch,
+    layers: BTreeMap<i32, Layer>,
+    #[cfg(feature = "text")]
+    text: Option<(TextureId, TextBatch)>,
+}
+
+impl Renderer2D {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a texture of the given size, returning its id. When the frame is
+    /// drawn, a binding is needed for each texture, in the order they were
+    /// added.
+    pub fn texture(&mut self, w: u32, h: u32) -> TextureId {
+        self.textures.texture(w, h)
+    }
+
+    /// Use a text batch to draw text, returning the id of its glyph atlas
+    /// texture. The atlas still has to be uploaded once the frame is
+    /// finished, see `Renderer2D::text_batch`.
+    #[cfg(feature = "text")]
+    pub fn text(&mut self, batch: TextBatch) -> TextureId {
+        let id = self.textures.texture(batch.atlas.w, batch.atlas.h);
+        self.text = Some((id, batch));
+        id
+    }
+
+    /// The text batch used to draw text, if any, eg. to upload its atlas.
+    #[cfg(feature = "text")]
+    pub fn text_batch(&mut self) -> Option<&mut TextBatch> {
+        self.text.as_mut().map(|(_, batch)| batch)
+    }
+
+    /// Draw a region of a texture.
+    pub fn draw_sprite(
+        &mut self,
+        layer: i32,
+        texture: TextureId,
+        src: Rect<f32>,
+        dst: Rect<f32>,
+        depth: ZDepth,
+        color: Rgba,
+    ) {
+        self.layer(layer).sprites.push(Sprite {
+            texture,
+            src,
+            dst,
+            depth,
+            color,
+        });
+    }
+
+    /// Draw a shape, at its own depth.
+    pub fn draw_shape(&mut self, layer: i32, shape: Shape) {
+        self.layer(layer).shapes.push(shape);
+    }
+
+    /// Draw text, with the text batch given to `Renderer2D::text`. The
+    /// position is the start of the baseline of the first line.
+    #[cfg(feature = "text")]
+    pub fn draw_text(
+        &mut self,
+        layer: i32,
+        depth: ZDepth,
+        text: &str,
+        pos: Point2<f32>,
+        size: f32,
+        color: Rgba,
+    ) {
+        assert!(
+            self.text.is_some(),
+            "fatal: `Renderer2D::text` must be called before drawing text"
+        );
+        self.layer(layer).text.push((
+            depth,
+            Run {
+                text: text.to_owned(),
+                pos,
+                size,
+                color,
+                orientation: Orientation::Horizontal,
+            },
+        ));
+    }
+
+    /// The layers with draws, in the order they are drawn.
+    pub fn layers(&self) -> Vec<i32> {
+        self.layers.keys().copied().collect()
+    }
+
+    /// Number of draws submitted.
+    pub fn len(&self) -> usize {
+        self.layers
+            .values()
+            .map(|l| {
+                #[cfg(feature = "text")]
+                let text = l.text.len();
+                #[cfg(not(feature = "text"))]
+                let text = 0;
+
+                l.shapes.len() + l.sprites.len() + text
+            })
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Discard the draws submitted, keeping the textures.
+    pub fn clear(&mut self) {
+        self.layers.clear();
+    }
+
+    /// Sort and batch the draws submitted, creating the buffers of the
+    /// frame. Draws are cleared, for the next frame to be submitted.
+    pub fn finish(&mut self, r: &core::Renderer) -> Frame2D {
+        let count = self.layers.len();
+        let mut layers = Vec::with_capacity(count);
+
+        for (i, (_, layer)) in std::mem::take(&mut self.layers).into_iter().enumerate() {
+            let depth = |z: ZDepth| depth(z, i, count);
+
+            let mut shapes = shape2d::Batch::new();
+            for mut shape in layer.shapes {
+                shape_depth(&mut shape, depth);
+                shapes.add(shape);
+            }
+
+            let mut sprites = self.textures.clone();
+            for s in layer.sprites {
+                sprites.batch(s.texture).add(
+                    s.src,
+                    s.dst,
+                    depth(s.depth),
+                    s.color,
+                    1.,
+                    Repeat::default(),
+                );
+            }
+
+            #[cfg(feature = "text")]
+            let text = match self.text.as_mut() {
+                Some((id, batch)) if !layer.text.is_empty() => {
+                    batch.clear();
+                    for (z, run) in layer.text {
+                        batch.zdepth = depth(z);
+                        batch.add_run(run);
+                    }
+                    Some((*id, batch.buffer(r)))
+                }
+                _ => None,
+            };
+
+            layers.push(LayerBuffers {
+                shapes: if shapes.is_empty() {
+                    None
+                } else {
+                    Some(shapes.buffer(r))
+                },
+                sprites: if sprites.is_empty() {
+                    None
+                } else {
+                    Some(sprites.finish(r))
+                },
+                #[cfg(feature = "text")]
+                text,
+            });
+        }
+        Frame2D {
+            layers,
+            textures: self.textures.textures(),
+        }
+    }
+
+    fn layer(&mut self, layer: i32) -> &mut Layer {
+        self.layers.entry(layer).or_default()
+    }
+}
+
+/// Remap a depth into the depth range of a layer. Visible depths are in the
+/// `[-1, 0]` range, with zero nearest, which is split between the layers.
+fn depth(z: ZDepth, layer: usize, count: usize) -> ZDepth {
+    let z = z.0.clamp(-1., 0.);
+    ZDepth((z - (count - 1 - layer) as f32) / count as f32)
+}
+
+/// Remap the depth of a shape, or of each vertex of a mesh.
+fn shape_depth<F: Fn(ZDepth) -> ZDepth>(shape: &mut Shape, depth: F) {
+    let z = match shape {
+        Shape::Line(_, z, ..)
+        | Shape::Rectangle(_, z, ..)
+        | Shape::RoundedRectangle(_, _, z, ..)
+        | Shape::Circle(_, z, ..)
+        | Shape::Polygon(_, z, ..)
+        | Shape::Polyline(_, z, ..)
+        | Shape::Path(_, z, ..)
+        | Shape::Triangle(_, _, _, z, ..) => z,
+        Shape::Mesh(mesh) => {
+            for v in mesh.vertices.iter_mut() {
+                v.position.z = depth(ZDepth(v.position.z)).0;
+            }
+            return;
+        }
+    };
+    *z = depth(*z);
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Frame2D
+///////////////////////////////////////////////////////////////////////////
+
+/// The buffers of a layer.
+#[derive(Debug)]
+struct LayerBuffers {
+    shapes: Option<core::IndexedBuffer>,
+    sprites: Option<MultiBuffer>,
+    #[cfg(feature = "text")]
+    text: Option<(TextureId, core::IndexedBuffer)>,
+}
+
+/// The pipeline last set while drawing a frame.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Current {
+    Shapes,
+    Sprites,
+}
+
+/// The batched draws of a frame, created with `Renderer2D::finish`.
+#[derive(Debug)]
+pub struct Frame2D {
+    layers: Vec<LayerBuffers>,
+    textures: usize,
+}
+
+impl Frame2D {
+    /// Draw the frame in a pass. Shapes are drawn with the shape pipeline,
+    /// and sprites and text with the sprite pipeline, with a binding for
+    /// each texture of the renderer, in order.
+    pub fn draw(
+        &self,
+        shapes: &shape2d::Pipeline,
+        sprites: &sprite2d::Pipeline,
+        bindings: &[&core::BindingGroup],
+        pass: &mut core::Pass,
+    ) {
+        assert_eq!(
+            bindings.len(),
+            self.textures,
+            "fatal: a binding is needed for each texture of the renderer"
+        );
+        let mut current = None;
+
+        for layer in self.layers.iter() {
+            if let Some(buffer) = &layer.shapes {
+                if current != Some(Current::Shapes) {
+                    pass.set_pipeline(shapes);
+                    current = Some(Current::Shapes);
+                }
+                pass.draw_indexed_buffer(buffer);
+            }
+            if let Some(buffer) = &layer.sprites {
+                if current != Some(Current::Sprites) {
+                    pass.set_pipeline(sprites);
+                    current = Some(Current::Sprites);
+                }
+                buffer.draw(bindings, pass);
+            }
+            #[cfg(feature = "text")]
+            {
+                if let Some((texture, buffer)) = &layer.text {
+                    if current != Some(Current::Sprites) {
+                        pass.set_pipeline(sprites);
+                        current = Some(Current::Sprites);
+                    }
+                    pass.draw(buffer, bindings[texture.index()]);
+                }
+            }
+        }
+    }
+
+    /// Number of layers drawn.
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+}
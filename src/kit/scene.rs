@@ -0,0 +1,220 @@
+//! Static scenes, baked once into buffers and a minimal list of draws, eg.
+//! for menu screens and backgrounds that never change.
+//!
+//! Batches are added in drawing order. Consecutive batches which can be
+//! drawn together, ie. shape batches with the same tessellation settings,
+//! or sprite batches of the same texture, are merged as they are added, so
+//! that the scene is drawn with as few draw calls and pipeline switches as
+//! possible. The signed distance quads of shape batches with `sdf` set are
+//! drawn after their tessellated shapes, see `shape2d::Batch::sdf`.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use rgx::kit::scene::SceneBuilder;
+//!
+//! let mut builder = SceneBuilder::new();
+//!
+//! builder.sprites(0, background);
+//! builder.shapes(frame);
+//! builder.shapes(buttons);
+//! builder.sprites(1, icons);
+//!
+//! // Three draws: the background, the frame and buttons, and the icons.
+//! let scene = builder.bake(&r);
+//!
+//! // Every frame.
+//! scene.draw(
+//!     &shapes,
+//!     &rounded,
+//!     &sprites,
+//!     &[&background_binding, &icons_binding],
+//!     &mut pass,
+//! );
+//! ```
+use crate::core;
+use crate::kit::shape2d::{self, sdf};
+use crate::kit::sprite2d;
+
+///////////////////////////////////////////////////////////////////////////
+// SceneBuilder
+///////////////////////////////////////////////////////////////////////////
+
+/// A batch added to a scene.
+#[derive(Debug)]
+enum Item {
+    Shapes(shape2d::Batch),
+    /// Sprites, with the index of their texture binding.
+    Sprites(usize, sprite2d::Batch),
+}
+
+/// Collects the batches of a static scene, to be baked with
+/// `SceneBuilder::bake`.
+#[derive(Debug, Default)]
+pub struct SceneBuilder {
+    items: Vec<Item>,
+}
+
+impl SceneBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a batch of shapes, drawn over the batches added before it. It is
+    /// merged into the last batch if it is tessellated the same way.
+    ///
+    /// ```
+    /// use rgx::kit::scene::SceneBuilder;
+    /// use rgx::kit::shape2d::Batch;
+    ///
+    /// let mut builder = SceneBuilder::new();
+    /// let mut smooth = Batch::new();
+    /// smooth.antialias = true;
+    ///
+    /// builder.shapes(Batch::new());
+    /// builder.shapes(Batch::new());
+    /// builder.shapes(smooth);
+    ///
+    /// assert_eq!(builder.len(), 2);
+    /// ```
+    pub fn shapes(&mut self, mut batch: shape2d::Batch) {
+        match self.items.last_mut() {
+            Some(Item::Shapes(last)) if mergeable(last, &batch) => {
+                last.merge(&mut batch);
+            }
+            _ => self.items.push(Item::Shapes(batch)),
+        }
+    }
+
+    /// Add a batch of sprites, drawn with the binding at the given index
+    /// when the scene is drawn, over the batches added before it.
+    pub fn sprites(&mut self, binding: usize, mut batch: sprite2d::Batch) {
+        match self.items.last_mut() {
+            Some(Item::Sprites(b, last))
+                if *b == binding && last.w == batch.w && last.h == batch.h =>
+            {
+                last.merge(&mut batch);
+            }
+            _ => self.items.push(Item::Sprites(binding, batch)),
+        }
+    }
+
+    /// Number of draws the scene will be drawn with.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Create the buffers of the scene.
+    pub fn bake(self, r: &core::Renderer) -> StaticScene {
+        let draws = self
+            .items
+            .into_iter()
+            .filter_map(|item| match item {
+                Item::Shapes(batch) if !batch.is_empty() => Some(Draw::Shapes(batch.finish(r))),
+                Item::Sprites(binding, batch) if !batch.is_empty() => {
+                    Some(Draw::Sprites(binding, batch.finish(r)))
+                }
+                _ => None,
+            })
+            .collect();
+
+        StaticScene { draws }
+    }
+}
+
+/// Whether the shapes of `batch` are drawn the same once merged into `last`.
+/// `Batch::merge` keeps the opacity of the merged batch, but the shapes are
+/// tessellated with the settings of `last`, and its opacity applies to all
+/// of them.
+fn mergeable(last: &shape2d::Batch, batch: &shape2d::Batch) -> bool {
+    last.opacity == 1.
+        && last.antialias == batch.antialias
+        && last.deterministic == batch.deterministic
+        && last.feather == batch.feather
+        && last.sdf == batch.sdf
+        && last.tolerance == batch.tolerance
+}
+
+///////////////////////////////////////////////////////////////////////////
+// StaticScene
+///////////////////////////////////////////////////////////////////////////
+
+/// A draw of a static scene.
+#[derive(Debug)]
+enum Draw {
+    Shapes(shape2d::Buffers<core::IndexedBuffer>),
+    Sprites(usize, core::IndexedBuffer),
+}
+
+/// The pipeline last set on the pass, while drawing a scene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Current {
+    Shapes,
+    Sdf,
+    Sprites,
+}
+
+/// A scene baked into buffers, which can be drawn every frame without
+/// creating or uploading anything.
+#[derive(Debug)]
+pub struct StaticScene {
+    draws: Vec<Draw>,
+}
+
+impl StaticScene {
+    /// Draw the scene in a pass, with the shape pipeline for shapes, the
+    /// signed distance pipeline for the quads of shape batches with `sdf`
+    /// set, and the sprite pipeline and the given bindings for sprites. The
+    /// pipeline is only switched when it changes from one draw to the next.
+    pub fn draw(
+        &self,
+        shapes: &shape2d::Pipeline,
+        rounded: &sdf::Pipeline,
+        sprites: &sprite2d::Pipeline,
+        bindings: &[&core::BindingGroup],
+        pass: &mut core::Pass,
+    ) {
+        let mut current = None;
+
+        for draw in self.draws.iter() {
+            match draw {
+                Draw::Shapes(buffers) => {
+                    if let Some(buffer) = &buffers.shapes {
+                        if current != Some(Current::Shapes) {
+                            pass.set_pipeline(shapes);
+                            current = Some(Current::Shapes);
+                        }
+                        pass.draw_indexed_buffer(buffer);
+                    }
+                    if let Some(buffer) = &buffers.sdf {
+                        if current != Some(Current::Sdf) {
+                            pass.set_pipeline(rounded);
+                            current = Some(Current::Sdf);
+                        }
+                        pass.draw_indexed_buffer(buffer);
+                    }
+                }
+                Draw::Sprites(binding, buffer) => {
+                    if current != Some(Current::Sprites) {
+                        pass.set_pipeline(sprites);
+                        current = Some(Current::Sprites);
+                    }
+                    pass.draw(buffer, bindings[*binding]);
+                }
+            }
+        }
+    }
+
+    /// Number of draws.
+    pub fn len(&self) -> usize {
+        self.draws.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.draws.is_empty()
+    }
+}
@@ -1,12 +1,23 @@
 use std::f32;
 
+use crate::math::portable;
 use crate::math::*;
 
 use crate::core;
 use crate::core::{Binding, BindingType, Rgba, Set, ShaderStage};
 use crate::rect::Rect;
 
-use crate::kit::{Model, Rgba8, ZDepth};
+use crate::kit::transform::{self, IntoMatrix, TransformStack};
+use crate::kit::{Camera2D, Model, Rgba8, ZDepth};
+
+#[cfg(feature = "lyon")]
+pub mod lyon;
+pub mod sdf;
+#[cfg(feature = "svg")]
+mod svg;
+
+#[cfg(feature = "svg")]
+pub use svg::from_svg;
 
 ///////////////////////////////////////////////////////////////////////////
 // Uniforms
@@ -17,6 +28,46 @@ use crate::kit::{Model, Rgba8, ZDepth};
 pub struct Uniforms {
     pub ortho: Matrix4<f32>,
     pub transform: Matrix4<f32>,
+    /// Time, in seconds, that animated shapes are drawn at.
+    pub time: f32,
+}
+
+/// What the shape pipeline is prepared with: a projection, and the time in
+/// seconds that shapes added with `Batch::add_animated` are drawn at.
+/// Projections, eg. from `kit::ortho` or a `Camera2D`, convert into globals
+/// at time zero.
+///
+/// ```ignore
+/// r.update_pipeline(&shapes, Globals::new(kit::ortho(w, h), elapsed), &mut frame);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Globals {
+    pub ortho: Matrix4<f32>,
+    pub time: f32,
+}
+
+impl Globals {
+    pub fn new(ortho: Matrix4<f32>, time: f32) -> Self {
+        Self { ortho, time }
+    }
+}
+
+impl From<Matrix4<f32>> for Globals {
+    fn from(ortho: Matrix4<f32>) -> Self {
+        Self::new(ortho, 0.)
+    }
+}
+
+impl From<Camera2D> for Globals {
+    fn from(camera: Camera2D) -> Self {
+        Self::new(camera.matrix(), 0.)
+    }
+}
+
+impl From<&Camera2D> for Globals {
+    fn from(camera: &Camera2D) -> Self {
+        Self::new(camera.matrix(), 0.)
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////
@@ -26,10 +77,16 @@ pub struct Uniforms {
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
 pub struct Vertex {
-    position: Vector3<f32>,
-    angle: f32,
-    center: Vector2<f32>,
-    color: Rgba8,
+    pub(crate) position: Vector3<f32>,
+    pub(crate) angle: f32,
+    pub(crate) center: Vector2<f32>,
+    pub(crate) color: Rgba8,
+    pub(crate) opacity: f32,
+    data: Vector2<f32>,
+    pub(crate) edge: Vector4<f32>,
+    pub(crate) pattern: Vector4<f32>,
+    motion: Vector4<f32>,
+    offset: Vector2<f32>,
 }
 
 impl Vertex {
@@ -39,8 +96,29 @@ impl Vertex {
             angle,
             center: Vector2::new(center.x, center.y),
             color,
+            opacity: 1.0,
+            data: Vector2::new(0., 0.),
+            edge: Vector4::new(0., 0., 0., 0.),
+            pattern: Vector4::new(0., 0., 0., 0.),
+            motion: Vector4::new(0., 0., 0., 0.),
+            offset: Vector2::new(0., 0.),
         }
     }
+
+    pub fn position(&self) -> Vector3<f32> {
+        self.position
+    }
+
+    /// Generic data passed on to the fragment shader, for use by custom
+    /// shaders. Shapes set this to the vertex coordinates relative to
+    /// the shape's bounding box, in the `[0, 1]` range.
+    pub fn data(&self) -> Vector2<f32> {
+        self.data
+    }
+
+    pub fn set_data(&mut self, data: Vector2<f32>) {
+        self.data = data;
+    }
 }
 
 #[inline]
@@ -59,6 +137,38 @@ pub const fn vertex(
 // Pipeline
 ///////////////////////////////////////////////////////////////////////////
 
+/// Vertex layout of shapes, for use in custom pipelines.
+pub const VERTEX_LAYOUT: &[core::VertexFormat] = &[
+    // Position
+    core::VertexFormat::Float3,
+    // Roation angle.
+    core::VertexFormat::Float,
+    // Center of rotation.
+    core::VertexFormat::Float2,
+    // Color
+    core::VertexFormat::UByte4,
+    // Opacity
+    core::VertexFormat::Float,
+    // Generic data
+    core::VertexFormat::Float2,
+    // Edge distance
+    core::VertexFormat::Float4,
+    // Pattern
+    core::VertexFormat::Float4,
+    // Motion
+    core::VertexFormat::Float4,
+    // Pulse offset
+    core::VertexFormat::Float2,
+];
+
+/// Vertex shader of shapes, for use in custom pipelines. Outputs the vertex
+/// color at location `0`, the vertex data at location `1`, the edge
+/// distance attribute at location `2`, the pattern attribute at location `3`,
+/// the untransformed vertex position at location `4`, and the shimmer of the
+/// vertex's `Motion` at location `5`, if any: its phase in `x`, which is
+/// negative otherwise, its width in `y` and its intensity in `z`.
+pub const VERTEX_SHADER: &[u8] = include_bytes!("data/shape.vert.spv");
+
 pub struct Pipeline {
     pipeline: core::Pipeline,
     bindings: core::BindingGroup,
@@ -69,21 +179,12 @@ pub struct Pipeline {
 //////////////////////////////////////////////////////////////////////////
 
 impl<'a> core::AbstractPipeline<'a> for Pipeline {
-    type PrepareContext = Matrix4<f32>;
+    type PrepareContext = Globals;
     type Uniforms = self::Uniforms;
 
     fn description() -> core::PipelineDescription<'a> {
         core::PipelineDescription {
-            vertex_layout: &[
-                // Position
-                core::VertexFormat::Float3,
-                // Roation angle.
-                core::VertexFormat::Float,
-                // Center of rotation.
-                core::VertexFormat::Float2,
-                // Color
-                core::VertexFormat::UByte4,
-            ],
+            vertex_layout: VERTEX_LAYOUT,
             pipeline_layout: &[
                 Set(&[Binding {
                     binding: BindingType::UniformBuffer,
@@ -95,7 +196,7 @@ impl<'a> core::AbstractPipeline<'a> for Pipeline {
                 }]),
             ],
             // TODO: Use `env("CARGO_MANIFEST_DIR")`
-            vertex_shader: include_bytes!("data/shape.vert.spv"),
+            vertex_shader: VERTEX_SHADER,
             fragment_shader: include_bytes!("data/shape.frag.spv"),
         }
     }
@@ -104,7 +205,11 @@ impl<'a> core::AbstractPipeline<'a> for Pipeline {
         let transform = Matrix4::identity();
         let ortho = Matrix4::identity();
         let model = Model::new(&pipeline.layout.sets[1], &[Matrix4::identity()], dev);
-        let buf = dev.create_uniform_buffer(&[self::Uniforms { ortho, transform }]);
+        let buf = dev.create_uniform_buffer(&[self::Uniforms {
+            ortho,
+            transform,
+            time: 0.,
+        }]);
         let bindings = dev.create_binding_group(&pipeline.layout.sets[0], &[&buf]);
 
         Self {
@@ -121,12 +226,23 @@ impl<'a> core::AbstractPipeline<'a> for Pipeline {
         pass.set_binding(&self.model.binding, &[]);
     }
 
+    fn projection(&self) -> Option<&core::UniformBuffer> {
+        Some(&self.buf)
+    }
+
     fn prepare(
         &'a self,
-        ortho: Matrix4<f32>,
+        Globals { ortho, time }: Globals,
     ) -> Option<(&'a core::UniformBuffer, Vec<self::Uniforms>)> {
         let transform = Matrix4::identity();
-        Some((&self.buf, vec![self::Uniforms { transform, ortho }]))
+        Some((
+            &self.buf,
+            vec![self::Uniforms {
+                transform,
+                ortho,
+                time,
+            }],
+        ))
     }
 }
 
@@ -134,34 +250,423 @@ impl<'a> core::AbstractPipeline<'a> for Pipeline {
 /// Shapes
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
-#[derive(PartialEq, Copy, Clone, Debug)]
+#[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Stroke {
-    width: f32,
-    color: Rgba,
+    pub(crate) width: f32,
+    pub(crate) color: Rgba,
+    pub(crate) gradient: Option<Gradient>,
+    pub(crate) join: Join,
+    pub(crate) cap: Cap,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) hairline: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) dash: Option<Dash>,
 }
 
 impl Stroke {
     pub const NONE: Self = Self {
         width: 0.,
         color: Rgba::TRANSPARENT,
+        gradient: None,
+        join: Join::Miter,
+        cap: Cap::Butt,
+        hairline: false,
+        dash: None,
     };
 
     pub fn new(width: f32, color: Rgba) -> Self {
-        Self { width, color }
+        Self {
+            width,
+            color,
+            gradient: None,
+            join: Join::Miter,
+            cap: Cap::Butt,
+            hairline: false,
+            dash: None,
+        }
+    }
+
+    /// A one pixel wide stroke, for lines, polylines and paths, whose
+    /// coverage is computed per pixel instead of rasterizing a thin quad,
+    /// so that thin diagonal lines look smooth, eg. in plots and diagrams.
+    /// Segments are drawn separately, without joins.
+    pub fn hairline(color: Rgba) -> Self {
+        Self {
+            hairline: true,
+            ..Self::new(1., color)
+        }
+    }
+
+    /// A stroke fading from one color at the start of the stroked line to
+    /// another at its end, eg. for lines with a color per end point.
+    pub fn fade(width: f32, from: Rgba, to: Rgba) -> Self {
+        Self::gradient(width, Gradient::new(&[(0., from), (1., to)]))
+    }
+
+    /// A stroke whose color follows the gradient along the length of the
+    /// stroked line. For rectangles and circles, the gradient runs along the
+    /// perimeter, counter-clockwise from the bottom-left corner and from the
+    /// right-most point respectively.
+    ///
+    /// ```
+    /// use rgx::core::Rgba;
+    /// use rgx::kit::shape2d::{Fill, Gradient, Rotation, Shape, Stroke};
+    /// use rgx::kit::ZDepth;
+    /// use rgx::rect::Rect;
+    ///
+    /// let gradient = Gradient::new(&[(0., Rgba::BLACK), (0.125, Rgba::WHITE), (1., Rgba::BLACK)]);
+    /// let square = Shape::Rectangle(
+    ///     Rect::new(0., 0., 10., 10.),
+    ///     ZDepth::ZERO,
+    ///     Rotation::ZERO,
+    ///     Stroke::gradient(1., gradient),
+    ///     Fill::Empty(),
+    /// );
+    /// let mesh = square.triangulate();
+    ///
+    /// // The bottom side is split at the white stop, halfway along it.
+    /// assert_eq!(mesh.vertices.len(), 5 * 4);
+    /// assert!(mesh.vertices.iter().any(|v| v.position().x == 5. && v.position().y == 0.));
+    /// ```
+    pub fn gradient(width: f32, gradient: Gradient) -> Self {
+        Self {
+            width,
+            color: gradient.color(0.),
+            gradient: Some(gradient),
+            join: Join::Miter,
+            cap: Cap::Butt,
+            hairline: false,
+            dash: None,
+        }
+    }
+
+    /// Set how segments are joined, in polylines and paths.
+    pub fn join(mut self, join: Join) -> Self {
+        self.join = join;
+        self
+    }
+
+    /// Set how open polylines and paths are ended.
+    pub fn cap(mut self, cap: Cap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    /// Set the dash pattern of lines, eg. for dashed or dotted gridlines.
+    /// With square caps, each dash is extended by half the stroke width.
+    ///
+    /// ```
+    /// use rgx::core::Rgba;
+    /// use rgx::kit::shape2d::{Dash, Shape, Stroke, Line, Rotation};
+    /// use rgx::kit::ZDepth;
+    ///
+    /// let line = Shape::Line(
+    ///     Line::new(0., 0., 100., 0.),
+    ///     ZDepth::ZERO,
+    ///     Rotation::ZERO,
+    ///     Stroke::new(1., Rgba::WHITE).dash(Dash::new(&[10., 5.])),
+    /// );
+    ///
+    /// // Seven dashes, of four vertices each.
+    /// assert_eq!(line.triangulate().vertices.len(), 7 * 4);
+    /// ```
+    pub fn dash(mut self, dash: Dash) -> Self {
+        self.dash = Some(dash);
+        self
+    }
+
+    /// The stroke color at the given position along the stroke.
+    fn color(&self, pos: f32) -> Rgba8 {
+        match self.gradient {
+            Some(ref g) => g.color(pos).into(),
+            None => self.color.into(),
+        }
+    }
+
+    /// Positions at which the stroke needs to be subdivided.
+    fn positions(&self) -> Vec<f32> {
+        match self.gradient {
+            Some(ref g) => g.positions(),
+            None => vec![0., 1.],
+        }
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+/// A dash pattern, as alternating lengths of dashes and gaps along a stroke,
+/// starting with a dash. As with SVG, a pattern with an odd number of lengths
+/// is repeated twice.
+#[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dash {
+    pub(crate) pattern: Vec<f32>,
+    pub(crate) offset: f32,
+}
+
+impl Dash {
+    pub fn new(pattern: &[f32]) -> Self {
+        assert!(
+            pattern.iter().all(|l| *l >= 0.) && pattern.iter().sum::<f32>() > 0.,
+            "fatal: dash patterns must have positive lengths"
+        );
+        Self {
+            pattern: pattern.to_vec(),
+            offset: 0.,
+        }
+    }
+
+    /// Dots of the given size, separated by the given gap.
+    pub fn dotted(size: f32, gap: f32) -> Self {
+        Self::new(&[size, gap])
+    }
+
+    /// Set the distance into the pattern at which the stroke starts, eg. to
+    /// animate the dashes.
+    pub fn offset(mut self, offset: f32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// The dashes along a stroke of the given length, as start and end
+    /// positions relative to its length.
+    fn intervals(&self, length: f32) -> Vec<(f32, f32)> {
+        let mut intervals = Vec::new();
+        if length <= 0. {
+            return intervals;
+        }
+        // Odd patterns only repeat after two runs, with dashes and gaps
+        // swapped in the second one, so the offset wraps around both.
+        let pattern = if self.pattern.len() % 2 == 1 {
+            [&self.pattern[..], &self.pattern[..]].concat()
+        } else {
+            self.pattern.clone()
+        };
+        let total = pattern.iter().sum::<f32>();
+        let mut pos = -self.offset.rem_euclid(total);
+
+        for (i, l) in pattern.iter().cycle().enumerate() {
+            if pos >= length {
+                break;
+            }
+            let end = pos + l;
+
+            if i % 2 == 0 {
+                let (start, end) = (pos.max(0.), end.min(length));
+                if end > start {
+                    intervals.push((start / length, end / length));
+                }
+            }
+            pos = end;
+        }
+        intervals
+    }
+}
+
+/// How stroked segments are joined.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Join {
+    /// Sharp corners. Corners sharper than the miter limit are beveled.
+    Miter,
+    Round,
+    Bevel,
+}
+
+/// How the ends of open strokes are drawn.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Cap {
+    /// The stroke ends exactly at the end point.
+    Butt,
+    /// The stroke ends with a half circle around the end point.
+    Round,
+    /// The stroke extends past the end point, by half its width.
+    Square,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Fill {
     Empty(),
     Solid(Rgba),
-    Gradient(Rgba, Rgba),
+    /// Gradient between two colors. Rectangles and polygons are filled
+    /// linearly, in the direction of the given angle, in radians, where `0.0`
+    /// goes from left to right. Circles are filled radially from the center
+    /// outwards, and ignore the angle.
+    Gradient(Rgba, Rgba, f32),
+    /// Gradient with an arbitrary number of color stops. Rectangles and
+    /// polygons are filled from left to right, circles from the center
+    /// outwards.
+    Stops(Gradient),
+    /// Procedural pattern in the given color, over a transparent background.
+    Pattern(Rgba, Pattern),
+}
+
+/// Style of a pattern fill.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PatternStyle {
+    /// Parallel lines.
+    Stripes,
+    /// Two sets of perpendicular lines.
+    Hatching,
+    /// A grid of dots.
+    Dots,
+}
+
+/// A pattern fill, evaluated in the fragment shader. Patterns are laid out
+/// in shape coordinates, and so follow the shape's rotation.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pattern {
+    pub style: PatternStyle,
+    /// Angle of the pattern, in radians. At `0.0`, stripes are horizontal.
+    pub angle: f32,
+    /// Distance between lines or dots.
+    pub spacing: f32,
+    /// Thickness of lines, or diameter of dots.
+    pub thickness: f32,
+}
+
+impl Pattern {
+    pub fn stripes(angle: f32, spacing: f32, thickness: f32) -> Self {
+        Self {
+            style: PatternStyle::Stripes,
+            angle,
+            spacing,
+            thickness,
+        }
+    }
+
+    pub fn hatching(angle: f32, spacing: f32, thickness: f32) -> Self {
+        Self {
+            style: PatternStyle::Hatching,
+            angle,
+            spacing,
+            thickness,
+        }
+    }
+
+    pub fn dots(angle: f32, spacing: f32, diameter: f32) -> Self {
+        Self {
+            style: PatternStyle::Dots,
+            angle,
+            spacing,
+            thickness: diameter,
+        }
+    }
+
+    /// Vertex attribute read by the fragment shader.
+    fn attribute(&self) -> Vector4<f32> {
+        let style = match self.style {
+            PatternStyle::Stripes => 1.,
+            PatternStyle::Hatching => 2.,
+            PatternStyle::Dots => 3.,
+        };
+        Vector4::new(style, self.angle, self.spacing, self.thickness)
+    }
+}
+
+/// A list of color stops, each with a position in the `[0, 1]` range.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gradient {
+    stops: Vec<(f32, Rgba)>,
+}
+
+impl Gradient {
+    pub fn new(stops: &[(f32, Rgba)]) -> Self {
+        let mut gradient = Self { stops: Vec::new() };
+        for (pos, color) in stops {
+            gradient.add(*pos, *color);
+        }
+        gradient
+    }
+
+    /// Add a color stop. Stops are kept sorted by position.
+    pub fn add(&mut self, pos: f32, color: Rgba) {
+        let pos = pos.clamp(0., 1.);
+        let ix = self
+            .stops
+            .iter()
+            .position(|(p, _)| *p > pos)
+            .unwrap_or(self.stops.len());
+
+        self.stops.insert(ix, (pos, color));
+    }
+
+    pub fn stops(&self) -> &[(f32, Rgba)] {
+        &self.stops
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stops.is_empty()
+    }
+
+    /// The interpolated color at the given position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::core::Rgba;
+    /// use rgx::kit::shape2d::Gradient;
+    ///
+    /// let g = Gradient::new(&[(0.0, Rgba::BLACK), (1.0, Rgba::WHITE)]);
+    ///
+    /// assert_eq!(g.color(0.5), Rgba::new(0.5, 0.5, 0.5, 1.0));
+    /// assert_eq!(g.color(2.0), Rgba::WHITE);
+    /// ```
+    pub fn color(&self, pos: f32) -> Rgba {
+        let (first, last) = match (self.stops.first(), self.stops.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Rgba::TRANSPARENT,
+        };
+
+        if pos <= first.0 {
+            return first.1;
+        }
+        if pos >= last.0 {
+            return last.1;
+        }
+
+        for w in self.stops.windows(2) {
+            let ((p0, c0), (p1, c1)) = (w[0], w[1]);
+
+            if pos >= p0 && pos <= p1 {
+                let t = if p1 > p0 { (pos - p0) / (p1 - p0) } else { 0. };
+
+                return Rgba::new(
+                    c0.r + (c1.r - c0.r) * t,
+                    c0.g + (c1.g - c0.g) * t,
+                    c0.b + (c1.b - c0.b) * t,
+                    c0.a + (c1.a - c0.a) * t,
+                );
+            }
+        }
+        last.1
+    }
+
+    /// Positions at which the geometry needs to be subdivided, including
+    /// both ends.
+    fn positions(&self) -> Vec<f32> {
+        let mut positions = vec![0.];
+        for (pos, _) in self.stops.iter() {
+            if *pos > *positions.last().unwrap() {
+                positions.push(*pos);
+            }
+        }
+        if *positions.last().unwrap() < 1. {
+            positions.push(1.);
+        }
+        positions
+    }
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rotation {
-    angle: f32,
-    center: Point2<f32>,
+    pub(crate) angle: f32,
+    pub(crate) center: Point2<f32>,
 }
 
 impl Rotation {
@@ -181,172 +686,1605 @@ impl Default for Rotation {
     }
 }
 
+/// A built-in animation of a shape, driven by the time the shape pipeline
+/// is prepared with, see `Globals`, so that shapes don't have to be
+/// tessellated again as they move. See `Batch::add_animated`. Periods are in
+/// seconds.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Motion {
+    /// The stroke width grows to `scale` times its width, and back. Strokes
+    /// whose tessellation depends on their width, eg. with round joins, don't
+    /// pulse.
+    Pulse { period: f32, scale: f32 },
+    /// The opacity fades to `min`, and back.
+    Blink { period: f32, min: f32 },
+    /// A band of light sweeps across the shape, from left to right, with a
+    /// width relative to the shape's width.
+    Shimmer {
+        period: f32,
+        width: f32,
+        intensity: f32,
+    },
+}
+
+impl Motion {
+    /// The motion vertex attribute: the kind of motion, its period, and its
+    /// parameters.
+    fn attribute(&self) -> Vector4<f32> {
+        match *self {
+            Motion::Pulse { period, .. } => Vector4::new(1., period, 0., 0.),
+            Motion::Blink { period, min } => Vector4::new(2., period, min, 0.),
+            Motion::Shimmer {
+                period,
+                width,
+                intensity,
+            } => Vector4::new(3., period, width.max(f32::EPSILON), intensity),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Shape {
     Line(Line, ZDepth, Rotation, Stroke),
     Rectangle(Rect<f32>, ZDepth, Rotation, Stroke, Fill),
+    /// A rectangle with corners rounded to the given radius, which is
+    /// clamped to half its smallest side. Drawn like a polygon of its
+    /// outline, or as a single quad in batches with `sdf` set.
+    RoundedRectangle(Rect<f32>, f32, ZDepth, Rotation, Stroke, Fill),
     Circle(Point2<f32>, ZDepth, f32, u32, Stroke, Fill),
+    /// A simple polygon, convex or concave, given by its outline. As with
+    /// rectangles and circles, the stroke is drawn on the inside of the
+    /// outline. Gradient fills are interpolated between vertices.
+    Polygon(Vec<Point2<f32>>, ZDepth, Rotation, Stroke, Fill),
+    /// Connected line segments, stroked with the stroke's joins and caps.
+    Polyline(Vec<Point2<f32>>, ZDepth, Rotation, Stroke),
+    /// A path of line and bezier curve segments. The stroke is centered on
+    /// the path, and the fill implicitly closes it.
+    Path(Path, ZDepth, Rotation, Stroke, Fill),
+    /// A triangle, drawn like a polygon of its three corners, in any order.
+    Triangle(
+        Point2<f32>,
+        Point2<f32>,
+        Point2<f32>,
+        ZDepth,
+        Rotation,
+        Stroke,
+        Fill,
+    ),
+    /// Pre-triangulated geometry, eg. for custom gizmos or procedural
+    /// shapes, added to batches as is. Vertices carry their own depth and
+    /// rotation. Meshes can't be serialized, and aren't exported to vector
+    /// formats.
+    ///
+    /// ```
+    /// use rgx::kit::shape2d::{vertex, Batch, Mesh, Shape};
+    /// use rgx::kit::Rgba8;
+    /// use rgx::math::Point2;
+    ///
+    /// let v = |x, y| vertex(x, y, 0., 0., Point2::new(0., 0.), Rgba8::WHITE);
+    /// let mesh = Mesh {
+    ///     vertices: vec![v(0., 0.), v(8., 0.), v(4., 8.)],
+    ///     indices: vec![0, 1, 2],
+    /// };
+    ///
+    /// let mut batch = Batch::new();
+    /// batch.add(Shape::Mesh(mesh));
+    ///
+    /// assert_eq!(batch.mesh().indices, vec![0, 1, 2]);
+    /// ```
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Mesh(Mesh),
 }
 
 impl Shape {
-    pub fn triangulate(&self) -> Vec<Vertex> {
+    /// The stroke of the shape, if it has one.
+    fn stroke_mut(&mut self) -> Option<&mut Stroke> {
+        match self {
+            Shape::Line(_, _, _, stroke)
+            | Shape::Rectangle(_, _, _, stroke, _)
+            | Shape::RoundedRectangle(_, _, _, _, stroke, _)
+            | Shape::Circle(_, _, _, _, stroke, _)
+            | Shape::Polygon(_, _, _, stroke, _)
+            | Shape::Polyline(_, _, _, stroke)
+            | Shape::Path(_, _, _, stroke, _)
+            | Shape::Triangle(_, _, _, _, _, stroke, _) => Some(stroke),
+            Shape::Mesh(_) => None,
+        }
+    }
+
+    pub fn triangulate(&self) -> Mesh {
+        self.tessellate(false, Precision::NATIVE)
+    }
+
+    /// Like `triangulate`, with curves tessellated so that they are at most
+    /// `tolerance` away from their chords, in pixels. The side count of
+    /// circles is ignored, and round joins and caps, as well as paths, are
+    /// flattened to the same tolerance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::core::Rgba;
+    /// use rgx::kit::shape2d::{Fill, Shape, Stroke};
+    /// use rgx::kit::ZDepth;
+    /// use rgx::math::Point2;
+    ///
+    /// let circle = |radius| {
+    ///     Shape::Circle(
+    ///         Point2::new(0., 0.),
+    ///         ZDepth::default(),
+    ///         radius,
+    ///         8,
+    ///         Stroke::NONE,
+    ///         Fill::Solid(Rgba::WHITE),
+    ///     )
+    /// };
+    ///
+    /// // Larger circles get more sides.
+    /// let small = circle(4.).triangulate_with_tolerance(0.25);
+    /// let large = circle(64.).triangulate_with_tolerance(0.25);
+    /// assert!(large.vertices.len() > small.vertices.len());
+    /// ```
+    pub fn triangulate_with_tolerance(&self, tolerance: f32) -> Mesh {
+        self.tessellate(
+            false,
+            Precision {
+                tolerance: Some(tolerance),
+                ..Precision::NATIVE
+            },
+        )
+    }
+
+    /// Like `triangulate`, with antialiased fill edges. The fill is grown
+    /// by one unit, and its vertices carry their position relative to the
+    /// shape edge, which the fragment shader turns into coverage.
+    ///
+    /// Only rectangle and circle fills without a stroke are antialiased,
+    /// since a stroke covers the edge of the fill.
+    pub fn triangulate_antialiased(&self) -> Mesh {
+        self.tessellate(true, Precision::NATIVE)
+    }
+
+    /// Like `triangulate`, with the outer edges of strokes and fills
+    /// feathered over the given width, for smooth edges without MSAA. See
+    /// `Mesh::feather`.
+    ///
+    /// ```
+    /// use rgx::core::Rgba;
+    /// use rgx::kit::shape2d::{Fill, Shape, Stroke};
+    /// use rgx::kit::ZDepth;
+    /// use rgx::rect::Rect;
+    ///
+    /// let rect = Shape::Rectangle(
+    ///     Rect::new(0., 0., 16., 16.),
+    ///     ZDepth::default(),
+    ///     Default::default(),
+    ///     Stroke::new(2., Rgba::WHITE),
+    ///     Fill::Solid(Rgba::BLACK),
+    /// );
+    /// let plain = rect.triangulate();
+    /// let mesh = rect.triangulate_feathered(1.5);
+    ///
+    /// // One skirt quad per side, on the outside of the stroke only.
+    /// assert_eq!(mesh.indices.len(), plain.indices.len() + 4 * 6);
+    /// assert!(mesh.vertices[plain.vertices.len()..]
+    ///     .iter()
+    ///     .all(|v| v.position().x == -1.5 || v.position().x == 17.5));
+    /// ```
+    pub fn triangulate_feathered(&self, width: f32) -> Mesh {
+        let mut mesh = self.triangulate();
+        mesh.feather(width);
+        mesh
+    }
+
+    /// Like `triangulate` or `triangulate_antialiased`, with results that
+    /// are bit-identical on all platforms, eg. for geometry that is cached
+    /// or shared over the network. This is slower, as trigonometric
+    /// functions are computed in software, with `math::portable`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::core::Rgba;
+    /// use rgx::kit::shape2d::{Fill, Shape, Stroke};
+    /// use rgx::kit::ZDepth;
+    /// use rgx::math::Point2;
+    ///
+    /// let circle = Shape::Circle(
+    ///     Point2::new(0., 0.),
+    ///     ZDepth::default(),
+    ///     8.,
+    ///     16,
+    ///     Stroke::new(1., Rgba::WHITE),
+    ///     Fill::Empty(),
+    /// );
+    /// let mesh = circle.triangulate_deterministic(false);
+    /// let checksum = mesh.vertices.iter().fold(0u32, |sum, v| {
+    ///     let p = v.position();
+    ///     sum.wrapping_mul(31).wrapping_add(p.x.to_bits() ^ p.y.to_bits())
+    /// });
+    ///
+    /// // The same on every platform.
+    /// assert_eq!(checksum, 0xa96b_7ac3);
+    /// ```
+    pub fn triangulate_deterministic(&self, antialias: bool) -> Mesh {
+        self.tessellate(antialias, Precision::PORTABLE)
+    }
+
+    /// Extrude the outline of the shape along the Z axis, towards the
+    /// viewer, eg. for bar maps or buildings in a 2.5D view. The shape is
+    /// raised by `height`, and walls join it to its outline at its depth.
+    ///
+    /// Faces are shaded by a directional light, pointing from the surface
+    /// towards the light, with a fixed ambient term. Walls use the first
+    /// color of the fill, or the stroke color if there is no fill. Lines
+    /// and polylines have no outline, and are only raised.
+    ///
+    /// Extruded shapes are meant to be drawn with a perspective projection,
+    /// such as `kit::perspective`, since the orthographic projection of the
+    /// kit clips anything in front of depth zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::core::Rgba;
+    /// use rgx::kit::shape2d::{Fill, Shape, Stroke};
+    /// use rgx::kit::ZDepth;
+    /// use rgx::math::Vector3;
+    /// use rgx::rect::Rect;
+    ///
+    /// let bar = Shape::Rectangle(
+    ///     Rect::new(0., 0., 16., 16.),
+    ///     ZDepth::default(),
+    ///     Default::default(),
+    ///     Stroke::NONE,
+    ///     Fill::Solid(Rgba::WHITE),
+    /// );
+    /// let mesh = bar.extrude(64., Vector3::new(-1., 1., 2.));
+    ///
+    /// // The top face, and four walls.
+    /// assert_eq!(mesh.indices.len(), 6 + 4 * 6);
+    /// assert!(mesh.vertices.iter().all(|v| v.position().z == 0. || v.position().z == 64.));
+    /// ```
+    pub fn extrude(&self, height: f32, light: Vector3<f32>) -> Mesh {
+        let mut mesh = self.triangulate();
+        let len = (light.x * light.x + light.y * light.y + light.z * light.z).sqrt();
+        let light = if len > 0. {
+            Vector3::new(light.x / len, light.y / len, light.z / len)
+        } else {
+            Vector3::new(0., 0., 1.)
+        };
+
+        let top = Self::shade(light.z);
+        for v in mesh.vertices.iter_mut() {
+            v.position.z += height;
+            v.color = Rgba8::new(
+                (v.color.r as f32 * top).round() as u8,
+                (v.color.g as f32 * top).round() as u8,
+                (v.color.b as f32 * top).round() as u8,
+                v.color.a,
+            );
+        }
+
+        let (points, z, rotation, stroke, fill) = match self {
+            Shape::Rectangle(r, ZDepth(z), rot, stroke, fill) => (
+                vec![
+                    Point2::new(r.x1, r.y1),
+                    Point2::new(r.x2, r.y1),
+                    Point2::new(r.x2, r.y2),
+                    Point2::new(r.x1, r.y2),
+                ],
+                *z,
+                rot.clone(),
+                stroke,
+                fill,
+            ),
+            Shape::RoundedRectangle(r, radius, ZDepth(z), rot, stroke, fill) => (
+                Self::rounded(*r, *radius, Self::CORNER_SEGMENTS, Trig::Native),
+                *z,
+                rot.clone(),
+                stroke,
+                fill,
+            ),
+            Shape::Circle(p, ZDepth(z), radius, sides, stroke, fill) => (
+                Self::circle(*p, *radius, *sides, Trig::Native),
+                *z,
+                Rotation::ZERO,
+                stroke,
+                fill,
+            ),
+            Shape::Polygon(points, ZDepth(z), rot, stroke, fill) => {
+                (points.clone(), *z, rot.clone(), stroke, fill)
+            }
+            Shape::Path(path, ZDepth(z), rot, stroke, fill) => {
+                (path.flatten(), *z, rot.clone(), stroke, fill)
+            }
+            Shape::Triangle(a, b, c, ZDepth(z), rot, stroke, fill) => {
+                (vec![*a, *b, *c], *z, rot.clone(), stroke, fill)
+            }
+            Shape::Line(..) | Shape::Polyline(..) | Shape::Mesh(..) => return mesh,
+        };
+        let color = match fill {
+            Fill::Solid(c) | Fill::Gradient(c, _, _) | Fill::Pattern(c, _) => *c,
+            Fill::Stops(gradient) => gradient.color(0.),
+            Fill::Empty() => stroke.color,
+        };
+        let outline = Self::outline(&points);
+        let (sin, cos) = rotation.angle.sin_cos();
+        let n = outline.len();
+
+        for i in 0..n {
+            let (a, b) = (outline[i], outline[(i + 1) % n]);
+            let d = Vector2::new(b.x - a.x, b.y - a.y);
+            let len = (d.x * d.x + d.y * d.y).sqrt();
+
+            if len == 0. {
+                continue;
+            }
+            // Outward normal of the counter-clockwise outline, rotated
+            // clockwise like the shape.
+            let (nx, ny) = (d.y / len, -d.x / len);
+            let (nx, ny) = (nx * cos + ny * sin, ny * cos - nx * sin);
+            let k = Self::shade(nx * light.x + ny * light.y);
+            let c = Rgba8::from(Rgba::new(color.r * k, color.g * k, color.b * k, color.a));
+            let vertex =
+                |p: Point2<f32>, z| Vertex::new(p.x, p.y, z, rotation.angle, rotation.center, c);
+
+            mesh.quad([
+                vertex(a, z),
+                vertex(b, z),
+                vertex(b, z + height),
+                vertex(a, z + height),
+            ]);
+        }
+        mesh
+    }
+
+    /// Brightness of a face, given the cosine of the angle between its
+    /// normal and the light.
+    fn shade(cos: f32) -> f32 {
+        const AMBIENT: f32 = 0.4;
+        AMBIENT + (1. - AMBIENT) * cos.max(0.)
+    }
+
+    fn tessellate(&self, antialias: bool, precision: Precision) -> Mesh {
+        let mut mesh = Mesh::new();
+        let trig = precision.trig;
+
         match *self {
-            Shape::Line(l, ZDepth(z), Rotation { angle, center }, Stroke { width, color }) => {
+            Shape::Line(l, ZDepth(z), ref rot, ref stroke) if stroke.hairline => {
+                for (start, end) in Self::dashes(l, stroke) {
+                    let (a, b) = (l.p1 + (l.p2 - l.p1) * start, l.p1 + (l.p2 - l.p1) * end);
+                    let points = [Point2::new(a.x, a.y), Point2::new(b.x, b.y)];
+                    Self::stroke_hairline(
+                        &mut mesh,
+                        &points,
+                        false,
+                        stroke,
+                        (start, end),
+                        z,
+                        rot.clone(),
+                    );
+                }
+            }
+            Shape::Line(l, ZDepth(z), Rotation { angle, center }, ref stroke) => {
                 let v = (l.p2 - l.p1).normalize();
 
-                let wx = width / 2.0 * v.y;
-                let wy = width / 2.0 * v.x;
-                let rgba8 = color.into();
+                let wx = stroke.width / 2.0 * v.y;
+                let wy = stroke.width / 2.0 * v.x;
+                // Square caps extend each dash by half the stroke width.
+                let cap = if stroke.dash.is_some() && stroke.cap == Cap::Square {
+                    v * (stroke.width / 2.)
+                } else {
+                    Vector2::new(0., 0.)
+                };
+                let positions = stroke.positions();
 
-                vec![
-                    vertex(l.p1.x - wx, l.p1.y + wy, z, angle, center, rgba8),
-                    vertex(l.p1.x + wx, l.p1.y - wy, z, angle, center, rgba8),
-                    vertex(l.p2.x - wx, l.p2.y + wy, z, angle, center, rgba8),
-                    vertex(l.p2.x - wx, l.p2.y + wy, z, angle, center, rgba8),
-                    vertex(l.p1.x + wx, l.p1.y - wy, z, angle, center, rgba8),
-                    vertex(l.p2.x + wx, l.p2.y - wy, z, angle, center, rgba8),
-                ]
-            }
-            Shape::Rectangle(r, ZDepth(z), Rotation { angle, center }, stroke, fill) => {
+                for (start, end) in Self::dashes(l, stroke) {
+                    // Subdivide the dash, so that gradient stops fall on vertices.
+                    let mut stops = vec![start];
+                    stops.extend(positions.iter().filter(|p| **p > start && **p < end));
+                    stops.push(end);
+
+                    for w in stops.windows(2) {
+                        let mut p1 = l.p1 + (l.p2 - l.p1) * w[0];
+                        let mut p2 = l.p1 + (l.p2 - l.p1) * w[1];
+                        let c1 = stroke.color(w[0]);
+                        let c2 = stroke.color(w[1]);
+
+                        if w[0] == start {
+                            p1 -= cap;
+                        }
+                        if w[1] == end {
+                            p2 += cap;
+                        }
+                        mesh.quad([
+                            vertex(p1.x - wx, p1.y + wy, z, angle, center, c1),
+                            vertex(p1.x + wx, p1.y - wy, z, angle, center, c1),
+                            vertex(p2.x + wx, p2.y - wy, z, angle, center, c2),
+                            vertex(p2.x - wx, p2.y + wy, z, angle, center, c2),
+                        ]);
+                    }
+                }
+            }
+            Shape::Rectangle(r, ZDepth(z), Rotation { angle, center }, ref stroke, ref fill) => {
                 let width = stroke.width;
                 let inner = Rect::new(r.x1 + width, r.y1 + width, r.x2 - width, r.y2 - width);
 
-                let mut verts = if stroke != Stroke::NONE {
-                    let rgba8 = stroke.color.into();
-
-                    let outer = r;
-
-                    vec![
-                        // Bottom
-                        vertex(outer.x1, outer.y1, z, angle, center, rgba8),
-                        vertex(outer.x2, outer.y1, z, angle, center, rgba8),
-                        vertex(inner.x1, inner.y1, z, angle, center, rgba8),
-                        vertex(inner.x1, inner.y1, z, angle, center, rgba8),
-                        vertex(outer.x2, outer.y1, z, angle, center, rgba8),
-                        vertex(inner.x2, inner.y1, z, angle, center, rgba8),
-                        // Left
-                        vertex(outer.x1, outer.y1, z, angle, center, rgba8),
-                        vertex(inner.x1, inner.y1, z, angle, center, rgba8),
-                        vertex(outer.x1, outer.y2, z, angle, center, rgba8),
-                        vertex(outer.x1, outer.y2, z, angle, center, rgba8),
-                        vertex(inner.x1, inner.y1, z, angle, center, rgba8),
-                        vertex(inner.x1, inner.y2, z, angle, center, rgba8),
-                        // Right
-                        vertex(inner.x2, inner.y1, z, angle, center, rgba8),
-                        vertex(outer.x2, outer.y1, z, angle, center, rgba8),
-                        vertex(outer.x2, outer.y2, z, angle, center, rgba8),
-                        vertex(inner.x2, inner.y1, z, angle, center, rgba8),
-                        vertex(inner.x2, inner.y2, z, angle, center, rgba8),
-                        vertex(outer.x2, outer.y2, z, angle, center, rgba8),
-                        // Top
-                        vertex(outer.x1, outer.y2, z, angle, center, rgba8),
-                        vertex(outer.x2, outer.y2, z, angle, center, rgba8),
-                        vertex(inner.x1, inner.y2, z, angle, center, rgba8),
-                        vertex(inner.x1, inner.y2, z, angle, center, rgba8),
-                        vertex(outer.x2, outer.y2, z, angle, center, rgba8),
-                        vertex(inner.x2, inner.y2, z, angle, center, rgba8),
-                    ]
+                if *stroke != Stroke::NONE {
+                    // Outer and inner corners, counter-clockwise from the
+                    // bottom-left, back to it, with their position along the
+                    // perimeter.
+                    let perimeter = 2. * (r.width() + r.height());
+                    let corners = [
+                        (
+                            Vector2::new(r.x1, r.y1),
+                            Vector2::new(inner.x1, inner.y1),
+                            0.,
+                        ),
+                        (
+                            Vector2::new(r.x2, r.y1),
+                            Vector2::new(inner.x2, inner.y1),
+                            r.width() / perimeter,
+                        ),
+                        (
+                            Vector2::new(r.x2, r.y2),
+                            Vector2::new(inner.x2, inner.y2),
+                            0.5,
+                        ),
+                        (
+                            Vector2::new(r.x1, r.y2),
+                            Vector2::new(inner.x1, inner.y2),
+                            0.5 + r.width() / perimeter,
+                        ),
+                        (
+                            Vector2::new(r.x1, r.y1),
+                            Vector2::new(inner.x1, inner.y1),
+                            1.,
+                        ),
+                    ];
+                    let positions = stroke.positions();
+
+                    for side in corners.windows(2) {
+                        let ((o1, i1, t1), (o2, i2, t2)) = (side[0], side[1]);
+                        // Subdivide the side, so that gradient stops fall on vertices.
+                        let mut stops = vec![t1];
+                        stops.extend(positions.iter().filter(|p| **p > t1 && **p < t2));
+                        stops.push(t2);
+
+                        let at = |t: f32| {
+                            let s = if t2 > t1 { (t - t1) / (t2 - t1) } else { 0. };
+                            let c = stroke.color(t);
+                            let (o, i) = (o1 + (o2 - o1) * s, i1 + (i2 - i1) * s);
+
+                            (
+                                vertex(o.x, o.y, z, angle, center, c),
+                                vertex(i.x, i.y, z, angle, center, c),
+                            )
+                        };
+                        for w in stops.windows(2) {
+                            let ((oa, ia), (ob, ib)) = (at(w[0]), at(w[1]));
+                            mesh.quad([oa, ob, ib, ia]);
+                        }
+                    }
+                }
+
+                let antialias = antialias && *stroke == Stroke::NONE;
+                // Area covered by the fill. When antialiased, it extends past
+                // the edge, for the coverage to fade out.
+                let area = if antialias {
+                    inner.expand(1., 1., 1., 1.)
                 } else {
-                    Vec::with_capacity(6)
+                    inner
                 };
+                let start = mesh.vertices.len();
 
-                match fill {
-                    Fill::Solid(color) => {
+                match *fill {
+                    Fill::Solid(color) | Fill::Pattern(color, _) => {
                         let rgba8 = color.into();
 
-                        verts.extend_from_slice(&[
-                            vertex(inner.x1, inner.y1, z, angle, center, rgba8),
-                            vertex(inner.x2, inner.y1, z, angle, center, rgba8),
-                            vertex(inner.x2, inner.y2, z, angle, center, rgba8),
-                            vertex(inner.x1, inner.y1, z, angle, center, rgba8),
-                            vertex(inner.x1, inner.y2, z, angle, center, rgba8),
-                            vertex(inner.x2, inner.y2, z, angle, center, rgba8),
+                        mesh.quad([
+                            vertex(area.x1, area.y1, z, angle, center, rgba8),
+                            vertex(area.x2, area.y1, z, angle, center, rgba8),
+                            vertex(area.x2, area.y2, z, angle, center, rgba8),
+                            vertex(area.x1, area.y2, z, angle, center, rgba8),
                         ]);
                     }
-                    Fill::Gradient(_, _) => {
-                        unimplemented!();
+                    Fill::Gradient(from, to, dir) => {
+                        let gradient = Gradient::new(&[(0., from), (1., to)]);
+                        let position = Self::linear_gradient(
+                            dir,
+                            &[
+                                Point2::new(inner.x1, inner.y1),
+                                Point2::new(inner.x2, inner.y1),
+                                Point2::new(inner.x2, inner.y2),
+                                Point2::new(inner.x1, inner.y2),
+                            ],
+                            trig,
+                        );
+                        let color = |x, y| -> Rgba8 { gradient.color(position(x, y)).into() };
+
+                        mesh.quad([
+                            vertex(area.x1, area.y1, z, angle, center, color(area.x1, area.y1)),
+                            vertex(area.x2, area.y1, z, angle, center, color(area.x2, area.y1)),
+                            vertex(area.x2, area.y2, z, angle, center, color(area.x2, area.y2)),
+                            vertex(area.x1, area.y2, z, angle, center, color(area.x1, area.y2)),
+                        ]);
+                    }
+                    Fill::Stops(ref gradient) => {
+                        let positions = gradient.positions();
+
+                        for w in positions.windows(2) {
+                            let x1 = if w[0] > 0. {
+                                inner.x1 + inner.width() * w[0]
+                            } else {
+                                area.x1
+                            };
+                            let x2 = if w[1] < 1. {
+                                inner.x1 + inner.width() * w[1]
+                            } else {
+                                area.x2
+                            };
+                            let c1: Rgba8 = gradient.color(w[0]).into();
+                            let c2: Rgba8 = gradient.color(w[1]).into();
+
+                            mesh.quad([
+                                vertex(x1, area.y1, z, angle, center, c1),
+                                vertex(x2, area.y1, z, angle, center, c2),
+                                vertex(x2, area.y2, z, angle, center, c2),
+                                vertex(x1, area.y2, z, angle, center, c1),
+                            ]);
+                        }
                     }
                     Fill::Empty() => {}
                 }
-                verts
+
+                if let Fill::Pattern(_, pattern) = *fill {
+                    for v in mesh.vertices[start..].iter_mut() {
+                        v.pattern = pattern.attribute();
+                    }
+                }
+
+                if antialias {
+                    let c = Point2::new((inner.x1 + inner.x2) / 2., (inner.y1 + inner.y2) / 2.);
+                    let (hw, hh) = (inner.width().abs() / 2., inner.height().abs() / 2.);
+
+                    for v in mesh.vertices[start..].iter_mut() {
+                        let p = v.position;
+                        v.edge = Vector4::new(p.x - c.x, p.y - c.y, hw, hh);
+                    }
+                }
             }
-            Shape::Circle(position, ZDepth(z), radius, sides, stroke, fill) => {
-                let inner = Self::circle(position, radius - stroke.width, sides);
+            Shape::Circle(position, ZDepth(z), radius, sides, ref stroke, ref fill) => {
+                let sides = precision.tolerance.map_or(sides, |t| {
+                    precision
+                        .arc_segments(radius, 2. * f32::consts::PI, t)
+                        .max(3)
+                });
+                let origin = Point2::new(0.0, 0.0);
+                let inner = Self::circle(position, radius - stroke.width, sides, trig);
 
-                let mut verts = if stroke != Stroke::NONE {
+                if *stroke != Stroke::NONE {
                     // If there is a stroke, the outer circle is larger.
-                    let outer = Self::circle(position, radius, sides);
-                    let rgba8 = stroke.color.into();
-
-                    let n = inner.len() - 1;
-                    let mut vs = Vec::with_capacity(n * 6);
-                    for i in 0..n {
-                        let (i0, i1) = (inner[i], inner[i + 1]);
-                        let (o0, o1) = (outer[i], outer[i + 1]);
-
-                        vs.extend_from_slice(&[
-                            vertex(i0.x, i0.y, z, 0.0, Point2::new(0.0, 0.0), rgba8),
-                            vertex(o0.x, o0.y, z, 0.0, Point2::new(0.0, 0.0), rgba8),
-                            vertex(o1.x, o1.y, z, 0.0, Point2::new(0.0, 0.0), rgba8),
-                            vertex(i0.x, i0.y, z, 0.0, Point2::new(0.0, 0.0), rgba8),
-                            vertex(o1.x, o1.y, z, 0.0, Point2::new(0.0, 0.0), rgba8),
-                            vertex(i1.x, i1.y, z, 0.0, Point2::new(0.0, 0.0), rgba8),
-                        ]);
-                    }
-                    vs
+                    let outer = Self::circle(position, radius, sides, trig);
+                    let n = sides as f32;
+
+                    mesh.ring(
+                        inner.iter().enumerate().map(|(i, p)| {
+                            vertex(p.x, p.y, z, 0., origin, stroke.color(i as f32 / n))
+                        }),
+                        outer.iter().enumerate().map(|(i, p)| {
+                            vertex(p.x, p.y, z, 0., origin, stroke.color(i as f32 / n))
+                        }),
+                    );
+                }
+
+                let antialias = antialias && *stroke == Stroke::NONE;
+                let r = radius - stroke.width;
+                // Radius of the area covered by the fill. When antialiased,
+                // the polygon circumscribes the circle, and extends past its
+                // edge, for the coverage to fade out.
+                let reach = if antialias {
+                    r / trig.cos(f32::consts::PI / sides as f32) + 1.
                 } else {
-                    Vec::new()
+                    r
                 };
+                let start = mesh.vertices.len();
 
-                match fill {
-                    Fill::Solid(color) => {
+                match *fill {
+                    Fill::Solid(color) | Fill::Pattern(color, _) => {
                         let rgba8 = color.into();
-                        let center = Vertex::new(
-                            position.x,
-                            position.y,
-                            z,
-                            0.0,
-                            Point2::new(0.0, 0.0),
-                            rgba8,
+                        let area = Self::circle(position, reach, sides, trig);
+
+                        mesh.fan(
+                            vertex(position.x, position.y, z, 0., origin, rgba8),
+                            area.iter().map(|p| vertex(p.x, p.y, z, 0., origin, rgba8)),
                         );
-                        let inner_verts: Vec<Vertex> = inner
-                            .iter()
-                            .map(|p| Vertex::new(p.x, p.y, z, 0., Point2::new(0.0, 0.0), rgba8))
-                            .collect();
-                        for i in 0..sides as usize {
-                            verts.extend_from_slice(&[center, inner_verts[i], inner_verts[i + 1]]);
+                    }
+                    Fill::Gradient(from, to, _) => {
+                        let (from, to): (Rgba8, Rgba8) = (from.into(), to.into());
+                        let edge = Self::circle(position, r, sides, trig);
+
+                        mesh.fan(
+                            vertex(position.x, position.y, z, 0., origin, from),
+                            edge.iter().map(|p| vertex(p.x, p.y, z, 0., origin, to)),
+                        );
+                        if reach > r {
+                            let area = Self::circle(position, reach, sides, trig);
+
+                            mesh.ring(
+                                edge.iter().map(|p| vertex(p.x, p.y, z, 0., origin, to)),
+                                area.iter().map(|p| vertex(p.x, p.y, z, 0., origin, to)),
+                            );
                         }
-                        verts.extend_from_slice(&[
-                            center,
-                            *inner_verts.last().unwrap(),
-                            *inner_verts.first().unwrap(),
-                        ]);
                     }
-                    Fill::Gradient(_, _) => {
-                        unimplemented!();
+                    Fill::Stops(ref gradient) => {
+                        let positions = gradient.positions();
+                        let radius = |t: f32| if t < 1. { r * t } else { reach };
+
+                        // Center disc, up to the first subdivision.
+                        let c0: Rgba8 = gradient.color(0.).into();
+                        let c1: Rgba8 = gradient.color(positions[1]).into();
+                        let disc = Self::circle(position, radius(positions[1]), sides, trig);
+
+                        mesh.fan(
+                            vertex(position.x, position.y, z, 0., origin, c0),
+                            disc.iter().map(|p| vertex(p.x, p.y, z, 0., origin, c1)),
+                        );
+
+                        // Rings, between each pair of subdivisions.
+                        for w in positions[1..].windows(2) {
+                            let inner = Self::circle(position, radius(w[0]), sides, trig);
+                            let outer = Self::circle(position, radius(w[1]), sides, trig);
+                            let ci: Rgba8 = gradient.color(w[0]).into();
+                            let co: Rgba8 = gradient.color(w[1]).into();
+
+                            mesh.ring(
+                                inner.iter().map(|p| vertex(p.x, p.y, z, 0., origin, ci)),
+                                outer.iter().map(|p| vertex(p.x, p.y, z, 0., origin, co)),
+                            );
+                        }
                     }
                     Fill::Empty() => {}
                 }
-                verts
+
+                if let Fill::Pattern(_, pattern) = *fill {
+                    for v in mesh.vertices[start..].iter_mut() {
+                        v.pattern = pattern.attribute();
+                    }
+                }
+
+                if antialias {
+                    for v in mesh.vertices[start..].iter_mut() {
+                        let p = v.position;
+                        v.edge = Vector4::new(p.x - position.x, p.y - position.y, r, 0.);
+                    }
+                }
             }
-        }
-    }
+            Shape::Polygon(
+                ref points,
+                ZDepth(z),
+                Rotation { angle, center },
+                ref stroke,
+                ref fill,
+            ) => {
+                let outer = Self::outline(points);
+                let n = outer.len();
 
-    fn circle(position: Point2<f32>, radius: f32, sides: u32) -> Vec<Point2<f32>> {
-        let mut verts = Vec::with_capacity(sides as usize + 1);
+                if n < 3 {
+                    return mesh;
+                }
+                let inner = if *stroke != Stroke::NONE {
+                    Self::inset(&outer, stroke.width)
+                } else {
+                    outer.clone()
+                };
 
-        for i in 0..=sides as usize {
-            let angle: f32 = i as f32 * ((2. * f32::consts::PI) / sides as f32);
-            verts.push(Point2::new(
-                position.x + radius * angle.cos(),
-                position.y + radius * angle.sin(),
-            ));
+                if *stroke != Stroke::NONE {
+                    // Position of each vertex along the perimeter.
+                    let mut positions = Vec::with_capacity(n + 1);
+                    let mut length = 0.;
+
+                    for i in 0..=n {
+                        positions.push(length);
+
+                        let (a, b) = (outer[i % n], outer[(i + 1) % n]);
+                        length += Vector2::new(b.x - a.x, b.y - a.y).magnitude();
+                    }
+                    let perimeter = positions[n].max(f32::EPSILON);
+                    let color = |i: usize| stroke.color(positions[i] / perimeter);
+
+                    mesh.ring(
+                        (0..n + 1).map(|i| {
+                            let p = inner[i % n];
+                            vertex(p.x, p.y, z, angle, center, color(i))
+                        }),
+                        (0..n + 1).map(|i| {
+                            let p = outer[i % n];
+                            vertex(p.x, p.y, z, angle, center, color(i))
+                        }),
+                    );
+                }
+
+                Self::fill_polygon(&mut mesh, &inner, fill, z, angle, center, trig);
+            }
+            Shape::Polyline(ref points, ZDepth(z), Rotation { angle, center }, ref stroke) => {
+                Self::stroke_polyline(
+                    &mut mesh,
+                    points,
+                    false,
+                    stroke,
+                    z,
+                    Rotation { angle, center },
+                    precision,
+                );
+            }
+            Shape::Triangle(a, b, c, z, ref rot, ref stroke, ref fill) => {
+                return Shape::Polygon(vec![a, b, c], z, rot.clone(), stroke.clone(), fill.clone())
+                    .tessellate(antialias, precision);
+            }
+            Shape::RoundedRectangle(r, radius, z, ref rot, ref stroke, ref fill) => {
+                let segments = precision.tolerance.map_or(Self::CORNER_SEGMENTS, |t| {
+                    precision.arc_segments(radius, f32::consts::FRAC_PI_2, t)
+                });
+                let points = Self::rounded(r, radius, segments, trig);
+
+                return Shape::Polygon(points, z, rot.clone(), stroke.clone(), fill.clone())
+                    .tessellate(antialias, precision);
+            }
+            Shape::Mesh(ref m) => return m.clone(),
+            Shape::Path(ref path, ZDepth(z), Rotation { angle, center }, ref stroke, ref fill) => {
+                let points =
+                    path.flatten_with_tolerance(precision.tolerance.unwrap_or(Path::TOLERANCE));
+                let outline = Self::outline(&points);
+
+                if outline.len() >= 3 {
+                    Self::fill_polygon(&mut mesh, &outline, fill, z, angle, center, trig);
+                }
+                if *stroke != Stroke::NONE {
+                    Self::stroke_polyline(
+                        &mut mesh,
+                        &points,
+                        path.closed,
+                        stroke,
+                        z,
+                        Rotation { angle, center },
+                        precision,
+                    );
+                }
+            }
         }
-        verts
+        mesh.set_local_coordinates();
+        mesh
     }
-}
+
+    /// The dashes of a line, as start and end positions relative to its
+    /// length. Lines without a dash pattern are a single dash.
+    fn dashes(l: Line, stroke: &Stroke) -> Vec<(f32, f32)> {
+        match stroke.dash {
+            Some(ref dash) => dash.intervals((l.p2 - l.p1).magnitude()),
+            None => vec![(0., 1.)],
+        }
+    }
+
+    /// Stroke connected line segments with a hairline. Each segment is a quad
+    /// one pixel larger than the line on all sides, whose vertices carry
+    /// their position relative to the line, for the fragment shader to
+    /// compute the coverage of each pixel. The segments span the given range
+    /// of the stroke's gradient.
+    fn stroke_hairline(
+        mesh: &mut Mesh,
+        points: &[Point2<f32>],
+        closed: bool,
+        stroke: &Stroke,
+        (from, to): (f32, f32),
+        z: f32,
+        Rotation { angle, center }: Rotation,
+    ) {
+        let n = points.len();
+        if n < 2 {
+            return;
+        }
+        let segments = if closed { n } else { n - 1 };
+        let point = |i: usize| points[i % n];
+        let hw = stroke.width / 2.;
+        // Square caps extend the line by half its width.
+        let cap = if stroke.cap == Cap::Square { hw } else { 0. };
+
+        let length = (0..segments)
+            .map(|i| Vector2::new(point(i + 1).x - point(i).x, point(i + 1).y - point(i).y))
+            .map(|v| v.magnitude())
+            .sum::<f32>()
+            .max(f32::EPSILON);
+        let mut position = 0.;
+
+        for i in 0..segments {
+            let (a, b) = (point(i), point(i + 1));
+            let v = Vector2::new(b.x - a.x, b.y - a.y);
+            let len = v.magnitude();
+            if len == 0. {
+                continue;
+            }
+            let at = |pos: f32| stroke.color(from + (to - from) * pos / length);
+            let (c1, c2) = (at(position), at(position + len));
+            position += len;
+
+            let d = v * (1. / len);
+            let nrm = Vector2::new(-d.y, d.x);
+            let mid = Point2::new((a.x + b.x) / 2., (a.y + b.y) / 2.);
+            let (hl, ex, ey) = (len / 2. + cap, len / 2. + cap + 1., hw + 1.);
+
+            let corner = |u: f32, w: f32, c: Rgba8| {
+                let p = mid + d * u + nrm * w;
+                let mut v = vertex(p.x, p.y, z, angle, center, c);
+                v.edge = Vector4::new(u, w, hl, hw);
+                v
+            };
+            mesh.quad([
+                corner(-ex, -ey, c1),
+                corner(ex, -ey, c2),
+                corner(ex, ey, c2),
+                corner(-ex, ey, c1),
+            ]);
+        }
+    }
+
+    /// Fill a simple counter-clockwise polygon.
+    fn fill_polygon(
+        mesh: &mut Mesh,
+        outline: &[Point2<f32>],
+        fill: &Fill,
+        z: f32,
+        angle: f32,
+        center: Point2<f32>,
+        trig: Trig,
+    ) {
+        let start = mesh.vertices.len();
+        let indices = Self::earcut(outline);
+
+        match *fill {
+            Fill::Solid(color) | Fill::Pattern(color, _) => {
+                let rgba8 = color.into();
+
+                mesh.triangles(
+                    outline
+                        .iter()
+                        .map(|p| vertex(p.x, p.y, z, angle, center, rgba8)),
+                    &indices,
+                );
+            }
+            Fill::Gradient(from, to, dir) => {
+                let gradient = Gradient::new(&[(0., from), (1., to)]);
+                let position = Self::linear_gradient(dir, outline, trig);
+                let color = |x, y| -> Rgba8 { gradient.color(position(x, y)).into() };
+
+                mesh.triangles(
+                    outline
+                        .iter()
+                        .map(|p| vertex(p.x, p.y, z, angle, center, color(p.x, p.y))),
+                    &indices,
+                );
+            }
+            Fill::Stops(ref gradient) => {
+                let position = Self::linear_gradient(0., outline, trig);
+                let color = |x, y| -> Rgba8 { gradient.color(position(x, y)).into() };
+
+                mesh.triangles(
+                    outline
+                        .iter()
+                        .map(|p| vertex(p.x, p.y, z, angle, center, color(p.x, p.y))),
+                    &indices,
+                );
+            }
+            Fill::Empty() => {}
+        }
+
+        if let Fill::Pattern(_, pattern) = *fill {
+            for v in mesh.vertices[start..].iter_mut() {
+                v.pattern = pattern.attribute();
+            }
+        }
+    }
+
+    /// Stroke connected line segments, with the stroke's joins, and caps if
+    /// the line isn't closed.
+    fn stroke_polyline(
+        mesh: &mut Mesh,
+        points: &[Point2<f32>],
+        closed: bool,
+        stroke: &Stroke,
+        z: f32,
+        Rotation { angle, center }: Rotation,
+        precision: Precision,
+    ) {
+        // Limit on the miter length, relative to the half width. Sharper
+        // corners are beveled.
+        const MITER_LIMIT: f32 = 4.;
+        // Maximum angle between two vertices of a round join or cap.
+        const ROUND_STEP: f32 = f32::consts::PI / 16.;
+
+        let trig = precision.trig;
+
+        if stroke.hairline {
+            return Self::stroke_hairline(
+                mesh,
+                points,
+                closed,
+                stroke,
+                (0., 1.),
+                z,
+                Rotation { angle, center },
+            );
+        }
+        let mut pts: Vec<Point2<f32>> = Vec::with_capacity(points.len());
+        for p in points {
+            if pts.last() != Some(p) {
+                pts.push(*p);
+            }
+        }
+        if closed && pts.len() > 1 && pts.first() == pts.last() {
+            pts.pop();
+        }
+        let n = pts.len();
+        if n < 2 {
+            return;
+        }
+        let hw = stroke.width / 2.;
+        let segments = if closed { n } else { n - 1 };
+        let point = |i: usize| pts[i % n];
+        let dir = |i: usize| {
+            let (a, b) = (point(i), point(i + 1));
+            Vector2::new(b.x - a.x, b.y - a.y).normalize()
+        };
+        let normal = |i: usize| {
+            let d = dir(i);
+            Vector2::new(-d.y, d.x) * hw
+        };
+        let offset = |p: Point2<f32>, v: Vector2<f32>| Point2::new(p.x + v.x, p.y + v.y);
+
+        // Position of each vertex along the line.
+        let mut positions = Vec::with_capacity(segments + 1);
+        let mut length = 0.;
+        for i in 0..segments {
+            positions.push(length);
+
+            let (a, b) = (point(i), point(i + 1));
+            length += Vector2::new(b.x - a.x, b.y - a.y).magnitude();
+        }
+        positions.push(length);
+        let length = length.max(f32::EPSILON);
+        let color = |i: usize| stroke.color(positions[i] / length);
+
+        for i in 0..segments {
+            let (a, b) = (point(i), point(i + 1));
+            let nrm = normal(i);
+            let (c1, c2) = (color(i), color(i + 1));
+            let (a1, a2) = (offset(a, nrm * -1.), offset(a, nrm));
+            let (b1, b2) = (offset(b, nrm * -1.), offset(b, nrm));
+
+            mesh.quad([
+                vertex(a1.x, a1.y, z, angle, center, c1),
+                vertex(b1.x, b1.y, z, angle, center, c2),
+                vertex(b2.x, b2.y, z, angle, center, c2),
+                vertex(a2.x, a2.y, z, angle, center, c1),
+            ]);
+        }
+
+        // Add a fan of vertices around `p`, from `from` to `from + sweep`, in radians.
+        let arc = |mesh: &mut Mesh, p: Point2<f32>, from: f32, sweep: f32, c: Rgba8| {
+            let steps = match precision.tolerance {
+                Some(t) => precision.arc_segments(hw, sweep, t) as usize,
+                None => (sweep.abs() / ROUND_STEP).ceil().max(1.) as usize,
+            };
+
+            mesh.fan(
+                vertex(p.x, p.y, z, angle, center, c),
+                (0..=steps).map(|k| {
+                    let a = from + sweep * k as f32 / steps as f32;
+                    let (sin, cos) = trig.sin_cos(a);
+                    vertex(p.x + hw * cos, p.y + hw * sin, z, angle, center, c)
+                }),
+            );
+        };
+
+        // Joins, at vertices between two segments.
+        let joins = if closed { 0..n } else { 1..n - 1 };
+        for i in joins {
+            let prev = (i + segments - 1) % segments;
+            let (d0, d1) = (dir(prev), dir(i));
+            let cross = d0.x * d1.y - d0.y * d1.x;
+
+            if cross.abs() <= f32::EPSILON {
+                continue;
+            }
+            // The gap to fill is on the outer side of the turn.
+            let side = if cross > 0. { -1. } else { 1. };
+            let (o0, o1) = (normal(prev) * side, normal(i) * side);
+            let (p, c) = (point(i), color(i));
+            let bevel = |mesh: &mut Mesh| {
+                let (p0, p1) = (offset(p, o0), offset(p, o1));
+
+                mesh.triangles(
+                    [p, p0, p1]
+                        .iter()
+                        .map(|q| vertex(q.x, q.y, z, angle, center, c)),
+                    &[0, 1, 2],
+                );
+            };
+
+            match stroke.join {
+                Join::Bevel => bevel(mesh),
+                Join::Miter => {
+                    let m = (o0 + o1).normalize();
+                    let cos = (m.x * o0.x + m.y * o0.y) / hw;
+
+                    if cos <= 1. / MITER_LIMIT {
+                        bevel(mesh);
+                    } else {
+                        let (p0, p1, pm) =
+                            (offset(p, o0), offset(p, o1), offset(p, m * (hw / cos)));
+
+                        mesh.quad([
+                            vertex(p.x, p.y, z, angle, center, c),
+                            vertex(p0.x, p0.y, z, angle, center, c),
+                            vertex(pm.x, pm.y, z, angle, center, c),
+                            vertex(p1.x, p1.y, z, angle, center, c),
+                        ]);
+                    }
+                }
+                Join::Round => {
+                    let from = trig.atan2(o0.y, o0.x);
+                    let mut sweep = trig.atan2(o1.y, o1.x) - from;
+
+                    if sweep > f32::consts::PI {
+                        sweep -= 2. * f32::consts::PI;
+                    } else if sweep < -f32::consts::PI {
+                        sweep += 2. * f32::consts::PI;
+                    }
+                    arc(mesh, p, from, sweep, c);
+                }
+            }
+        }
+
+        if closed {
+            return;
+        }
+
+        // Caps, at both ends, facing outwards.
+        let ends = [
+            (point(0), dir(0) * -1., color(0)),
+            (point(n - 1), dir(n - 2), color(segments)),
+        ];
+        for &(p, d, c) in ends.iter() {
+            let nrm = Vector2::new(-d.y, d.x) * hw;
+
+            match stroke.cap {
+                Cap::Butt => {}
+                Cap::Square => {
+                    let ext = d * hw;
+                    let corners = [
+                        offset(p, nrm),
+                        offset(p, nrm * -1.),
+                        offset(p, nrm * -1. + ext),
+                        offset(p, nrm + ext),
+                    ];
+                    mesh.quad([
+                        vertex(corners[0].x, corners[0].y, z, angle, center, c),
+                        vertex(corners[1].x, corners[1].y, z, angle, center, c),
+                        vertex(corners[2].x, corners[2].y, z, angle, center, c),
+                        vertex(corners[3].x, corners[3].y, z, angle, center, c),
+                    ]);
+                }
+                Cap::Round => {
+                    // Half circle from one side of the stroke to the other,
+                    // through the stroke direction.
+                    arc(mesh, p, trig.atan2(nrm.y, nrm.x), -f32::consts::PI, c);
+                }
+            }
+        }
+    }
+
+    /// Returns a function giving the position along a linear gradient, in
+    /// the `[0, 1]` range. The gradient spans the given points, in the
+    /// direction of `dir`, in radians.
+    fn linear_gradient(dir: f32, points: &[Point2<f32>], trig: Trig) -> impl Fn(f32, f32) -> f32 {
+        let (dy, dx) = trig.sin_cos(dir);
+        let project = move |x: f32, y: f32| x * dx + y * dy;
+
+        // Extent of the points along the gradient direction.
+        let (min, max) = points.iter().fold((f32::MAX, f32::MIN), |(min, max), p| {
+            let t = project(p.x, p.y);
+            (min.min(t), max.max(t))
+        });
+
+        move |x: f32, y: f32| ((project(x, y) - min) / (max - min).max(f32::EPSILON)).clamp(0., 1.)
+    }
+
+    /// Returns the outline of a polygon, without a closing point, and with
+    /// counter-clockwise winding.
+    fn outline(points: &[Point2<f32>]) -> Vec<Point2<f32>> {
+        let mut outline = points.to_vec();
+
+        if outline.len() > 1 && outline.first() == outline.last() {
+            outline.pop();
+        }
+        let n = outline.len();
+        let area: f32 = (0..n)
+            .map(|i| {
+                let (a, b) = (outline[i], outline[(i + 1) % n]);
+                a.x * b.y - b.x * a.y
+            })
+            .sum();
+
+        if area < 0. {
+            outline.reverse();
+        }
+        outline
+    }
+
+    /// Offset a counter-clockwise outline inwards, with mitered corners.
+    fn inset(outline: &[Point2<f32>], width: f32) -> Vec<Point2<f32>> {
+        // Limit on the miter length, relative to the width, to avoid spikes
+        // at sharp corners.
+        const MITER_LIMIT: f32 = 4.;
+
+        let n = outline.len();
+        let normal = |a: Point2<f32>, b: Point2<f32>| {
+            let d = Vector2::new(b.x - a.x, b.y - a.y);
+            let len = d.magnitude().max(f32::EPSILON);
+            Vector2::new(-d.y / len, d.x / len)
+        };
+
+        (0..n)
+            .map(|i| {
+                let (prev, p, next) = (outline[(i + n - 1) % n], outline[i], outline[(i + 1) % n]);
+                let (n0, n1) = (normal(prev, p), normal(p, next));
+                let miter = n0 + n1;
+                let len = miter.magnitude();
+
+                let offset = if len > f32::EPSILON {
+                    let miter = miter * (1. / len);
+                    let scale =
+                        (width / (miter.x * n1.x + miter.y * n1.y)).min(width * MITER_LIMIT);
+                    miter * scale
+                } else {
+                    n1 * width
+                };
+                Point2::new(p.x + offset.x, p.y + offset.y)
+            })
+            .collect()
+    }
+
+    /// Triangulate a simple counter-clockwise polygon, by ear clipping.
+    /// Returns indices into the polygon points.
+    fn earcut(points: &[Point2<f32>]) -> Vec<u32> {
+        let cross = |o: Point2<f32>, a: Point2<f32>, b: Point2<f32>| {
+            (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+        };
+        let mut remaining: Vec<usize> = (0..points.len()).collect();
+        let mut indices = Vec::with_capacity(points.len().saturating_sub(2) * 3);
+
+        while remaining.len() > 3 {
+            let n = remaining.len();
+            let corner = |i: usize| {
+                (
+                    remaining[(i + n - 1) % n],
+                    remaining[i],
+                    remaining[(i + 1) % n],
+                )
+            };
+
+            let ear = (0..n).find(|&i| {
+                let (ia, ib, ic) = corner(i);
+                let (a, b, c) = (points[ia], points[ib], points[ic]);
+
+                if cross(a, b, c) <= 0. {
+                    return false;
+                }
+                // No other vertex may lie inside the ear.
+                remaining.iter().all(|&j| {
+                    let p = points[j];
+
+                    j == ia
+                        || j == ib
+                        || j == ic
+                        || cross(a, b, p) < 0.
+                        || cross(b, c, p) < 0.
+                        || cross(c, a, p) < 0.
+                })
+            });
+
+            if let Some(i) = ear {
+                let (a, b, c) = corner(i);
+                indices.extend_from_slice(&[a as u32, b as u32, c as u32]);
+                remaining.remove(i);
+            } else if let Some(i) = (0..n).find(|&i| {
+                let (a, b, c) = corner(i);
+                cross(points[a], points[b], points[c]).abs() <= f32::EPSILON
+            }) {
+                // Drop collinear vertices, which can't form ears.
+                remaining.remove(i);
+            } else {
+                // The polygon is self-intersecting.
+                break;
+            }
+        }
+        if remaining.len() == 3 {
+            indices.extend(remaining.iter().map(|&i| i as u32));
+        }
+        indices
+    }
+
+    /// Number of segments of the corners of rounded rectangles, when no
+    /// tolerance is set.
+    const CORNER_SEGMENTS: u32 = 8;
+
+    /// Outline of a rounded rectangle, for exporting it as a polygon.
+    pub(crate) fn rounded_outline(r: Rect<f32>, radius: f32) -> Vec<Point2<f32>> {
+        Self::rounded(r, radius, Self::CORNER_SEGMENTS, Trig::Native)
+    }
+
+    /// Outline of a rounded rectangle, counter-clockwise, with corners of
+    /// the given number of segments.
+    fn rounded(r: Rect<f32>, radius: f32, segments: u32, trig: Trig) -> Vec<Point2<f32>> {
+        let (x1, x2) = (r.x1.min(r.x2), r.x1.max(r.x2));
+        let (y1, y2) = (r.y1.min(r.y2), r.y1.max(r.y2));
+        let radius = radius.min((x2 - x1) / 2.).min((y2 - y1) / 2.);
+
+        if radius <= 0. {
+            return vec![
+                Point2::new(x1, y1),
+                Point2::new(x2, y1),
+                Point2::new(x2, y2),
+                Point2::new(x1, y2),
+            ];
+        }
+        let corners = [
+            Point2::new(x2 - radius, y1 + radius),
+            Point2::new(x2 - radius, y2 - radius),
+            Point2::new(x1 + radius, y2 - radius),
+            Point2::new(x1 + radius, y1 + radius),
+        ];
+        let mut verts = Vec::with_capacity(4 * (segments as usize + 1));
+
+        for (i, c) in corners.iter().enumerate() {
+            // Each corner starts where the previous one ends, a quarter turn
+            // further, from the bottom-right corner.
+            let start = (i as f32 - 1.) * f32::consts::FRAC_PI_2;
+
+            for j in 0..=segments {
+                let angle = start + j as f32 * f32::consts::FRAC_PI_2 / segments as f32;
+                let (sin, cos) = trig.sin_cos(angle);
+
+                verts.push(Point2::new(c.x + radius * cos, c.y + radius * sin));
+            }
+        }
+        verts
+    }
+
+    fn circle(position: Point2<f32>, radius: f32, sides: u32, trig: Trig) -> Vec<Point2<f32>> {
+        let mut verts = Vec::with_capacity(sides as usize + 1);
+
+        for i in 0..=sides as usize {
+            let angle: f32 = i as f32 * ((2. * f32::consts::PI) / sides as f32);
+            let (sin, cos) = trig.sin_cos(angle);
+            verts.push(Point2::new(
+                position.x + radius * cos,
+                position.y + radius * sin,
+            ));
+        }
+        verts
+    }
+}
+
+/// Trigonometric functions used by tessellation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Trig {
+    /// The standard library functions, which may differ across platforms.
+    Native,
+    /// Portable functions, with bit-identical results on all platforms.
+    Portable,
+}
+
+/// How precisely curves are tessellated.
+#[derive(Copy, Clone)]
+struct Precision {
+    trig: Trig,
+    /// Maximum distance between curves and their chords, if set. Otherwise,
+    /// circles have the given number of sides, and round joins and caps
+    /// have a fixed angle between vertices.
+    tolerance: Option<f32>,
+}
+
+impl Precision {
+    const NATIVE: Precision = Precision {
+        trig: Trig::Native,
+        tolerance: None,
+    };
+    const PORTABLE: Precision = Precision {
+        trig: Trig::Portable,
+        tolerance: None,
+    };
+
+    /// Most segments an arc is split into, however small the tolerance.
+    const MAX_SEGMENTS: u32 = 1024;
+
+    /// Number of segments an arc of the given radius and sweep is split into,
+    /// for its chords to be at most `tolerance` away from it.
+    fn arc_segments(self, radius: f32, sweep: f32, tolerance: f32) -> u32 {
+        let radius = radius.abs();
+        if radius <= tolerance {
+            return 1;
+        }
+        // A chord spanning an angle `a` is `r * (1 - cos(a / 2))` away from
+        // the arc, at its middle.
+        let c = 1. - tolerance / radius;
+        let step = 2. * self.trig.atan2((1. - c * c).sqrt(), c);
+
+        ((sweep.abs() / step).ceil() as u32).clamp(1, Self::MAX_SEGMENTS)
+    }
+}
+
+impl Trig {
+    fn sin_cos(self, a: f32) -> (f32, f32) {
+        match self {
+            Trig::Native => (a.sin(), a.cos()),
+            Trig::Portable => portable::sin_cos(a),
+        }
+    }
+
+    fn cos(self, a: f32) -> f32 {
+        self.sin_cos(a).1
+    }
+
+    fn atan2(self, y: f32, x: f32) -> f32 {
+        match self {
+            Trig::Native => y.atan2(x),
+            Trig::Portable => portable::atan2(y, x),
+        }
+    }
+
+    /// Rotate a vertex position around a center, like the vertex shader.
+    fn rotate(self, p: Vector3<f32>, angle: f32, center: Vector2<f32>) -> Point2<f32> {
+        let (sin, cos) = self.sin_cos(angle);
+        let (dx, dy) = (p.x - center.x, p.y - center.y);
+
+        Point2::new(
+            center.x + dx * cos + dy * sin,
+            center.y - dx * sin + dy * cos,
+        )
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Mesh
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Indexed triangle geometry.
+#[derive(Clone, Debug, Default)]
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Create the buffers of the mesh, eg. of an extruded shape, to be drawn
+    /// with the shape pipeline.
+    pub fn buffer(&self, r: &core::Renderer) -> core::IndexedBuffer {
+        core::IndexedBuffer {
+            vertices: r.device.create_buffer(self.vertices.as_slice()),
+            indices: r.device.create_index_buffer(self.indices.as_slice()),
+        }
+    }
+
+    /// Append another mesh, offsetting its indices.
+    pub fn append(&mut self, other: &mut Mesh) {
+        let offset = self.vertices.len() as u32;
+
+        self.indices
+            .extend(other.indices.drain(..).map(|i| i + offset));
+        self.vertices.append(&mut other.vertices);
+    }
+
+    /// Feather the outer edges of the mesh over the given width, with a skirt
+    /// of quads fading from the edge color to transparent. Outer edges are
+    /// those of a single triangle, once vertices at the same position are
+    /// merged, so that edges between the stroke and fill of a shape, or
+    /// between its pieces, aren't feathered. Triangles already smoothed by
+    /// the fragment shader, such as those of hairlines and antialiased
+    /// fills, are left as is.
+    pub fn feather(&mut self, width: f32) {
+        use std::collections::HashMap;
+
+        // Limit on how far corners are pushed out, relative to the width,
+        // to avoid spikes at sharp corners.
+        const MITER_LIMIT: f32 = 2.;
+
+        if width <= 0. {
+            return;
+        }
+        // Merge vertices at the same position, to 1/256th of a pixel.
+        let mut ids = HashMap::new();
+        let welded: Vec<usize> = self
+            .vertices
+            .iter()
+            .map(|v| {
+                let key = (
+                    (v.position.x * 256.).round() as i64,
+                    (v.position.y * 256.).round() as i64,
+                    v.position.z.to_bits(),
+                );
+                let n = ids.len();
+                *ids.entry(key).or_insert(n)
+            })
+            .collect();
+
+        let triangles: Vec<[u32; 3]> = self
+            .indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]])
+            .filter(|t| t.iter().all(|&i| self.vertices[i as usize].edge.z == 0.))
+            .collect();
+        let edge = |a: u32, b: u32| {
+            let (a, b) = (welded[a as usize], welded[b as usize]);
+            (a.min(b), a.max(b))
+        };
+        let mut counts = HashMap::new();
+        for t in triangles.iter() {
+            for k in 0..3 {
+                *counts.entry(edge(t[k], t[(k + 1) % 3])).or_insert(0) += 1;
+            }
+        }
+
+        // Outer edges, with their outward normal.
+        let mut outer = Vec::new();
+        for t in triangles.iter() {
+            for k in 0..3 {
+                let (a, b, c) = (t[k], t[(k + 1) % 3], t[(k + 2) % 3]);
+                if counts[&edge(a, b)] != 1 {
+                    continue;
+                }
+                let p = |i: u32| self.vertices[i as usize].position;
+                let (dx, dy) = (p(b).x - p(a).x, p(b).y - p(a).y);
+                let len = (dx * dx + dy * dy).sqrt();
+
+                if len == 0. {
+                    continue;
+                }
+                let (mut nx, mut ny) = (dy / len, -dx / len);
+                if nx * (p(c).x - p(a).x) + ny * (p(c).y - p(a).y) > 0. {
+                    nx = -nx;
+                    ny = -ny;
+                }
+                outer.push((a, b, Vector2::new(nx, ny)));
+            }
+        }
+
+        // Offset of each outer vertex: the sum of the normals of its edges,
+        // scaled for the skirt to have the given width along each of them.
+        let mut normals: HashMap<usize, (Vector2<f32>, Vector2<f32>)> = HashMap::new();
+        for &(a, b, n) in outer.iter() {
+            for &i in &[a, b] {
+                let entry = normals
+                    .entry(welded[i as usize])
+                    .or_insert((Vector2::new(0., 0.), n));
+                entry.0 = Vector2::new(entry.0.x + n.x, entry.0.y + n.y);
+            }
+        }
+        let offset = |i: u32| {
+            let (sum, n) = normals[&welded[i as usize]];
+            let len = (sum.x * sum.x + sum.y * sum.y).sqrt();
+
+            if len < 1e-3 {
+                return Vector2::new(n.x * width, n.y * width);
+            }
+            let (dx, dy) = (sum.x / len, sum.y / len);
+            let cos = (dx * n.x + dy * n.y).max(1. / MITER_LIMIT);
+
+            Vector2::new(dx * width / cos, dy * width / cos)
+        };
+
+        for &(a, b, _) in outer.iter() {
+            let skirt = |i: u32| {
+                let mut v = self.vertices[i as usize];
+                let o = offset(i);
+
+                v.position.x += o.x;
+                v.position.y += o.y;
+                v.color.a = 0;
+                v
+            };
+            let (sa, sb) = (skirt(a), skirt(b));
+            let v = self.vertices.len() as u32;
+
+            self.vertices.extend_from_slice(&[sb, sa]);
+            self.indices.extend_from_slice(&[a, b, v, a, v, v + 1]);
+        }
+    }
+
+    /// Set the vertex data to the vertex coordinates relative to the
+    /// bounding box of the mesh.
+    fn set_local_coordinates(&mut self) {
+        let (mut min, mut max) = (
+            Vector2::new(f32::MAX, f32::MAX),
+            Vector2::new(f32::MIN, f32::MIN),
+        );
+        for v in self.vertices.iter() {
+            min = Vector2::new(min.x.min(v.position.x), min.y.min(v.position.y));
+            max = Vector2::new(max.x.max(v.position.x), max.y.max(v.position.y));
+        }
+        let size = max - min;
+        let sx = if size.x > 0. { 1. / size.x } else { 0. };
+        let sy = if size.y > 0. { 1. / size.y } else { 0. };
+
+        for v in self.vertices.iter_mut() {
+            v.data = Vector2::new((v.position.x - min.x) * sx, (v.position.y - min.y) * sy);
+        }
+    }
+
+    /// Add a convex quad, with vertices in order around its edge.
+    fn quad(&mut self, verts: [Vertex; 4]) {
+        let v = self.vertices.len() as u32;
+
+        self.vertices.extend_from_slice(&verts);
+        self.indices
+            .extend_from_slice(&[v, v + 1, v + 2, v, v + 2, v + 3]);
+    }
+
+    /// Add a triangle fan around a center vertex.
+    fn fan<I: Iterator<Item = Vertex>>(&mut self, center: Vertex, edge: I) {
+        let c = self.vertices.len() as u32;
+
+        self.vertices.push(center);
+        self.vertices.extend(edge);
+
+        for v in c + 1..self.vertices.len() as u32 - 1 {
+            self.indices.extend_from_slice(&[c, v, v + 1]);
+        }
+    }
+
+    /// Add indexed triangles.
+    fn triangles<I: Iterator<Item = Vertex>>(&mut self, verts: I, indices: &[u32]) {
+        let v = self.vertices.len() as u32;
+
+        self.vertices.extend(verts);
+        self.indices.extend(indices.iter().map(|i| i + v));
+    }
+
+    /// Add a strip of quads between two edges with the same number of vertices.
+    fn ring<I, O>(&mut self, inner: I, outer: O)
+    where
+        I: ExactSizeIterator<Item = Vertex>,
+        O: ExactSizeIterator<Item = Vertex>,
+    {
+        let n = inner.len() as u32;
+        let i = self.vertices.len() as u32;
+        let o = i + n;
+
+        debug_assert_eq!(inner.len(), outer.len());
+
+        self.vertices.extend(inner);
+        self.vertices.extend(outer);
+
+        for k in 0..n.saturating_sub(1) {
+            self.indices
+                .extend_from_slice(&[i + k, o + k, o + k + 1, i + k, o + k + 1, i + k + 1]);
+        }
+    }
+}
 
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Line {
     pub p1: Vector2<f32>,
     pub p2: Vector2<f32>,
@@ -361,18 +2299,403 @@ impl Line {
     }
 }
 
+/// A segment of a `Path`, from the end of the previous segment.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Segment {
+    /// A straight line to the given point.
+    Line(Point2<f32>),
+    /// A quadratic bezier curve, with one control point.
+    Quadratic(Point2<f32>, Point2<f32>),
+    /// A cubic bezier curve, with two control points.
+    Cubic(Point2<f32>, Point2<f32>, Point2<f32>),
+}
+
+/// A sequence of line and curve segments.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Path {
+    pub start: Point2<f32>,
+    pub segments: Vec<Segment>,
+    /// Whether the end of the path is connected back to its start.
+    pub closed: bool,
+}
+
+impl Path {
+    /// Maximum distance between a curve and its flattened approximation.
+    const TOLERANCE: f32 = 0.25;
+
+    pub fn new(start: Point2<f32>) -> Self {
+        Self {
+            start,
+            segments: Vec::new(),
+            closed: false,
+        }
+    }
+
+    pub fn line_to(mut self, p: Point2<f32>) -> Self {
+        self.segments.push(Segment::Line(p));
+        self
+    }
+
+    pub fn quadratic_to(mut self, ctrl: Point2<f32>, p: Point2<f32>) -> Self {
+        self.segments.push(Segment::Quadratic(ctrl, p));
+        self
+    }
+
+    pub fn cubic_to(mut self, ctrl1: Point2<f32>, ctrl2: Point2<f32>, p: Point2<f32>) -> Self {
+        self.segments.push(Segment::Cubic(ctrl1, ctrl2, p));
+        self
+    }
+
+    pub fn close(mut self) -> Self {
+        self.closed = true;
+        self
+    }
+
+    /// Approximate the path with straight lines. Returns the points along
+    /// the path, starting with `start`.
+    pub fn flatten(&self) -> Vec<Point2<f32>> {
+        self.flatten_with_tolerance(Self::TOLERANCE)
+    }
+
+    /// Like `flatten`, with curves at most `tolerance` away from the lines
+    /// approximating them.
+    pub fn flatten_with_tolerance(&self, tolerance: f32) -> Vec<Point2<f32>> {
+        let tolerance = tolerance.max(f32::EPSILON);
+        let mut points = vec![self.start];
+        let mut from = self.start;
+
+        for segment in self.segments.iter() {
+            match *segment {
+                Segment::Line(p) => {
+                    points.push(p);
+                    from = p;
+                }
+                Segment::Quadratic(c, p) => {
+                    // The distance between the curve and its chords is bounded
+                    // by a quarter of the second difference, over the square
+                    // of the number of chords.
+                    let dd = Self::second_difference(from, c, p);
+                    let n = (dd / (4. * tolerance)).sqrt().ceil().max(1.) as usize;
+
+                    for i in 1..=n {
+                        let t = i as f32 / n as f32;
+                        let u = 1. - t;
+
+                        points.push(Point2::new(
+                            u * u * from.x + 2. * u * t * c.x + t * t * p.x,
+                            u * u * from.y + 2. * u * t * c.y + t * t * p.y,
+                        ));
+                    }
+                    from = p;
+                }
+                Segment::Cubic(c1, c2, p) => {
+                    let dd = Self::second_difference(from, c1, c2)
+                        .max(Self::second_difference(c1, c2, p));
+                    let n = (3. * dd / (4. * tolerance)).sqrt().ceil().max(1.) as usize;
+
+                    for i in 1..=n {
+                        let t = i as f32 / n as f32;
+                        let u = 1. - t;
+                        let (a, b, c, d) = (u * u * u, 3. * u * u * t, 3. * u * t * t, t * t * t);
+
+                        points.push(Point2::new(
+                            a * from.x + b * c1.x + c * c2.x + d * p.x,
+                            a * from.y + b * c1.y + c * c2.y + d * p.y,
+                        ));
+                    }
+                    from = p;
+                }
+            }
+        }
+        points
+    }
+
+    /// Measure the arc length of the path.
+    pub fn measure(&self) -> PathMeasure {
+        PathMeasure::new(self)
+    }
+
+    fn second_difference(a: Point2<f32>, b: Point2<f32>, c: Point2<f32>) -> f32 {
+        Vector2::new(a.x - 2. * b.x + c.x, a.y - 2. * b.y + c.y).magnitude()
+    }
+}
+
+/// Arc-length measurements of a flattened `Path`, eg. to move sprites along
+/// it, or to place dashes and markers at regular intervals.
+///
+/// Distances are clamped to the length of the path.
+///
+/// # Examples
+///
+/// ```
+/// use rgx::kit::shape2d::Path;
+/// use rgx::math::Point2;
+///
+/// let path = Path::new(Point2::new(0., 0.))
+///     .line_to(Point2::new(10., 0.))
+///     .line_to(Point2::new(10., 10.));
+/// let measure = path.measure();
+///
+/// assert_eq!(measure.length(), 20.);
+/// assert_eq!(measure.point_at(15.), Point2::new(10., 5.));
+/// assert_eq!(measure.extract(5., 15.).len(), 3);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathMeasure {
+    points: Vec<Point2<f32>>,
+    /// Distance along the path at each point.
+    distances: Vec<f32>,
+}
+
+impl PathMeasure {
+    pub fn new(path: &Path) -> Self {
+        let mut points = path.flatten();
+
+        if path.closed && points.last() != Some(&path.start) {
+            points.push(path.start);
+        }
+        Self::from_points(points)
+    }
+
+    /// Measure a polyline.
+    pub fn from_points(points: Vec<Point2<f32>>) -> Self {
+        let mut distances = Vec::with_capacity(points.len());
+        let mut total = 0.;
+
+        for (i, p) in points.iter().enumerate() {
+            if i > 0 {
+                let a = points[i - 1];
+                total += Vector2::new(p.x - a.x, p.y - a.y).magnitude();
+            }
+            distances.push(total);
+        }
+        Self { points, distances }
+    }
+
+    /// Total length of the path.
+    pub fn length(&self) -> f32 {
+        self.distances.last().cloned().unwrap_or(0.)
+    }
+
+    /// The point at the given distance along the path.
+    pub fn point_at(&self, distance: f32) -> Point2<f32> {
+        self.sample(distance).0
+    }
+
+    /// The unit tangent at the given distance along the path, in the
+    /// direction of travel.
+    pub fn tangent_at(&self, distance: f32) -> Vector2<f32> {
+        self.sample(distance).1
+    }
+
+    /// The angle of the tangent at the given distance along the path, in
+    /// radians, for rotating sprites that follow it.
+    pub fn angle_at(&self, distance: f32) -> f32 {
+        let t = self.tangent_at(distance);
+        t.y.atan2(t.x)
+    }
+
+    /// The point and unit tangent at the given distance along the path.
+    pub fn sample(&self, distance: f32) -> (Point2<f32>, Vector2<f32>) {
+        let n = self.points.len();
+
+        if n == 0 {
+            return (Point2::new(0., 0.), Vector2::new(1., 0.));
+        }
+        let i = self.segment(distance);
+        let (a, b) = (self.points[i], self.points[(i + 1).min(n - 1)]);
+        let (d0, d1) = (self.distances[i], self.distances[(i + 1).min(n - 1)]);
+
+        let ab = Vector2::new(b.x - a.x, b.y - a.y);
+        let tangent = if d1 > d0 {
+            ab * (1. / (d1 - d0))
+        } else {
+            Vector2::new(1., 0.)
+        };
+        let t = (distance.max(0.).min(self.length()) - d0).max(0.);
+
+        (a + tangent * t, tangent)
+    }
+
+    /// The part of the path between two distances, as a polyline.
+    pub fn extract(&self, start: f32, end: f32) -> Vec<Point2<f32>> {
+        let len = self.length();
+        let (start, end) = (start.max(0.).min(len), end.max(0.).min(len));
+
+        if self.points.is_empty() || start > end {
+            return Vec::new();
+        }
+        let (i, j) = (self.segment(start), self.segment(end));
+        let mut points = vec![self.point_at(start)];
+
+        for k in i + 1..=j {
+            if self.distances[k] > start && self.distances[k] < end {
+                points.push(self.points[k]);
+            }
+        }
+        points.push(self.point_at(end));
+        points
+    }
+
+    /// Sample `n` points at equal distances along the path. Closed paths
+    /// aren't sampled at their end, since it is also their start.
+    pub fn resample(&self, n: usize, closed: bool) -> Vec<Point2<f32>> {
+        let len = self.length();
+        let step = match (closed, n) {
+            (_, 0) => return Vec::new(),
+            (false, 1) => 0.,
+            (false, _) => len / (n - 1) as f32,
+            (true, _) => len / n as f32,
+        };
+        (0..n).map(|i| self.point_at(i as f32 * step)).collect()
+    }
+
+    /// Index of the segment containing the given distance. Zero-length
+    /// segments are skipped.
+    fn segment(&self, distance: f32) -> usize {
+        if self.points.len() < 2 {
+            return 0;
+        }
+        let last = self.points.len().saturating_sub(2);
+        let i = self
+            .distances
+            .iter()
+            .skip(1)
+            .position(|d| *d >= distance)
+            .unwrap_or(last);
+
+        (i..=last)
+            .find(|k| self.distances[k + 1] > self.distances[*k])
+            .unwrap_or(i.min(last))
+    }
+}
+
+/// An interpolation between two paths, eg. for morphing icons or loaders.
+///
+/// Both paths are resampled to the same number of points, at equal
+/// distances along them, and the points are interpolated linearly. If both
+/// paths are closed, the starting point of the second one is chosen to
+/// minimize the distance travelled by the points.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Morph {
+    from: Vec<Point2<f32>>,
+    to: Vec<Point2<f32>>,
+    closed: bool,
+}
+
+impl Morph {
+    pub fn new(from: &Path, to: &Path, samples: usize) -> Self {
+        let closed = from.closed && to.closed;
+        let from = from.measure().resample(samples, closed);
+        let mut to = to.measure().resample(samples, closed);
+
+        if closed && !to.is_empty() {
+            let cost = |offset: usize| -> f32 {
+                from.iter()
+                    .enumerate()
+                    .map(|(i, a)| {
+                        let b = to[(i + offset) % to.len()];
+                        (a.x - b.x) * (a.x - b.x) + (a.y - b.y) * (a.y - b.y)
+                    })
+                    .sum()
+            };
+            let best = (0..to.len())
+                .min_by(|a, b| {
+                    cost(*a)
+                        .partial_cmp(&cost(*b))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap_or(0);
+            to.rotate_left(best);
+        }
+        Self { from, to, closed }
+    }
+
+    /// The interpolated points at `t`, from `0.` to `1.`.
+    pub fn points(&self, t: f32) -> Vec<Point2<f32>> {
+        self.from
+            .iter()
+            .zip(self.to.iter())
+            .map(|(a, b)| Point2::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t))
+            .collect()
+    }
+
+    /// The interpolated shape at `t`. Closed morphs are drawn as polygons,
+    /// and open ones as polylines, without a fill.
+    pub fn shape(&self, t: f32, z: ZDepth, stroke: Stroke, fill: Fill) -> Shape {
+        let points = self.points(t);
+
+        if self.closed {
+            Shape::Polygon(points, z, Rotation::default(), stroke, fill)
+        } else {
+            Shape::Polyline(points, z, Rotation::default(), stroke)
+        }
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 /// Batch
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Batch {
-    items: Vec<Shape>,
+    /// Opacity applied to all shapes in the batch.
+    pub opacity: f32,
+    /// Whether shape fills are antialiased. See `Shape::triangulate_antialiased`.
+    pub antialias: bool,
+    /// Whether shapes are tessellated identically on all platforms. See
+    /// `Shape::triangulate_deterministic`. Set before adding transformed
+    /// shapes, so that their transforms are also computed portably.
+    pub deterministic: bool,
+    /// Width over which the outer edges of strokes and fills are feathered,
+    /// if set. See `Shape::triangulate_feathered`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub feather: Option<f32>,
+    /// Whether circles and rounded rectangles are drawn as quads with signed
+    /// distance edges, when they can be. They are then in `sdf_mesh` instead
+    /// of `mesh`. See `sdf`.
+    ///
+    /// The quads are drawn after all the tessellated shapes of the batch, by
+    /// `Buffers::draw`, so they cover shapes added after them at the same
+    /// depth. Give shapes distinct depths for them to overlap in the order
+    /// they were added.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sdf: bool,
+    /// Maximum distance between curves and their tessellation, in pixels.
+    /// When set, it replaces the side count of circles. See
+    /// `Shape::triangulate_with_tolerance`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tolerance: Option<f32>,
+
+    /// Shapes, with their opacity and transform.
+    pub(crate) items: Vec<(Shape, f32, Option<Matrix4<f32>>)>,
+    /// Animations of shapes, by index, in increasing order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    motions: Vec<(usize, Motion)>,
+    /// Indices of the emissive shapes, in increasing order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    emissive: Vec<usize>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    transforms: TransformStack,
 }
 
 impl Batch {
     pub fn new() -> Self {
-        Self { items: Vec::new() }
+        Self {
+            opacity: 1.0,
+            antialias: false,
+            deterministic: false,
+            feather: None,
+            sdf: false,
+            tolerance: None,
+            items: Vec::new(),
+            motions: Vec::new(),
+            emissive: Vec::new(),
+            transforms: TransformStack::new(),
+        }
     }
 
     pub fn singleton(shape: Shape) -> Self {
@@ -382,35 +2705,534 @@ impl Batch {
     }
 
     pub fn add(&mut self, shape: Shape) {
-        self.items.push(shape);
+        self.add_with_opacity(shape, 1.0);
+    }
+
+    /// Add a shape with an opacity multiplier, independent of its stroke
+    /// and fill colors.
+    pub fn add_with_opacity(&mut self, shape: Shape, opacity: f32) {
+        let transform = if self.transforms.is_empty() {
+            None
+        } else {
+            Some(self.transforms.current())
+        };
+        self.items.push((shape, opacity, transform));
     }
 
-    pub fn vertices(&self) -> Vec<Vertex> {
-        // TODO: This is a lower-bound estimate of how much space we need.
-        // We should get the actual numbers from the shapes.
-        let mut buf = Vec::with_capacity(6 * self.items.len());
+    /// Add a shape animated with the given motion. The motion is evaluated
+    /// by the shape pipeline, at the time it's prepared with, so the batch is
+    /// only tessellated once.
+    ///
+    /// ```
+    /// use rgx::core::Rgba;
+    /// use rgx::kit::shape2d::{Batch, Fill, Motion, Shape, Stroke};
+    /// use rgx::kit::ZDepth;
+    /// use rgx::rect::Rect;
+    ///
+    /// let rect = Shape::Rectangle(
+    ///     Rect::new(0., 0., 32., 32.),
+    ///     ZDepth::ZERO,
+    ///     Default::default(),
+    ///     Stroke::new(2., Rgba::WHITE),
+    ///     Fill::Solid(Rgba::WHITE),
+    /// );
+    /// let mut batch = Batch::new();
+    /// batch.add_animated(rect.clone(), Motion::Pulse { period: 2., scale: 3. });
+    ///
+    /// // The shape is tessellated as is, and pulses when drawn.
+    /// let (animated, still) = (batch.mesh(), Batch::singleton(rect).mesh());
+    /// assert!(animated
+    ///     .vertices
+    ///     .iter()
+    ///     .zip(still.vertices.iter())
+    ///     .all(|(a, b)| a.position() == b.position()));
+    /// ```
+    pub fn add_animated(&mut self, shape: Shape, motion: Motion) {
+        self.add(shape);
+        self.motions.push((self.items.len() - 1, motion));
+    }
+
+    /// Add a shape tagged as emissive, eg. to be bloomed. Emissive shapes
+    /// are drawn with the rest of the batch, and can also be drawn on their
+    /// own with `Batch::emissive`.
+    pub fn add_emissive(&mut self, shape: Shape) {
+        self.add(shape);
+        self.emissive.push(self.items.len() - 1);
+    }
+
+    /// A batch of the emissive shapes only, with the same settings, eg. to
+    /// draw into the target of a `Bloom`.
+    ///
+    /// ```
+    /// use rgx::core::Rgba;
+    /// use rgx::kit::shape2d::{Batch, Fill, Shape, Stroke};
+    /// use rgx::kit::ZDepth;
+    /// use rgx::math::Point2;
+    ///
+    /// let circle = |x| {
+    ///     Shape::Circle(
+    ///         Point2::new(x, 0.),
+    ///         ZDepth::ZERO,
+    ///         4.,
+    ///         8,
+    ///         Stroke::NONE,
+    ///         Fill::Solid(Rgba::WHITE),
+    ///     )
+    /// };
+    /// let mut batch = Batch::new();
+    /// batch.add(circle(0.));
+    /// batch.add_emissive(circle(16.));
+    ///
+    /// let mut alone = Batch::new();
+    /// alone.add(circle(16.));
+    ///
+    /// assert_eq!(
+    ///     batch.emissive().mesh().vertices.len(),
+    ///     alone.mesh().vertices.len()
+    /// );
+    /// ```
+    pub fn emissive(&self) -> Batch {
+        self.select(&self.emissive)
+    }
+
+    /// A batch of the shapes with the given ids only, with the same
+    /// settings, eg. to outline them. Shapes are identified by the order they
+    /// were added in, from zero.
+    pub fn select(&self, ids: &[usize]) -> Batch {
+        let mut batch = Batch {
+            opacity: self.opacity,
+            antialias: self.antialias,
+            deterministic: self.deterministic,
+            feather: self.feather,
+            sdf: self.sdf,
+            tolerance: self.tolerance,
+            ..Batch::new()
+        };
+        for (n, &i) in ids.iter().enumerate() {
+            assert!(
+                i < self.items.len(),
+                "fatal: shape {} is out of range of a batch of {}",
+                i,
+                self.items.len()
+            );
+            batch.items.push(self.items[i].clone());
+
+            if let Ok(j) = self.motions.binary_search_by_key(&i, |(k, _)| *k) {
+                batch.motions.push((n, self.motions[j].1));
+            }
+            if self.emissive.binary_search(&i).is_ok() {
+                batch.emissive.push(n);
+            }
+        }
+        batch
+    }
+
+    /// Add a shape with a transform, eg. a `Transform2D`, applied after its
+    /// rotation. The transform is in the local space of the current
+    /// transform, if any.
+    pub fn add_transformed<T: IntoMatrix>(&mut self, shape: Shape, transform: T) {
+        let transform = self.transforms.current() * transform.into_matrix(self.deterministic);
+        self.items.push((shape, 1.0, Some(transform)));
+    }
+
+    /// Transform the shapes added until the matching `pop_transform`. The
+    /// transform is in the local space of the current transform, if any.
+    pub fn push_transform<T: IntoMatrix>(&mut self, transform: T) {
+        self.transforms
+            .push(transform.into_matrix(self.deterministic));
+    }
+
+    /// Stop transforming shapes with the last pushed transform.
+    pub fn pop_transform(&mut self) {
+        assert!(
+            self.transforms.pop().is_some(),
+            "fatal: `pop_transform` called without a matching `push_transform`"
+        );
+    }
+
+    /// Add shapes with a transform, in the local space of the current
+    /// transform.
+    pub fn with_transform<T, F>(&mut self, transform: T, f: F)
+    where
+        T: IntoMatrix,
+        F: FnOnce(&mut Self),
+    {
+        self.push_transform(transform);
+        f(self);
+        self.pop_transform();
+    }
+
+    pub fn mesh(&self) -> Mesh {
+        let mut buf = Mesh::new();
+
+        for i in 0..self.items.len() {
+            buf.append(&mut self.item_mesh(i));
+        }
+        buf
+    }
+
+    /// Like `mesh`, with shapes tessellated in parallel, on the rayon thread
+    /// pool. The meshes are concatenated in the order the shapes were added,
+    /// so the result is the same as with `mesh`.
+    ///
+    /// ```
+    /// use rgx::core::Rgba;
+    /// use rgx::kit::shape2d::{Batch, Fill, Shape, Stroke};
+    /// use rgx::kit::ZDepth;
+    /// use rgx::math::Point2;
+    ///
+    /// let mut batch = Batch::new();
+    /// for i in 0..1000 {
+    ///     batch.add(Shape::Circle(
+    ///         Point2::new(i as f32, 0.),
+    ///         ZDepth::ZERO,
+    ///         4.,
+    ///         16,
+    ///         Stroke::new(1., Rgba::WHITE),
+    ///         Fill::Solid(Rgba::WHITE),
+    ///     ));
+    /// }
+    /// assert_eq!(batch.mesh_parallel().indices, batch.mesh().indices);
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn mesh_parallel(&self) -> Mesh {
+        use rayon::prelude::*;
+
+        let meshes = (0..self.items.len())
+            .into_par_iter()
+            .map(|i| self.item_mesh(i))
+            .collect::<Vec<_>>();
 
-        for shape in self.items.iter() {
-            let mut verts: Vec<Vertex> = shape.triangulate();
-            buf.append(&mut verts);
+        let mut buf = Mesh::new();
+        for mut mesh in meshes {
+            buf.append(&mut mesh);
         }
         buf
     }
 
+    /// The quads of the circles and rounded rectangles drawn with signed
+    /// distance edges, in batches with `sdf` set, to be drawn with an
+    /// `sdf::Pipeline`. These shapes are left out of `mesh`.
+    pub fn sdf_mesh(&self) -> sdf::Mesh {
+        let mut mesh = sdf::Mesh::new();
+        let trig = self.precision().trig;
+
+        for (i, (shape, opacity, transform)) in self.items.iter().enumerate() {
+            if !self.is_sdf(i) {
+                continue;
+            }
+            sdf::add(&mut mesh, shape, opacity * self.opacity, |v| {
+                if let Some(m) = transform {
+                    let p = transform::apply(m, trig.rotate(v.position, v.angle, v.center));
+
+                    v.position = Vector3::new(p.x, p.y, v.position.z);
+                    v.angle = 0.;
+                }
+            });
+        }
+        mesh
+    }
+
+    /// The animation of the shape at the given index, if any.
+    fn motion(&self, i: usize) -> Option<Motion> {
+        self.motions
+            .binary_search_by_key(&i, |(j, _)| *j)
+            .ok()
+            .map(|j| self.motions[j].1)
+    }
+
+    /// Whether the shape at the given index is drawn with signed distance
+    /// edges, instead of being tessellated. Animated shapes are tessellated.
+    fn is_sdf(&self, i: usize) -> bool {
+        self.sdf && self.motion(i).is_none() && sdf::supported(&self.items[i].0)
+    }
+
+    fn precision(&self) -> Precision {
+        Precision {
+            tolerance: self.tolerance,
+            ..if self.deterministic {
+                Precision::PORTABLE
+            } else {
+                Precision::NATIVE
+            }
+        }
+    }
+
+    /// Tessellate the batch with each shape in a flat color, given by its
+    /// index, eg. to draw object ids with `kit::outline`. Color alpha is
+    /// kept, for edges to stay antialiased. Shapes aren't animated, and are
+    /// tessellated even in batches with `sdf` set.
+    ///
+    /// ```
+    /// use rgx::core::Rgba;
+    /// use rgx::kit::shape2d::{Batch, Fill, Shape, Stroke};
+    /// use rgx::kit::{Rgba8, ZDepth};
+    /// use rgx::math::Point2;
+    ///
+    /// let mut batch = Batch::new();
+    /// batch.sdf = true;
+    /// batch.add(Shape::Circle(
+    ///     Point2::new(8., 8.),
+    ///     ZDepth::ZERO,
+    ///     4.,
+    ///     16,
+    ///     Stroke::NONE,
+    ///     Fill::Solid(Rgba::WHITE),
+    /// ));
+    /// let ids = batch.flat_mesh(|i| Rgba8::new(i as u8, 0, 0, 255));
+    ///
+    /// // The circle is tessellated, instead of being in `sdf_mesh`.
+    /// batch.sdf = false;
+    /// assert_eq!(ids.indices, batch.mesh().indices);
+    /// ```
+    pub fn flat_mesh<F: Fn(usize) -> Rgba8>(&self, color: F) -> Mesh {
+        let mut buf = Mesh::new();
+
+        for (i, (shape, _, transform)) in self.items.iter().enumerate() {
+            let mut mesh = self.shape_mesh(shape, 1., transform);
+            let color = color(i);
+
+            for v in mesh.vertices.iter_mut() {
+                v.color = Rgba8 {
+                    a: v.color.a,
+                    ..color
+                };
+                v.opacity = 1.;
+            }
+            buf.append(&mut mesh);
+        }
+        buf
+    }
+
+    /// Tessellate the shape at the given index, with its opacity, transform
+    /// and animation applied.
+    fn item_mesh(&self, i: usize) -> Mesh {
+        if self.is_sdf(i) {
+            return Mesh::new();
+        }
+        let (shape, opacity, transform) = &self.items[i];
+        let motion = self.motion(i);
+        let mut mesh = self.shape_mesh(shape, *opacity, transform);
+
+        if let Some(m) = motion {
+            if let Motion::Pulse { scale, .. } = m {
+                // Pulsing vertices are moved towards their position with the
+                // stroke at its widest, in the vertex shader.
+                let mut widest = shape.clone();
+
+                if let Some(stroke) = widest.stroke_mut() {
+                    stroke.width *= scale;
+                }
+                let widest = self.shape_mesh(&widest, *opacity, transform);
+
+                if widest.vertices.len() == mesh.vertices.len() {
+                    for (v, w) in mesh.vertices.iter_mut().zip(widest.vertices.iter()) {
+                        v.offset =
+                            Vector2::new(w.position.x - v.position.x, w.position.y - v.position.y);
+                    }
+                }
+            }
+            let attribute = m.attribute();
+
+            for v in mesh.vertices.iter_mut() {
+                v.motion = attribute;
+            }
+        }
+        mesh
+    }
+
+    /// Tessellate a shape with the settings of the batch, and the given
+    /// opacity and transform applied.
+    fn shape_mesh(&self, shape: &Shape, opacity: f32, transform: &Option<Matrix4<f32>>) -> Mesh {
+        let opacity = opacity * self.opacity;
+        let precision = self.precision();
+        let mut mesh = shape.tessellate(self.antialias, precision);
+
+        for v in mesh.vertices.iter_mut() {
+            v.opacity = opacity;
+
+            if let Some(m) = transform {
+                // Shapes are rotated in the vertex shader, before any other
+                // transform, so the rotation is applied here first.
+                let p = precision.trig.rotate(v.position, v.angle, v.center);
+                let p = transform::apply(m, p);
+
+                v.position = Vector3::new(p.x, p.y, v.position.z);
+                v.angle = 0.;
+            }
+        }
+        if let Some(width) = self.feather {
+            mesh.feather(width);
+        }
+        mesh
+    }
+
+    /// Move the shapes of another batch to the end of this one, leaving the
+    /// other batch empty. Shapes keep their transform, animation and
+    /// opacity, including the opacity of the other batch, and are tessellated
+    /// with the settings of this batch.
+    ///
+    /// ```
+    /// use rgx::core::Rgba;
+    /// use rgx::kit::shape2d::{Batch, Line, Shape, Stroke};
+    /// use rgx::kit::ZDepth;
+    ///
+    /// let line = |y| {
+    ///     Shape::Line(
+    ///         Line::new(0., y, 8., y),
+    ///         ZDepth::ZERO,
+    ///         Default::default(),
+    ///         Stroke::new(1., Rgba::WHITE),
+    ///     )
+    /// };
+    /// let (mut a, mut b) = (Batch::singleton(line(0.)), Batch::singleton(line(4.)));
+    /// a.merge(&mut b);
+    ///
+    /// assert!(b.is_empty());
+    /// assert_eq!(a.mesh().vertices.len(), 8);
+    /// ```
+    pub fn merge(&mut self, other: &mut Batch) {
+        let offset = self.items.len();
+        let opacity = other.opacity;
+
+        self.motions
+            .extend(other.motions.drain(..).map(|(i, m)| (i + offset, m)));
+        self.emissive
+            .extend(other.emissive.drain(..).map(|i| i + offset));
+        self.items.extend(
+            other
+                .items
+                .drain(..)
+                .map(|(shape, o, transform)| (shape, o * opacity, transform)),
+        );
+        other.clear();
+    }
+
     pub fn is_empty(&self) -> bool {
         self.items.is_empty()
     }
 
     pub fn clear(&mut self) {
         self.items.clear();
+        self.motions.clear();
+        self.emissive.clear();
+        self.transforms = TransformStack::new();
+    }
+
+    /// Create the buffers of the batch's tessellated `mesh`, leaving out
+    /// the shapes in its `sdf_mesh`. See `finish` for both.
+    pub fn buffer(&self, r: &core::Renderer) -> core::IndexedBuffer {
+        let mesh = self.mesh();
+
+        core::IndexedBuffer {
+            vertices: r.device.create_buffer(mesh.vertices.as_slice()),
+            indices: r.device.create_index_buffer(mesh.indices.as_slice()),
+        }
     }
 
-    pub fn buffer(&self, r: &core::Renderer) -> core::VertexBuffer {
-        let buf = self.vertices();
-        r.device.create_buffer(buf.as_slice())
+    /// Create the buffers of the batch's tessellated shapes, and of its
+    /// signed distance quads, if it has any.
+    pub fn finish(self, r: &core::Renderer) -> Buffers<core::IndexedBuffer> {
+        let mesh = self.mesh();
+        let sdf = self.sdf_mesh();
+
+        Buffers {
+            shapes: if mesh.is_empty() {
+                None
+            } else {
+                Some(mesh.buffer(r))
+            },
+            sdf: if sdf.is_empty() {
+                None
+            } else {
+                Some(sdf.buffer(r))
+            },
+        }
     }
 
-    pub fn finish(self, r: &core::Renderer) -> core::VertexBuffer {
-        self.buffer(r)
+    /// Create the buffers of the batch's `sdf_mesh`.
+    pub fn sdf_buffer(&self, r: &core::Renderer) -> core::IndexedBuffer {
+        self.sdf_mesh().buffer(r)
+    }
+
+    /// Write the batch into a ring buffer, instead of creating buffers for
+    /// it. Returns `None` if it doesn't fit, see `RingBuffer::write`, in
+    /// which case nothing is written.
+    pub fn write(&self, ring: &mut core::RingBuffer) -> Option<Buffers<core::RingSlice>> {
+        let mesh = self.mesh();
+        let sdf = self.sdf_mesh();
+        let mark = ring.mark();
+
+        let shapes = if mesh.is_empty() {
+            None
+        } else {
+            Some(ring.write_indexed(mesh.vertices.as_slice(), mesh.indices.as_slice())?)
+        };
+        let sdf = if sdf.is_empty() {
+            None
+        } else {
+            match ring.write_indexed(sdf.vertices.as_slice(), sdf.indices.as_slice()) {
+                Some(slice) => Some(slice),
+                None => {
+                    // Undo the write of the tessellated shapes.
+                    ring.rollback(mark);
+                    return None;
+                }
+            }
+        };
+        Some(Buffers { shapes, sdf })
+    }
+}
+
+/// The buffers of a `Batch`: its tessellated shapes, and the signed distance
+/// quads of its circles and rounded rectangles, if it has any of either.
+/// Buffers are either `core::IndexedBuffer`s, created by `Batch::finish`, or
+/// `core::RingSlice`s, written by `Batch::write`.
+#[derive(Debug)]
+pub struct Buffers<T> {
+    pub shapes: Option<T>,
+    pub sdf: Option<T>,
+}
+
+impl<T> Buffers<T> {
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_none() && self.sdf.is_none()
+    }
+}
+
+impl Buffers<core::IndexedBuffer> {
+    /// Draw the tessellated shapes with the shape pipeline, and then the
+    /// quads with the signed distance pipeline. Both pipelines should be
+    /// prepared with the same projection.
+    pub fn draw<'a>(&self, shapes: &Pipeline, sdf: &sdf::Pipeline, pass: &mut core::Pass<'a>) {
+        if let Some(buffer) = &self.shapes {
+            pass.set_pipeline(shapes);
+            pass.draw_indexed_buffer(buffer);
+        }
+        if let Some(buffer) = &self.sdf {
+            pass.set_pipeline(sdf);
+            pass.draw_indexed_buffer(buffer);
+        }
+    }
+}
+
+impl Buffers<core::RingSlice> {
+    /// Draw the slices of the ring buffer they were written into, like
+    /// `Buffers::draw`.
+    pub fn draw<'a>(
+        &self,
+        ring: &core::RingBuffer,
+        shapes: &Pipeline,
+        sdf: &sdf::Pipeline,
+        pass: &mut core::Pass<'a>,
+    ) {
+        if let Some(slice) = &self.shapes {
+            pass.set_pipeline(shapes);
+            pass.draw_ring(ring, slice);
+        }
+        if let Some(slice) = &self.sdf {
+            pass.set_pipeline(sdf);
+            pass.draw_ring(ring, slice);
+        }
     }
 }
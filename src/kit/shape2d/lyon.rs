@@ -0,0 +1,202 @@
+//! Tessellation of lyon paths into shape batches, for complex vector
+//! content, eg. with holes, self-intersections or non-zero fill rules,
+//! which `Shape::Path` doesn't handle.
+//!
+//! The lyon types used here are re-exported, so that they match the version
+//! used by this crate.
+//!
+//! # Examples
+//!
+//! ```
+//! use rgx::core::Rgba;
+//! use rgx::kit::shape2d::lyon::math::point;
+//! use rgx::kit::shape2d::lyon::{FillOptions, Path, StrokeOptions};
+//! use rgx::kit::shape2d::Batch;
+//! use rgx::kit::ZDepth;
+//!
+//! let mut builder = Path::builder();
+//! builder.begin(point(0., 0.));
+//! builder.line_to(point(32., 0.));
+//! builder.line_to(point(16., 32.));
+//! builder.end(true);
+//! let path = builder.build();
+//!
+//! let mut batch = Batch::new();
+//! batch
+//!     .fill_lyon(&path, &FillOptions::default(), ZDepth::ZERO, Rgba::WHITE)
+//!     .unwrap();
+//! batch
+//!     .stroke_lyon(&path, &StrokeOptions::default().with_line_width(2.), ZDepth::ZERO, Rgba::BLACK)
+//!     .unwrap();
+//!
+//! let mesh = batch.mesh();
+//! assert_eq!(mesh.indices.len() % 3, 0);
+//! assert!(mesh.indices.len() > 3);
+//! ```
+pub use lyon_tessellation::math;
+pub use lyon_tessellation::path::Path;
+pub use lyon_tessellation::{
+    BuffersBuilder, FillOptions, FillTessellator, StrokeOptions, StrokeTessellator,
+    TessellationError, VertexBuffers,
+};
+
+use lyon_tessellation::{FillVertex, FillVertexConstructor, StrokeVertex, StrokeVertexConstructor};
+
+use crate::core::Rgba;
+use crate::kit::shape2d::{self, vertex, Batch, Mesh, Segment, Shape, Vertex};
+use crate::kit::{Rgba8, ZDepth};
+use crate::math::Point2;
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Vertex constructor
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Creates shape vertices of a single color and depth from lyon vertices,
+/// for use with a `BuffersBuilder`, to tessellate into `VertexBuffers`
+/// which convert into a `Mesh`.
+#[derive(Copy, Clone, Debug)]
+pub struct Solid {
+    pub z: ZDepth,
+    pub color: Rgba8,
+}
+
+impl Solid {
+    pub fn new(z: ZDepth, color: Rgba) -> Self {
+        Self {
+            z,
+            color: color.into(),
+        }
+    }
+
+    fn vertex(&self, x: f32, y: f32) -> Vertex {
+        vertex(x, y, self.z.0, 0., Point2::new(0., 0.), self.color)
+    }
+}
+
+impl FillVertexConstructor<Vertex> for Solid {
+    fn new_vertex(&mut self, v: FillVertex) -> Vertex {
+        let p = v.position();
+        self.vertex(p.x, p.y)
+    }
+}
+
+impl StrokeVertexConstructor<Vertex> for Solid {
+    fn new_vertex(&mut self, v: StrokeVertex) -> Vertex {
+        let p = v.position();
+        self.vertex(p.x, p.y)
+    }
+}
+
+impl<I: Into<u32>> From<VertexBuffers<Vertex, I>> for Mesh {
+    fn from(buffers: VertexBuffers<Vertex, I>) -> Self {
+        let mut mesh = Mesh {
+            vertices: buffers.vertices,
+            indices: buffers.indices.into_iter().map(Into::into).collect(),
+        };
+        mesh.set_local_coordinates();
+        mesh
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Paths
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A single lyon path of the given paths, each as a sub-path, eg. the
+/// contours of glyphs, to be filled with their holes.
+///
+/// ```
+/// use rgx::core::Rgba;
+/// use rgx::kit::shape2d::lyon::{self, FillOptions};
+/// use rgx::kit::shape2d::{Batch, Path};
+/// use rgx::kit::ZDepth;
+/// use rgx::math::Point2;
+///
+/// let square = |x1: f32, x2: f32| {
+///     Path::new(Point2::new(x1, x1))
+///         .line_to(Point2::new(x2, x1))
+///         .line_to(Point2::new(x2, x2))
+///         .line_to(Point2::new(x1, x2))
+///         .close()
+/// };
+/// // A square with a square hole.
+/// let path = lyon::from_paths(&[square(0., 30.), square(10., 20.)]);
+///
+/// let mut batch = Batch::new();
+/// batch
+///     .fill_lyon(&path, &FillOptions::default(), ZDepth::ZERO, Rgba::WHITE)
+///     .unwrap();
+///
+/// // Eight triangles around the hole.
+/// assert_eq!(batch.mesh().indices.len(), 8 * 3);
+/// ```
+pub fn from_paths(paths: &[shape2d::Path]) -> Path {
+    let point = |p: Point2<f32>| math::point(p.x, p.y);
+    let mut builder = Path::builder();
+
+    for path in paths {
+        builder.begin(point(path.start));
+
+        for segment in path.segments.iter() {
+            match *segment {
+                Segment::Line(p) => {
+                    builder.line_to(point(p));
+                }
+                Segment::Quadratic(c, p) => {
+                    builder.quadratic_bezier_to(point(c), point(p));
+                }
+                Segment::Cubic(c1, c2, p) => {
+                    builder.cubic_bezier_to(point(c1), point(c2), point(p));
+                }
+            }
+        }
+        builder.end(path.closed);
+    }
+    builder.build()
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Batch
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+impl Batch {
+    /// Fill a lyon path with a solid color, adding it as a `Shape::Mesh`.
+    pub fn fill_lyon(
+        &mut self,
+        path: &Path,
+        options: &FillOptions,
+        z: ZDepth,
+        color: Rgba,
+    ) -> Result<(), TessellationError> {
+        let mut buffers: VertexBuffers<Vertex, u32> = VertexBuffers::new();
+
+        FillTessellator::new().tessellate_path(
+            path,
+            options,
+            &mut BuffersBuilder::new(&mut buffers, Solid::new(z, color)),
+        )?;
+        self.add(Shape::Mesh(buffers.into()));
+
+        Ok(())
+    }
+
+    /// Stroke a lyon path with a solid color, adding it as a `Shape::Mesh`.
+    pub fn stroke_lyon(
+        &mut self,
+        path: &Path,
+        options: &StrokeOptions,
+        z: ZDepth,
+        color: Rgba,
+    ) -> Result<(), TessellationError> {
+        let mut buffers: VertexBuffers<Vertex, u32> = VertexBuffers::new();
+
+        StrokeTessellator::new().tessellate_path(
+            path,
+            options,
+            &mut BuffersBuilder::new(&mut buffers, Solid::new(z, color)),
+        )?;
+        self.add(Shape::Mesh(buffers.into()));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,326 @@
+//! Circles and rounded rectangles drawn as a single quad each, by their own
+//! pipeline, with their fill, stroke and edges computed per pixel from their
+//! signed distance, instead of being tessellated into polygons. Edges stay
+//! smooth at any scale, and the cost of a shape doesn't depend on its size or
+//! side count.
+//!
+//! Shapes are added to a regular `Batch`, with `sdf` set. Circles and
+//! rounded rectangles with a solid or empty fill, and a plain stroke, if
+//! any, are then left out of the batch's tessellated `mesh`, and are instead
+//! in its `sdf_mesh`, to be drawn with this module's `Pipeline`. Other
+//! shapes, and animated ones, are tessellated as usual.
+//!
+//! # Examples
+//!
+//! ```
+//! use rgx::core::Rgba;
+//! use rgx::kit::shape2d::{Batch, Fill, Shape, Stroke};
+//! use rgx::kit::ZDepth;
+//! use rgx::math::Point2;
+//! use rgx::rect::Rect;
+//!
+//! let mut batch = Batch::new();
+//! batch.sdf = true;
+//! batch.add(Shape::Circle(
+//!     Point2::new(64., 64.),
+//!     ZDepth::ZERO,
+//!     32.,
+//!     128,
+//!     Stroke::NONE,
+//!     Fill::Solid(Rgba::WHITE),
+//! ));
+//!
+//! // Two triangles, whatever the side count, and nothing to tessellate.
+//! assert!(batch.mesh().is_empty());
+//! assert_eq!(batch.sdf_mesh().indices.len(), 6);
+//!
+//! batch.add(Shape::RoundedRectangle(
+//!     Rect::new(0., 0., 128., 64.),
+//!     8.,
+//!     ZDepth::ZERO,
+//!     Default::default(),
+//!     Stroke::new(2., Rgba::BLACK),
+//!     Fill::Solid(Rgba::WHITE),
+//! ));
+//!
+//! // Strokes are drawn by the same quad.
+//! assert_eq!(batch.sdf_mesh().indices.len(), 6 * 2);
+//! ```
+//!
+//! Both meshes are drawn with the same projection, the quads after the
+//! tessellated shapes:
+//!
+//! ```ignore
+//! use rgx::kit::shape2d::{self, sdf};
+//!
+//! let shapes: shape2d::Pipeline = r.pipeline(Blending::default());
+//! let rounded: sdf::Pipeline = r.pipeline(Blending::default());
+//! let buffers = batch.finish(&r);
+//!
+//! r.update_pipeline(&shapes, kit::ortho(w, h), &mut frame);
+//! r.update_pipeline(&rounded, kit::ortho(w, h), &mut frame);
+//! {
+//!     let mut pass = frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &output);
+//!
+//!     buffers.draw(&shapes, &rounded, &mut pass);
+//! }
+//! ```
+use crate::math::*;
+
+use crate::core;
+use crate::core::{Binding, BindingType, Set, ShaderStage};
+use crate::kit::shape2d::{Fill, Globals, Rotation, Shape, Stroke, Uniforms};
+use crate::kit::{Rgba8, ZDepth};
+use crate::rect::Rect;
+
+///////////////////////////////////////////////////////////////////////////
+// Vertex
+///////////////////////////////////////////////////////////////////////////
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct Vertex {
+    pub(crate) position: Vector3<f32>,
+    pub(crate) angle: f32,
+    pub(crate) center: Vector2<f32>,
+    pub(crate) fill: Rgba8,
+    pub(crate) stroke: Rgba8,
+    pub(crate) opacity: f32,
+    local: Vector2<f32>,
+    bounds: Vector4<f32>,
+}
+
+impl Vertex {
+    pub fn position(&self) -> Vector3<f32> {
+        self.position
+    }
+}
+
+/// Vertex layout of signed distance shapes.
+pub const VERTEX_LAYOUT: &[core::VertexFormat] = &[
+    // Position
+    core::VertexFormat::Float3,
+    // Rotation angle.
+    core::VertexFormat::Float,
+    // Center of rotation.
+    core::VertexFormat::Float2,
+    // Fill color
+    core::VertexFormat::UByte4,
+    // Stroke color
+    core::VertexFormat::UByte4,
+    // Opacity
+    core::VertexFormat::Float,
+    // Position relative to the shape center
+    core::VertexFormat::Float2,
+    // Half size, corner radius and stroke width
+    core::VertexFormat::Float4,
+];
+
+///////////////////////////////////////////////////////////////////////////
+// Mesh
+///////////////////////////////////////////////////////////////////////////
+
+/// Quads of signed distance shapes, two triangles each.
+#[derive(Clone, Debug, Default)]
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn buffer(&self, r: &core::Renderer) -> core::IndexedBuffer {
+        core::IndexedBuffer {
+            vertices: r.device.create_buffer(self.vertices.as_slice()),
+            indices: r.device.create_index_buffer(self.indices.as_slice()),
+        }
+    }
+
+    fn quad(&mut self, verts: [Vertex; 4]) {
+        let i = self.vertices.len() as u32;
+
+        self.vertices.extend_from_slice(&verts);
+        self.indices
+            .extend_from_slice(&[i, i + 1, i + 2, i, i + 2, i + 3]);
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Pipeline
+///////////////////////////////////////////////////////////////////////////
+
+/// The pipeline to draw the `sdf_mesh` of batches. Prepared like the shape
+/// pipeline.
+pub struct Pipeline {
+    pipeline: core::Pipeline,
+    bindings: core::BindingGroup,
+    buf: core::UniformBuffer,
+}
+
+impl<'a> core::AbstractPipeline<'a> for Pipeline {
+    type PrepareContext = Globals;
+    type Uniforms = Uniforms;
+
+    fn description() -> core::PipelineDescription<'a> {
+        core::PipelineDescription {
+            vertex_layout: VERTEX_LAYOUT,
+            pipeline_layout: &[Set(&[Binding {
+                binding: BindingType::UniformBuffer,
+                stage: ShaderStage::Vertex,
+            }])],
+            vertex_shader: include_bytes!("../data/sdf.vert.spv"),
+            fragment_shader: include_bytes!("../data/sdf.frag.spv"),
+        }
+    }
+
+    fn setup(pipeline: core::Pipeline, dev: &core::Device) -> Self {
+        let buf = dev.create_uniform_buffer(&[Uniforms {
+            ortho: Matrix4::identity(),
+            transform: Matrix4::identity(),
+            time: 0.,
+        }]);
+        let bindings = dev.create_binding_group(&pipeline.layout.sets[0], &[&buf]);
+
+        Self {
+            pipeline,
+            bindings,
+            buf,
+        }
+    }
+
+    fn apply(&self, pass: &mut core::Pass) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_binding(&self.bindings, &[]);
+    }
+
+    fn projection(&self) -> Option<&core::UniformBuffer> {
+        Some(&self.buf)
+    }
+
+    fn prepare(
+        &'a self,
+        Globals { ortho, time }: Globals,
+    ) -> Option<(&'a core::UniformBuffer, Vec<Uniforms>)> {
+        Some((
+            &self.buf,
+            vec![Uniforms {
+                ortho,
+                transform: Matrix4::identity(),
+                time,
+            }],
+        ))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Shapes
+///////////////////////////////////////////////////////////////////////////
+
+/// Whether a shape can be drawn with signed distance edges.
+pub(super) fn supported(shape: &Shape) -> bool {
+    match *shape {
+        Shape::Circle(_, _, _, _, ref stroke, ref fill)
+        | Shape::RoundedRectangle(_, _, _, _, ref stroke, ref fill) => {
+            colors(stroke, fill).is_some()
+        }
+        _ => false,
+    }
+}
+
+/// Add the quad of a shape to the mesh, with the given opacity. Vertex
+/// positions are passed through `place`, eg. to transform them. Returns
+/// `false` if the shape can't be drawn with signed distance edges.
+pub(super) fn add<F>(mesh: &mut Mesh, shape: &Shape, opacity: f32, place: F) -> bool
+where
+    F: Fn(&mut Vertex),
+{
+    let (area, center, z, rotation, bounds, stroke, fill) = match *shape {
+        Shape::Circle(p, ZDepth(z), radius, _, ref stroke, ref fill) => {
+            let radius = radius.max(0.);
+            (
+                Rect::new(p.x - radius, p.y - radius, p.x + radius, p.y + radius),
+                p,
+                z,
+                Rotation::default(),
+                Vector4::new(radius, radius, radius, 0.),
+                stroke,
+                fill,
+            )
+        }
+        Shape::RoundedRectangle(r, radius, ZDepth(z), ref rot, ref stroke, ref fill) => {
+            let (x1, x2) = (r.x1.min(r.x2), r.x1.max(r.x2));
+            let (y1, y2) = (r.y1.min(r.y2), r.y1.max(r.y2));
+            let (hw, hh) = ((x2 - x1) / 2., (y2 - y1) / 2.);
+
+            (
+                Rect::new(x1, y1, x2, y2),
+                Point2::new(x1 + hw, y1 + hh),
+                z,
+                rot.clone(),
+                Vector4::new(hw, hh, radius.min(hw).min(hh).max(0.), 0.),
+                stroke,
+                fill,
+            )
+        }
+        _ => return false,
+    };
+    let (stroke_color, fill_color) = match colors(stroke, fill) {
+        Some(colors) => colors,
+        None => return false,
+    };
+    let bounds = Vector4::new(
+        bounds.x,
+        bounds.y,
+        bounds.z,
+        stroke.width.min(bounds.x).min(bounds.y).max(0.),
+    );
+    let Rotation {
+        angle,
+        center: pivot,
+    } = rotation;
+
+    // The quad reaches a pixel past the edge, for it to be smoothed.
+    let v = |x: f32, y: f32| {
+        let mut v = Vertex {
+            position: Vector3::new(x, y, z),
+            angle,
+            center: Vector2::new(pivot.x, pivot.y),
+            fill: fill_color,
+            stroke: stroke_color,
+            opacity,
+            local: Vector2::new(x - center.x, y - center.y),
+            bounds,
+        };
+        place(&mut v);
+        v
+    };
+    mesh.quad([
+        v(area.x1 - 1., area.y1 - 1.),
+        v(area.x2 + 1., area.y1 - 1.),
+        v(area.x2 + 1., area.y2 + 1.),
+        v(area.x1 - 1., area.y2 + 1.),
+    ]);
+
+    true
+}
+
+/// The stroke and fill colors of a shape which can be drawn with signed
+/// distance edges.
+fn colors(stroke: &Stroke, fill: &Fill) -> Option<(Rgba8, Rgba8)> {
+    if stroke.gradient.is_some() || stroke.dash.is_some() || stroke.hairline {
+        return None;
+    }
+    let fill = match *fill {
+        Fill::Solid(color) => color.into(),
+        Fill::Empty() => Rgba8::TRANSPARENT,
+        _ => return None,
+    };
+    Some((stroke.color.into(), fill))
+}
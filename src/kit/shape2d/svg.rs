@@ -0,0 +1,910 @@
+//! Import of SVG documents into shape batches, eg. for icons and vector
+//! assets.
+//!
+//! Paths, rectangles, circles, ellipses, lines, polylines and polygons are
+//! imported, with their solid fill and stroke colors, stroke width, opacity
+//! and transform, including those inherited from groups, and set with the
+//! `style` attribute. Other elements, such as text and images, are skipped,
+//! as are gradient and pattern paints. Paints which can't be parsed, eg.
+//! unknown color names, are ignored, as if they weren't set.
+//!
+//! With the `lyon` feature, the subpaths of an element are filled together,
+//! following its `fill-rule`, so that paths can have holes. Without it,
+//! each subpath is filled on its own, so holes are filled in.
+use std::f32;
+use std::str::FromStr;
+
+use xml::attribute::OwnedAttribute;
+use xml::reader::{EventReader, XmlEvent};
+
+use crate::core::{Rgba, Rgba8};
+use crate::error::Error;
+#[cfg(feature = "lyon")]
+use crate::kit::shape2d::lyon;
+use crate::kit::shape2d::{Batch, Fill, Path, Rotation, Segment, Shape, Stroke};
+use crate::kit::ZDepth;
+use crate::math::Point2;
+
+/// Control point distance of the cubic approximation of a quarter circle,
+/// relative to its radius.
+const KAPPA: f32 = 0.552_284_8;
+
+/// Parse an SVG document into a batch of shapes, in the user units of the
+/// document. The y axis is flipped within the view box of the document, or
+/// its height if it has no view box, so that the origin is at the
+/// bottom-left, as with `kit::ortho` and `kit::svg::export`. The view box
+/// isn't otherwise applied.
+///
+/// # Examples
+///
+/// ```
+/// use rgx::kit::shape2d;
+///
+/// let icon = br##"
+///     <svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24">
+///         <g fill="#e04040" transform="translate(2, 2)">
+///             <path d="M0 0h20v20H0z"/>
+///             <circle cx="10" cy="10" r="4" fill="none" stroke="white" stroke-width="2"/>
+///         </g>
+///         <title>Icon</title>
+///     </svg>
+/// "##;
+/// let batch = shape2d::from_svg(icon).unwrap();
+/// let mesh = batch.mesh();
+///
+/// assert!(mesh.vertices.iter().all(|v| {
+///     let p = v.position();
+///     p.x >= 2. && p.x <= 22. && p.y >= 2. && p.y <= 22.
+/// }));
+/// assert!(shape2d::from_svg(b"<svg><path d=\"M0 0 X\"/></svg>").is_err());
+///
+/// // The top of the document is at the top of the view box.
+/// let bar = br#"<svg viewBox="0 0 8 8"><rect width="8" height="2"/></svg>"#;
+/// let mesh = shape2d::from_svg(bar).unwrap().mesh();
+///
+/// assert!(mesh.vertices.iter().all(|v| v.position().y >= 6.));
+///
+/// // Paints which can't be parsed are inherited instead, here no fill.
+/// let unknown = br#"<svg fill="none"><rect width="8" height="8" fill="nope"/></svg>"#;
+/// assert!(shape2d::from_svg(unknown).unwrap().is_empty());
+///
+/// // With the `lyon` feature, subpaths are filled together, with holes.
+/// let frame = br#"<svg viewBox="0 0 8 8"><path d="M0 0h8v8H0z M2 2h4v4H2z" fill-rule="evenodd"/></svg>"#;
+/// #[cfg(feature = "lyon")]
+/// assert_eq!(shape2d::from_svg(frame).unwrap().mesh().indices.len(), 24);
+/// ```
+pub fn from_svg(bytes: &[u8]) -> Result<Batch, Error> {
+    let mut r = EventReader::new(bytes);
+    let mut batch = Batch::new();
+    let mut styles = vec![Style::default()];
+    // Depth of the elements being skipped, if any.
+    let mut skipping = 0;
+
+    loop {
+        match r.next().map_err(|_| Error::InvalidSvg)? {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => {
+                if skipping > 0 {
+                    skipping += 1;
+                    continue;
+                }
+                let parent = styles.last().copied().unwrap_or_default();
+
+                if prop(&attributes, "display").as_deref() == Some("none") {
+                    skipping = 1;
+                    continue;
+                }
+                let mut style = Style::parse(&attributes, parent)?;
+
+                match name.local_name.as_str() {
+                    "svg" if styles.len() == 1 => {
+                        style.transform = multiply(flip(&attributes)?, style.transform);
+                    }
+                    "svg" | "g" | "a" => {}
+                    element @ ("path" | "rect" | "circle" | "ellipse" | "line" | "polyline"
+                    | "polygon") => {
+                        add(&mut batch, paths(element, &attributes)?, &style)?;
+                    }
+                    _ => {
+                        skipping = 1;
+                        continue;
+                    }
+                }
+                styles.push(style);
+            }
+            XmlEvent::EndElement { .. } => {
+                if skipping > 0 {
+                    skipping -= 1;
+                } else {
+                    styles.pop();
+                }
+            }
+            XmlEvent::EndDocument => return Ok(batch),
+            _ => {}
+        }
+    }
+}
+
+/// The transform flipping the y axis of the root `svg` element, within its
+/// view box, or its height if it has no view box.
+fn flip(attrs: &[OwnedAttribute]) -> Result<Transform, Error> {
+    let (y, h) = match attr(attrs, "viewBox") {
+        Some(view_box) => {
+            let mut tokens = Tokens::new(view_box);
+            let (_, y, _, h) = (
+                tokens.number()?,
+                tokens.number()?,
+                tokens.number()?,
+                tokens.number()?,
+            );
+            (y, h)
+        }
+        None => (0., attr(attrs, "height").map_or(Ok(0.), length)?),
+    };
+    Ok([1., 0., 0., -1., 0., 2. * y + h])
+}
+
+/// Add the paths of an element to the batch, with the given style.
+fn add(batch: &mut Batch, paths: Vec<Path>, style: &Style) -> Result<(), Error> {
+    let t = style.transform;
+    let apply = |p: Point2<f32>| {
+        Point2::new(
+            t[0] * p.x + t[2] * p.y + t[4],
+            t[1] * p.x + t[3] * p.y + t[5],
+        )
+    };
+    let paths = paths
+        .into_iter()
+        .map(|path| Path {
+            start: apply(path.start),
+            segments: path
+                .segments
+                .iter()
+                .map(|s| match *s {
+                    Segment::Line(p) => Segment::Line(apply(p)),
+                    Segment::Quadratic(c, p) => Segment::Quadratic(apply(c), apply(p)),
+                    Segment::Cubic(c1, c2, p) => Segment::Cubic(apply(c1), apply(c2), apply(p)),
+                })
+                .collect(),
+            closed: path.closed,
+        })
+        .collect::<Vec<_>>();
+    let fill = style
+        .fill
+        .map(|c| Rgba::new(c.r, c.g, c.b, c.a * style.fill_opacity * style.opacity));
+    // Strokes are scaled with the transform, by its average scale.
+    let width = style.stroke_width * (t[0] * t[3] - t[1] * t[2]).abs().sqrt();
+    let stroke = match style.stroke {
+        Some(c) if width > 0. => Stroke::new(
+            width,
+            Rgba::new(c.r, c.g, c.b, c.a * style.stroke_opacity * style.opacity),
+        ),
+        _ => Stroke::NONE,
+    };
+
+    // Subpaths are filled together, and only stroked one by one.
+    #[cfg(feature = "lyon")]
+    let fill = match fill {
+        Some(color) if !paths.is_empty() => {
+            use lyon_tessellation::FillRule;
+
+            let rule = if style.even_odd {
+                FillRule::EvenOdd
+            } else {
+                FillRule::NonZero
+            };
+            batch
+                .fill_lyon(
+                    &lyon::from_paths(&paths),
+                    &lyon::FillOptions::default().with_fill_rule(rule),
+                    ZDepth::ZERO,
+                    color,
+                )
+                .map_err(|_| Error::InvalidSvg)?;
+            None
+        }
+        fill => fill,
+    };
+    let fill = fill.map_or(Fill::Empty(), Fill::Solid);
+
+    if !matches!(fill, Fill::Empty()) || stroke != Stroke::NONE {
+        for path in paths {
+            batch.add(Shape::Path(
+                path,
+                ZDepth::ZERO,
+                Rotation::ZERO,
+                stroke.clone(),
+                fill.clone(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Style
+///////////////////////////////////////////////////////////////////////////
+
+/// An affine transform, as the `a b c d e f` parameters of an SVG matrix.
+type Transform = [f32; 6];
+
+const IDENTITY: Transform = [1., 0., 0., 1., 0., 0.];
+
+/// Style of an element, inherited from its parents.
+#[derive(Copy, Clone, Debug)]
+struct Style {
+    fill: Option<Rgba>,
+    stroke: Option<Rgba>,
+    stroke_width: f32,
+    fill_opacity: f32,
+    stroke_opacity: f32,
+    /// Opacity of the element, multiplied by that of its parents.
+    opacity: f32,
+    transform: Transform,
+    /// Whether the fill rule is `evenodd`, rather than `nonzero`.
+    #[cfg(feature = "lyon")]
+    even_odd: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            fill: Some(Rgba::BLACK),
+            stroke: None,
+            stroke_width: 1.,
+            fill_opacity: 1.,
+            stroke_opacity: 1.,
+            opacity: 1.,
+            transform: IDENTITY,
+            #[cfg(feature = "lyon")]
+            even_odd: false,
+        }
+    }
+}
+
+impl Style {
+    /// The style of an element with the given attributes and parent style.
+    /// Properties which can't be parsed keep their inherited value, except
+    /// for transforms, which are attributes.
+    fn parse(attrs: &[OwnedAttribute], parent: Style) -> Result<Self, Error> {
+        let mut style = parent;
+
+        if let Some(fill) = prop(attrs, "fill") {
+            style.fill = paint(&fill).unwrap_or(parent.fill);
+        }
+        if let Some(stroke) = prop(attrs, "stroke") {
+            style.stroke = paint(&stroke).unwrap_or(parent.stroke);
+        }
+        if let Some(width) = prop(attrs, "stroke-width") {
+            style.stroke_width = length(&width).unwrap_or(parent.stroke_width);
+        }
+        if let Some(o) = prop(attrs, "fill-opacity") {
+            style.fill_opacity = number(&o).unwrap_or(parent.fill_opacity);
+        }
+        if let Some(o) = prop(attrs, "stroke-opacity") {
+            style.stroke_opacity = number(&o).unwrap_or(parent.stroke_opacity);
+        }
+        if let Some(o) = prop(attrs, "opacity") {
+            style.opacity *= number(&o).unwrap_or(1.);
+        }
+        #[cfg(feature = "lyon")]
+        match prop(attrs, "fill-rule").as_deref() {
+            Some("evenodd") => style.even_odd = true,
+            Some("nonzero") => style.even_odd = false,
+            _ => {}
+        }
+        if let Some(t) = attr(attrs, "transform") {
+            style.transform = multiply(parent.transform, transform(t)?);
+        }
+        Ok(style)
+    }
+}
+
+/// The value of a presentation attribute, or of the same property in the
+/// `style` attribute, which takes precedence.
+fn prop(attrs: &[OwnedAttribute], name: &str) -> Option<String> {
+    attr(attrs, "style")
+        .and_then(|style| {
+            style.split(';').find_map(|decl| {
+                let mut kv = decl.splitn(2, ':');
+                match (kv.next(), kv.next()) {
+                    (Some(k), Some(v)) if k.trim() == name => Some(v.trim().to_owned()),
+                    _ => None,
+                }
+            })
+        })
+        .or_else(|| attr(attrs, name).map(|v| v.trim().to_owned()))
+}
+
+fn attr<'a>(attrs: &'a [OwnedAttribute], name: &str) -> Option<&'a str> {
+    attrs
+        .iter()
+        .find(|a| a.name.local_name == name)
+        .map(|a| a.value.as_str())
+}
+
+fn number(s: &str) -> Result<f32, Error> {
+    f32::from_str(s.trim()).map_err(|_| Error::InvalidSvg)
+}
+
+/// A length in user units, with an optional `px` unit.
+fn length(s: &str) -> Result<f32, Error> {
+    let s = s.trim();
+    number(s.strip_suffix("px").unwrap_or(s))
+}
+
+/// A length attribute, zero if it's missing.
+fn length_attr(attrs: &[OwnedAttribute], name: &str) -> Result<f32, Error> {
+    attr(attrs, name).map_or(Ok(0.), length)
+}
+
+/// A paint, which is `None` when there's none. Gradients and patterns,
+/// referenced by url, aren't supported and aren't painted. Returns an error
+/// for paints which can't be parsed, and for `inherit`, for the inherited
+/// paint to be used instead.
+fn paint(s: &str) -> Result<Option<Rgba>, Error> {
+    let rgb = |r: u8, g: u8, b: u8| Ok(Some(Rgba::from(Rgba8::new(r, g, b, 0xff))));
+    // The arguments of a color function, given its name and opening
+    // parenthesis, eg. `rgb(`.
+    let args = |name: &str| {
+        s.strip_prefix(name)
+            .and_then(|s| s.strip_suffix(')'))
+            .map(|args| {
+                args.split([',', '/', ' '])
+                    .filter(|a| !a.is_empty())
+                    .collect::<Vec<_>>()
+            })
+    };
+    // A number, or a percentage of the given maximum.
+    let value = |s: &str, max: f32| match s.strip_suffix('%') {
+        Some(p) => number(p).map(|p| p / 100. * max),
+        None => number(s),
+    };
+    let alpha = |c: Option<Rgba>, a: Option<&&str>| match (c, a) {
+        (Some(c), Some(a)) => Ok(Some(Rgba::new(c.r, c.g, c.b, value(a, 1.)?.clamp(0., 1.)))),
+        (c, _) => Ok(c),
+    };
+
+    match s {
+        "none" => Ok(None),
+        "transparent" => Ok(Some(Rgba::TRANSPARENT)),
+        "black" | "currentColor" => rgb(0, 0, 0),
+        "white" => rgb(255, 255, 255),
+        "red" => rgb(255, 0, 0),
+        "green" => rgb(0, 128, 0),
+        "lime" => rgb(0, 255, 0),
+        "blue" => rgb(0, 0, 255),
+        "yellow" => rgb(255, 255, 0),
+        "orange" => rgb(255, 165, 0),
+        "purple" => rgb(128, 0, 128),
+        "gray" | "grey" => rgb(128, 128, 128),
+        "silver" => rgb(192, 192, 192),
+        "maroon" => rgb(128, 0, 0),
+        "olive" => rgb(128, 128, 0),
+        "navy" => rgb(0, 0, 128),
+        "teal" => rgb(0, 128, 128),
+        "aqua" | "cyan" => rgb(0, 255, 255),
+        "fuchsia" | "magenta" => rgb(255, 0, 255),
+        _ if s.starts_with("url(") => Ok(None),
+        _ if s.starts_with('#') => {
+            let hex = &s[1..];
+            let digit = |i: usize| {
+                u8::from_str_radix(hex.get(i..=i).ok_or(Error::InvalidSvg)?, 16)
+                    .map_err(|_| Error::InvalidSvg)
+            };
+            match hex.len() {
+                3 => rgb(digit(0)? * 17, digit(1)? * 17, digit(2)? * 17),
+                6 => rgb(
+                    digit(0)? * 16 + digit(1)?,
+                    digit(2)? * 16 + digit(3)?,
+                    digit(4)? * 16 + digit(5)?,
+                ),
+                _ => Err(Error::InvalidSvg),
+            }
+        }
+        _ => {
+            if let Some(args) = args("rgb(").or_else(|| args("rgba(")) {
+                let channel = |c: &str| value(c, 255.).map(|c| c.round().clamp(0., 255.) as u8);
+
+                match args[..] {
+                    [r, g, b, ..] if args.len() <= 4 => {
+                        alpha(rgb(channel(r)?, channel(g)?, channel(b)?)?, args.get(3))
+                    }
+                    _ => Err(Error::InvalidSvg),
+                }
+            } else if let Some(args) = args("hsl(").or_else(|| args("hsla(")) {
+                match args[..] {
+                    [h, sat, l, ..] if args.len() <= 4 => {
+                        let h = number(h.strip_suffix("deg").unwrap_or(h))?;
+                        let (sat, l) = (value(sat, 1.)?.clamp(0., 1.), value(l, 1.)?.clamp(0., 1.));
+                        let (r, g, b) = hsl(h, sat, l);
+
+                        alpha(Some(Rgba::new(r, g, b, 1.)), args.get(3))
+                    }
+                    _ => Err(Error::InvalidSvg),
+                }
+            } else {
+                Err(Error::InvalidSvg)
+            }
+        }
+    }
+}
+
+/// Convert a color from hue, in degrees, saturation and lightness, to red,
+/// green and blue.
+fn hsl(h: f32, s: f32, l: f32) -> (f32, f32, f32) {
+    let k = |n: f32| (n + h / 30.).rem_euclid(12.);
+    let a = s * l.min(1. - l);
+    let f = |n: f32| l - a * (k(n) - 3.).min(9. - k(n)).clamp(-1., 1.);
+
+    (f(0.), f(8.), f(4.))
+}
+
+/// The product of two transforms, applying `n` first.
+fn multiply(m: Transform, n: Transform) -> Transform {
+    [
+        m[0] * n[0] + m[2] * n[1],
+        m[1] * n[0] + m[3] * n[1],
+        m[0] * n[2] + m[2] * n[3],
+        m[1] * n[2] + m[3] * n[3],
+        m[0] * n[4] + m[2] * n[5] + m[4],
+        m[1] * n[4] + m[3] * n[5] + m[5],
+    ]
+}
+
+/// Parse a transform list, eg. `translate(8 8) rotate(45)`.
+fn transform(s: &str) -> Result<Transform, Error> {
+    let mut result = IDENTITY;
+    let mut rest = s.trim();
+
+    while !rest.is_empty() {
+        let open = rest.find('(').ok_or(Error::InvalidSvg)?;
+        let close = rest.find(')').ok_or(Error::InvalidSvg)?;
+        let name = rest[..open].trim_matches(|c: char| c.is_whitespace() || c == ',');
+        let mut tokens = Tokens::new(&rest[open + 1..close]);
+        let mut args = Vec::new();
+
+        while tokens.has_number() {
+            args.push(tokens.number()?);
+        }
+        let t = match (name, &args[..]) {
+            ("matrix", &[a, b, c, d, e, f]) => [a, b, c, d, e, f],
+            ("translate", &[x]) => [1., 0., 0., 1., x, 0.],
+            ("translate", &[x, y]) => [1., 0., 0., 1., x, y],
+            ("scale", &[s]) => [s, 0., 0., s, 0., 0.],
+            ("scale", &[x, y]) => [x, 0., 0., y, 0., 0.],
+            ("rotate", &[a]) => rotation(a),
+            ("rotate", &[a, x, y]) => multiply(
+                multiply([1., 0., 0., 1., x, y], rotation(a)),
+                [1., 0., 0., 1., -x, -y],
+            ),
+            ("skewX", &[a]) => [1., 0., a.to_radians().tan(), 1., 0., 0.],
+            ("skewY", &[a]) => [1., a.to_radians().tan(), 0., 1., 0., 0.],
+            _ => return Err(Error::InvalidSvg),
+        };
+        result = multiply(result, t);
+        rest = rest[close + 1..].trim_start();
+    }
+    Ok(result)
+}
+
+/// A rotation by the given angle, in degrees, clockwise on screen.
+fn rotation(degrees: f32) -> Transform {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    [cos, sin, -sin, cos, 0., 0.]
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Paths
+///////////////////////////////////////////////////////////////////////////
+
+/// The paths of a shape element.
+fn paths(element: &str, attrs: &[OwnedAttribute]) -> Result<Vec<Path>, Error> {
+    let len = |name| length_attr(attrs, name);
+
+    match element {
+        "path" => path_data(attr(attrs, "d").unwrap_or_default()),
+        "rect" => {
+            let (x, y, w, h) = (len("x")?, len("y")?, len("width")?, len("height")?);
+            let (rx, ry) = match (attr(attrs, "rx"), attr(attrs, "ry")) {
+                (None, None) => (0., 0.),
+                (Some(r), None) | (None, Some(r)) => (length(r)?, length(r)?),
+                (Some(rx), Some(ry)) => (length(rx)?, length(ry)?),
+            };
+            if w <= 0. || h <= 0. {
+                return Ok(Vec::new());
+            }
+            Ok(vec![rect(x, y, w, h, rx.min(w / 2.), ry.min(h / 2.))])
+        }
+        "circle" => {
+            let r = len("r")?;
+            Ok(vec![ellipse(len("cx")?, len("cy")?, r, r)])
+        }
+        "ellipse" => Ok(vec![ellipse(
+            len("cx")?,
+            len("cy")?,
+            len("rx")?,
+            len("ry")?,
+        )]),
+        "line" => Ok(vec![Path::new(Point2::new(len("x1")?, len("y1")?))
+            .line_to(Point2::new(len("x2")?, len("y2")?))]),
+        _ => {
+            let mut tokens = Tokens::new(attr(attrs, "points").unwrap_or_default());
+            let mut points = Vec::new();
+
+            while tokens.has_number() {
+                points.push(tokens.point(Point2::new(0., 0.))?);
+            }
+            let path = match points.split_first() {
+                Some((&start, rest)) if !rest.is_empty() => rest
+                    .iter()
+                    .fold(Path::new(start), |path, &p| path.line_to(p)),
+                _ => return Ok(Vec::new()),
+            };
+            Ok(vec![if element == "polygon" {
+                path.close()
+            } else {
+                path
+            }])
+        }
+    }
+}
+
+/// A rectangle, with corners of the given radii.
+fn rect(x: f32, y: f32, w: f32, h: f32, rx: f32, ry: f32) -> Path {
+    let (x2, y2) = (x + w, y + h);
+
+    if rx <= 0. || ry <= 0. {
+        return Path::new(Point2::new(x, y))
+            .line_to(Point2::new(x2, y))
+            .line_to(Point2::new(x2, y2))
+            .line_to(Point2::new(x, y2))
+            .close();
+    }
+    let (kx, ky) = (rx * (1. - KAPPA), ry * (1. - KAPPA));
+
+    Path::new(Point2::new(x + rx, y))
+        .line_to(Point2::new(x2 - rx, y))
+        .cubic_to(
+            Point2::new(x2 - kx, y),
+            Point2::new(x2, y + ky),
+            Point2::new(x2, y + ry),
+        )
+        .line_to(Point2::new(x2, y2 - ry))
+        .cubic_to(
+            Point2::new(x2, y2 - ky),
+            Point2::new(x2 - kx, y2),
+            Point2::new(x2 - rx, y2),
+        )
+        .line_to(Point2::new(x + rx, y2))
+        .cubic_to(
+            Point2::new(x + kx, y2),
+            Point2::new(x, y2 - ky),
+            Point2::new(x, y2 - ry),
+        )
+        .line_to(Point2::new(x, y + ry))
+        .cubic_to(
+            Point2::new(x, y + ky),
+            Point2::new(x + kx, y),
+            Point2::new(x + rx, y),
+        )
+        .close()
+}
+
+/// An ellipse, as four cubic curves.
+fn ellipse(cx: f32, cy: f32, rx: f32, ry: f32) -> Path {
+    let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+
+    Path::new(Point2::new(cx + rx, cy))
+        .cubic_to(
+            Point2::new(cx + rx, cy + ky),
+            Point2::new(cx + kx, cy + ry),
+            Point2::new(cx, cy + ry),
+        )
+        .cubic_to(
+            Point2::new(cx - kx, cy + ry),
+            Point2::new(cx - rx, cy + ky),
+            Point2::new(cx - rx, cy),
+        )
+        .cubic_to(
+            Point2::new(cx - rx, cy - ky),
+            Point2::new(cx - kx, cy - ry),
+            Point2::new(cx, cy - ry),
+        )
+        .cubic_to(
+            Point2::new(cx + kx, cy - ry),
+            Point2::new(cx + rx, cy - ky),
+            Point2::new(cx + rx, cy),
+        )
+        .close()
+}
+
+/// Parse path data, into one path per subpath.
+fn path_data(d: &str) -> Result<Vec<Path>, Error> {
+    let mut tokens = Tokens::new(d);
+    let mut paths = Vec::new();
+    let mut path: Option<Path> = None;
+    // Current point, start of the current subpath, and last control point,
+    // which smooth curves reflect.
+    let origin = Point2::new(0., 0.);
+    let (mut p, mut start, mut ctrl) = (origin, origin, origin);
+    let mut prev = None;
+
+    loop {
+        let cmd = match (tokens.command(), prev) {
+            (Some(cmd), _) => cmd,
+            (None, _) if tokens.is_empty() => break,
+            // Repeated commands, where coordinates after a move are lines.
+            (None, Some(b'M')) => b'L',
+            (None, Some(b'm')) => b'l',
+            (None, Some(cmd)) if !matches!(cmd, b'Z' | b'z') => cmd,
+            _ => return Err(Error::InvalidSvg),
+        };
+        let o = if cmd.is_ascii_lowercase() { p } else { origin };
+        let segment = |path: &mut Option<Path>, s| {
+            path.get_or_insert_with(|| Path::new(p)).segments.push(s);
+        };
+
+        match cmd.to_ascii_uppercase() {
+            b'M' => {
+                p = tokens.point(o)?;
+                paths.extend(path.replace(Path::new(p)));
+                start = p;
+                ctrl = p;
+            }
+            b'L' | b'H' | b'V' => {
+                let q = match cmd.to_ascii_uppercase() {
+                    b'L' => tokens.point(o)?,
+                    b'H' => Point2::new(o.x + tokens.number()?, p.y),
+                    _ => Point2::new(p.x, o.y + tokens.number()?),
+                };
+                segment(&mut path, Segment::Line(q));
+                p = q;
+                ctrl = q;
+            }
+            b'C' | b'S' => {
+                let c1 = if cmd.eq_ignore_ascii_case(&b'C') {
+                    tokens.point(o)?
+                } else if matches!(prev, Some(b'C' | b'c' | b'S' | b's')) {
+                    Point2::new(2. * p.x - ctrl.x, 2. * p.y - ctrl.y)
+                } else {
+                    p
+                };
+                let c2 = tokens.point(o)?;
+                let q = tokens.point(o)?;
+
+                segment(&mut path, Segment::Cubic(c1, c2, q));
+                p = q;
+                ctrl = c2;
+            }
+            b'Q' | b'T' => {
+                let c = if cmd.eq_ignore_ascii_case(&b'Q') {
+                    tokens.point(o)?
+                } else if matches!(prev, Some(b'Q' | b'q' | b'T' | b't')) {
+                    Point2::new(2. * p.x - ctrl.x, 2. * p.y - ctrl.y)
+                } else {
+                    p
+                };
+                let q = tokens.point(o)?;
+
+                segment(&mut path, Segment::Quadratic(c, q));
+                p = q;
+                ctrl = c;
+            }
+            b'A' => {
+                let (rx, ry, angle) = (tokens.number()?, tokens.number()?, tokens.number()?);
+                let (large, sweep) = (tokens.flag()?, tokens.flag()?);
+                let q = tokens.point(o)?;
+
+                for s in arc(p, rx, ry, angle, large, sweep, q) {
+                    segment(&mut path, s);
+                }
+                p = q;
+                ctrl = q;
+            }
+            b'Z' => {
+                if let Some(mut closed) = path.take() {
+                    closed.closed = true;
+                    paths.push(closed);
+                }
+                p = start;
+                ctrl = start;
+            }
+            _ => return Err(Error::InvalidSvg),
+        }
+        prev = Some(cmd);
+    }
+    paths.extend(path);
+    paths.retain(|path| !path.segments.is_empty());
+
+    Ok(paths)
+}
+
+/// An elliptical arc, as cubic curves of at most a quarter turn each. See
+/// the implementation notes of the SVG specification.
+#[allow(clippy::too_many_arguments)]
+fn arc(
+    from: Point2<f32>,
+    rx: f32,
+    ry: f32,
+    angle: f32,
+    large: bool,
+    sweep: bool,
+    to: Point2<f32>,
+) -> Vec<Segment> {
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+
+    if from == to {
+        return Vec::new();
+    }
+    if rx == 0. || ry == 0. {
+        return vec![Segment::Line(to)];
+    }
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let (dx, dy) = ((from.x - to.x) / 2., (from.y - to.y) / 2.);
+    let (x1, y1) = (cos * dx + sin * dy, cos * dy - sin * dx);
+
+    // Scale the radii up if they're too small to reach the end point.
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1. {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+    let (rx2, ry2) = (rx * rx, ry * ry);
+    let num = rx2 * ry2 - rx2 * y1 * y1 - ry2 * x1 * x1;
+    let den = rx2 * y1 * y1 + ry2 * x1 * x1;
+    let sign = if large == sweep { -1. } else { 1. };
+    let k = sign * (num / den).max(0.).sqrt();
+    let (cx1, cy1) = (k * rx * y1 / ry, -k * ry * x1 / rx);
+    let (cx, cy) = (
+        cos * cx1 - sin * cy1 + (from.x + to.x) / 2.,
+        sin * cx1 + cos * cy1 + (from.y + to.y) / 2.,
+    );
+
+    let between = |ux: f32, uy: f32, vx: f32, vy: f32| (ux * vy - uy * vx).atan2(ux * vx + uy * vy);
+    let theta = between(1., 0., (x1 - cx1) / rx, (y1 - cy1) / ry);
+    let mut delta = between(
+        (x1 - cx1) / rx,
+        (y1 - cy1) / ry,
+        (-x1 - cx1) / rx,
+        (-y1 - cy1) / ry,
+    );
+    if !sweep && delta > 0. {
+        delta -= 2. * f32::consts::PI;
+    } else if sweep && delta < 0. {
+        delta += 2. * f32::consts::PI;
+    }
+
+    let n = (delta.abs() / f32::consts::FRAC_PI_2).ceil().max(1.) as usize;
+    let step = delta / n as f32;
+    let t = 4. / 3. * (step / 4.).tan();
+    let point = |a: f32| {
+        let (s, c) = a.sin_cos();
+        Point2::new(
+            cx + rx * c * cos - ry * s * sin,
+            cy + rx * c * sin + ry * s * cos,
+        )
+    };
+    let tangent = |a: f32| {
+        let (s, c) = a.sin_cos();
+        (-rx * s * cos - ry * c * sin, -rx * s * sin + ry * c * cos)
+    };
+
+    (0..n)
+        .map(|i| {
+            let (a1, a2) = (theta + step * i as f32, theta + step * (i + 1) as f32);
+            let (p1, p2) = (point(a1), if i + 1 == n { to } else { point(a2) });
+            let (d1, d2) = (tangent(a1), tangent(a2));
+
+            Segment::Cubic(
+                Point2::new(p1.x + t * d1.0, p1.y + t * d1.1),
+                Point2::new(p2.x - t * d2.0, p2.y - t * d2.1),
+                p2,
+            )
+        })
+        .collect()
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Tokens
+///////////////////////////////////////////////////////////////////////////
+
+/// Tokenizer of path data and other lists of numbers.
+struct Tokens<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input: input.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    /// Skip whitespace and commas.
+    fn skip(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r' | b',') = self.input.get(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn is_empty(&mut self) -> bool {
+        self.skip();
+        self.pos >= self.input.len()
+    }
+
+    fn has_number(&mut self) -> bool {
+        self.skip();
+        matches!(
+            self.input.get(self.pos),
+            Some(b'0'..=b'9' | b'.' | b'-' | b'+')
+        )
+    }
+
+    /// The next path command, if the next token is one.
+    fn command(&mut self) -> Option<u8> {
+        self.skip();
+        match self.input.get(self.pos) {
+            Some(&c) if c.is_ascii_alphabetic() => {
+                self.pos += 1;
+                Some(c)
+            }
+            _ => None,
+        }
+    }
+
+    fn number(&mut self) -> Result<f32, Error> {
+        self.skip();
+        let start = self.pos;
+        let digits = |t: &mut Self| {
+            while let Some(b'0'..=b'9') = t.input.get(t.pos) {
+                t.pos += 1;
+            }
+        };
+
+        if let Some(b'-' | b'+') = self.input.get(self.pos) {
+            self.pos += 1;
+        }
+        digits(self);
+        if let Some(b'.') = self.input.get(self.pos) {
+            self.pos += 1;
+            digits(self);
+        }
+        if let Some(b'e' | b'E') = self.input.get(self.pos) {
+            self.pos += 1;
+            if let Some(b'-' | b'+') = self.input.get(self.pos) {
+                self.pos += 1;
+            }
+            digits(self);
+        }
+        std::str::from_utf8(&self.input[start..self.pos])
+            .ok()
+            .and_then(|s| f32::from_str(s).ok())
+            .ok_or(Error::InvalidSvg)
+    }
+
+    /// A point, relative to the given origin.
+    fn point(&mut self, origin: Point2<f32>) -> Result<Point2<f32>, Error> {
+        let x = self.number()?;
+        let y = self.number()?;
+
+        Ok(Point2::new(origin.x + x, origin.y + y))
+    }
+
+    /// An arc flag, which can be followed by the next token without a
+    /// separator.
+    fn flag(&mut self) -> Result<bool, Error> {
+        self.skip();
+        let flag = match self.input.get(self.pos) {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(Error::InvalidSvg),
+        };
+        self.pos += 1;
+
+        Ok(flag)
+    }
+}
@@ -0,0 +1,205 @@
+use crate::kit::camera::Camera2D;
+use crate::kit::gizmo::Guide;
+use crate::math::*;
+use crate::rect::Rect;
+
+///////////////////////////////////////////////////////////////////////////
+// Target
+///////////////////////////////////////////////////////////////////////////
+
+/// What a coordinate was snapped to.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Target {
+    /// The guide at the given index.
+    Guide(usize),
+    /// A grid line.
+    Grid,
+    /// An edge or the center of the object at the given index.
+    Edge(usize),
+}
+
+/// The result of a snapping query, with the snap target on each axis.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Snapped {
+    pub point: Point2<f32>,
+    pub x: Option<Target>,
+    pub y: Option<Target>,
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Snapper
+///////////////////////////////////////////////////////////////////////////
+
+/// Snaps points and rectangles to guides, a grid and the edges of other
+/// objects, eg. while dragging.
+///
+/// Coordinates are in world space, while the tolerance is in screen
+/// pixels: queries take the current zoom factor, so that snapping feels
+/// the same at any zoom level. The `_screen` variants take screen positions
+/// and a camera instead. Guides take precedence over objects, which take
+/// precedence over the grid.
+#[derive(Clone, Debug)]
+pub struct Snapper {
+    pub guides: Vec<Guide>,
+    /// Grid spacing, if grid snapping is enabled.
+    pub grid: Option<f32>,
+    /// Bounds of the objects to snap to.
+    pub objects: Vec<Rect<f32>>,
+    /// Maximum snapping distance, in screen pixels.
+    pub tolerance: f32,
+}
+
+impl Snapper {
+    pub fn new(tolerance: f32) -> Self {
+        Self {
+            guides: Vec::new(),
+            grid: None,
+            objects: Vec::new(),
+            tolerance,
+        }
+    }
+
+    pub fn grid(mut self, spacing: f32) -> Self {
+        self.grid = Some(spacing);
+        self
+    }
+
+    /// Snap a point. Each axis is snapped independently.
+    pub fn snap(&self, p: Point2<f32>, zoom: f32) -> Snapped {
+        let tolerance = self.tolerance / zoom;
+        let x = self.snap_axis(&[p.x], Axis::X, tolerance);
+        let y = self.snap_axis(&[p.y], Axis::Y, tolerance);
+
+        Snapped {
+            point: Point2::new(
+                p.x + x.map_or(0., |(d, _)| d),
+                p.y + y.map_or(0., |(d, _)| d),
+            ),
+            x: x.map(|(_, t)| t),
+            y: y.map(|(_, t)| t),
+        }
+    }
+
+    /// Snap a rectangle being dragged, by its edges or its center. Returns
+    /// the snapped position of the rectangle's first corner, `(x1, y1)`.
+    pub fn snap_rect(&self, r: Rect<f32>, zoom: f32) -> Snapped {
+        let tolerance = self.tolerance / zoom;
+        let xs = [r.x1, (r.x1 + r.x2) / 2., r.x2];
+        let ys = [r.y1, (r.y1 + r.y2) / 2., r.y2];
+        let x = self.snap_axis(&xs, Axis::X, tolerance);
+        let y = self.snap_axis(&ys, Axis::Y, tolerance);
+
+        Snapped {
+            point: Point2::new(
+                r.x1 + x.map_or(0., |(d, _)| d),
+                r.y1 + y.map_or(0., |(d, _)| d),
+            ),
+            x: x.map(|(_, t)| t),
+            y: y.map(|(_, t)| t),
+        }
+    }
+
+    /// Snap a screen position, eg. of the cursor, seen through the given
+    /// camera. The position is snapped in world space, with the tolerance
+    /// in screen pixels, and the snapped point is returned in screen space.
+    ///
+    /// ```
+    /// use rgx::kit::gizmo::Guide;
+    /// use rgx::kit::snap::{Snapper, Target};
+    /// use rgx::kit::Camera2D;
+    /// use rgx::math::Point2;
+    ///
+    /// let mut camera = Camera2D::new(100, 100);
+    /// camera.look_at(Point2::new(10., 10.));
+    /// camera.zoom = 2.;
+    ///
+    /// let mut snapper = Snapper::new(4.);
+    /// snapper.guides.push(Guide::Vertical(10.));
+    ///
+    /// // Three pixels away from the guide, which is at the center.
+    /// let snapped = snapper.snap_screen(Point2::new(53., 70.), &camera);
+    /// assert_eq!(snapped.point, Point2::new(50., 70.));
+    /// assert_eq!(snapped.x, Some(Target::Guide(0)));
+    ///
+    /// // Five pixels away, out of tolerance.
+    /// assert_eq!(snapper.snap_screen(Point2::new(55., 70.), &camera).x, None);
+    /// ```
+    pub fn snap_screen(&self, p: Point2<f32>, camera: &Camera2D) -> Snapped {
+        let snapped = self.snap(camera.screen_to_world(p), camera.zoom);
+
+        Snapped {
+            point: camera.world_to_screen(snapped.point),
+            ..snapped
+        }
+    }
+
+    /// Snap a rectangle being dragged, given in screen space and seen through
+    /// the given camera. Returns the snapped position of the rectangle's
+    /// first corner, in screen space. See `Snapper::snap_screen`.
+    pub fn snap_rect_screen(&self, r: Rect<f32>, camera: &Camera2D) -> Snapped {
+        let a = camera.screen_to_world(Point2::new(r.x1, r.y1));
+        let b = camera.screen_to_world(Point2::new(r.x2, r.y2));
+        let snapped = self.snap_rect(Rect::new(a.x, a.y, b.x, b.y), camera.zoom);
+
+        Snapped {
+            point: camera.world_to_screen(snapped.point),
+            ..snapped
+        }
+    }
+
+    /// Find the closest snap target for any of the given coordinates, and
+    /// return the offset to snap to it.
+    fn snap_axis(&self, coords: &[f32], axis: Axis, tolerance: f32) -> Option<(f32, Target)> {
+        let closest = |candidates: &mut dyn Iterator<Item = (f32, Target)>| {
+            let mut best: Option<(f32, Target)> = None;
+
+            for (target, t) in candidates {
+                for c in coords {
+                    let d = target - c;
+                    let closer = match best {
+                        Some((b, _)) => d.abs() < b.abs(),
+                        None => true,
+                    };
+                    if d.abs() <= tolerance && closer {
+                        best = Some((d, t));
+                    }
+                }
+            }
+            best
+        };
+
+        let mut guides = self
+            .guides
+            .iter()
+            .enumerate()
+            .filter_map(|(i, g)| match (g, axis) {
+                (Guide::Vertical(x), Axis::X) => Some((*x, Target::Guide(i))),
+                (Guide::Horizontal(y), Axis::Y) => Some((*y, Target::Guide(i))),
+                _ => None,
+            });
+        let mut edges = self.objects.iter().enumerate().flat_map(|(i, r)| {
+            let (a, b) = match axis {
+                Axis::X => (r.x1, r.x2),
+                Axis::Y => (r.y1, r.y2),
+            };
+            vec![a, (a + b) / 2., b]
+                .into_iter()
+                .map(move |e| (e, Target::Edge(i)))
+        });
+        let mut grid = coords.iter().filter_map(|c| {
+            self.grid
+                .filter(|s| *s > 0.)
+                .map(|s| ((c / s).round() * s, Target::Grid))
+        });
+
+        closest(&mut guides)
+            .or_else(|| closest(&mut edges))
+            .or_else(|| closest(&mut grid))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Axis {
+    X,
+    Y,
+}
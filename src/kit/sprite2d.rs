@@ -1,12 +1,16 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
 use nonempty::NonEmpty;
 
 use crate::core;
-use crate::core::{Binding, BindingType, Rgba, Set, ShaderStage};
+use crate::core::{Binding, BindingType, Error, Rgba, Set, ShaderStage};
 use crate::kit::ZDepth;
 use crate::rect::Rect;
 
 use crate::math::*;
 
+use crate::kit::transform::{self, TransformStack};
 use crate::kit::{Model, Repeat, Rgba8};
 
 ///////////////////////////////////////////////////////////////////////////
@@ -27,14 +31,14 @@ pub struct Uniforms {
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
 pub struct Vertex {
-    position: Vector3<f32>,
-    uv: Vector2<f32>,
-    color: Rgba8,
-    opacity: f32,
+    pub(crate) position: Vector3<f32>,
+    pub(crate) uv: Vector2<f32>,
+    pub(crate) color: Rgba8,
+    pub(crate) opacity: f32,
 }
 
 impl Vertex {
-    fn new(x: f32, y: f32, z: f32, u: f32, v: f32, color: Rgba8, opacity: f32) -> Self {
+    pub(crate) fn new(x: f32, y: f32, z: f32, u: f32, v: f32, color: Rgba8, opacity: f32) -> Self {
         Self {
             position: Vector3::new(x, y, z),
             uv: Vector2::new(u, v),
@@ -70,7 +74,11 @@ impl Pipeline {
 
 //////////////////////////////////////////////////////////////////////////
 
-pub struct Command<'a>(&'a core::VertexBuffer, &'a core::BindingGroup, Matrix4<f32>);
+pub struct Command<'a>(
+    &'a core::IndexedBuffer,
+    &'a core::BindingGroup,
+    Matrix4<f32>,
+);
 
 pub struct Frame<'a> {
     commands: Vec<Command<'a>>,
@@ -78,7 +86,7 @@ pub struct Frame<'a> {
 }
 
 impl<'a> Frame<'a> {
-    pub fn draw(&mut self, buffer: &'a core::VertexBuffer, binding: &'a core::BindingGroup) {
+    pub fn draw(&mut self, buffer: &'a core::IndexedBuffer, binding: &'a core::BindingGroup) {
         self.commands
             .push(Command(buffer, binding, *self.transforms.last()));
     }
@@ -168,6 +176,10 @@ impl<'a> core::AbstractPipeline<'a> for Pipeline {
         pass.set_binding(&self.model.binding, &[]);
     }
 
+    fn projection(&self) -> Option<&core::UniformBuffer> {
+        Some(&self.buf)
+    }
+
     fn prepare(
         &'a self,
         ortho: Matrix4<f32>,
@@ -188,6 +200,11 @@ pub struct Batch {
     pub size: usize,
 
     items: Vec<(Rect<f32>, Rect<f32>, ZDepth, Rgba, f32, Repeat)>,
+    /// Transform of each item, if any.
+    item_transforms: Vec<Option<Matrix4<f32>>>,
+    /// Indices of the emissive items, in increasing order.
+    emissive: Vec<usize>,
+    transforms: TransformStack,
 }
 
 impl Batch {
@@ -196,6 +213,9 @@ impl Batch {
             w,
             h,
             items: Vec::new(),
+            item_transforms: Vec::new(),
+            emissive: Vec::new(),
+            transforms: TransformStack::new(),
             size: 0,
         }
     }
@@ -233,13 +253,148 @@ impl Batch {
             );
         }
         self.items.push((src, dst, depth, rgba, opacity, rep));
+        self.item_transforms.push(if self.transforms.is_empty() {
+            None
+        } else {
+            Some(self.transforms.current())
+        });
+        self.size += 1;
+    }
+
+    /// Add a sprite tagged as emissive, eg. to be bloomed. Emissive sprites
+    /// are drawn with the rest of the batch, and can also be drawn on their
+    /// own with `Batch::emissive`.
+    pub fn add_emissive(
+        &mut self,
+        src: Rect<f32>,
+        dst: Rect<f32>,
+        depth: ZDepth,
+        rgba: Rgba,
+        opacity: f32,
+        rep: Repeat,
+    ) {
+        self.add(src, dst, depth, rgba, opacity, rep);
+        self.emissive.push(self.items.len() - 1);
+    }
+
+    /// A batch of the emissive sprites only, eg. to draw into the target of
+    /// a `Bloom`.
+    pub fn emissive(&self) -> Batch {
+        self.select(&self.emissive)
+    }
+
+    /// A batch of the sprites with the given ids only, eg. to outline them.
+    /// Sprites are identified by the order they were added in, from zero.
+    pub fn select(&self, ids: &[usize]) -> Batch {
+        let mut batch = Batch::new(self.w, self.h);
+
+        for &i in ids {
+            assert!(
+                i < self.items.len(),
+                "fatal: sprite {} is out of range of a batch of {}",
+                i,
+                self.items.len()
+            );
+            if self.emissive.binary_search(&i).is_ok() {
+                batch.emissive.push(batch.items.len());
+            }
+            batch.items.push(self.items[i].clone());
+            batch.item_transforms.push(self.item_transforms[i]);
+        }
+        batch.size = batch.items.len();
+        batch
+    }
+
+    /// A copy of the batch with every sprite filled with the given color,
+    /// keeping the alpha of its texture, eg. for shadows and outlines.
+    pub fn silhouette(&self, color: Rgba) -> Batch {
+        let mut batch = self.clone();
+
+        for (_, _, _, rgba, opacity, _) in batch.items.iter_mut() {
+            *rgba = Rgba::new(color.r, color.g, color.b, 1.);
+            *opacity *= color.a;
+        }
+        batch
+    }
+
+    /// Add a sprite with a transform, eg. a `Transform2D`, applied to its
+    /// destination rectangle. The transform is in the local space of the
+    /// current transform, if any.
+    pub fn add_transformed<T: Into<Matrix4<f32>>>(
+        &mut self,
+        src: Rect<f32>,
+        dst: Rect<f32>,
+        depth: ZDepth,
+        rgba: Rgba,
+        opacity: f32,
+        transform: T,
+    ) {
+        let transform = self.transforms.current() * transform.into();
+
+        self.items
+            .push((src, dst, depth, rgba, opacity, Repeat::default()));
+        self.item_transforms.push(Some(transform));
         self.size += 1;
     }
 
+    /// Transform the sprites added until the matching `pop_transform`. The
+    /// transform is in the local space of the current transform, if any.
+    pub fn push_transform<T: Into<Matrix4<f32>>>(&mut self, transform: T) {
+        self.transforms.push(transform);
+    }
+
+    /// Stop transforming sprites with the last pushed transform.
+    pub fn pop_transform(&mut self) {
+        assert!(
+            self.transforms.pop().is_some(),
+            "fatal: `pop_transform` called without a matching `push_transform`"
+        );
+    }
+
+    /// Add sprites with a transform, in the local space of the current
+    /// transform.
+    pub fn with_transform<T, F>(&mut self, transform: T, f: F)
+    where
+        T: Into<Matrix4<f32>>,
+        F: FnOnce(&mut Self),
+    {
+        self.push_transform(transform);
+        f(self);
+        self.pop_transform();
+    }
+
+    /// Add a sprite from a texture atlas region. The atlas must have the
+    /// same dimensions as the batch.
+    ///
+    /// Panics if the region isn't found in the atlas.
+    pub fn add_region<K: AtlasKey>(
+        &mut self,
+        atlas: &TextureAtlas,
+        key: K,
+        dst: Rect<f32>,
+        depth: ZDepth,
+        rgba: Rgba,
+        opacity: f32,
+    ) {
+        assert!(
+            atlas.w == self.w && atlas.h == self.h,
+            "atlas size {}x{} doesn't match batch size {}x{}",
+            atlas.w,
+            atlas.h,
+            self.w,
+            self.h
+        );
+        let src = atlas.get(key).expect("region not found in texture atlas");
+
+        self.add(src, dst, depth, rgba, opacity, Repeat::default());
+    }
+
     pub fn vertices(&self) -> Vec<Vertex> {
-        let mut buf = Vec::with_capacity(6 * self.items.len());
+        let mut buf = Vec::with_capacity(4 * self.items.len());
 
-        for (src, dst, ZDepth(z), rgba, o, rep) in self.items.iter() {
+        for ((src, dst, ZDepth(z), rgba, o, rep), t) in
+            self.items.iter().zip(self.item_transforms.iter())
+        {
             // Relative texture coordinates
             let rx1: f32 = src.x1 / self.w as f32;
             let ry1: f32 = src.y1 / self.h as f32;
@@ -248,29 +403,86 @@ impl Batch {
 
             let c: Rgba8 = (*rgba).into();
 
-            // TODO: Use an index buffer
+            // Corners, counter-clockwise from the bottom-left.
+            let mut corners = [
+                Point2::new(dst.x1, dst.y1),
+                Point2::new(dst.x2, dst.y1),
+                Point2::new(dst.x2, dst.y2),
+                Point2::new(dst.x1, dst.y2),
+            ];
+            if let Some(m) = t {
+                for p in corners.iter_mut() {
+                    *p = transform::apply(m, *p);
+                }
+            }
+            let [p1, p2, p3, p4] = corners;
+
             buf.extend_from_slice(&[
-                Vertex::new(dst.x1, dst.y1, *z, rx1 * rep.x, ry2 * rep.y, c, *o),
-                Vertex::new(dst.x2, dst.y1, *z, rx2 * rep.x, ry2 * rep.y, c, *o),
-                Vertex::new(dst.x2, dst.y2, *z, rx2 * rep.x, ry1 * rep.y, c, *o),
-                Vertex::new(dst.x1, dst.y1, *z, rx1 * rep.x, ry2 * rep.y, c, *o),
-                Vertex::new(dst.x1, dst.y2, *z, rx1 * rep.x, ry1 * rep.y, c, *o),
-                Vertex::new(dst.x2, dst.y2, *z, rx2 * rep.x, ry1 * rep.y, c, *o),
+                Vertex::new(p1.x, p1.y, *z, rx1 * rep.x, ry2 * rep.y, c, *o),
+                Vertex::new(p2.x, p2.y, *z, rx2 * rep.x, ry2 * rep.y, c, *o),
+                Vertex::new(p3.x, p3.y, *z, rx2 * rep.x, ry1 * rep.y, c, *o),
+                Vertex::new(p4.x, p4.y, *z, rx1 * rep.x, ry1 * rep.y, c, *o),
             ]);
         }
         buf
     }
 
-    pub fn finish(self, r: &core::Renderer) -> core::VertexBuffer {
-        let buf = self.vertices();
-        r.device.create_buffer(buf.as_slice())
+    /// Indices into `vertices`, two triangles per quad.
+    pub fn indices(&self) -> Vec<u32> {
+        let mut buf = Vec::with_capacity(6 * self.items.len());
+
+        for i in 0..self.items.len() as u32 {
+            let v = i * 4;
+            buf.extend_from_slice(&[v, v + 1, v + 2, v, v + 3, v + 2]);
+        }
+        buf
+    }
+
+    pub fn finish(self, r: &core::Renderer) -> core::IndexedBuffer {
+        let vertices = self.vertices();
+        let indices = self.indices();
+
+        core::IndexedBuffer {
+            vertices: r.device.create_buffer(vertices.as_slice()),
+            indices: r.device.create_index_buffer(indices.as_slice()),
+        }
+    }
+
+    /// Write the batch into a ring buffer, instead of creating buffers for
+    /// it. Returns `None` if it doesn't fit, see `RingBuffer::write`.
+    pub fn write(&self, ring: &mut core::RingBuffer) -> Option<core::RingSlice> {
+        ring.write_indexed(self.vertices().as_slice(), self.indices().as_slice())
     }
 
     pub fn clear(&mut self) {
         self.items.clear();
+        self.item_transforms.clear();
+        self.emissive.clear();
+        self.transforms = TransformStack::new();
         self.size = 0;
     }
 
+    /// Move the sprites of another batch of the same texture size to the end
+    /// of this one, leaving the other batch empty.
+    pub fn merge(&mut self, other: &mut Batch) {
+        assert!(
+            self.w == other.w && self.h == other.h,
+            "fatal: can't merge a {}x{} batch into a {}x{} batch",
+            other.w,
+            other.h,
+            self.w,
+            self.h
+        );
+        let offset = self.items.len();
+
+        self.emissive
+            .extend(other.emissive.drain(..).map(|i| i + offset));
+        self.items.append(&mut other.items);
+        self.item_transforms.append(&mut other.item_transforms);
+        self.size += other.size;
+        other.clear();
+    }
+
     pub fn offset(&mut self, x: f32, y: f32) {
         for (_, dst, _, _, _, _) in self.items.iter_mut() {
             *dst = *dst + Vector2::new(x, y);
@@ -281,3 +493,1058 @@ impl Batch {
         self.items.is_empty()
     }
 }
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// MultiBatch
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Identifies a texture of a `MultiBatch`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct TextureId(usize);
+
+impl TextureId {
+    /// Index of the texture, in the order textures were added.
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+/// Sprites from multiple textures, split into one batch per texture. Sprites
+/// are drawn grouped by texture, in the order textures were added, with one
+/// binding switch per texture.
+///
+/// Since sprites aren't drawn in the order they were added, overlapping
+/// sprites from different textures should be ordered by their depth.
+///
+/// ```
+/// use rgx::core::Rgba;
+/// use rgx::kit::sprite2d::MultiBatch;
+/// use rgx::kit::{Repeat, ZDepth};
+/// use rgx::math::Vector2;
+/// use rgx::rect::Rect;
+///
+/// let mut batch = MultiBatch::new();
+/// let (player, tiles) = (batch.texture(32, 32), batch.texture(256, 256));
+///
+/// // The player is drawn over the tiles, whatever the order they're drawn in.
+/// for i in 0..4 {
+///     let (src, dst) = (Rect::origin(16., 16.), Rect::origin(16., 16.) + Vector2::new(i as f32 * 16., 0.));
+///     batch.batch(tiles).add(src, dst, ZDepth(-0.5), Rgba::WHITE, 1., Repeat::default());
+/// }
+/// let (src, dst) = (Rect::origin(32., 32.), Rect::origin(32., 32.));
+/// batch.batch(player).add(src, dst, ZDepth::ZERO, Rgba::WHITE, 1., Repeat::default());
+///
+/// assert_eq!(batch.len(), 5);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MultiBatch {
+    batches: Vec<Batch>,
+}
+
+impl MultiBatch {
+    pub fn new() -> Self {
+        Self {
+            batches: Vec::new(),
+        }
+    }
+
+    /// Add a texture of the given size, returning its id.
+    pub fn texture(&mut self, w: u32, h: u32) -> TextureId {
+        self.batches.push(Batch::new(w, h));
+        TextureId(self.batches.len() - 1)
+    }
+
+    /// The batch of the sprites of a texture, to add sprites to.
+    pub fn batch(&mut self, texture: TextureId) -> &mut Batch {
+        &mut self.batches[texture.0]
+    }
+
+    /// Number of textures.
+    pub fn textures(&self) -> usize {
+        self.batches.len()
+    }
+
+    /// Number of sprites, across all textures.
+    pub fn len(&self) -> usize {
+        self.batches.iter().map(|b| b.size).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.batches.iter().all(|b| b.is_empty())
+    }
+
+    /// Remove all sprites, keeping the textures.
+    pub fn clear(&mut self) {
+        for b in self.batches.iter_mut() {
+            b.clear();
+        }
+    }
+
+    /// Create the buffers of each texture. Textures without sprites have no
+    /// buffer, and aren't drawn.
+    pub fn finish(self, r: &core::Renderer) -> MultiBuffer {
+        MultiBuffer {
+            buffers: self
+                .batches
+                .into_iter()
+                .map(|b| {
+                    if b.is_empty() {
+                        None
+                    } else {
+                        Some(b.finish(r))
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The buffers of a `MultiBatch`, one per texture.
+#[derive(Debug)]
+pub struct MultiBuffer {
+    buffers: Vec<Option<core::IndexedBuffer>>,
+}
+
+impl MultiBuffer {
+    /// Draw the sprites of each texture, with the binding of the texture,
+    /// created with `Pipeline::binding`. Bindings are given in the order the
+    /// textures were added to the batch.
+    pub fn draw(&self, bindings: &[&core::BindingGroup], pass: &mut core::Pass) {
+        assert_eq!(
+            bindings.len(),
+            self.buffers.len(),
+            "fatal: a binding is needed for each texture of the batch"
+        );
+        for (buffer, binding) in self.buffers.iter().zip(bindings) {
+            if let Some(buffer) = buffer {
+                pass.draw(buffer, binding);
+            }
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// StrokeBatch
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A batch of textured strokes along polylines, eg. for map routes, ropes
+/// or brush strokes, drawn with the sprite pipeline.
+///
+/// The texture is stretched across the width of a stroke, and repeated
+/// along its length, parameterized by arc length so that it isn't
+/// distorted by uneven segments. Its top edge runs along the left side of
+/// the stroke. The texture must be sampled with `AddressMode::Repeat`.
+#[derive(Clone, Debug)]
+pub struct StrokeBatch {
+    pub w: u32,
+    pub h: u32,
+
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+}
+
+impl StrokeBatch {
+    /// Limit on the miter length of joins, relative to the half width.
+    const MITER_LIMIT: f32 = 4.;
+
+    pub fn new(w: u32, h: u32) -> Self {
+        Self {
+            w,
+            h,
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    /// Add a stroke of the given width along the points, with the texture
+    /// repeating every `length` units. If the first and last points are the
+    /// same, the stroke is closed.
+    pub fn add(
+        &mut self,
+        points: &[Point2<f32>],
+        width: f32,
+        length: f32,
+        depth: ZDepth,
+        rgba: Rgba,
+        opacity: f32,
+    ) {
+        let mut pts: Vec<Point2<f32>> = Vec::with_capacity(points.len() + 1);
+        for p in points {
+            if pts.last() != Some(p) {
+                pts.push(*p);
+            }
+        }
+        let closed = pts.len() > 2 && pts.first() == pts.last();
+        if closed {
+            pts.pop();
+        }
+        let n = pts.len();
+        if n < 2 || width <= 0. {
+            return;
+        }
+
+        let hw = width / 2.;
+        let ZDepth(z) = depth;
+        let c: Rgba8 = rgba.into();
+        let normal = |a: Point2<f32>, b: Point2<f32>| {
+            let d = Vector2::new(b.x - a.x, b.y - a.y).normalize();
+            Vector2::new(-d.y, d.x)
+        };
+
+        // Offset from each point to the left side of the stroke, mitered
+        // with the neighbouring segments.
+        let offset = |i: usize| -> Vector2<f32> {
+            let prev = if i > 0 || closed {
+                Some(normal(pts[(i + n - 1) % n], pts[i]))
+            } else {
+                None
+            };
+            let next = if i < n - 1 || closed {
+                Some(normal(pts[i], pts[(i + 1) % n]))
+            } else {
+                None
+            };
+            match (prev, next) {
+                (Some(n0), Some(n1)) => {
+                    let m = n0 + n1;
+                    if Vector2::dot(m, m) < f32::EPSILON {
+                        return n0 * hw;
+                    }
+                    let m = m.normalize();
+                    m * (hw / Vector2::dot(m, n0)).min(hw * Self::MITER_LIMIT)
+                }
+                (Some(nrm), None) | (None, Some(nrm)) => nrm * hw,
+                (None, None) => unreachable!(),
+            }
+        };
+
+        // Closed strokes end where they start, with the texture coordinate
+        // of the full length.
+        let count = if closed { n + 1 } else { n };
+        let first = self.vertices.len() as u32;
+        let mut s = 0.;
+
+        for i in 0..count {
+            let p = pts[i % n];
+            if i > 0 {
+                let q = pts[i - 1];
+                s += Vector2::new(p.x - q.x, p.y - q.y).magnitude();
+            }
+            let off = offset(i % n);
+            let (l, r) = (p + off, p - off);
+            let u = s / length;
+
+            self.vertices.extend_from_slice(&[
+                Vertex::new(l.x, l.y, z, u, 0., c, opacity),
+                Vertex::new(r.x, r.y, z, u, 1., c, opacity),
+            ]);
+        }
+        for i in 0..count as u32 - 1 {
+            let v = first + i * 2;
+            self.indices
+                .extend_from_slice(&[v + 1, v + 3, v + 2, v + 1, v, v + 2]);
+        }
+    }
+
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    pub fn finish(self, r: &core::Renderer) -> core::IndexedBuffer {
+        core::IndexedBuffer {
+            vertices: r.device.create_buffer(self.vertices.as_slice()),
+            indices: r.device.create_index_buffer(self.indices.as_slice()),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// InstanceBatch
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A single sprite of an `InstanceBatch`. Its quad is generated by the
+/// vertex shader.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct Instance {
+    /// Center of the sprite, and its depth.
+    position: Vector3<f32>,
+    /// Width and height of the sprite.
+    scale: Vector2<f32>,
+    /// Rotation around the center, in radians.
+    angle: f32,
+    /// Relative texture coordinates, as `(x1, y1, x2, y2)`.
+    uv: Vector4<f32>,
+    color: Rgba8,
+    opacity: f32,
+}
+
+/// A batch of sprites drawn with the instanced sprite pipeline. Each sprite
+/// is a single 48-byte instance, instead of four vertices and six indices.
+#[derive(Clone, Debug)]
+pub struct InstanceBatch {
+    pub w: u32,
+    pub h: u32,
+
+    instances: Vec<Instance>,
+}
+
+impl InstanceBatch {
+    pub fn new(w: u32, h: u32) -> Self {
+        Self {
+            w,
+            h,
+            instances: Vec::new(),
+        }
+    }
+
+    /// Add a sprite, rotated by `angle` radians around the center of `dst`.
+    pub fn add(
+        &mut self,
+        src: Rect<f32>,
+        dst: Rect<f32>,
+        depth: ZDepth,
+        angle: f32,
+        rgba: Rgba,
+        opacity: f32,
+    ) {
+        let (w, h) = (self.w as f32, self.h as f32);
+        let ZDepth(z) = depth;
+
+        self.instances.push(Instance {
+            position: Vector3::new((dst.x1 + dst.x2) / 2., (dst.y1 + dst.y2) / 2., z),
+            scale: Vector2::new(dst.width(), dst.height()),
+            angle,
+            uv: Vector4::new(src.x1 / w, src.y1 / h, src.x2 / w, src.y2 / h),
+            color: rgba.into(),
+            opacity,
+        });
+    }
+
+    pub fn instances(&self) -> &[Instance] {
+        &self.instances
+    }
+
+    pub fn finish(self, r: &core::Renderer) -> core::VertexBuffer {
+        r.device.create_buffer(self.instances.as_slice())
+    }
+
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.instances.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// InstancePipeline
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Draws an `InstanceBatch`. Bindings are the same as the sprite pipeline's.
+pub struct InstancePipeline {
+    pipeline: core::Pipeline,
+    bindings: core::BindingGroup,
+    buf: core::UniformBuffer,
+    model: Model,
+}
+
+impl InstancePipeline {
+    pub fn binding(
+        &self,
+        renderer: &core::Renderer,
+        texture: &core::Texture,
+        sampler: &core::Sampler,
+    ) -> core::BindingGroup {
+        renderer
+            .device
+            .create_binding_group(&self.pipeline.layout.sets[2], &[texture, sampler])
+    }
+
+    /// Draw the instances of a batch, as returned by `InstanceBatch::finish`.
+    pub fn draw(
+        &self,
+        instances: &core::VertexBuffer,
+        binding: &core::BindingGroup,
+        pass: &mut core::Pass,
+    ) {
+        pass.set_binding(binding, &[]);
+        pass.draw_instances(instances, 0..6);
+    }
+}
+
+impl<'a> core::AbstractPipeline<'a> for InstancePipeline {
+    type PrepareContext = Matrix4<f32>;
+    type Uniforms = self::Uniforms;
+
+    fn description() -> core::PipelineDescription<'a> {
+        core::PipelineDescription {
+            vertex_layout: &[
+                core::VertexFormat::Float3,
+                core::VertexFormat::Float2,
+                core::VertexFormat::Float,
+                core::VertexFormat::Float4,
+                core::VertexFormat::UByte4,
+                core::VertexFormat::Float,
+            ],
+            pipeline_layout: Pipeline::description().pipeline_layout,
+            // TODO: Use `env("CARGO_MANIFEST_DIR")`
+            vertex_shader: include_bytes!("data/sprite_instanced.vert.spv"),
+            fragment_shader: include_bytes!("data/sprite.frag.spv"),
+        }
+    }
+
+    fn setup(pipeline: core::Pipeline, dev: &core::Device) -> Self {
+        let transform = Matrix4::identity();
+        let ortho = Matrix4::identity();
+        let model = Model::new(&pipeline.layout.sets[1], &[Matrix4::identity()], dev);
+        let buf = dev.create_uniform_buffer(&[self::Uniforms { ortho, transform }]);
+        let bindings = dev.create_binding_group(&pipeline.layout.sets[0], &[&buf]);
+
+        Self {
+            pipeline,
+            buf,
+            bindings,
+            model,
+        }
+    }
+
+    fn step_mode() -> core::StepMode {
+        core::StepMode::Instance
+    }
+
+    fn apply(&self, pass: &mut core::Pass) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_binding(&self.bindings, &[]);
+        pass.set_binding(&self.model.binding, &[]);
+    }
+
+    fn projection(&self) -> Option<&core::UniformBuffer> {
+        Some(&self.buf)
+    }
+
+    fn prepare(
+        &'a self,
+        ortho: Matrix4<f32>,
+    ) -> Option<(&'a core::UniformBuffer, Vec<self::Uniforms>)> {
+        let transform = Matrix4::identity();
+        Some((&self.buf, vec![self::Uniforms { transform, ortho }]))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// ObjectBatch
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Number of objects of an `ObjectPipeline`. The objects fit in the minimum
+/// size of a uniform buffer binding.
+pub const MAX_OBJECTS: usize = 128;
+
+/// The uniforms of an object drawn with the object pipeline, shared by all
+/// the sprites indexing it.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct Object {
+    pub transform: Matrix4<f32>,
+    /// Color the sprites of the object are tinted with, as with the sprite
+    /// pipeline. Transparent colors leave the sprites untinted.
+    pub color: Rgba,
+}
+
+impl Object {
+    pub fn new(transform: Matrix4<f32>, color: Rgba) -> Self {
+        Self { transform, color }
+    }
+}
+
+impl Default for Object {
+    fn default() -> Self {
+        Self::new(Matrix4::identity(), Rgba::TRANSPARENT)
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct ObjectVertex {
+    position: Vector3<f32>,
+    uv: Vector2<f32>,
+    opacity: f32,
+    object: u32,
+}
+
+/// A batch of sprites drawn with the object pipeline. Each sprite belongs to
+/// an object, whose transform and color it reads from the objects of the
+/// pipeline, so that objects can move without their sprites being rebuilt.
+#[derive(Clone, Debug)]
+pub struct ObjectBatch {
+    pub w: u32,
+    pub h: u32,
+
+    items: Vec<ObjectSprite>,
+}
+
+#[derive(Clone, Debug)]
+struct ObjectSprite {
+    src: Rect<f32>,
+    dst: Rect<f32>,
+    depth: ZDepth,
+    opacity: f32,
+    object: u32,
+}
+
+impl ObjectBatch {
+    pub fn new(w: u32, h: u32) -> Self {
+        Self {
+            w,
+            h,
+            items: Vec::new(),
+        }
+    }
+
+    /// Add a sprite of the object at index `object`. The sprite is positioned
+    /// relative to the object, with the object's transform.
+    ///
+    /// Panics if the index isn't smaller than `MAX_OBJECTS`.
+    pub fn add(
+        &mut self,
+        src: Rect<f32>,
+        dst: Rect<f32>,
+        depth: ZDepth,
+        opacity: f32,
+        object: u32,
+    ) {
+        assert!(
+            (object as usize) < MAX_OBJECTS,
+            "fatal: object index {} out of range",
+            object
+        );
+        self.items.push(ObjectSprite {
+            src,
+            dst,
+            depth,
+            opacity,
+            object,
+        });
+    }
+
+    pub fn vertices(&self) -> Vec<ObjectVertex> {
+        let mut buf = Vec::with_capacity(4 * self.items.len());
+        let (w, h) = (self.w as f32, self.h as f32);
+
+        for sprite in self.items.iter() {
+            let (src, dst) = (sprite.src, sprite.dst);
+            let (rx1, ry1, rx2, ry2) = (src.x1 / w, src.y1 / h, src.x2 / w, src.y2 / h);
+            let vertex = |x, y, u, v| ObjectVertex {
+                position: Vector3::new(x, y, *sprite.depth),
+                uv: Vector2::new(u, v),
+                opacity: sprite.opacity,
+                object: sprite.object,
+            };
+
+            buf.extend_from_slice(&[
+                vertex(dst.x1, dst.y1, rx1, ry2),
+                vertex(dst.x2, dst.y1, rx2, ry2),
+                vertex(dst.x2, dst.y2, rx2, ry1),
+                vertex(dst.x1, dst.y2, rx1, ry1),
+            ]);
+        }
+        buf
+    }
+
+    /// Indices into `vertices`, two triangles per quad.
+    pub fn indices(&self) -> Vec<u32> {
+        let mut buf = Vec::with_capacity(6 * self.items.len());
+
+        for i in 0..self.items.len() as u32 {
+            let v = i * 4;
+            buf.extend_from_slice(&[v, v + 1, v + 2, v, v + 3, v + 2]);
+        }
+        buf
+    }
+
+    pub fn finish(self, r: &core::Renderer) -> core::IndexedBuffer {
+        let vertices = self.vertices();
+        let indices = self.indices();
+
+        core::IndexedBuffer {
+            vertices: r.device.create_buffer(vertices.as_slice()),
+            indices: r.device.create_index_buffer(indices.as_slice()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// ObjectPipeline
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Draws an `ObjectBatch`, with the transforms and colors of up to
+/// `MAX_OBJECTS` objects in a single uniform array, bound once for all of
+/// them. Texture bindings are the same as the sprite pipeline's.
+pub struct ObjectPipeline {
+    pipeline: core::Pipeline,
+    bindings: core::BindingGroup,
+    buf: core::UniformBuffer,
+    objects: core::UniformBuffer,
+    objects_binding: core::BindingGroup,
+}
+
+impl ObjectPipeline {
+    pub fn binding(
+        &self,
+        renderer: &core::Renderer,
+        texture: &core::Texture,
+        sampler: &core::Sampler,
+    ) -> core::BindingGroup {
+        renderer
+            .device
+            .create_binding_group(&self.pipeline.layout.sets[2], &[texture, sampler])
+    }
+
+    /// Update the objects, starting from the first. Objects past the given
+    /// ones keep their previous values.
+    ///
+    /// Panics if there are more than `MAX_OBJECTS` objects.
+    pub fn update(&self, objects: &[Object], r: &core::Renderer, f: &mut core::Frame) {
+        assert!(
+            objects.len() <= MAX_OBJECTS,
+            "fatal: too many objects ({} > {})",
+            objects.len(),
+            MAX_OBJECTS
+        );
+        if !objects.is_empty() {
+            r.device
+                .update_uniform_buffer(objects, &self.objects, f.encoder_mut());
+        }
+    }
+}
+
+impl<'a> core::AbstractPipeline<'a> for ObjectPipeline {
+    type PrepareContext = Matrix4<f32>;
+    type Uniforms = self::Uniforms;
+
+    fn description() -> core::PipelineDescription<'a> {
+        core::PipelineDescription {
+            vertex_layout: &[
+                core::VertexFormat::Float3,
+                core::VertexFormat::Float2,
+                core::VertexFormat::Float,
+                core::VertexFormat::Uint,
+            ],
+            pipeline_layout: Pipeline::description().pipeline_layout,
+            // TODO: Use `env("CARGO_MANIFEST_DIR")`
+            vertex_shader: include_bytes!("data/sprite_objects.vert.spv"),
+            fragment_shader: include_bytes!("data/sprite.frag.spv"),
+        }
+    }
+
+    fn setup(pipeline: core::Pipeline, dev: &core::Device) -> Self {
+        let transform = Matrix4::identity();
+        let ortho = Matrix4::identity();
+        let buf = dev.create_uniform_buffer(&[self::Uniforms { ortho, transform }]);
+        let bindings = dev.create_binding_group(&pipeline.layout.sets[0], &[&buf]);
+        let objects = dev.create_uniform_array(&[Object::default(); MAX_OBJECTS]);
+        let objects_binding = dev.create_binding_group(&pipeline.layout.sets[1], &[&objects]);
+
+        Self {
+            pipeline,
+            buf,
+            bindings,
+            objects,
+            objects_binding,
+        }
+    }
+
+    fn apply(&self, pass: &mut core::Pass) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_binding(&self.bindings, &[]);
+        pass.set_binding(&self.objects_binding, &[]);
+    }
+
+    fn projection(&self) -> Option<&core::UniformBuffer> {
+        Some(&self.buf)
+    }
+
+    fn prepare(
+        &'a self,
+        ortho: Matrix4<f32>,
+    ) -> Option<(&'a core::UniformBuffer, Vec<self::Uniforms>)> {
+        let transform = Matrix4::identity();
+        Some((&self.buf, vec![self::Uniforms { transform, ortho }]))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// TextureAtlas
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Identifies a region of a `TextureAtlas`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct AtlasId(usize);
+
+/// A key which can be used to look up a region of a `TextureAtlas`.
+pub trait AtlasKey {
+    fn lookup(&self, atlas: &TextureAtlas) -> Option<AtlasId>;
+}
+
+impl AtlasKey for AtlasId {
+    fn lookup(&self, atlas: &TextureAtlas) -> Option<AtlasId> {
+        if self.0 < atlas.regions.len() {
+            Some(*self)
+        } else {
+            None
+        }
+    }
+}
+
+impl AtlasKey for &str {
+    fn lookup(&self, atlas: &TextureAtlas) -> Option<AtlasId> {
+        atlas.id(self)
+    }
+}
+
+/// A texture packed with multiple sprites, each accessible by name or id.
+#[derive(Clone, Debug)]
+pub struct TextureAtlas {
+    pub w: u32,
+    pub h: u32,
+
+    regions: Vec<Rect<f32>>,
+    names: HashMap<String, AtlasId>,
+}
+
+impl TextureAtlas {
+    pub fn new(w: u32, h: u32) -> Self {
+        Self {
+            w,
+            h,
+            regions: Vec::new(),
+            names: HashMap::new(),
+        }
+    }
+
+    pub fn from_regions(w: u32, h: u32, regions: &[(&str, Rect<f32>)]) -> Self {
+        let mut atlas = Self::new(w, h);
+        for (name, rect) in regions {
+            atlas.add(name, *rect);
+        }
+        atlas
+    }
+
+    /// Parse an atlas definition, with one region per line, in the form
+    /// `<name> <x> <y> <width> <height>`. Empty lines and lines starting
+    /// with `#` are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::kit::sprite2d::TextureAtlas;
+    /// use rgx::rect::Rect;
+    ///
+    /// let atlas = TextureAtlas::parse(64, 32, "idle 0 0 32 32\nwalk 32 0 32 32").unwrap();
+    ///
+    /// assert_eq!(atlas.get("walk"), Some(Rect::new(32., 0., 64., 32.)));
+    /// assert!(TextureAtlas::parse(64, 32, "idle 0 0 32").is_err());
+    /// ```
+    pub fn parse(w: u32, h: u32, input: &str) -> Result<Self, Error> {
+        let mut atlas = Self::new(w, h);
+
+        for (i, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let nums: Vec<f32> = fields[1..].iter().filter_map(|s| s.parse().ok()).collect();
+
+            match (fields.as_slice(), nums.as_slice()) {
+                ([name, _, _, _, _], [x, y, w, h]) => {
+                    atlas.add(name, Rect::new(*x, *y, x + w, y + h));
+                }
+                _ => return Err(Error::InvalidAtlas(i + 1)),
+            }
+        }
+        Ok(atlas)
+    }
+
+    /// Add a named region, returning its id. If a region with the same name
+    /// already exists, the name will refer to the new region.
+    pub fn add(&mut self, name: &str, rect: Rect<f32>) -> AtlasId {
+        let id = AtlasId(self.regions.len());
+
+        self.regions.push(rect);
+        self.names.insert(name.to_owned(), id);
+
+        id
+    }
+
+    pub fn id(&self, name: &str) -> Option<AtlasId> {
+        self.names.get(name).copied()
+    }
+
+    pub fn get<K: AtlasKey>(&self, key: K) -> Option<Rect<f32>> {
+        key.lookup(self).map(|AtlasId(ix)| self.regions[ix])
+    }
+
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// Create an empty sprite batch for this atlas.
+    pub fn batch(&self) -> Batch {
+        Batch::new(self.w, self.h)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Animator
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// How an `Animation` continues after its last frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Playback {
+    /// Stop on the last frame.
+    Once,
+    /// Start over from the first frame.
+    Loop,
+    /// Play backwards to the first frame, then forwards again.
+    PingPong,
+}
+
+/// A frame of an `Animation`: a region of the sprite texture, and how long
+/// it is shown for.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AnimationFrame {
+    pub rect: Rect<f32>,
+    pub duration: Duration,
+}
+
+/// A sequence of frames, eg. of a walk cycle. Unlike `kit::Animation`, each
+/// frame has its own duration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Animation {
+    pub frames: Vec<AnimationFrame>,
+    pub playback: Playback,
+}
+
+impl Animation {
+    /// Create an animation from its frames.
+    ///
+    /// Panics if there are no frames.
+    pub fn new(frames: Vec<AnimationFrame>, playback: Playback) -> Self {
+        assert!(
+            !frames.is_empty(),
+            "fatal: an animation must have at least one frame"
+        );
+        Self { frames, playback }
+    }
+
+    /// Create an animation from texture regions, each shown for the same
+    /// duration.
+    pub fn uniform(rects: &[Rect<f32>], duration: Duration, playback: Playback) -> Self {
+        let frames = rects
+            .iter()
+            .map(|rect| AnimationFrame {
+                rect: *rect,
+                duration,
+            })
+            .collect();
+
+        Self::new(frames, playback)
+    }
+
+    /// Create an animation from texture atlas regions, each shown for the
+    /// same duration. Returns `None` if a region isn't found in the atlas.
+    pub fn from_atlas<K: AtlasKey>(
+        atlas: &TextureAtlas,
+        keys: impl IntoIterator<Item = K>,
+        duration: Duration,
+        playback: Playback,
+    ) -> Option<Self> {
+        let rects = keys
+            .into_iter()
+            .map(|k| atlas.get(k))
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self::uniform(&rects, duration, playback))
+    }
+
+    /// The duration of a single run through the frames. With
+    /// `Playback::PingPong`, this includes playing backwards.
+    pub fn duration(&self) -> Duration {
+        self.sequence().map(|i| self.frames[i].duration).sum()
+    }
+
+    /// The index of the frame shown after the given time.
+    pub fn index_at(&self, elapsed: Duration) -> usize {
+        let total = self.duration().as_nanos();
+        let last = self.frames.len() - 1;
+
+        if total == 0 {
+            return 0;
+        }
+        let mut t = match self.playback {
+            Playback::Once if elapsed.as_nanos() >= total => return last,
+            Playback::Once => elapsed.as_nanos(),
+            Playback::Loop | Playback::PingPong => elapsed.as_nanos() % total,
+        };
+        for i in self.sequence() {
+            let d = self.frames[i].duration.as_nanos();
+            if t < d {
+                return i;
+            }
+            t -= d;
+        }
+        last
+    }
+
+    /// The indices of the frames, in the order they are shown.
+    fn sequence(&self) -> impl Iterator<Item = usize> {
+        let n = self.frames.len();
+        let back = match self.playback {
+            Playback::PingPong => 1..n.saturating_sub(1),
+            Playback::Once | Playback::Loop => 0..0,
+        };
+        (0..n).chain(back.rev())
+    }
+}
+
+/// Plays an `Animation`, advanced by the time elapsed between frames.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use rgx::kit::sprite2d::{Animation, Animator, Playback};
+/// use rgx::rect::Rect;
+///
+/// let frames = [
+///     Rect::new(0., 0., 16., 16.),
+///     Rect::new(16., 0., 32., 16.),
+///     Rect::new(32., 0., 48., 16.),
+/// ];
+/// let walk = Animation::uniform(&frames, Duration::from_millis(100), Playback::PingPong);
+/// let mut animator = Animator::new(walk);
+///
+/// animator.update(Duration::from_millis(250));
+/// assert_eq!(animator.rect(), frames[2]);
+///
+/// animator.update(Duration::from_millis(100));
+/// assert_eq!(animator.rect(), frames[1]);
+///
+/// animator.update(Duration::from_millis(100));
+/// assert_eq!(animator.rect(), frames[0]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Animator {
+    animation: Animation,
+    elapsed: Duration,
+    playing: bool,
+    /// Playback speed, where `1.0` is the speed of the animation.
+    pub speed: f32,
+}
+
+impl Animator {
+    pub fn new(animation: Animation) -> Self {
+        Self {
+            animation,
+            elapsed: Duration::default(),
+            playing: true,
+            speed: 1.,
+        }
+    }
+
+    /// Advance the animation, eg. by the time elapsed since the last frame.
+    /// Does nothing while paused.
+    pub fn update(&mut self, delta: Duration) {
+        if self.playing {
+            self.elapsed += delta.mul_f32(self.speed.max(0.));
+        }
+    }
+
+    /// The texture region of the current frame, eg. to be passed as the
+    /// source of `Batch::add`.
+    pub fn rect(&self) -> Rect<f32> {
+        self.animation.frames[self.index()].rect
+    }
+
+    /// The index of the current frame.
+    pub fn index(&self) -> usize {
+        self.animation.index_at(self.elapsed)
+    }
+
+    pub fn animation(&self) -> &Animation {
+        &self.animation
+    }
+
+    /// Switch to another animation, eg. from idle to walking, and start it
+    /// from its first frame. Does nothing if it is the current animation.
+    pub fn set_animation(&mut self, animation: Animation) {
+        if animation != self.animation {
+            self.animation = animation;
+            self.reset();
+        }
+    }
+
+    /// The time played since the animation started.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Whether an animation played once has shown its last frame for its
+    /// whole duration. Looping animations never finish.
+    pub fn is_finished(&self) -> bool {
+        self.animation.playback == Playback::Once && self.elapsed >= self.animation.duration()
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Go back to the first frame.
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::default();
+    }
+}
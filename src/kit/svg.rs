@@ -0,0 +1,454 @@
+use std::fmt::Write;
+
+use crate::core::Rgba;
+use crate::kit::shape2d::{
+    Batch, Cap, Dash, Fill, Join, Path, PatternStyle, Rotation, Segment, Shape,
+};
+use crate::math::*;
+
+/// Export a shape batch as an SVG document of the given size.
+///
+/// Shapes are written in the order they were added, and the Y axis is
+/// flipped so that the document matches what is rendered with an
+/// orthographic projection whose origin is at the bottom-left. Depth is
+/// ignored, and gradient strokes are exported with their first color,
+/// since SVG has no equivalent. Meshes are skipped.
+pub fn export(batch: &Batch, w: u32, h: u32) -> String {
+    let mut svg = Svg::default();
+
+    for (shape, opacity, transform) in batch.items.iter() {
+        if let Some(m) = transform {
+            writeln!(
+                svg.body,
+                r#"<g transform="matrix({} {} {} {} {} {})">"#,
+                m.x.x, m.x.y, m.y.x, m.y.y, m.w.x, m.w.y
+            )
+            .ok();
+            svg.shape(shape, opacity * batch.opacity);
+            svg.body.push_str("</g>\n");
+        } else {
+            svg.shape(shape, opacity * batch.opacity);
+        }
+    }
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = w,
+        h = h
+    )
+    .ok();
+    if !svg.defs.is_empty() {
+        writeln!(out, "<defs>\n{}</defs>", svg.defs).ok();
+    }
+    writeln!(out, r#"<g transform="matrix(1 0 0 -1 0 {})">"#, h).ok();
+    out.push_str(&svg.body);
+    out.push_str("</g>\n</svg>\n");
+    out
+}
+
+/// The kind of shape a fill applies to, which determines how gradients
+/// are laid out.
+#[derive(Copy, Clone, PartialEq)]
+enum Kind {
+    Linear,
+    Radial,
+}
+
+#[derive(Default)]
+struct Svg {
+    defs: String,
+    body: String,
+    ids: usize,
+}
+
+impl Svg {
+    fn shape(&mut self, shape: &Shape, opacity: f32) {
+        match shape {
+            Shape::Line(l, _, rot, stroke) => {
+                if stroke.width > 0. {
+                    let attrs = self.attrs(rot, opacity);
+                    writeln!(
+                        self.body,
+                        r#"<line x1="{}" y1="{}" x2="{}" y2="{}" fill="none" {}{}{}/>"#,
+                        l.p1.x,
+                        l.p1.y,
+                        l.p2.x,
+                        l.p2.y,
+                        stroke_attrs(stroke.color, stroke.width, stroke.join, stroke.cap),
+                        dash_attrs(&stroke.dash),
+                        attrs
+                    )
+                    .ok();
+                }
+            }
+            Shape::Rectangle(r, _, rot, stroke, fill) => {
+                let attrs = self.attrs(rot, opacity);
+                let (x1, y1) = (r.x1.min(r.x2), r.y1.min(r.y2));
+                let (w, h) = (r.width().abs(), r.height().abs());
+
+                if let Some(paint) = self.fill(fill, Kind::Linear) {
+                    writeln!(
+                        self.body,
+                        r#"<rect x="{}" y="{}" width="{}" height="{}" {}{}/>"#,
+                        x1, y1, w, h, paint, attrs
+                    )
+                    .ok();
+                }
+                // Strokes are drawn on the inside of the rectangle.
+                if stroke.width > 0. {
+                    let s = stroke.width;
+                    writeln!(
+                        self.body,
+                        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" {}{}/>"#,
+                        x1 + s / 2.,
+                        y1 + s / 2.,
+                        (w - s).max(0.),
+                        (h - s).max(0.),
+                        stroke_attrs(stroke.color, s, Join::Miter, Cap::Butt),
+                        attrs
+                    )
+                    .ok();
+                }
+            }
+            Shape::RoundedRectangle(r, radius, _, rot, stroke, fill) => {
+                let attrs = self.attrs(rot, opacity);
+                let (x1, y1) = (r.x1.min(r.x2), r.y1.min(r.y2));
+                let (w, h) = (r.width().abs(), r.height().abs());
+                let radius = radius.min(w / 2.).min(h / 2.).max(0.);
+
+                if let Some(paint) = self.fill(fill, Kind::Linear) {
+                    writeln!(
+                        self.body,
+                        r#"<rect x="{}" y="{}" width="{}" height="{}" rx="{}" {}{}/>"#,
+                        x1, y1, w, h, radius, paint, attrs
+                    )
+                    .ok();
+                }
+                // Strokes are drawn on the inside of the rectangle.
+                if stroke.width > 0. {
+                    let s = stroke.width;
+                    writeln!(
+                        self.body,
+                        r#"<rect x="{}" y="{}" width="{}" height="{}" rx="{}" fill="none" {}{}/>"#,
+                        x1 + s / 2.,
+                        y1 + s / 2.,
+                        (w - s).max(0.),
+                        (h - s).max(0.),
+                        (radius - s / 2.).max(0.),
+                        stroke_attrs(stroke.color, s, Join::Miter, Cap::Butt),
+                        attrs
+                    )
+                    .ok();
+                }
+            }
+            Shape::Circle(center, _, radius, _, stroke, fill) => {
+                let attrs = self.attrs(&Rotation::ZERO, opacity);
+
+                if let Some(paint) = self.fill(fill, Kind::Radial) {
+                    writeln!(
+                        self.body,
+                        r#"<circle cx="{}" cy="{}" r="{}" {}{}/>"#,
+                        center.x, center.y, radius, paint, attrs
+                    )
+                    .ok();
+                }
+                // Strokes are drawn on the inside of the circle.
+                if stroke.width > 0. {
+                    let s = stroke.width;
+                    writeln!(
+                        self.body,
+                        r#"<circle cx="{}" cy="{}" r="{}" fill="none" {}{}/>"#,
+                        center.x,
+                        center.y,
+                        (radius - s / 2.).max(0.),
+                        stroke_attrs(stroke.color, s, Join::Miter, Cap::Butt),
+                        attrs
+                    )
+                    .ok();
+                }
+            }
+            Shape::Polygon(points, _, rot, stroke, fill) => {
+                let attrs = self.attrs(rot, opacity);
+                let points = points_attr(points);
+
+                if let Some(paint) = self.fill(fill, Kind::Linear) {
+                    writeln!(
+                        self.body,
+                        r#"<polygon points="{}" {}{}/>"#,
+                        points, paint, attrs
+                    )
+                    .ok();
+                }
+                // Strokes are drawn on the inside of the outline: draw a
+                // stroke twice as wide, clipped to the polygon.
+                if stroke.width > 0. {
+                    let id = self.id("clip");
+                    writeln!(
+                        self.defs,
+                        r#"<clipPath id="{}"><polygon points="{}"/></clipPath>"#,
+                        id, points
+                    )
+                    .ok();
+                    writeln!(
+                        self.body,
+                        r#"<polygon points="{}" fill="none" clip-path="url(#{})" {}{}/>"#,
+                        points,
+                        id,
+                        stroke_attrs(stroke.color, stroke.width * 2., Join::Miter, Cap::Butt),
+                        attrs
+                    )
+                    .ok();
+                }
+            }
+            Shape::Polyline(points, _, rot, stroke) => {
+                if stroke.width > 0. {
+                    let attrs = self.attrs(rot, opacity);
+                    writeln!(
+                        self.body,
+                        r#"<polyline points="{}" fill="none" {}{}/>"#,
+                        points_attr(points),
+                        stroke_attrs(stroke.color, stroke.width, stroke.join, stroke.cap),
+                        attrs
+                    )
+                    .ok();
+                }
+            }
+            Shape::Triangle(a, b, c, z, rot, stroke, fill) => {
+                let polygon = Shape::Polygon(
+                    vec![*a, *b, *c],
+                    *z,
+                    rot.clone(),
+                    stroke.clone(),
+                    fill.clone(),
+                );
+                self.shape(&polygon, opacity);
+            }
+            Shape::Mesh(_) => {}
+            Shape::Path(path, _, rot, stroke, fill) => {
+                let attrs = self.attrs(rot, opacity);
+                let d = path_attr(path);
+
+                if let Some(paint) = self.fill(fill, Kind::Linear) {
+                    writeln!(self.body, r#"<path d="{}" {}{}/>"#, d, paint, attrs).ok();
+                }
+                if stroke.width > 0. {
+                    writeln!(
+                        self.body,
+                        r#"<path d="{}" fill="none" {}{}/>"#,
+                        d,
+                        stroke_attrs(stroke.color, stroke.width, stroke.join, stroke.cap),
+                        attrs
+                    )
+                    .ok();
+                }
+            }
+        }
+    }
+
+    /// The fill attributes of a shape, defining gradients and patterns as
+    /// needed. Returns `None` if the shape isn't filled.
+    fn fill(&mut self, fill: &Fill, kind: Kind) -> Option<String> {
+        match fill {
+            Fill::Empty() => None,
+            Fill::Solid(color) => Some(paint_attrs("fill", *color)),
+            Fill::Gradient(from, to, angle) => {
+                let stops = [(0., *from), (1., *to)];
+                let id = self.gradient(&stops, kind, *angle);
+                Some(format!(r#"fill="url(#{})""#, id))
+            }
+            Fill::Stops(gradient) => {
+                let id = self.gradient(gradient.stops(), kind, 0.);
+                Some(format!(r#"fill="url(#{})""#, id))
+            }
+            Fill::Pattern(color, pattern) => {
+                let id = self.id("pattern");
+                let (s, t) = (pattern.spacing.max(1.), pattern.thickness);
+                let paint = paint_attrs("fill", *color);
+
+                // Lines and dots are centered on the pattern cell.
+                let tile = match pattern.style {
+                    PatternStyle::Stripes => format!(
+                        r#"<rect x="0" y="{}" width="{}" height="{}" {}/>"#,
+                        (s - t) / 2.,
+                        s,
+                        t,
+                        paint
+                    ),
+                    PatternStyle::Hatching => format!(
+                        r#"<rect x="0" y="{y}" width="{s}" height="{t}" {p}/><rect x="{y}" y="0" width="{t}" height="{s}" {p}/>"#,
+                        y = (s - t) / 2.,
+                        s = s,
+                        t = t,
+                        p = paint
+                    ),
+                    PatternStyle::Dots => format!(
+                        r#"<circle cx="{}" cy="{}" r="{}" {}/>"#,
+                        s / 2.,
+                        s / 2.,
+                        t / 2.,
+                        paint
+                    ),
+                };
+                writeln!(
+                    self.defs,
+                    r#"<pattern id="{}" x="{}" y="{}" width="{}" height="{}" patternUnits="userSpaceOnUse" patternTransform="rotate({})">{}</pattern>"#,
+                    id,
+                    -s / 2.,
+                    -s / 2.,
+                    s,
+                    s,
+                    pattern.angle.to_degrees(),
+                    tile
+                )
+                .ok();
+
+                Some(format!(r#"fill="url(#{})""#, id))
+            }
+        }
+    }
+
+    /// Define a gradient, and return its id. Linear gradients run in the
+    /// direction of the angle, radial gradients from the center outwards.
+    fn gradient(&mut self, stops: &[(f32, Rgba)], kind: Kind, angle: f32) -> String {
+        let mut out = String::new();
+
+        for (pos, color) in stops {
+            writeln!(
+                out,
+                r#"<stop offset="{}" stop-color="{}" stop-opacity="{}"/>"#,
+                pos,
+                rgb(*color),
+                color.a
+            )
+            .ok();
+        }
+
+        match kind {
+            Kind::Linear => {
+                let id = self.id("linear");
+                let (dx, dy) = (angle.cos() / 2., angle.sin() / 2.);
+                writeln!(
+                    self.defs,
+                    "<linearGradient id=\"{}\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\">\n{}</linearGradient>",
+                    id,
+                    0.5 - dx,
+                    0.5 - dy,
+                    0.5 + dx,
+                    0.5 + dy,
+                    out
+                )
+                .ok();
+                id
+            }
+            Kind::Radial => {
+                let id = self.id("radial");
+                writeln!(
+                    self.defs,
+                    "<radialGradient id=\"{}\">\n{}</radialGradient>",
+                    id, out
+                )
+                .ok();
+                id
+            }
+        }
+    }
+
+    /// Rotation and opacity attributes, with a leading space.
+    fn attrs(&self, rot: &Rotation, opacity: f32) -> String {
+        let mut attrs = String::new();
+
+        if rot.angle != 0. {
+            // Shapes are rotated clockwise.
+            write!(
+                attrs,
+                r#" transform="rotate({} {} {})""#,
+                -rot.angle.to_degrees(),
+                rot.center.x,
+                rot.center.y
+            )
+            .ok();
+        }
+        if opacity < 1. {
+            write!(attrs, r#" opacity="{}""#, opacity).ok();
+        }
+        attrs
+    }
+
+    fn id(&mut self, prefix: &str) -> String {
+        self.ids += 1;
+        format!("{}{}", prefix, self.ids)
+    }
+}
+
+fn rgb(c: Rgba) -> String {
+    let c8 = |v: f32| (v.clamp(0., 1.) * 255.).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", c8(c.r), c8(c.g), c8(c.b))
+}
+
+fn paint_attrs(attr: &str, c: Rgba) -> String {
+    format!(r#"{a}="{}" {a}-opacity="{}""#, rgb(c), c.a, a = attr)
+}
+
+fn stroke_attrs(color: Rgba, width: f32, join: Join, cap: Cap) -> String {
+    let join = match join {
+        Join::Miter => "miter",
+        Join::Round => "round",
+        Join::Bevel => "bevel",
+    };
+    let cap = match cap {
+        Cap::Butt => "butt",
+        Cap::Round => "round",
+        Cap::Square => "square",
+    };
+    format!(
+        r#"{} stroke-width="{}" stroke-linejoin="{}" stroke-linecap="{}" stroke-miterlimit="4""#,
+        paint_attrs("stroke", color),
+        width,
+        join,
+        cap
+    )
+}
+
+fn dash_attrs(dash: &Option<Dash>) -> String {
+    match dash {
+        Some(dash) => format!(
+            r#" stroke-dasharray="{}" stroke-dashoffset="{}""#,
+            dash.pattern
+                .iter()
+                .map(|l| l.to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            dash.offset
+        ),
+        None => String::new(),
+    }
+}
+
+fn points_attr(points: &[Point2<f32>]) -> String {
+    points
+        .iter()
+        .map(|p| format!("{},{}", p.x, p.y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn path_attr(path: &Path) -> String {
+    let mut d = format!("M{},{}", path.start.x, path.start.y);
+
+    for segment in path.segments.iter() {
+        match segment {
+            Segment::Line(p) => write!(d, " L{},{}", p.x, p.y),
+            Segment::Quadratic(c, p) => write!(d, " Q{},{} {},{}", c.x, c.y, p.x, p.y),
+            Segment::Cubic(c1, c2, p) => {
+                write!(d, " C{},{} {},{} {},{}", c1.x, c1.y, c2.x, c2.y, p.x, p.y)
+            }
+        }
+        .ok();
+    }
+    if path.closed {
+        d.push_str(" Z");
+    }
+    d
+}
@@ -0,0 +1,1360 @@
+use std::collections::HashMap;
+
+use rusttype as rt;
+
+use crate::core;
+use crate::core::{Error, Rgba, Rgba8};
+use crate::kit::shape2d::{self, Fill, Path, Rotation, Shape, Stroke};
+use crate::kit::sprite2d;
+use crate::kit::{Repeat, Transform2D, ZDepth};
+use crate::math::*;
+use crate::rect::Rect;
+
+///////////////////////////////////////////////////////////////////////////
+// Font
+///////////////////////////////////////////////////////////////////////////
+
+/// A TrueType or OpenType font.
+#[derive(Clone, Debug)]
+pub struct Font {
+    inner: rt::Font<'static>,
+    /// Position of the top of underlines, and their thickness, in font
+    /// units, from the `post` table.
+    underline: Option<(i16, i16)>,
+    /// Position of the top of strikeouts, and their thickness, in font
+    /// units, from the `OS/2` table.
+    strikeout: Option<(i16, i16)>,
+}
+
+impl Font {
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        let underline = table(&bytes, b"post").and_then(|t| Some((i16_at(t, 8)?, i16_at(t, 10)?)));
+        let strikeout = table(&bytes, b"OS/2").and_then(|t| Some((i16_at(t, 28)?, i16_at(t, 26)?)));
+
+        rt::Font::from_bytes(bytes)
+            .map(|inner| Self {
+                inner,
+                underline,
+                strikeout,
+            })
+            .map_err(|_| Error::InvalidFont)
+    }
+
+    /// Scale from font units to pixels, at the given size.
+    fn units(&self, size: f32) -> f32 {
+        let v = self.inner.v_metrics_unscaled();
+        size / (v.ascent - v.descent)
+    }
+
+    /// Distance from the baseline to the top of the highest glyph, at the
+    /// given size.
+    pub fn ascent(&self, size: f32) -> f32 {
+        self.inner.v_metrics(rt::Scale::uniform(size)).ascent
+    }
+
+    /// Distance from the baseline to the bottom of the lowest glyph, at the
+    /// given size. This is usually negative.
+    pub fn descent(&self, size: f32) -> f32 {
+        self.inner.v_metrics(rt::Scale::uniform(size)).descent
+    }
+
+    /// Distance between two consecutive baselines, at the given size.
+    pub fn line_height(&self, size: f32) -> f32 {
+        let v = self.inner.v_metrics(rt::Scale::uniform(size));
+        v.ascent - v.descent + v.line_gap
+    }
+
+    /// Height of the lowercase letters, from the baseline, at the given
+    /// size. Falls back to half the ascent for fonts without an `x`.
+    pub fn x_height(&self, size: f32) -> f32 {
+        self.inner
+            .glyph('x')
+            .scaled(rt::Scale::uniform(size))
+            .exact_bounding_box()
+            .map_or(self.ascent(size) / 2., |bb| -bb.min.y)
+    }
+
+    /// Offset of the center of underlines from the baseline, at the given
+    /// size, as given by the font. This is negative, as underlines are below
+    /// the baseline. Fonts without underline metrics are underlined half
+    /// way down to their descent.
+    pub fn underline_position(&self, size: f32) -> f32 {
+        match self.underline {
+            Some((top, thickness)) => (top as f32 - thickness as f32 / 2.) * self.units(size),
+            None => self.descent(size) / 2.,
+        }
+    }
+
+    /// Thickness of underlines, at the given size, as given by the font,
+    /// and at least a pixel. Fonts without underline metrics get lines a
+    /// sixteenth of their size thick.
+    pub fn underline_thickness(&self, size: f32) -> f32 {
+        match self.underline {
+            Some((_, thickness)) => thickness as f32 * self.units(size),
+            None => size / 16.,
+        }
+        .max(1.)
+    }
+
+    /// Offset of the center of strikethroughs from the baseline, at the
+    /// given size, as given by the font. Fonts without strikeout metrics are
+    /// struck through the middle of their lowercase letters.
+    pub fn strikeout_position(&self, size: f32) -> f32 {
+        match self.strikeout {
+            Some((top, thickness)) => (top as f32 - thickness as f32 / 2.) * self.units(size),
+            None => self.x_height(size) / 2.,
+        }
+    }
+
+    /// Thickness of strikethroughs, at the given size, as given by the
+    /// font, and at least a pixel. Fonts without strikeout metrics use the
+    /// underline thickness.
+    pub fn strikeout_thickness(&self, size: f32) -> f32 {
+        match self.strikeout {
+            Some((_, thickness)) => (thickness as f32 * self.units(size)).max(1.),
+            None => self.underline_thickness(size),
+        }
+    }
+
+    /// Width of the given single line of text, at the given size.
+    pub fn width(&self, text: &str, size: f32) -> f32 {
+        let scale = rt::Scale::uniform(size);
+        let mut width = 0.;
+        let mut prev = None;
+
+        for c in text.chars() {
+            let glyph = self.inner.glyph(c);
+            let id = glyph.id();
+
+            if let Some(prev) = prev {
+                width += self.inner.pair_kerning(scale, prev, id);
+            }
+            width += glyph.scaled(scale).h_metrics().advance_width;
+            prev = Some(id);
+        }
+        width
+    }
+
+    /// Height of the given single column of vertical text, at the given
+    /// size. See `Orientation::Vertical`.
+    ///
+    /// ```no_run
+    /// use rgx::kit::text::Font;
+    ///
+    /// let font = Font::from_bytes(std::fs::read("font.ttf").unwrap()).unwrap();
+    ///
+    /// // Glyphs of vertical text are a line apart, whatever their width.
+    /// assert_eq!(font.column_height("縦書き", 16.), 3. * font.line_height(16.));
+    /// ```
+    pub fn column_height(&self, text: &str, size: f32) -> f32 {
+        text.chars().count() as f32 * self.line_height(size)
+    }
+
+    /// The outline of a glyph, as one closed path per contour, with its
+    /// origin on the baseline at the given position, and an upwards pointing
+    /// Y axis, as with `TextBatch`. Glyphs without outlines, eg. spaces, have
+    /// no paths.
+    pub fn glyph_outline(&self, c: char, pos: Point2<f32>, size: f32) -> Vec<Path> {
+        let glyph = self.inner.glyph(c).scaled(rt::Scale::uniform(size));
+        Self::contours(&glyph, pos)
+    }
+
+    /// The outlines of the glyphs of the given text, laid out as with
+    /// `TextBatch::add`, eg. to stroke text, or fill it with a gradient, as
+    /// a `Shape::Path` per contour.
+    ///
+    /// Path fills don't cut out the holes of glyphs, eg. of `o`. To fill
+    /// text with its holes, build a single path of all its contours with
+    /// `shape2d::lyon::from_paths`, with the `lyon` feature.
+    pub fn outlines(&self, text: &str, pos: Point2<f32>, size: f32) -> Vec<Path> {
+        self.layout(text, pos, size)
+            .iter()
+            .flat_map(|(glyph, origin)| Self::contours(glyph, *origin))
+            .collect()
+    }
+
+    /// The outlines of the glyphs of a run of text, in its orientation,
+    /// before the rotation of rotated runs.
+    pub(crate) fn run_outlines(&self, run: &Run) -> Vec<Path> {
+        self.layout_run(run)
+            .iter()
+            .flat_map(|(glyph, origin)| Self::contours(glyph, *origin))
+            .collect()
+    }
+
+    /// The contours of a glyph, with its origin at the given position.
+    fn contours(glyph: &rt::ScaledGlyph<'static>, origin: Point2<f32>) -> Vec<Path> {
+        // Unlike glyph bounds, glyph outlines have an upwards pointing Y axis.
+        let point = |p: rt::Point<f32>| Point2::new(origin.x + p.x, origin.y + p.y);
+
+        glyph
+            .shape()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|contour| {
+                let start = match contour.segments.first()? {
+                    rt::Segment::Line(l) => l.p[0],
+                    rt::Segment::Curve(c) => c.p[0],
+                };
+                let path =
+                    contour
+                        .segments
+                        .iter()
+                        .fold(Path::new(point(start)), |path, segment| match segment {
+                            rt::Segment::Line(l) => path.line_to(point(l.p[1])),
+                            rt::Segment::Curve(c) => {
+                                path.quadratic_to(point(c.p[1]), point(c.p[2]))
+                            }
+                        });
+                Some(path.close())
+            })
+            .collect()
+    }
+
+    /// Lay out the given text, returning each glyph with its origin on the
+    /// baseline. The position is the start of the baseline of the first
+    /// line. Lines are separated by `\n`.
+    pub(crate) fn layout(
+        &self,
+        text: &str,
+        pos: Point2<f32>,
+        size: f32,
+    ) -> Vec<(rt::ScaledGlyph<'static>, Point2<f32>)> {
+        let scale = rt::Scale::uniform(size);
+        let line_height = self.line_height(size);
+        let mut glyphs = Vec::new();
+
+        let (mut x, mut y) = (pos.x, pos.y);
+        let mut prev = None;
+
+        for c in text.chars() {
+            if c == '\n' {
+                x = pos.x;
+                y -= line_height;
+                prev = None;
+                continue;
+            }
+            let glyph = self.inner.glyph(c);
+            let id = glyph.id();
+
+            if let Some(prev) = prev {
+                x += self.inner.pair_kerning(scale, prev, id);
+            }
+            let glyph = glyph.scaled(scale);
+            let advance = glyph.h_metrics().advance_width;
+
+            glyphs.push((glyph, Point2::new(x, y)));
+
+            x += advance;
+            prev = Some(id);
+        }
+        glyphs
+    }
+
+    /// Lay out the given text top to bottom, returning each glyph with its
+    /// origin on the baseline. The position is the top of the center of the
+    /// first column. Columns are separated by `\n`, and stacked right to
+    /// left.
+    pub(crate) fn layout_vertical(
+        &self,
+        text: &str,
+        pos: Point2<f32>,
+        size: f32,
+    ) -> Vec<(rt::ScaledGlyph<'static>, Point2<f32>)> {
+        let scale = rt::Scale::uniform(size);
+        let ascent = self.ascent(size);
+        // Glyphs are upright, so they are a line apart, whatever their width,
+        // and columns are as wide as lines are high.
+        let advance = self.line_height(size);
+        let mut glyphs = Vec::new();
+
+        let (mut x, mut y) = (pos.x, pos.y);
+
+        for c in text.chars() {
+            if c == '\n' {
+                x -= advance;
+                y = pos.y;
+                continue;
+            }
+            let glyph = self.inner.glyph(c).scaled(scale);
+            let width = glyph.h_metrics().advance_width;
+
+            glyphs.push((glyph, Point2::new(x - width / 2., y - ascent)));
+
+            y -= advance;
+        }
+        glyphs
+    }
+
+    /// Lay out a run of text in its orientation, before the rotation of
+    /// rotated runs.
+    pub(crate) fn layout_run(&self, run: &Run) -> Vec<(rt::ScaledGlyph<'static>, Point2<f32>)> {
+        match run.orientation {
+            Orientation::Vertical => self.layout_vertical(&run.text, run.pos, run.size),
+            _ => self.layout(&run.text, run.pos, run.size),
+        }
+    }
+}
+
+/// The data of a table of a font, or of the first font of a collection.
+fn table<'a>(bytes: &'a [u8], tag: &[u8; 4]) -> Option<&'a [u8]> {
+    let font = if bytes.get(..4)? == b"ttcf" {
+        u32_at(bytes, 12)? as usize
+    } else {
+        0
+    };
+    let tables = u16_at(bytes, font + 4)? as usize;
+
+    (0..tables).find_map(|i| {
+        let record = font + 12 + i * 16;
+
+        if bytes.get(record..record + 4)? != tag {
+            return None;
+        }
+        let offset = u32_at(bytes, record + 8)? as usize;
+        let len = u32_at(bytes, record + 12)? as usize;
+
+        bytes.get(offset..offset.checked_add(len)?)
+    })
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+    let b = bytes.get(at..at + 2)?;
+    Some(u16::from_be_bytes([b[0], b[1]]))
+}
+
+fn i16_at(bytes: &[u8], at: usize) -> Option<i16> {
+    u16_at(bytes, at).map(|n| n as i16)
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    let b = bytes.get(at..at + 4)?;
+    Some(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+///////////////////////////////////////////////////////////////////////////
+// GlyphAtlas
+///////////////////////////////////////////////////////////////////////////
+
+#[derive(Copy, Clone, Debug)]
+struct CachedGlyph {
+    /// Location of the glyph in the atlas, in pixels.
+    src: Rect<f32>,
+    /// Bounds of the glyph relative to its origin on the baseline, with a
+    /// downwards pointing Y axis.
+    bounds: Rect<f32>,
+}
+
+/// A texture atlas of rasterized glyphs. Glyphs are rasterized on demand,
+/// and cached for each size they are rendered at.
+///
+/// When a glyph doesn't fit, the atlas can be repacked to reclaim the space
+/// wasted at the end of its rows, which moves the glyphs already in it.
+/// `TextBatch` does this on its own, and lays its text out again.
+#[derive(Debug)]
+pub struct GlyphAtlas {
+    pub w: u32,
+    pub h: u32,
+
+    pixels: Vec<Rgba8>,
+    glyphs: HashMap<(rt::GlyphId, u32), Option<CachedGlyph>>,
+    cursor: (u32, u32),
+    row: u32,
+    dirty: bool,
+    /// Whether a glyph didn't fit since the last call to `overflowed`.
+    overflow: bool,
+    /// Whether the atlas is packed, ie. no glyph was added since the last
+    /// repack.
+    packed: bool,
+}
+
+impl GlyphAtlas {
+    /// Padding between glyphs, to avoid sampling neighbours.
+    const PADDING: u32 = 1;
+
+    pub fn new(w: u32, h: u32) -> Self {
+        Self {
+            w,
+            h,
+            pixels: vec![Rgba8::TRANSPARENT; (w * h) as usize],
+            glyphs: HashMap::new(),
+            cursor: (Self::PADDING, Self::PADDING),
+            row: 0,
+            dirty: true,
+            overflow: false,
+            packed: true,
+        }
+    }
+
+    /// The atlas pixels, as white glyphs on a transparent background.
+    pub fn pixels(&self) -> &[Rgba8] {
+        &self.pixels
+    }
+
+    /// Whether glyphs were added since the atlas was last uploaded.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Create a texture of the atlas size. The atlas still needs to be
+    /// uploaded to it.
+    pub fn texture(&self, r: &core::Renderer) -> core::Texture {
+        r.texture(self.w, self.h)
+    }
+
+    /// Upload the atlas to the given texture, if it changed.
+    pub fn upload(&mut self, texture: &core::Texture, r: &mut core::Renderer) {
+        if self.dirty {
+            r.submit(&[core::Op::Fill(texture, &self.pixels)]);
+            self.dirty = false;
+        }
+    }
+
+    /// Remove all glyphs from the atlas.
+    pub fn clear(&mut self) {
+        for p in self.pixels.iter_mut() {
+            *p = Rgba8::TRANSPARENT;
+        }
+        self.glyphs.clear();
+        self.cursor = (Self::PADDING, Self::PADDING);
+        self.row = 0;
+        self.dirty = true;
+        self.overflow = false;
+        self.packed = true;
+    }
+
+    /// Whether a glyph didn't fit in the atlas since this was last called.
+    pub fn overflowed(&mut self) -> bool {
+        std::mem::replace(&mut self.overflow, false)
+    }
+
+    /// Repack the glyphs, tallest first, into the least rows possible. Returns
+    /// `false` if the atlas was already packed, in which case nothing moves.
+    ///
+    /// Glyphs move in the atlas, so text laid out before must be laid out
+    /// again. Glyphs that no longer fit are evicted, to be rasterized again
+    /// when next used.
+    pub fn repack(&mut self) -> bool {
+        if self.packed {
+            return false;
+        }
+        let mut glyphs: Vec<_> = self
+            .glyphs
+            .iter()
+            .filter_map(|(k, g)| g.map(|g| (*k, g)))
+            .collect();
+        glyphs.sort_by(|(ka, a), (kb, b)| {
+            b.src
+                .height()
+                .partial_cmp(&a.src.height())
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then((ka.0).0.cmp(&(kb.0).0))
+                .then(ka.1.cmp(&kb.1))
+        });
+
+        let old = std::mem::replace(
+            &mut self.pixels,
+            vec![Rgba8::TRANSPARENT; (self.w * self.h) as usize],
+        );
+        // Glyphs without pixels are cached again when next used.
+        self.glyphs.clear();
+        self.cursor = (Self::PADDING, Self::PADDING);
+        self.row = 0;
+
+        for (key, mut g) in glyphs {
+            let (w, h) = (g.src.width() as u32, g.src.height() as u32);
+            let (x, y) = match self.allocate(w, h) {
+                Some(pos) => pos,
+                None => continue,
+            };
+            let (sx, sy) = (g.src.x1 as u32, g.src.y1 as u32);
+
+            for row in 0..h {
+                let src = ((sy + row) * self.w + sx) as usize;
+                let dst = ((y + row) * self.w + x) as usize;
+
+                self.pixels[dst..dst + w as usize].copy_from_slice(&old[src..src + w as usize]);
+            }
+            g.src = Rect::new(x as f32, y as f32, (x + w) as f32, (y + h) as f32);
+            self.glyphs.insert(key, Some(g));
+        }
+        self.dirty = true;
+        self.packed = true;
+
+        true
+    }
+
+    /// Find room for a glyph of the given size, with simple shelf packing:
+    /// glyphs are placed left to right, on rows as high as their tallest
+    /// glyph.
+    fn allocate(&mut self, w: u32, h: u32) -> Option<(u32, u32)> {
+        if self.cursor.0 + w + Self::PADDING > self.w {
+            self.cursor = (Self::PADDING, self.cursor.1 + self.row + Self::PADDING);
+            self.row = 0;
+        }
+        if self.cursor.0 + w + Self::PADDING > self.w || self.cursor.1 + h + Self::PADDING > self.h
+        {
+            return None;
+        }
+        let (x, y) = self.cursor;
+
+        self.cursor.0 += w + Self::PADDING;
+        self.row = self.row.max(h);
+
+        Some((x, y))
+    }
+
+    fn glyph(&mut self, glyph: rt::ScaledGlyph<'static>) -> Option<CachedGlyph> {
+        let key = (glyph.id(), glyph.scale().y.to_bits());
+
+        if let Some(cached) = self.glyphs.get(&key) {
+            return *cached;
+        }
+        match self.rasterize(glyph) {
+            Ok(cached) => {
+                self.glyphs.insert(key, cached);
+                cached
+            }
+            // Glyphs that don't fit aren't cached, so that they can be
+            // added once the atlas is repacked.
+            Err(()) => {
+                self.overflow = true;
+                None
+            }
+        }
+    }
+
+    /// Rasterize a glyph into the atlas. Returns `Ok(None)` for glyphs
+    /// without pixels, eg. spaces, and an error if the atlas is full.
+    fn rasterize(&mut self, glyph: rt::ScaledGlyph<'static>) -> Result<Option<CachedGlyph>, ()> {
+        let glyph = glyph.positioned(rt::point(0., 0.));
+        let bb = match glyph.pixel_bounding_box() {
+            Some(bb) => bb,
+            None => return Ok(None),
+        };
+        let (w, h) = (bb.width() as u32, bb.height() as u32);
+        let (x, y) = self.allocate(w, h).ok_or(())?;
+
+        let atlas_w = self.w;
+        let pixels = &mut self.pixels;
+        glyph.draw(|gx, gy, v| {
+            let ix = ((y + gy) * atlas_w + x + gx) as usize;
+            pixels[ix] = Rgba8::new(0xff, 0xff, 0xff, (v * 255.).round() as u8);
+        });
+
+        self.dirty = true;
+        self.packed = false;
+
+        Ok(Some(CachedGlyph {
+            src: Rect::new(x as f32, y as f32, (x + w) as f32, (y + h) as f32),
+            bounds: Rect::new(
+                bb.min.x as f32,
+                bb.min.y as f32,
+                bb.max.x as f32,
+                bb.max.y as f32,
+            ),
+        }))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// TextBatch
+///////////////////////////////////////////////////////////////////////////
+
+/// A decoration of a run of text, drawn as a rectangle placed with the
+/// underline and strikeout metrics of its font. See `TextBatch::decorate`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Decoration {
+    /// A line under the baseline, or to the right of vertical text.
+    Underline(Rgba),
+    /// A line through the lowercase letters, or through the center of
+    /// vertical text.
+    Strikethrough(Rgba),
+    /// A background, from the ascent to the descent of each line, or across
+    /// the width of each column of vertical text.
+    Highlight(Rgba),
+}
+
+/// Direction of a run of text.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Orientation {
+    /// Left to right, with lines stacked downwards.
+    Horizontal,
+    /// Left to right, rotated counter-clockwise by the given angle, in
+    /// radians, around the position of the run, eg. for chart axis labels.
+    Rotated(f32),
+    /// Top to bottom, with upright glyphs centered on their column, and
+    /// columns stacked right to left, as with CJK text. The position of the
+    /// run is the top of the center of its first column.
+    Vertical,
+}
+
+/// A run of text, as added to a `TextBatch`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Run {
+    pub text: String,
+    /// Start of the baseline of the first line, unless the text is vertical.
+    pub pos: Point2<f32>,
+    pub size: f32,
+    pub color: Rgba,
+    pub orientation: Orientation,
+}
+
+impl Run {
+    /// The rotation of the run, as a shape rotation, which is clockwise.
+    pub(crate) fn rotation(&self) -> Rotation {
+        match self.orientation {
+            Orientation::Rotated(angle) => Rotation::new(-angle, self.pos),
+            _ => Rotation::ZERO,
+        }
+    }
+}
+
+/// A batch of text, rendered with the sprite pipeline, using the glyph
+/// atlas as texture.
+#[derive(Debug)]
+pub struct TextBatch {
+    pub font: Font,
+    pub atlas: GlyphAtlas,
+    pub zdepth: ZDepth,
+
+    sprites: sprite2d::Batch,
+    runs: Vec<Run>,
+    /// Decorations, with the index of the run they decorate.
+    decorations: Vec<(usize, Decoration, ZDepth)>,
+}
+
+impl TextBatch {
+    pub fn new(font: Font, atlas: GlyphAtlas) -> Self {
+        let sprites = sprite2d::Batch::new(atlas.w, atlas.h);
+
+        Self {
+            font,
+            atlas,
+            zdepth: ZDepth::default(),
+            sprites,
+            runs: Vec::new(),
+            decorations: Vec::new(),
+        }
+    }
+
+    /// Add text to the batch. The position is the start of the baseline
+    /// of the first line. Lines are separated by `\n`.
+    ///
+    /// If the glyph atlas overflows, it is repacked, and all the text in the
+    /// batch is laid out again. Glyphs that still don't fit are skipped.
+    pub fn add(&mut self, text: &str, pos: Point2<f32>, size: f32, color: Rgba) {
+        self.add_run(Run {
+            text: text.to_owned(),
+            pos,
+            size,
+            color,
+            orientation: Orientation::Horizontal,
+        });
+    }
+
+    /// Add text rotated counter-clockwise by the given angle, in radians,
+    /// around the start of the baseline of its first line.
+    ///
+    /// ```no_run
+    /// use rgx::core::Rgba;
+    /// use rgx::kit::text::{Font, GlyphAtlas, Orientation, TextBatch};
+    /// use rgx::math::Point2;
+    ///
+    /// let font = Font::from_bytes(std::fs::read("font.ttf").unwrap()).unwrap();
+    /// let mut batch = TextBatch::new(font, GlyphAtlas::new(512, 512));
+    ///
+    /// // A label along a vertical chart axis, reading upwards.
+    /// let angle = std::f32::consts::FRAC_PI_2;
+    /// batch.add_rotated("Revenue", Point2::new(24., 100.), 16., Rgba::BLACK, angle);
+    ///
+    /// assert_eq!(batch.runs()[0].orientation, Orientation::Rotated(angle));
+    /// ```
+    pub fn add_rotated(
+        &mut self,
+        text: &str,
+        pos: Point2<f32>,
+        size: f32,
+        color: Rgba,
+        angle: f32,
+    ) {
+        self.add_run(Run {
+            text: text.to_owned(),
+            pos,
+            size,
+            color,
+            orientation: Orientation::Rotated(angle),
+        });
+    }
+
+    /// Add vertical text, from the top of the center of its first column.
+    /// Columns are separated by `\n`. See `Orientation::Vertical`.
+    ///
+    /// ```no_run
+    /// use rgx::core::Rgba;
+    /// use rgx::kit::text::{Font, GlyphAtlas, TextBatch};
+    /// use rgx::math::Point2;
+    ///
+    /// let font = Font::from_bytes(std::fs::read("font.ttf").unwrap()).unwrap();
+    /// let mut batch = TextBatch::new(font, GlyphAtlas::new(512, 512));
+    ///
+    /// // Two columns, the second to the left of the first.
+    /// batch.add_vertical("縦書き\n二列目", Point2::new(200., 300.), 16., Rgba::BLACK);
+    ///
+    /// // One quad per glyph, none for the line break.
+    /// assert_eq!(batch.vertices().len(), 6 * 4);
+    /// ```
+    pub fn add_vertical(&mut self, text: &str, pos: Point2<f32>, size: f32, color: Rgba) {
+        self.add_run(Run {
+            text: text.to_owned(),
+            pos,
+            size,
+            color,
+            orientation: Orientation::Vertical,
+        });
+    }
+
+    /// Add a run of text, in its orientation.
+    pub fn add_run(&mut self, run: Run) {
+        self.layout(&run);
+        self.runs.push(run);
+
+        if self.atlas.overflowed() && self.atlas.repack() {
+            self.sprites.clear();
+
+            for run in std::mem::take(&mut self.runs) {
+                self.layout(&run);
+                self.runs.push(run);
+            }
+            self.atlas.overflowed();
+        }
+    }
+
+    fn layout(&mut self, run: &Run) {
+        // The glyph color replaces the white of the atlas, while its alpha
+        // is applied as opacity.
+        let color = run.color;
+        let rgba = Rgba::new(color.r, color.g, color.b, 1.0);
+
+        if let Orientation::Rotated(angle) = run.orientation {
+            self.sprites.push_transform(
+                Transform2D::new()
+                    .origin(run.pos.x, run.pos.y)
+                    .rotate(angle),
+            );
+        }
+        for (glyph, origin) in self.font.layout_run(run) {
+            if let Some(g) = self.atlas.glyph(glyph) {
+                let (gx, gy) = (origin.x.round(), origin.y.round());
+                let dst = Rect::new(
+                    gx + g.bounds.x1,
+                    gy - g.bounds.y2,
+                    gx + g.bounds.x2,
+                    gy - g.bounds.y1,
+                );
+                self.sprites
+                    .add(g.src, dst, self.zdepth, rgba, color.a, Repeat::default());
+            }
+        }
+        if let Orientation::Rotated(_) = run.orientation {
+            self.sprites.pop_transform();
+        }
+    }
+
+    /// Decorate the run of text added last, eg. to underline a link, or
+    /// highlight a search result. Runs can have more than one decoration.
+    ///
+    /// Decorations are drawn with the shape pipeline, from the batch returned
+    /// by `TextBatch::decorations`.
+    pub fn decorate(&mut self, decoration: Decoration) {
+        assert!(
+            !self.runs.is_empty(),
+            "fatal: `decorate` called before any text was added"
+        );
+        self.decorations
+            .push((self.runs.len() - 1, decoration, self.zdepth));
+    }
+
+    /// The decorations of the text, as rectangles: highlights first, then
+    /// lines. Draw them before the text, for glyphs to be over them.
+    pub fn decorations(&self) -> shape2d::Batch {
+        let mut batch = shape2d::Batch::new();
+        let (highlights, lines): (Vec<_>, Vec<_>) = self
+            .decorations
+            .iter()
+            .partition(|(_, d, _)| matches!(d, Decoration::Highlight(_)));
+
+        for &(run, decoration, zdepth) in highlights.into_iter().chain(lines) {
+            for shape in self.decoration(&self.runs[run], decoration, zdepth) {
+                batch.add(shape);
+            }
+        }
+        batch
+    }
+
+    /// The rectangles of a decoration of a run, one per line or column.
+    fn decoration(&self, run: &Run, decoration: Decoration, zdepth: ZDepth) -> Vec<Shape> {
+        let font = &self.font;
+        let (pos, size) = (run.pos, run.size);
+        let line_height = font.line_height(size);
+        let mut shapes = Vec::new();
+
+        let color = match decoration {
+            Decoration::Underline(c) | Decoration::Strikethrough(c) | Decoration::Highlight(c) => c,
+        };
+        // Lines centered on the given offset, and highlights.
+        let (offset, thickness) = match decoration {
+            Decoration::Underline(_) => (
+                font.underline_position(size),
+                font.underline_thickness(size),
+            ),
+            Decoration::Strikethrough(_) => (
+                font.strikeout_position(size),
+                font.strikeout_thickness(size),
+            ),
+            Decoration::Highlight(_) => (0., 0.),
+        };
+        let rect = |r: Rect<f32>, rotation: Rotation| {
+            Shape::Rectangle(r, zdepth, rotation, Stroke::NONE, Fill::Solid(color))
+        };
+
+        if let Orientation::Vertical = run.orientation {
+            for (i, column) in run.text.split('\n').enumerate() {
+                let n = column.chars().count();
+                if n == 0 {
+                    continue;
+                }
+                let x = pos.x - i as f32 * line_height;
+                let (y1, y2) = (pos.y - n as f32 * line_height, pos.y);
+                // Underlines are to the right of the em box of the glyphs, and
+                // strikethroughs through their center.
+                let x = match decoration {
+                    Decoration::Underline(_) => x + size / 2. - offset,
+                    _ => x,
+                };
+                let (x1, x2) = match decoration {
+                    Decoration::Highlight(_) => (x - line_height / 2., x + line_height / 2.),
+                    _ => (x - thickness / 2., x + thickness / 2.),
+                };
+                shapes.push(rect(Rect::new(x1, y1, x2, y2), Rotation::ZERO));
+            }
+            return shapes;
+        }
+
+        for (i, line) in run.text.split('\n').enumerate() {
+            let width = font.width(line, size);
+            if width <= 0. {
+                continue;
+            }
+            let baseline = pos.y - i as f32 * line_height;
+            let (y1, y2) = match decoration {
+                Decoration::Highlight(_) => {
+                    (baseline + font.descent(size), baseline + font.ascent(size))
+                }
+                _ => (
+                    baseline + offset - thickness / 2.,
+                    baseline + offset + thickness / 2.,
+                ),
+            };
+            shapes.push(rect(
+                Rect::new(pos.x, y1, pos.x + width, y2),
+                run.rotation(),
+            ));
+        }
+        shapes
+    }
+
+    /// Lay the given text out at the given position, and add it to the
+    /// batch. Returns the bounds of the text.
+    pub fn add_layout(&mut self, layout: &TextLayout, pos: Point2<f32>) -> Rect<f32> {
+        let Layout { runs, bounds } = layout.layout(&self.font, pos);
+
+        for run in runs {
+            self.add_run(run);
+        }
+        bounds
+    }
+
+    /// The text added to the batch, in order.
+    pub fn runs(&self) -> &[Run] {
+        &self.runs
+    }
+
+    pub fn vertices(&self) -> Vec<sprite2d::Vertex> {
+        self.sprites.vertices()
+    }
+
+    pub fn indices(&self) -> Vec<u32> {
+        self.sprites.indices()
+    }
+
+    pub fn buffer(&self, r: &core::Renderer) -> core::IndexedBuffer {
+        core::IndexedBuffer {
+            vertices: r.device.create_buffer(self.vertices().as_slice()),
+            indices: r.device.create_index_buffer(self.indices().as_slice()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+
+    /// Clear the text and its decorations, keeping the glyph atlas.
+    pub fn clear(&mut self) {
+        self.sprites.clear();
+        self.runs.clear();
+        self.decorations.clear();
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// TextLayout
+///////////////////////////////////////////////////////////////////////////
+
+/// Horizontal alignment of the lines of a `TextLayout`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// Vertical alignment of a `TextLayout`, relative to its position.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VAlign {
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// A span of text, with its own size and color.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub size: f32,
+    pub color: Rgba,
+}
+
+/// Text laid out into positioned runs, by `TextLayout::layout`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layout {
+    /// Runs of a single line and span each.
+    pub runs: Vec<Run>,
+    /// Bounds of the text, from the top of its first line to the bottom of
+    /// its last line, with an upwards pointing Y axis, as with `Run::pos`.
+    pub bounds: Rect<f32>,
+}
+
+/// Rich text, made of spans of different sizes and colors, wrapped to a
+/// maximum width and aligned around a position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextLayout {
+    /// Width to wrap lines at, between words. Words wider than this aren't
+    /// broken, and overflow.
+    pub max_width: Option<f32>,
+    /// Alignment of the lines. Lines are aligned within the maximum width if
+    /// there is one, and around the position otherwise.
+    pub align: Align,
+    /// Whether the position is at the top, middle or bottom of the text.
+    pub valign: VAlign,
+    /// Multiplier of the line height of each line.
+    pub line_spacing: f32,
+
+    spans: Vec<Span>,
+}
+
+impl Default for TextLayout {
+    fn default() -> Self {
+        Self {
+            max_width: None,
+            align: Align::Left,
+            valign: VAlign::Top,
+            line_spacing: 1.,
+            spans: Vec::new(),
+        }
+    }
+}
+
+impl TextLayout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_width(mut self, width: f32) -> Self {
+        self.max_width = Some(width);
+        self
+    }
+
+    pub fn align(mut self, align: Align, valign: VAlign) -> Self {
+        self.align = align;
+        self.valign = valign;
+        self
+    }
+
+    pub fn line_spacing(mut self, spacing: f32) -> Self {
+        self.line_spacing = spacing;
+        self
+    }
+
+    /// Append a span of text. Lines are separated by `\n`.
+    pub fn span(mut self, text: &str, size: f32, color: Rgba) -> Self {
+        self.push(text, size, color);
+        self
+    }
+
+    /// Append a span of text, in place.
+    pub fn push(&mut self, text: &str, size: f32, color: Rgba) {
+        self.spans.push(Span {
+            text: text.to_owned(),
+            size,
+            color,
+        });
+    }
+
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    /// Size of the laid out text, eg. to size a container around it.
+    pub fn measure(&self, font: &Font) -> (f32, f32) {
+        let bounds = self.layout(font, Point2::new(0., 0.)).bounds;
+        (bounds.width(), bounds.height())
+    }
+
+    /// Lay the text out at the given position, which the text is aligned
+    /// around.
+    pub fn layout(&self, font: &Font, pos: Point2<f32>) -> Layout {
+        if self.spans.is_empty() {
+            return Layout {
+                runs: Vec::new(),
+                bounds: Rect::new(pos.x, pos.y, pos.x, pos.y),
+            };
+        }
+        let lines = self.lines(font);
+
+        // Ascent, descent and height of each line, from its tallest span, or
+        // from the span it was broken in, if it's empty.
+        let metrics: Vec<(f32, f32, f32)> = lines
+            .iter()
+            .map(|(start, line)| {
+                let spans: Vec<usize> = if line.is_empty() {
+                    vec![*start]
+                } else {
+                    line.iter().map(|(span, _)| *span).collect()
+                };
+                spans.iter().fold((0., 0., 0.), |(a, d, h), span| {
+                    let size = self.spans[*span].size;
+                    (
+                        f32::max(a, font.ascent(size)),
+                        f32::min(d, font.descent(size)),
+                        f32::max(h, font.line_height(size) * self.line_spacing),
+                    )
+                })
+            })
+            .collect();
+        // The last line ends at its descent, rather than at the next line.
+        let height = match metrics.split_last() {
+            Some(((ascent, descent, _), rest)) => {
+                rest.iter().map(|(_, _, h)| h).sum::<f32>() + ascent - descent
+            }
+            None => 0.,
+        };
+        let top = match self.valign {
+            VAlign::Top => pos.y,
+            VAlign::Middle => pos.y + height / 2.,
+            VAlign::Bottom => pos.y + height,
+        };
+
+        let mut runs = Vec::new();
+        let (mut left, mut right) = (f32::MAX, f32::MIN);
+        let mut y = top;
+
+        for ((_, line), (ascent, _, line_height)) in lines.iter().zip(metrics.iter()) {
+            let baseline = y - ascent;
+            let width = line
+                .iter()
+                .enumerate()
+                .map(|(i, (span, text))| {
+                    let text = if i == line.len() - 1 {
+                        text.trim_end()
+                    } else {
+                        text
+                    };
+                    font.width(text, self.spans[*span].size)
+                })
+                .sum::<f32>();
+            let mut x = match (self.align, self.max_width) {
+                (Align::Left, _) => pos.x,
+                (Align::Center, Some(max)) => pos.x + (max - width) / 2.,
+                (Align::Right, Some(max)) => pos.x + max - width,
+                (Align::Center, None) => pos.x - width / 2.,
+                (Align::Right, None) => pos.x - width,
+            };
+            left = left.min(x);
+            right = right.max(x + width);
+
+            for (span, text) in line {
+                let span = &self.spans[*span];
+
+                runs.push(Run {
+                    text: text.clone(),
+                    pos: Point2::new(x, baseline),
+                    size: span.size,
+                    color: span.color,
+                    orientation: Orientation::Horizontal,
+                });
+                x += font.width(text, span.size);
+            }
+            y -= line_height;
+        }
+        if left > right {
+            left = pos.x;
+            right = pos.x;
+        }
+
+        Layout {
+            runs,
+            bounds: Rect::new(left, top - height, right, top),
+        }
+    }
+
+    /// Break the spans into lines, of pieces of text of a single span each,
+    /// along with the span each line starts in.
+    fn lines(&self, font: &Font) -> Vec<Line> {
+        let mut lines: Vec<Line> = vec![(0, Vec::new())];
+        let mut width = 0.;
+
+        for (i, span) in self.spans.iter().enumerate() {
+            for (n, segment) in span.text.split('\n').enumerate() {
+                if n > 0 {
+                    lines.push((i, Vec::new()));
+                    width = 0.;
+                }
+                for word in words(segment) {
+                    let trimmed = font.width(word.trim_end(), span.size);
+
+                    if let Some(max) = self.max_width {
+                        if !lines[lines.len() - 1].1.is_empty() && width + trimmed > max {
+                            lines.push((i, Vec::new()));
+                            width = 0.;
+                        }
+                    }
+                    let (_, line) = lines.last_mut().expect("there is always a line");
+
+                    match line.last_mut() {
+                        Some((s, text)) if *s == i => text.push_str(word),
+                        _ => line.push((i, word.to_owned())),
+                    }
+                    width += font.width(word, span.size);
+                }
+            }
+        }
+        lines
+    }
+}
+
+/// A line of a `TextLayout`: the span it starts in, and its pieces of text,
+/// by span.
+type Line = (usize, Vec<(usize, String)>);
+
+/// Split text into words, each followed by the whitespace after it.
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let word = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let end = rest[word..]
+            .find(|c: char| !c.is_whitespace())
+            .map_or(rest.len(), |n| word + n);
+        let (word, tail) = rest.split_at(end);
+
+        rest = tail;
+        Some(word)
+    })
+}
+
+///////////////////////////////////////////////////////////////////////////
+// DynamicText
+///////////////////////////////////////////////////////////////////////////
+
+/// A single run of text which changes often, eg. a counter or a log, drawn
+/// with the sprite pipeline from a dynamic vertex buffer.
+///
+/// When the text changes, the glyphs of the prefix it shares with the
+/// previous text are kept, and only the glyphs after it are laid out again
+/// and uploaded. Appending a line to a log, or changing the last digits of
+/// a counter, only uploads the new glyphs.
+#[derive(Debug)]
+pub struct DynamicText {
+    pub font: Font,
+    pub atlas: GlyphAtlas,
+
+    text: String,
+    pos: Point2<f32>,
+    size: f32,
+    color: Rgba,
+    zdepth: ZDepth,
+
+    vertices: Vec<sprite2d::Vertex>,
+    /// Number of quads after each glyph, ie. each character but line breaks.
+    ends: Vec<usize>,
+    /// First vertex changed since the last upload, if any.
+    changed: Option<usize>,
+
+    buffer: Option<core::DynamicVertexBuffer>,
+    /// Indices for the quads, and the number of quads they cover.
+    indices: Option<(core::IndexBuffer, usize)>,
+}
+
+impl DynamicText {
+    /// Create an empty text, at the given position, which is the start of
+    /// the baseline of the first line.
+    pub fn new(
+        font: Font,
+        atlas: GlyphAtlas,
+        pos: Point2<f32>,
+        size: f32,
+        color: Rgba,
+        zdepth: ZDepth,
+    ) -> Self {
+        Self {
+            font,
+            atlas,
+            text: String::new(),
+            pos,
+            size,
+            color,
+            zdepth,
+            vertices: Vec::new(),
+            ends: Vec::new(),
+            changed: None,
+            buffer: None,
+            indices: None,
+        }
+    }
+
+    /// The current text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replace the text. Lines are separated by `\n`.
+    ///
+    /// If the glyph atlas overflows, it is repacked, and the whole text is
+    /// laid out again. Glyphs that still don't fit are skipped.
+    pub fn set(&mut self, text: &str) {
+        let prefix = self
+            .text
+            .chars()
+            .zip(text.chars())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let glyphs = text.chars().take(prefix).filter(|c| *c != '\n').count();
+
+        self.text = text.to_owned();
+        self.layout(glyphs);
+
+        if self.atlas.overflowed() && self.atlas.repack() {
+            self.layout(0);
+            self.atlas.overflowed();
+        }
+    }
+
+    /// Append to the text, eg. a line to a log.
+    pub fn push(&mut self, text: &str) {
+        let text = format!("{}{}", self.text, text);
+        self.set(&text);
+    }
+
+    /// Lay the text out again, keeping the given number of glyphs.
+    fn layout(&mut self, keep: usize) {
+        let quads = if keep > 0 { self.ends[keep - 1] } else { 0 };
+        let mut sprites = sprite2d::Batch::new(self.atlas.w, self.atlas.h);
+
+        self.ends.truncate(keep);
+        self.vertices.truncate(quads * 4);
+
+        // The glyph color replaces the white of the atlas, while its alpha
+        // is applied as opacity.
+        let color = self.color;
+        let rgba = Rgba::new(color.r, color.g, color.b, 1.0);
+
+        for (glyph, origin) in self
+            .font
+            .layout(&self.text, self.pos, self.size)
+            .into_iter()
+            .skip(keep)
+        {
+            if let Some(g) = self.atlas.glyph(glyph) {
+                let (gx, gy) = (origin.x.round(), origin.y.round());
+                let dst = Rect::new(
+                    gx + g.bounds.x1,
+                    gy - g.bounds.y2,
+                    gx + g.bounds.x2,
+                    gy - g.bounds.y1,
+                );
+                sprites.add(g.src, dst, self.zdepth, rgba, color.a, Repeat::default());
+            }
+            self.ends.push(quads + sprites.size);
+        }
+        self.vertices.extend(sprites.vertices());
+
+        let first = quads * 4;
+        self.changed = Some(self.changed.map_or(first, |c| c.min(first)));
+    }
+
+    /// Number of glyph quads.
+    pub fn len(&self) -> usize {
+        self.vertices.len() / 4
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// Upload the glyphs changed since the last upload. The glyph atlas
+    /// has to be uploaded separately, if it's dirty.
+    pub fn upload(&mut self, r: &mut core::Renderer) {
+        let from = match self.changed.take() {
+            Some(from) => from,
+            None => return,
+        };
+        match self.buffer {
+            Some(ref mut buffer) => buffer.update_from(&self.vertices, from, r),
+            None => self.buffer = Some(r.dynamic_vertex_buffer(&self.vertices)),
+        }
+
+        let quads = self.len();
+        let capacity = self.indices.as_ref().map_or(0, |(_, n)| *n);
+        if capacity < quads || capacity == 0 {
+            // Grow the indices ahead of the text, as the vertex buffer does.
+            let n = quads.max(capacity * 2).max(1);
+            let indices = (0..n as u32)
+                .flat_map(|i| {
+                    let v = i * 4;
+                    vec![v, v + 1, v + 2, v, v + 3, v + 2]
+                })
+                .collect::<Vec<_>>();
+
+            self.indices = Some((r.device.create_index_buffer(&indices), n));
+        }
+    }
+
+    /// Draw the text, as of the last upload, with a binding of the glyph
+    /// atlas texture.
+    pub fn draw(&self, binding: &core::BindingGroup, pass: &mut core::Pass) {
+        if let (Some(buffer), Some((indices, _))) = (&self.buffer, &self.indices) {
+            let quads = buffer.buffer().size / 4;
+            if quads == 0 {
+                return;
+            }
+            pass.set_binding(binding, &[]);
+            pass.set_index_buffer(indices);
+            pass.set_vertex_buffer(buffer.buffer());
+            pass.draw_indexed(0..quads * 6, 0..1);
+        }
+    }
+}
@@ -0,0 +1,347 @@
+//! Tile maps, drawn from a tileset texture with the sprite pipeline.
+//!
+//! The map is split into square chunks of tiles, each kept in its own
+//! static vertex buffer. Only the chunks changed since they were last built
+//! are rebuilt, and only the chunks intersecting the view are drawn, so
+//! large maps cost little more than the part of them on screen.
+//!
+//! # Examples
+//!
+//! ```
+//! use rgx::kit::tilemap::{Tilemap, Tileset};
+//! use rgx::rect::Rect;
+//!
+//! // A 128x64 tileset of 16x16 tiles, and a 256x256 map of 16x16 chunks.
+//! let tileset = Tileset::new(128, 64, 16, 16);
+//! let mut map = Tilemap::new(tileset, 256, 256, 16);
+//!
+//! map.set(0, 0, Some(9));
+//! assert_eq!(map.tileset.region(9), Rect::new(16., 16., 32., 32.));
+//!
+//! // Chunks in a 320x240 view of the bottom-left corner of the map.
+//! let view = Rect::new(0., 0., 320., 240.);
+//! assert_eq!(map.visible(view).collect::<Vec<_>>(), vec![(0, 0), (1, 0)]);
+//! ```
+#[cfg(feature = "tiled")]
+pub mod tiled;
+
+#[cfg(feature = "tiled")]
+pub use tiled::from_tiled;
+
+use crate::core;
+use crate::core::Rgba;
+use crate::kit::sprite2d::Batch;
+use crate::kit::{Repeat, ZDepth};
+use crate::rect::Rect;
+
+///////////////////////////////////////////////////////////////////////////
+// Tileset
+///////////////////////////////////////////////////////////////////////////
+
+/// A texture of equally sized tiles, numbered from the top-left, row by row.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Tileset {
+    /// Texture width.
+    pub w: u32,
+    /// Texture height.
+    pub h: u32,
+    /// Tile width, in pixels.
+    pub tile_w: u32,
+    /// Tile height, in pixels.
+    pub tile_h: u32,
+    /// Pixels around the tiles, on the edges of the texture.
+    pub margin: u32,
+    /// Pixels between tiles.
+    pub spacing: u32,
+}
+
+impl Tileset {
+    pub fn new(w: u32, h: u32, tile_w: u32, tile_h: u32) -> Self {
+        Self {
+            w,
+            h,
+            tile_w,
+            tile_h,
+            margin: 0,
+            spacing: 0,
+        }
+    }
+
+    pub fn margin(mut self, margin: u32) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn spacing(mut self, spacing: u32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Number of tiles in a row of the texture.
+    pub fn columns(&self) -> u32 {
+        let w = self.w.saturating_sub(self.margin * 2) + self.spacing;
+        (w / (self.tile_w + self.spacing)).max(1)
+    }
+
+    /// Number of tiles in the texture.
+    pub fn len(&self) -> u32 {
+        let h = self.h.saturating_sub(self.margin * 2) + self.spacing;
+        self.columns() * (h / (self.tile_h + self.spacing))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The texture region of a tile, to be used as a sprite source.
+    pub fn region(&self, index: u32) -> Rect<f32> {
+        let (col, row) = (index % self.columns(), index / self.columns());
+        let x = self.margin + col * (self.tile_w + self.spacing);
+        let y = self.margin + row * (self.tile_h + self.spacing);
+
+        Rect::new(
+            x as f32,
+            y as f32,
+            (x + self.tile_w) as f32,
+            (y + self.tile_h) as f32,
+        )
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Tilemap
+///////////////////////////////////////////////////////////////////////////
+
+/// A chunk of a `Tilemap`, and its vertex buffer. Empty chunks have no
+/// buffer.
+#[derive(Debug)]
+struct Chunk {
+    buffer: Option<core::IndexedBuffer>,
+    dirty: bool,
+}
+
+/// A grid of tiles from a `Tileset`. Tiles are positioned in tiles from the
+/// bottom-left of the map, which is drawn from the origin, with each tile
+/// the size of a tileset tile.
+#[derive(Debug)]
+pub struct Tilemap {
+    pub tileset: Tileset,
+    /// Width of the map, in tiles.
+    pub columns: u32,
+    /// Height of the map, in tiles.
+    pub rows: u32,
+    pub depth: ZDepth,
+    pub opacity: f32,
+
+    tiles: Vec<Option<u32>>,
+    /// Width and height of a chunk, in tiles.
+    chunk: u32,
+    chunks: Vec<Chunk>,
+}
+
+impl Tilemap {
+    /// Create an empty map, split into chunks of `chunk` by `chunk` tiles.
+    pub fn new(tileset: Tileset, columns: u32, rows: u32, chunk: u32) -> Self {
+        let chunk = chunk.max(1);
+        let n = (columns.div_ceil(chunk) * rows.div_ceil(chunk)) as usize;
+
+        Self {
+            tileset,
+            columns,
+            rows,
+            depth: ZDepth::default(),
+            opacity: 1.0,
+            tiles: vec![None; (columns * rows) as usize],
+            chunk,
+            chunks: (0..n)
+                .map(|_| Chunk {
+                    buffer: None,
+                    dirty: true,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn depth(mut self, depth: ZDepth) -> Self {
+        self.depth = depth;
+        self.invalidate();
+        self
+    }
+
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self.invalidate();
+        self
+    }
+
+    /// The tile at a position, if any.
+    pub fn get(&self, x: u32, y: u32) -> Option<u32> {
+        if x < self.columns && y < self.rows {
+            self.tiles[(y * self.columns + x) as usize]
+        } else {
+            None
+        }
+    }
+
+    /// Set or clear the tile at a position. Positions outside of the map
+    /// are ignored.
+    pub fn set(&mut self, x: u32, y: u32, tile: Option<u32>) {
+        if x >= self.columns || y >= self.rows {
+            return;
+        }
+        let i = (y * self.columns + x) as usize;
+
+        if self.tiles[i] != tile {
+            self.tiles[i] = tile;
+            let chunk = self.chunk_index(x / self.chunk, y / self.chunk);
+            self.chunks[chunk].dirty = true;
+        }
+    }
+
+    /// Replace all tiles, from rows of tiles starting at the bottom.
+    ///
+    /// Panics if there isn't one tile per position.
+    pub fn fill(&mut self, tiles: &[Option<u32>]) {
+        assert_eq!(
+            tiles.len(),
+            self.tiles.len(),
+            "fatal: incorrect number of tiles"
+        );
+        self.tiles.copy_from_slice(tiles);
+        self.invalidate();
+    }
+
+    /// Mark all chunks to be rebuilt.
+    pub fn invalidate(&mut self) {
+        for chunk in self.chunks.iter_mut() {
+            chunk.dirty = true;
+        }
+    }
+
+    /// The area covered by the map, in pixels.
+    pub fn rect(&self) -> Rect<f32> {
+        Rect::origin(
+            (self.columns * self.tileset.tile_w) as f32,
+            (self.rows * self.tileset.tile_h) as f32,
+        )
+    }
+
+    /// The area of a tile, in pixels.
+    pub fn tile_rect(&self, x: u32, y: u32) -> Rect<f32> {
+        let (w, h) = (self.tileset.tile_w as f32, self.tileset.tile_h as f32);
+        Rect::sized(x as f32 * w, y as f32 * h, w, h)
+    }
+
+    /// The position of the tile under a point in pixels, if any.
+    pub fn tile_at(&self, x: f32, y: f32) -> Option<(u32, u32)> {
+        if x < 0. || y < 0. {
+            return None;
+        }
+        let x = (x / self.tileset.tile_w as f32) as u32;
+        let y = (y / self.tileset.tile_h as f32) as u32;
+
+        if x < self.columns && y < self.rows {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+
+    /// Rebuild the vertex buffers of the changed chunks intersecting the
+    /// view. Other changed chunks are rebuilt once they are in view.
+    /// Returns the number of chunks rebuilt.
+    pub fn update(&mut self, view: Rect<f32>, r: &core::Renderer) -> usize {
+        let visible = self.visible(view).collect::<Vec<_>>();
+        let mut rebuilt = 0;
+
+        for (cx, cy) in visible {
+            let i = self.chunk_index(cx, cy);
+            if !self.chunks[i].dirty {
+                continue;
+            }
+            let batch = self.batch(cx, cy);
+
+            self.chunks[i] = Chunk {
+                buffer: if batch.is_empty() {
+                    None
+                } else {
+                    Some(batch.finish(r))
+                },
+                dirty: false,
+            };
+            rebuilt += 1;
+        }
+        rebuilt
+    }
+
+    /// The built chunks intersecting the view.
+    pub fn chunks(&self, view: Rect<f32>) -> impl Iterator<Item = &core::IndexedBuffer> + '_ {
+        self.visible(view)
+            .filter_map(move |(cx, cy)| self.chunks[self.chunk_index(cx, cy)].buffer.as_ref())
+    }
+
+    /// Draw the chunks intersecting the view, with a sprite pipeline binding
+    /// of the tileset texture.
+    pub fn draw(&self, view: Rect<f32>, binding: &core::BindingGroup, pass: &mut core::Pass) {
+        for buffer in self.chunks(view) {
+            pass.draw(buffer, binding);
+        }
+    }
+
+    /// A sprite batch of the tiles of a chunk.
+    pub fn batch(&self, cx: u32, cy: u32) -> Batch {
+        let mut batch = Batch::new(self.tileset.w, self.tileset.h);
+        let (x1, y1) = (cx * self.chunk, cy * self.chunk);
+        let (x2, y2) = (
+            (x1 + self.chunk).min(self.columns),
+            (y1 + self.chunk).min(self.rows),
+        );
+
+        for y in y1..y2 {
+            for x in x1..x2 {
+                if let Some(tile) = self.tiles[(y * self.columns + x) as usize] {
+                    batch.add(
+                        self.tileset.region(tile),
+                        self.tile_rect(x, y),
+                        self.depth,
+                        Rgba::TRANSPARENT,
+                        self.opacity,
+                        Repeat::default(),
+                    );
+                }
+            }
+        }
+        batch
+    }
+
+    /// The positions of the chunks intersecting the view, in chunks.
+    pub fn visible(&self, view: Rect<f32>) -> impl Iterator<Item = (u32, u32)> {
+        let bounds = self.rect();
+        let (w, h) = (
+            (self.chunk * self.tileset.tile_w) as f32,
+            (self.chunk * self.tileset.tile_h) as f32,
+        );
+        let (x1, y1) = (view.x1.min(view.x2).max(0.), view.y1.min(view.y2).max(0.));
+        let (x2, y2) = (
+            view.x1.max(view.x2).min(bounds.x2),
+            view.y1.max(view.y2).min(bounds.y2),
+        );
+
+        let (x1, y1, x2, y2) = if x1 < x2 && y1 < y2 {
+            (
+                (x1 / w) as u32,
+                (y1 / h) as u32,
+                (x2 / w).ceil() as u32,
+                (y2 / h).ceil() as u32,
+            )
+        } else {
+            (0, 0, 0, 0)
+        };
+
+        (y1..y2).flat_map(move |y| (x1..x2).map(move |x| (x, y)))
+    }
+
+    fn chunk_index(&self, cx: u32, cy: u32) -> usize {
+        (cy * self.columns.div_ceil(self.chunk) + cx) as usize
+    }
+}
@@ -0,0 +1,743 @@
+//! Maps made with the [Tiled](https://www.mapeditor.org) map editor, in its
+//! TMX or JSON formats.
+//!
+//! Tile layers are loaded as one `Tilemap` per tileset of the map, and
+//! object layers as shape batches outlining their objects, in the layer
+//! color. Everything is flipped to have its origin at the bottom-left, as
+//! with the rest of the kit.
+//!
+//! Not supported are infinite maps, compressed tile data, tilesets made of
+//! a collection of images, and image layers, which are skipped. Flipped
+//! tiles are drawn unflipped, and tile objects are outlined like
+//! rectangles.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use rgx::kit::tilemap::tiled::{self, Layer};
+//!
+//! let map = tiled::from_tiled("level.tmx", 16)?;
+//!
+//! for layer in map.layers.iter() {
+//!     match layer {
+//!         Layer::Tiles { maps, .. } => {
+//!             // One tilemap per tileset, eg. `map.tilesets[0].image`.
+//!             assert_eq!(maps.len(), map.tilesets.len());
+//!         }
+//!         Layer::Objects { objects, batch, .. } => {
+//!             // Shapes of the objects, to be drawn with the shape pipeline.
+//!         }
+//!     }
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::core::Rgba8;
+use crate::error::Error;
+use crate::kit::shape2d::{self, Fill, Rotation, Shape, Stroke};
+use crate::kit::tilemap::{Tilemap, Tileset};
+use crate::kit::ZDepth;
+use crate::math::*;
+use crate::rect::Rect;
+
+/// Flags stored in the high bits of tile ids, eg. for flipped tiles.
+const FLAGS: u32 = 0xf000_0000;
+
+/// Sides of the polygons approximating ellipses.
+const ELLIPSE_SIDES: u32 = 32;
+
+/// Radius of the circles marking point objects.
+const POINT_RADIUS: f32 = 2.;
+
+///////////////////////////////////////////////////////////////////////////
+// Map
+///////////////////////////////////////////////////////////////////////////
+
+/// A map loaded with `from_tiled`.
+#[derive(Debug)]
+pub struct Map {
+    /// Width of the map, in tiles.
+    pub columns: u32,
+    /// Height of the map, in tiles.
+    pub rows: u32,
+    pub tile_w: u32,
+    pub tile_h: u32,
+    pub tilesets: Vec<MapTileset>,
+    /// Layers, from the bottom-most to the top-most. Layers of groups are
+    /// included in place of their group.
+    pub layers: Vec<Layer>,
+}
+
+/// A tileset of a `Map`.
+#[derive(Debug, Clone)]
+pub struct MapTileset {
+    pub name: String,
+    /// Path of the tileset image, relative to the working directory.
+    pub image: PathBuf,
+    pub tileset: Tileset,
+    /// Id of the first tile of the tileset in the map.
+    pub first: u32,
+}
+
+/// A layer of a `Map`.
+#[derive(Debug)]
+pub enum Layer {
+    /// A tile layer, with one tilemap per tileset of the map, in the same
+    /// order. Tilemaps of tilesets without tiles in the layer are empty.
+    Tiles {
+        name: String,
+        visible: bool,
+        maps: Vec<Tilemap>,
+    },
+    /// An object layer, with the shapes of its objects in a batch.
+    Objects {
+        name: String,
+        visible: bool,
+        objects: Vec<Object>,
+        batch: shape2d::Batch,
+    },
+}
+
+/// An object of an object layer.
+#[derive(Debug, Clone)]
+pub struct Object {
+    pub name: String,
+    /// Class of the object, called type in older versions of Tiled.
+    pub class: String,
+    /// Bounding rectangle of the object, ignoring its rotation.
+    pub rect: Rect<f32>,
+    pub shape: Shape,
+    pub visible: bool,
+}
+
+/// Load a Tiled map, in TMX format if its extension is `.tmx`, and in JSON
+/// format otherwise. External tilesets are loaded relative to the map, in
+/// TSX format if their extension is `.tsx`. Tilemaps are split into chunks
+/// of `chunk` by `chunk` tiles.
+pub fn from_tiled<P: AsRef<Path>>(path: P, chunk: u32) -> io::Result<Map> {
+    let path = path.as_ref();
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut raw: RawMap = if is_xml(path) {
+        tmx::map(open(path)?)?
+    } else {
+        serde_json::from_reader(open(path)?)?
+    };
+    if raw.infinite {
+        return Err(Error::InvalidMap.into());
+    }
+
+    let mut tilesets = Vec::with_capacity(raw.tilesets.len());
+    for ts in raw.tilesets.drain(..) {
+        let first = ts.firstgid;
+        let (ts, dir) = match ts.source {
+            Some(ref source) => {
+                let path = dir.join(source);
+                let ts = if is_xml(&path) {
+                    tmx::tileset(open(&path)?)?
+                } else {
+                    serde_json::from_reader(open(&path)?)?
+                };
+                (ts, path.parent().map(Path::to_path_buf).unwrap_or_default())
+            }
+            None => (ts, dir.to_path_buf()),
+        };
+        let image = ts.image.ok_or(Error::InvalidMap)?;
+
+        tilesets.push(MapTileset {
+            name: ts.name,
+            image: dir.join(image),
+            tileset: Tileset::new(ts.imagewidth, ts.imageheight, ts.tilewidth, ts.tileheight)
+                .margin(ts.margin)
+                .spacing(ts.spacing),
+            first,
+        });
+    }
+    tilesets.sort_by_key(|ts| ts.first);
+
+    let mut map = Map {
+        columns: raw.width,
+        rows: raw.height,
+        tile_w: raw.tilewidth,
+        tile_h: raw.tileheight,
+        tilesets,
+        layers: Vec::new(),
+    };
+    for layer in raw.layers.drain(..) {
+        map.layer(layer, true, 1., chunk)?;
+    }
+    Ok(map)
+}
+
+impl Map {
+    /// Add a layer, or the layers of a group, to the map.
+    fn layer(&mut self, raw: RawLayer, visible: bool, opacity: f32, chunk: u32) -> io::Result<()> {
+        let visible = visible && raw.visible;
+        let opacity = opacity * raw.opacity;
+
+        match raw.kind.as_str() {
+            "tilelayer" => {
+                let gids = raw.gids()?;
+                if gids.len() != (self.columns * self.rows) as usize {
+                    return Err(Error::InvalidMap.into());
+                }
+                let mut maps: Vec<Tilemap> = self
+                    .tilesets
+                    .iter()
+                    .map(|ts| {
+                        Tilemap::new(ts.tileset, self.columns, self.rows, chunk).opacity(opacity)
+                    })
+                    .collect();
+
+                for (i, gid) in gids.iter().enumerate() {
+                    let gid = gid & !FLAGS;
+                    if gid == 0 {
+                        continue;
+                    }
+                    let t = self
+                        .tilesets
+                        .iter()
+                        .rposition(|ts| ts.first <= gid)
+                        .ok_or(Error::InvalidMap)?;
+                    let (x, y) = (i as u32 % self.columns, i as u32 / self.columns);
+
+                    maps[t].set(x, self.rows - 1 - y, Some(gid - self.tilesets[t].first));
+                }
+                self.layers.push(Layer::Tiles {
+                    name: raw.name,
+                    visible,
+                    maps,
+                });
+            }
+            "objectgroup" => {
+                let color = raw
+                    .color
+                    .as_ref()
+                    .and_then(|c| color(c))
+                    .unwrap_or_else(|| Rgba8::new(0xa0, 0xa0, 0xa4, 0xff));
+                let stroke = Stroke::new(1., color.into());
+                let height = (self.rows * self.tile_h) as f32;
+
+                let objects: Vec<Object> = raw
+                    .objects
+                    .iter()
+                    .map(|o| o.object(height, &stroke))
+                    .collect();
+                let mut batch = shape2d::Batch::new();
+                batch.opacity = opacity;
+
+                for o in objects.iter().filter(|o| o.visible) {
+                    batch.add(o.shape.clone());
+                }
+                self.layers.push(Layer::Objects {
+                    name: raw.name,
+                    visible,
+                    objects,
+                    batch,
+                });
+            }
+            "group" => {
+                for layer in raw.layers {
+                    self.layer(layer, visible, opacity, chunk)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl RawObject {
+    fn object(&self, height: f32, stroke: &Stroke) -> Object {
+        let flip = |x: f32, y: f32| Point2::new(x, height - y);
+        let origin = flip(self.x, self.y);
+        let rotation = Rotation::new(-self.rotation.to_radians(), origin);
+        let z = ZDepth::default();
+
+        let (shape, points) = if self.point {
+            let shape = Shape::Circle(origin, z, POINT_RADIUS, 8, stroke.clone(), Fill::Empty());
+            (shape, vec![origin])
+        } else if let Some(ps) = self.polygon.as_ref().or(self.polyline.as_ref()) {
+            let points: Vec<_> = ps
+                .iter()
+                .map(|p| flip(self.x + p.x, self.y + p.y))
+                .collect();
+            let shape = if self.polygon.is_some() {
+                Shape::Polygon(points.clone(), z, rotation, stroke.clone(), Fill::Empty())
+            } else {
+                Shape::Polyline(points.clone(), z, rotation, stroke.clone())
+            };
+            (shape, points)
+        } else {
+            // Tile objects are positioned by their bottom-left corner.
+            let y = if self.gid.is_some() {
+                self.y - self.height
+            } else {
+                self.y
+            };
+            let (a, b) = (flip(self.x, y + self.height), flip(self.x + self.width, y));
+
+            if self.ellipse {
+                let (rx, ry) = (self.width / 2., self.height / 2.);
+                let center = Point2::new(a.x + rx, a.y + ry);
+                let points = (0..ELLIPSE_SIDES)
+                    .map(|i| {
+                        let t = i as f32 / ELLIPSE_SIDES as f32 * std::f32::consts::PI * 2.;
+                        Point2::new(center.x + rx * t.cos(), center.y + ry * t.sin())
+                    })
+                    .collect();
+                let shape = Shape::Polygon(points, z, rotation, stroke.clone(), Fill::Empty());
+                (shape, vec![a, b])
+            } else {
+                let rect = Rect::new(a.x, a.y, b.x, b.y);
+                let shape = Shape::Rectangle(rect, z, rotation, stroke.clone(), Fill::Empty());
+                (shape, vec![a, b])
+            }
+        };
+
+        let (mut min, mut max) = (points[0], points[0]);
+        for p in points.iter() {
+            min = Point2::new(min.x.min(p.x), min.y.min(p.y));
+            max = Point2::new(max.x.max(p.x), max.y.max(p.y));
+        }
+
+        Object {
+            name: self.name.clone(),
+            class: if self.class.is_empty() {
+                self.kind.clone()
+            } else {
+                self.class.clone()
+            },
+            rect: Rect::new(min.x, min.y, max.x, max.y),
+            shape,
+            visible: self.visible,
+        }
+    }
+}
+
+impl RawLayer {
+    /// The tile ids of a tile layer, row by row from the top.
+    fn gids(&self) -> io::Result<Vec<u32>> {
+        match self.data {
+            Some(RawData::Gids(ref gids)) => Ok(gids.clone()),
+            Some(RawData::Encoded(ref data)) => {
+                if self.encoding != "base64" || !self.compression.is_empty() {
+                    return Err(Error::InvalidMap.into());
+                }
+                let bytes = base64(data).ok_or(Error::InvalidMap)?;
+
+                Ok(bytes
+                    .chunks_exact(4)
+                    .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect())
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+fn open(path: &Path) -> io::Result<io::BufReader<File>> {
+    File::open(path).map(io::BufReader::new)
+}
+
+fn is_xml(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("tmx") | Some("tsx")
+    )
+}
+
+/// Parse a color of the form `#rrggbb` or `#aarrggbb`.
+fn color(s: &str) -> Option<Rgba8> {
+    let s = s.strip_prefix('#').unwrap_or(s);
+    let n = u32::from_str_radix(s, 16).ok()?;
+    let a = match s.len() {
+        6 => 0xff,
+        8 => (n >> 24) as u8,
+        _ => return None,
+    };
+    Some(Rgba8::new((n >> 16) as u8, (n >> 8) as u8, n as u8, a))
+}
+
+/// Decode standard base64, ignoring whitespace.
+fn base64(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    let (mut acc, mut bits) = (0u32, 0);
+
+    for c in s.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ => return None,
+        };
+        acc = (acc << 6) | v as u32;
+        bits += 6;
+
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Raw
+///////////////////////////////////////////////////////////////////////////
+
+// The map as exported by Tiled, in JSON, and as parsed from TMX.
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default)]
+struct RawMap {
+    width: u32,
+    height: u32,
+    tilewidth: u32,
+    tileheight: u32,
+    infinite: bool,
+    tilesets: Vec<RawTileset>,
+    layers: Vec<RawLayer>,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default)]
+struct RawTileset {
+    firstgid: u32,
+    source: Option<String>,
+    name: String,
+    tilewidth: u32,
+    tileheight: u32,
+    margin: u32,
+    spacing: u32,
+    image: Option<String>,
+    imagewidth: u32,
+    imageheight: u32,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(default)]
+struct RawLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    name: String,
+    visible: bool,
+    opacity: f32,
+    data: Option<RawData>,
+    encoding: String,
+    compression: String,
+    color: Option<String>,
+    objects: Vec<RawObject>,
+    layers: Vec<RawLayer>,
+}
+
+impl Default for RawLayer {
+    fn default() -> Self {
+        Self {
+            kind: String::new(),
+            name: String::new(),
+            visible: true,
+            opacity: 1.,
+            data: None,
+            encoding: String::new(),
+            compression: String::new(),
+            color: None,
+            objects: Vec::new(),
+            layers: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(untagged)]
+enum RawData {
+    Gids(Vec<u32>),
+    Encoded(String),
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(default)]
+struct RawObject {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    class: String,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    rotation: f32,
+    visible: bool,
+    gid: Option<u32>,
+    ellipse: bool,
+    point: bool,
+    polygon: Option<Vec<RawPoint>>,
+    polyline: Option<Vec<RawPoint>>,
+}
+
+impl Default for RawObject {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            kind: String::new(),
+            class: String::new(),
+            x: 0.,
+            y: 0.,
+            width: 0.,
+            height: 0.,
+            rotation: 0.,
+            visible: true,
+            gid: None,
+            ellipse: false,
+            point: false,
+            polygon: None,
+            polyline: None,
+        }
+    }
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+struct RawPoint {
+    x: f32,
+    y: f32,
+}
+
+///////////////////////////////////////////////////////////////////////////
+// TMX
+///////////////////////////////////////////////////////////////////////////
+
+mod tmx {
+    use std::io;
+    use std::io::Read;
+    use std::str::FromStr;
+
+    use xml::attribute::OwnedAttribute;
+    use xml::reader::{EventReader, XmlEvent};
+
+    use super::{RawData, RawLayer, RawMap, RawObject, RawPoint, RawTileset};
+    use crate::error::Error;
+
+    /// Parse a TMX map.
+    pub fn map<R: Read>(r: R) -> io::Result<RawMap> {
+        let mut r = EventReader::new(r);
+        let attrs = root(&mut r, "map")?;
+        let mut map = RawMap {
+            width: attr(&attrs, "width").unwrap_or_default(),
+            height: attr(&attrs, "height").unwrap_or_default(),
+            tilewidth: attr(&attrs, "tilewidth").unwrap_or_default(),
+            tileheight: attr(&attrs, "tileheight").unwrap_or_default(),
+            infinite: attr::<u32>(&attrs, "infinite") == Some(1),
+            ..RawMap::default()
+        };
+
+        children(&mut r, |r, name, attrs| match name {
+            "tileset" => {
+                map.tilesets.push(tileset_element(r, attrs)?);
+                Ok(())
+            }
+            _ => layer(r, name, attrs, &mut map.layers),
+        })?;
+        Ok(map)
+    }
+
+    /// Parse a TSX tileset.
+    pub fn tileset<R: Read>(r: R) -> io::Result<RawTileset> {
+        let mut r = EventReader::new(r);
+        let attrs = root(&mut r, "tileset")?;
+
+        tileset_element(&mut r, &attrs)
+    }
+
+    fn tileset_element<R: Read>(
+        r: &mut EventReader<R>,
+        attrs: &[OwnedAttribute],
+    ) -> io::Result<RawTileset> {
+        let mut ts = RawTileset {
+            firstgid: attr(attrs, "firstgid").unwrap_or_default(),
+            source: attr(attrs, "source"),
+            name: attr(attrs, "name").unwrap_or_default(),
+            tilewidth: attr(attrs, "tilewidth").unwrap_or_default(),
+            tileheight: attr(attrs, "tileheight").unwrap_or_default(),
+            margin: attr(attrs, "margin").unwrap_or_default(),
+            spacing: attr(attrs, "spacing").unwrap_or_default(),
+            ..RawTileset::default()
+        };
+
+        children(r, |r, name, attrs| {
+            if name == "image" {
+                ts.image = attr(attrs, "source");
+                ts.imagewidth = attr(attrs, "width").unwrap_or_default();
+                ts.imageheight = attr(attrs, "height").unwrap_or_default();
+            }
+            skip(r)
+        })?;
+        Ok(ts)
+    }
+
+    /// Parse a layer element, if `name` is one, into `layers`.
+    fn layer<R: Read>(
+        r: &mut EventReader<R>,
+        name: &str,
+        attrs: &[OwnedAttribute],
+        layers: &mut Vec<RawLayer>,
+    ) -> io::Result<()> {
+        let kind = match name {
+            "layer" => "tilelayer",
+            "objectgroup" | "group" => name,
+            _ => return skip(r),
+        };
+        let mut raw = RawLayer {
+            kind: kind.to_owned(),
+            name: attr(attrs, "name").unwrap_or_default(),
+            visible: attr::<u32>(attrs, "visible") != Some(0),
+            opacity: attr(attrs, "opacity").unwrap_or(1.),
+            color: attr(attrs, "color"),
+            ..RawLayer::default()
+        };
+
+        children(r, |r, name, attrs| match name {
+            "data" => {
+                raw.encoding = attr(attrs, "encoding").unwrap_or_default();
+                raw.compression = attr(attrs, "compression").unwrap_or_default();
+                raw.data = Some(data(r, &raw.encoding)?);
+                Ok(())
+            }
+            "object" => {
+                raw.objects.push(object(r, attrs)?);
+                Ok(())
+            }
+            _ => layer(r, name, attrs, &mut raw.layers),
+        })?;
+        layers.push(raw);
+
+        Ok(())
+    }
+
+    fn data<R: Read>(r: &mut EventReader<R>, encoding: &str) -> io::Result<RawData> {
+        let mut gids = Vec::new();
+        let text = children(r, |r, name, attrs| match name {
+            "tile" => {
+                gids.push(attr(attrs, "gid").unwrap_or_default());
+                skip(r)
+            }
+            // Chunks of infinite maps.
+            _ => Err(Error::InvalidMap.into()),
+        })?;
+
+        match encoding {
+            "" => Ok(RawData::Gids(gids)),
+            "csv" => text
+                .split(',')
+                .map(|s| s.trim().parse())
+                .collect::<Result<_, _>>()
+                .map(RawData::Gids)
+                .map_err(|_| Error::InvalidMap.into()),
+            _ => Ok(RawData::Encoded(text)),
+        }
+    }
+
+    fn object<R: Read>(r: &mut EventReader<R>, attrs: &[OwnedAttribute]) -> io::Result<RawObject> {
+        let mut object = RawObject {
+            name: attr(attrs, "name").unwrap_or_default(),
+            kind: attr(attrs, "type").unwrap_or_default(),
+            class: attr(attrs, "class").unwrap_or_default(),
+            x: attr(attrs, "x").unwrap_or_default(),
+            y: attr(attrs, "y").unwrap_or_default(),
+            width: attr(attrs, "width").unwrap_or_default(),
+            height: attr(attrs, "height").unwrap_or_default(),
+            rotation: attr(attrs, "rotation").unwrap_or_default(),
+            visible: attr::<u32>(attrs, "visible") != Some(0),
+            gid: attr(attrs, "gid"),
+            ..RawObject::default()
+        };
+
+        children(r, |r, name, attrs| {
+            match name {
+                "ellipse" => object.ellipse = true,
+                "point" => object.point = true,
+                "polygon" => object.polygon = Some(points(attrs)?),
+                "polyline" => object.polyline = Some(points(attrs)?),
+                _ => {}
+            }
+            skip(r)
+        })?;
+        Ok(object)
+    }
+
+    /// Parse the points of a polygon or polyline, of the form `x,y x,y`.
+    fn points(attrs: &[OwnedAttribute]) -> io::Result<Vec<RawPoint>> {
+        let points: String = attr(attrs, "points").unwrap_or_default();
+
+        points
+            .split_whitespace()
+            .map(|p| {
+                let mut xy = p.split(',').map(f32::from_str);
+                match (xy.next(), xy.next()) {
+                    (Some(Ok(x)), Some(Ok(y))) => Ok(RawPoint { x, y }),
+                    _ => Err(Error::InvalidMap.into()),
+                }
+            })
+            .collect()
+    }
+
+    /// Read up to the root element, and return its attributes.
+    fn root<R: Read>(r: &mut EventReader<R>, root: &str) -> io::Result<Vec<OwnedAttribute>> {
+        loop {
+            match next(r)? {
+                XmlEvent::StartElement {
+                    name, attributes, ..
+                } if name.local_name == root => return Ok(attributes),
+                XmlEvent::StartElement { .. } | XmlEvent::EndDocument => {
+                    return Err(Error::InvalidMap.into())
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Call `f` with the name and attributes of each child of the current
+    /// element, which must read the child up to its end, and return the
+    /// text of the element.
+    fn children<R, F>(r: &mut EventReader<R>, mut f: F) -> io::Result<String>
+    where
+        R: Read,
+        F: FnMut(&mut EventReader<R>, &str, &[OwnedAttribute]) -> io::Result<()>,
+    {
+        let mut text = String::new();
+
+        loop {
+            match next(r)? {
+                XmlEvent::StartElement {
+                    name, attributes, ..
+                } => f(r, &name.local_name, &attributes)?,
+                XmlEvent::Characters(s) | XmlEvent::CData(s) => text.push_str(&s),
+                XmlEvent::EndElement { .. } => return Ok(text),
+                XmlEvent::EndDocument => return Err(Error::InvalidMap.into()),
+                _ => {}
+            }
+        }
+    }
+
+    /// Read the current element up to its end.
+    fn skip<R: Read>(r: &mut EventReader<R>) -> io::Result<()> {
+        children(r, |r, _, _| skip(r)).map(|_| ())
+    }
+
+    fn next<R: Read>(r: &mut EventReader<R>) -> io::Result<XmlEvent> {
+        r.next()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn attr<T: FromStr>(attrs: &[OwnedAttribute], name: &str) -> Option<T> {
+        attrs
+            .iter()
+            .find(|a| a.name.local_name == name)
+            .and_then(|a| a.value.parse().ok())
+    }
+}
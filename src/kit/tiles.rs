@@ -0,0 +1,357 @@
+use std::collections::HashSet;
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread;
+
+use crate::core::{Op, Renderer, Rgba8, Texture};
+use crate::error::Error;
+use crate::rect::Rect;
+
+///////////////////////////////////////////////////////////////////////////
+// Tile
+///////////////////////////////////////////////////////////////////////////
+
+/// A tile position in the grid, in tiles, starting at the bottom-left.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+}
+
+impl Tile {
+    pub fn new(x: u32, y: u32) -> Self {
+        Self { x, y }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Stats
+///////////////////////////////////////////////////////////////////////////
+
+/// Statistics of the last call to `Tiles::update`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Tiles intersecting the viewport.
+    pub visible: usize,
+    /// Visible tiles which were dirty, and need to be re-rendered.
+    pub rendered: usize,
+    /// Tiles left dirty, because they were outside of the viewport.
+    pub pending: usize,
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Tiles
+///////////////////////////////////////////////////////////////////////////
+
+/// Splits a large canvas into square tiles, and tracks which of them
+/// changed since they were last rendered.
+///
+/// Each tile is typically cached in its own `Framebuffer`. Every frame,
+/// `update` returns the visible tiles which need to be re-rendered into
+/// their framebuffer, after which all visible tiles are composited into
+/// the view, eg. with the sprite pipeline and `Tiles::rect`.
+#[derive(Clone, Debug)]
+pub struct Tiles {
+    /// Canvas width, in pixels.
+    pub w: u32,
+    /// Canvas height, in pixels.
+    pub h: u32,
+    /// Tile width and height, in pixels.
+    pub size: u32,
+
+    dirty: Vec<bool>,
+    stats: Stats,
+}
+
+impl Tiles {
+    /// Create a tile grid covering a `w` by `h` canvas. All tiles start
+    /// out dirty.
+    pub fn new(w: u32, h: u32, size: u32) -> Self {
+        assert!(size > 0, "Tiles::new: tile size must be greater than zero");
+
+        let count = (w.div_ceil(size) * h.div_ceil(size)) as usize;
+
+        Self {
+            w,
+            h,
+            size,
+            dirty: vec![true; count],
+            stats: Stats::default(),
+        }
+    }
+
+    /// Number of tiles along the X axis.
+    pub fn columns(&self) -> u32 {
+        self.w.div_ceil(self.size)
+    }
+
+    /// Number of tiles along the Y axis.
+    pub fn rows(&self) -> u32 {
+        self.h.div_ceil(self.size)
+    }
+
+    /// Bounds of the given tile on the canvas, in pixels. Tiles on the
+    /// right and top edges may be smaller than the tile size.
+    pub fn rect(&self, tile: Tile) -> Rect<f32> {
+        let x1 = tile.x * self.size;
+        let y1 = tile.y * self.size;
+        let x2 = (x1 + self.size).min(self.w);
+        let y2 = (y1 + self.size).min(self.h);
+
+        Rect::new(x1 as f32, y1 as f32, x2 as f32, y2 as f32)
+    }
+
+    /// The tiles intersecting the given canvas region.
+    pub fn tiles(&self, region: Rect<f32>) -> impl Iterator<Item = Tile> {
+        let size = self.size as f32;
+
+        let x1 = (region.x1.min(region.x2) / size).floor().max(0.) as u32;
+        let y1 = (region.y1.min(region.y2) / size).floor().max(0.) as u32;
+        let x2 = ((region.x1.max(region.x2) / size).ceil().max(0.) as u32).min(self.columns());
+        let y2 = ((region.y1.max(region.y2) / size).ceil().max(0.) as u32).min(self.rows());
+
+        (y1..y2).flat_map(move |y| (x1..x2).map(move |x| Tile::new(x, y)))
+    }
+
+    /// Mark the tiles intersecting the given canvas region as dirty, eg.
+    /// after a brush stroke.
+    pub fn invalidate(&mut self, region: Rect<f32>) {
+        for tile in self.tiles(region).collect::<Vec<_>>() {
+            let ix = self.index(tile);
+            self.dirty[ix] = true;
+        }
+    }
+
+    /// Mark all tiles as dirty.
+    pub fn invalidate_all(&mut self) {
+        for d in self.dirty.iter_mut() {
+            *d = true;
+        }
+    }
+
+    pub fn is_dirty(&self, tile: Tile) -> bool {
+        self.dirty[self.index(tile)]
+    }
+
+    /// Returns the dirty tiles intersecting the viewport, and marks them
+    /// as clean. Dirty tiles outside of the viewport are kept dirty until
+    /// they become visible.
+    pub fn update(&mut self, viewport: Rect<f32>) -> Vec<Tile> {
+        let visible = self.tiles(viewport).collect::<Vec<_>>();
+        let mut rendered = Vec::new();
+
+        for tile in visible.iter() {
+            let ix = self.index(*tile);
+
+            if self.dirty[ix] {
+                self.dirty[ix] = false;
+                rendered.push(*tile);
+            }
+        }
+        self.stats = Stats {
+            visible: visible.len(),
+            rendered: rendered.len(),
+            pending: self.dirty.iter().filter(|d| **d).count(),
+        };
+        rendered
+    }
+
+    /// Statistics of the last update.
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    fn index(&self, tile: Tile) -> usize {
+        (tile.y * self.columns() + tile.x) as usize
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Loader
+///////////////////////////////////////////////////////////////////////////
+
+/// Pixels of a loaded tile, as returned by `Loader::poll`.
+#[derive(Clone, Debug)]
+pub struct Loaded {
+    pub tile: Tile,
+    pub pixels: Vec<Rgba8>,
+}
+
+impl Loaded {
+    /// Upload the tile pixels to the given texture, which must have the
+    /// size of the tile.
+    pub fn upload(&self, texture: &Texture, r: &mut Renderer) {
+        r.submit(&[Op::Fill(texture, &self.pixels)]);
+    }
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    /// Requested tiles, with their priority. Lower values load first.
+    requests: Vec<(f32, Tile)>,
+    shutdown: bool,
+}
+
+/// Loads tiles in the background, on a pool of worker threads.
+///
+/// Tiles are decoded by the user-supplied function, closest to the
+/// viewport first, and handed back with `Loader::poll`, to be uploaded
+/// on the rendering thread. Until then, a placeholder such as
+/// `checkerboard` can be drawn in their place.
+#[derive(Debug)]
+pub struct Loader {
+    queue: Arc<(Mutex<Queue>, Condvar)>,
+    results: mpsc::Receiver<(Tile, Option<Vec<Rgba8>>)>,
+    workers: Vec<thread::JoinHandle<()>>,
+    pending: HashSet<Tile>,
+    loaded: HashSet<Tile>,
+}
+
+impl Loader {
+    /// Create a loader with the given number of worker threads. The `decode`
+    /// function returns the pixels of a tile, or `None` if it couldn't be
+    /// loaded.
+    pub fn new<F>(threads: usize, decode: F) -> Self
+    where
+        F: 'static + Fn(Tile) -> Option<Vec<Rgba8>> + Send + Sync,
+    {
+        let queue = Arc::new((Mutex::new(Queue::default()), Condvar::new()));
+        let decode = Arc::new(decode);
+        let (tx, results) = mpsc::channel();
+
+        let workers = (0..threads.max(1))
+            .map(|_| {
+                let queue = queue.clone();
+                let decode = decode.clone();
+                let tx = tx.clone();
+
+                thread::spawn(move || {
+                    while let Some(tile) = Self::next(&queue) {
+                        if tx.send((tile, decode(tile))).is_err() {
+                            break;
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        Self {
+            queue,
+            results,
+            workers,
+            pending: HashSet::new(),
+            loaded: HashSet::new(),
+        }
+    }
+
+    /// Request the tiles visible in the viewport which aren't loaded yet,
+    /// and re-prioritize all pending requests by their distance to the
+    /// viewport center.
+    pub fn request(&mut self, tiles: &Tiles, viewport: Rect<f32>) {
+        let cx = (viewport.x1 + viewport.x2) / 2.;
+        let cy = (viewport.y1 + viewport.y2) / 2.;
+        let distance = |t: Tile| {
+            let r = tiles.rect(t);
+            let (x, y) = ((r.x1 + r.x2) / 2. - cx, (r.y1 + r.y2) / 2. - cy);
+
+            x * x + y * y
+        };
+
+        let (lock, cvar) = &*self.queue;
+        let mut queue = lock.lock().unwrap();
+
+        for (priority, tile) in queue.requests.iter_mut() {
+            *priority = distance(*tile);
+        }
+        for tile in tiles.tiles(viewport) {
+            if !self.loaded.contains(&tile) && self.pending.insert(tile) {
+                queue.requests.push((distance(tile), tile));
+            }
+        }
+        cvar.notify_all();
+    }
+
+    /// Returns the tiles which finished loading since the last call, or
+    /// `Error::TileFailed` for tiles which couldn't be decoded. Failed tiles
+    /// aren't marked as loaded, and are retried the next time they are
+    /// requested.
+    pub fn poll(&mut self) -> Vec<Result<Loaded, Error>> {
+        let results = self.results.try_iter().collect::<Vec<_>>();
+
+        results
+            .into_iter()
+            .map(|(tile, pixels)| {
+                self.pending.remove(&tile);
+
+                match pixels {
+                    Some(pixels) => {
+                        self.loaded.insert(tile);
+                        Ok(Loaded { tile, pixels })
+                    }
+                    None => Err(Error::TileFailed(tile.x, tile.y)),
+                }
+            })
+            .collect()
+    }
+
+    pub fn is_loaded(&self, tile: Tile) -> bool {
+        self.loaded.contains(&tile)
+    }
+
+    pub fn is_pending(&self, tile: Tile) -> bool {
+        self.pending.contains(&tile)
+    }
+
+    /// Forget a loaded tile, eg. when its texture was evicted, so that it
+    /// is loaded again next time it is requested.
+    pub fn evict(&mut self, tile: Tile) {
+        self.loaded.remove(&tile);
+    }
+
+    /// Block until a request is available, and take the one with the
+    /// highest priority. Returns `None` once the loader is dropped.
+    fn next(queue: &(Mutex<Queue>, Condvar)) -> Option<Tile> {
+        let (lock, cvar) = queue;
+        let mut queue = lock.lock().unwrap();
+
+        loop {
+            if queue.shutdown {
+                return None;
+            }
+            let best = queue
+                .requests
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| a.0.total_cmp(&b.0))
+                .map(|(i, _)| i);
+
+            if let Some(i) = best {
+                return Some(queue.requests.swap_remove(i).1);
+            }
+            queue = cvar.wait(queue).unwrap();
+        }
+    }
+}
+
+impl Drop for Loader {
+    fn drop(&mut self) {
+        {
+            let (lock, cvar) = &*self.queue;
+            lock.lock().unwrap().shutdown = true;
+            cvar.notify_all();
+        }
+        for w in self.workers.drain(..) {
+            w.join().ok();
+        }
+    }
+}
+
+/// Checkerboard pixels of the given size, to draw as a placeholder for
+/// tiles which aren't loaded yet. `cell` is the size of a square, in pixels.
+pub fn checkerboard(w: u32, h: u32, cell: u32, a: Rgba8, b: Rgba8) -> Vec<Rgba8> {
+    let cell = cell.max(1);
+
+    (0..h)
+        .flat_map(|y| (0..w).map(move |x| (x / cell + y / cell) & 1 == 0))
+        .map(|even| if even { a } else { b })
+        .collect()
+}
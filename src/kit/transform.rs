@@ -0,0 +1,246 @@
+//! Hierarchical transforms, to move groups of shapes and sprites together,
+//! eg. nested UI elements, or the parts of a skeletal sprite.
+//!
+//! Transforms are pushed onto a stack, each in the local space of the one
+//! below it, and are composed on the CPU when batches are tessellated. Since
+//! shapes are transformed after being rotated, a rotated shape can still be
+//! part of a rotated group.
+//!
+//! # Examples
+//!
+//! ```
+//! use rgx::kit::transform::{self, TransformStack};
+//! use rgx::math::{Matrix4, Point2, Vector3};
+//!
+//! let mut stack = TransformStack::new();
+//!
+//! // A body, and an arm attached to it, scaled with the body.
+//! stack.push(Matrix4::from_translation(Vector3::new(100., 100., 0.)));
+//! stack.push(Matrix4::from_scale(2.));
+//! stack.push(Matrix4::from_translation(Vector3::new(10., 0., 0.)));
+//! assert_eq!(stack.apply(Point2::new(0., 0.)), Point2::new(120., 100.));
+//!
+//! stack.pop();
+//! stack.push(transform::rotation(std::f32::consts::FRAC_PI_2));
+//! let p = stack.apply(Point2::new(10., 0.));
+//! assert!((p.x - 100.).abs() < 1e-4 && (p.y - 120.).abs() < 1e-4);
+//! ```
+use crate::math::portable;
+use crate::math::*;
+
+/// A stack of transforms, each composed with the transforms below it.
+#[derive(Clone, Debug, Default)]
+pub struct TransformStack {
+    stack: Vec<Matrix4<f32>>,
+}
+
+impl TransformStack {
+    pub fn new() -> Self {
+        Self { stack: Vec::new() }
+    }
+
+    /// Push a transform, in the local space of the current transform.
+    pub fn push<T: Into<Matrix4<f32>>>(&mut self, transform: T) {
+        let current = self.current();
+        self.stack.push(current * transform.into());
+    }
+
+    /// Pop the last pushed transform. Returns the composed transform that
+    /// was current, or `None` if the stack was empty.
+    pub fn pop(&mut self) -> Option<Matrix4<f32>> {
+        self.stack.pop()
+    }
+
+    /// The composition of all pushed transforms, or the identity if none
+    /// were pushed.
+    pub fn current(&self) -> Matrix4<f32> {
+        self.stack.last().copied().unwrap_or_else(Matrix4::identity)
+    }
+
+    /// Number of pushed transforms.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// Transform a point by the current transform.
+    pub fn apply(&self, p: Point2<f32>) -> Point2<f32> {
+        apply(&self.current(), p)
+    }
+}
+
+/// A 2D transform, made of a translation, rotation, skew and scale around
+/// an origin. Converts to a matrix, to be added to batches or pushed onto a
+/// `TransformStack`.
+///
+/// ```
+/// use rgx::kit::transform::{self, Transform2D};
+/// use rgx::math::{Matrix4, Point2};
+///
+/// // Scale a 32x32 sprite by 2 around its center, and move it.
+/// let t = Transform2D::new().origin(16., 16.).scale(2., 2.).translate(100., 0.);
+/// let m: Matrix4<f32> = t.into();
+///
+/// assert_eq!(transform::apply(&m, Point2::new(0., 0.)), Point2::new(84., -16.));
+/// assert_eq!(transform::apply(&m, Point2::new(16., 16.)), Point2::new(116., 16.));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transform2D {
+    /// Translation, applied last.
+    pub translation: Vector2<f32>,
+    /// Counter-clockwise rotation around the origin, in radians.
+    pub rotation: f32,
+    /// Skew angles along the X and Y axes, in radians.
+    pub skew: Vector2<f32>,
+    /// Scale around the origin.
+    pub scale: Vector2<f32>,
+    /// Origin of the rotation, skew and scale, before translation.
+    pub origin: Point2<f32>,
+}
+
+impl Transform2D {
+    /// The identity transform.
+    pub fn new() -> Self {
+        Self {
+            translation: Vector2::new(0., 0.),
+            rotation: 0.,
+            skew: Vector2::new(0., 0.),
+            scale: Vector2::new(1., 1.),
+            origin: Point2::new(0., 0.),
+        }
+    }
+
+    pub fn translate(mut self, x: f32, y: f32) -> Self {
+        self.translation = Vector2::new(self.translation.x + x, self.translation.y + y);
+        self
+    }
+
+    pub fn rotate(mut self, angle: f32) -> Self {
+        self.rotation += angle;
+        self
+    }
+
+    pub fn skew(mut self, x: f32, y: f32) -> Self {
+        self.skew = Vector2::new(self.skew.x + x, self.skew.y + y);
+        self
+    }
+
+    pub fn scale(mut self, x: f32, y: f32) -> Self {
+        self.scale = Vector2::new(self.scale.x * x, self.scale.y * y);
+        self
+    }
+
+    pub fn origin(mut self, x: f32, y: f32) -> Self {
+        self.origin = Point2::new(x, y);
+        self
+    }
+
+    /// The transform as a matrix: the point is moved relative to the
+    /// origin, scaled, skewed, rotated, moved back and translated.
+    pub fn matrix(&self) -> Matrix4<f32> {
+        self.compose(
+            rotation(self.rotation),
+            (self.skew.x.tan(), self.skew.y.tan()),
+        )
+    }
+
+    /// The transform as a matrix, computed with portable trigonometry, so
+    /// that it's identical on all platforms. See `math::portable`.
+    ///
+    /// ```
+    /// use rgx::kit::transform::Transform2D;
+    ///
+    /// let t = Transform2D::new().rotate(0.5).skew(0.25, 0.);
+    /// let (a, b) = (t.matrix(), t.portable_matrix());
+    ///
+    /// assert!((a.x.x - b.x.x).abs() < 1e-6 && (a.y.x - b.y.x).abs() < 1e-6);
+    /// ```
+    pub fn portable_matrix(&self) -> Matrix4<f32> {
+        self.compose(
+            rotation_from(portable::sin_cos(self.rotation)),
+            (portable::tan(self.skew.x), portable::tan(self.skew.y)),
+        )
+    }
+
+    /// Compose the transform, given its rotation and the tangents of its
+    /// skew angles.
+    #[rustfmt::skip]
+    fn compose(&self, rotation: Matrix4<f32>, (tx, ty): (f32, f32)) -> Matrix4<f32> {
+        let (ox, oy) = (self.origin.x, self.origin.y);
+        let skew = Matrix4::new(
+            1.,  ty,  0., 0.,
+            tx,  1.,  0., 0.,
+            0.,  0.,  1., 0.,
+            0.,  0.,  0., 1.,
+        );
+
+        Matrix4::from_translation(Vector3::new(
+            self.translation.x + ox,
+            self.translation.y + oy,
+            0.,
+        )) * rotation
+            * skew
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, 1.)
+            * Matrix4::from_translation(Vector3::new(-ox, -oy, 0.))
+    }
+}
+
+impl Default for Transform2D {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<Transform2D> for Matrix4<f32> {
+    fn from(t: Transform2D) -> Self {
+        t.matrix()
+    }
+}
+
+/// A transform which converts to a matrix either natively, or with portable
+/// trigonometry, eg. for batches tessellated identically on all platforms.
+pub trait IntoMatrix {
+    fn into_matrix(self, portable: bool) -> Matrix4<f32>;
+}
+
+impl IntoMatrix for Matrix4<f32> {
+    fn into_matrix(self, _portable: bool) -> Matrix4<f32> {
+        self
+    }
+}
+
+impl IntoMatrix for Transform2D {
+    fn into_matrix(self, portable: bool) -> Matrix4<f32> {
+        if portable {
+            self.portable_matrix()
+        } else {
+            self.matrix()
+        }
+    }
+}
+
+/// A counter-clockwise rotation around the origin, in radians.
+pub fn rotation(angle: f32) -> Matrix4<f32> {
+    rotation_from(angle.sin_cos())
+}
+
+/// A counter-clockwise rotation, given the sine and cosine of its angle.
+#[rustfmt::skip]
+fn rotation_from((sin, cos): (f32, f32)) -> Matrix4<f32> {
+    Matrix4::new(
+        cos,  sin, 0., 0.,
+        -sin, cos, 0., 0.,
+        0.,   0.,  1., 0.,
+        0.,   0.,  0., 1.,
+    )
+}
+
+/// Transform a point by a matrix. The point is taken to be at depth zero.
+pub fn apply(m: &Matrix4<f32>, p: Point2<f32>) -> Point2<f32> {
+    let v = *m * Vector4::new(p.x, p.y, 0., 1.);
+    Point2::new(v.x, v.y)
+}
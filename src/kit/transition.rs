@@ -0,0 +1,299 @@
+//! Transitions between two rendered scenes, eg. between menus and levels.
+//!
+//! Both scenes are rendered to framebuffers, and a `Transition` composites
+//! them over the output, from the first to the second, over its duration.
+//! It's drawn with an effects pipeline created with `transition::blending`,
+//! which is prepared with `Transition::effect` every frame. Wipes reveal the
+//! second scene with the wipe pipeline, created with the same blending and
+//! prepared with `Transition::progress`, which thresholds the mask per
+//! fragment.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use rgx::kit::effects;
+//! use rgx::kit::procedural::{Image, Noise};
+//! use rgx::kit::transition::{self, Kind, Transition};
+//!
+//! let pipeline: effects::Pipeline = r.pipeline(transition::blending());
+//! let wipe: transition::Pipeline = r.pipeline(transition::blending());
+//! let sampler = r.sampler(Filter::Linear, Filter::Linear);
+//! let mask = Image::noise(128, 128, Noise::Perlin, 32., 4, 7);
+//! let mut transition = Transition::new(
+//!     &mut r,
+//!     &pipeline,
+//!     &wipe,
+//!     &sampler,
+//!     &menu,
+//!     &level,
+//!     Kind::Wipe(mask),
+//!     Duration::from_millis(800),
+//! );
+//!
+//! // Every frame, with both scenes rendered.
+//! transition.step(delta);
+//! r.update_pipeline(&pipeline, (transition.effect(), w, h), &mut frame);
+//! r.update_pipeline(&wipe, transition.progress(), &mut frame);
+//! {
+//!     let mut pass = frame.pass(PassOp::Clear(Rgba::BLACK), &output);
+//!
+//!     transition.draw(&pipeline, &wipe, &mut pass);
+//! }
+//! ```
+use std::time;
+
+use crate::core;
+use crate::core::{Binding, BindingType, BlendFactor, BlendOp, Blending, Rgba, Set, ShaderStage};
+use crate::kit::effects::{self, Effect};
+use crate::kit::procedural::Image;
+
+/// Blending of the pipeline drawing transitions, which mixes the scenes by
+/// the blend color.
+pub fn blending() -> Blending {
+    Blending::new(
+        BlendFactor::BlendColor,
+        BlendFactor::OneMinusBlendColor,
+        BlendOp::Add,
+    )
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Kind
+///////////////////////////////////////////////////////////////////////////
+
+/// A kind of transition.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Kind {
+    /// Fade from the first scene to the second.
+    Crossfade,
+    /// Reveal the second scene where the mask, stretched over the output,
+    /// is darker than the progress of the transition, eg. a horizontal
+    /// gradient for a wipe from left to right, or noise for a dissolve.
+    /// The mask is read from its red channel, and uploaded to a texture
+    /// when the transition is created.
+    Wipe(Image),
+    /// Pixelate the first scene into cells growing up to the given size, in
+    /// pixels of the scenes, then switch to the second scene and shrink
+    /// them back.
+    Pixelate { size: f32 },
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Transition
+///////////////////////////////////////////////////////////////////////////
+
+/// A transition between two scenes.
+pub struct Transition {
+    pub kind: Kind,
+    pub duration: time::Duration,
+
+    elapsed: time::Duration,
+    /// Bindings of the first and second scene.
+    from: core::BindingGroup,
+    to: core::BindingGroup,
+    quad: core::VertexBuffer,
+    /// Binding of the second scene and the mask of a wipe, and the mask
+    /// texture, kept alive with it.
+    revealed: Option<(core::BindingGroup, core::Texture)>,
+}
+
+impl Transition {
+    /// Create a transition between two scenes, drawn with the given
+    /// pipelines and sampler. The mask of a wipe is uploaded right away.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        r: &mut core::Renderer,
+        pipeline: &effects::Pipeline,
+        wipe: &Pipeline,
+        sampler: &core::Sampler,
+        from: &core::Framebuffer,
+        to: &core::Framebuffer,
+        kind: Kind,
+        duration: time::Duration,
+    ) -> Self {
+        let revealed = if let Kind::Wipe(ref mask) = kind {
+            // Thresholds are compared to the raw mask, not converted from sRGB.
+            let texture = r.texture_with_format(mask.w, mask.h, core::TextureFormat::Rgba8);
+            r.submit(&[core::Op::Fill(&texture, mask.pixels.as_slice())]);
+
+            Some((wipe.binding(r, to, sampler, &texture, sampler), texture))
+        } else {
+            None
+        };
+
+        Self {
+            kind,
+            duration,
+            elapsed: time::Duration::new(0, 0),
+            from: pipeline.binding(r, from, sampler),
+            to: pipeline.binding(r, to, sampler),
+            quad: effects::screen_quad(&r.device),
+            revealed,
+        }
+    }
+
+    /// Advance the transition by the given time.
+    pub fn step(&mut self, delta: time::Duration) {
+        self.elapsed = (self.elapsed + delta).min(self.duration);
+    }
+
+    /// Progress of the transition, from `0` to `1`.
+    pub fn progress(&self) -> f32 {
+        if self.duration.as_secs_f32() <= 0. {
+            return 1.;
+        }
+        (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.)
+    }
+
+    /// Whether the transition is over, and the second scene can be drawn on
+    /// its own.
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// The effect to prepare the pipeline with, this frame.
+    pub fn effect(&self) -> Effect {
+        match self.kind {
+            Kind::Pixelate { size } => {
+                // Cells grow until halfway, and shrink back.
+                let peak = 1. - (self.progress() * 2. - 1.).abs();
+                Effect::Pixelate {
+                    size: 1. + (size - 1.).max(0.) * peak,
+                }
+            }
+            _ => Effect::NONE,
+        }
+    }
+
+    /// Draw the transition over the target of the pass.
+    pub fn draw(&self, pipeline: &effects::Pipeline, wipe: &Pipeline, pass: &mut core::Pass) {
+        let t = self.progress();
+
+        pass.set_pipeline(pipeline);
+        pass.set_blend_color(Rgba::WHITE);
+
+        match self.kind {
+            Kind::Crossfade => {
+                pass.draw(&self.quad, &self.from);
+                pass.set_blend_color(Rgba::new(t, t, t, t));
+                pass.draw(&self.quad, &self.to);
+            }
+            Kind::Wipe(_) => {
+                pass.draw(&self.quad, &self.from);
+
+                if let Some((ref revealed, _)) = self.revealed {
+                    pass.set_pipeline(wipe);
+                    wipe.draw(revealed, pass);
+                }
+            }
+            Kind::Pixelate { .. } => {
+                if t < 0.5 {
+                    pass.draw(&self.quad, &self.from);
+                } else {
+                    pass.draw(&self.quad, &self.to);
+                }
+            }
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Pipeline
+///////////////////////////////////////////////////////////////////////////
+
+/// The uniforms of the wipe shader.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct Uniforms {
+    progress: f32,
+}
+
+/// Reveals the second scene of a wipe over the whole render target, where
+/// the mask is at most the progress of the transition.
+pub struct Pipeline {
+    pipeline: core::Pipeline,
+    bindings: core::BindingGroup,
+    buf: core::UniformBuffer,
+    vertices: core::VertexBuffer,
+}
+
+impl Pipeline {
+    fn binding(
+        &self,
+        renderer: &core::Renderer,
+        scene: &dyn core::Bind,
+        sampler: &core::Sampler,
+        mask: &core::Texture,
+        mask_sampler: &core::Sampler,
+    ) -> core::BindingGroup {
+        renderer.device.create_binding_group(
+            &self.pipeline.layout.sets[1],
+            &[scene, sampler, mask, mask_sampler],
+        )
+    }
+
+    fn draw(&self, binding: &core::BindingGroup, pass: &mut core::Pass) {
+        pass.draw(&self.vertices, binding);
+    }
+}
+
+impl<'a> core::AbstractPipeline<'a> for Pipeline {
+    /// The progress of the transition, from `0` to `1`.
+    type PrepareContext = f32;
+    type Uniforms = Uniforms;
+
+    fn description() -> core::PipelineDescription<'a> {
+        core::PipelineDescription {
+            vertex_layout: &[core::VertexFormat::Float2, core::VertexFormat::Float2],
+            pipeline_layout: &[
+                Set(&[Binding {
+                    binding: BindingType::UniformBuffer,
+                    stage: ShaderStage::Fragment,
+                }]),
+                Set(&[
+                    Binding {
+                        binding: BindingType::SampledTexture,
+                        stage: ShaderStage::Fragment,
+                    },
+                    Binding {
+                        binding: BindingType::Sampler,
+                        stage: ShaderStage::Fragment,
+                    },
+                    Binding {
+                        binding: BindingType::SampledTexture,
+                        stage: ShaderStage::Fragment,
+                    },
+                    Binding {
+                        binding: BindingType::Sampler,
+                        stage: ShaderStage::Fragment,
+                    },
+                ]),
+            ],
+            // TODO: Use `env("CARGO_MANIFEST_DIR")`
+            vertex_shader: include_bytes!("data/screen.vert.spv"),
+            fragment_shader: include_bytes!("data/wipe.frag.spv"),
+        }
+    }
+
+    fn setup(pipeline: core::Pipeline, dev: &core::Device) -> Self {
+        let vertices = effects::screen_quad(dev);
+        let buf = dev.create_uniform_buffer(&[Uniforms { progress: 0. }]);
+        let bindings = dev.create_binding_group(&pipeline.layout.sets[0], &[&buf]);
+
+        Self {
+            pipeline,
+            bindings,
+            buf,
+            vertices,
+        }
+    }
+
+    fn apply(&self, pass: &mut core::Pass) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_binding(&self.bindings, &[]);
+    }
+
+    fn prepare(&'a self, progress: f32) -> Option<(&'a core::UniformBuffer, Vec<Uniforms>)> {
+        Some((&self.buf, vec![Uniforms { progress }]))
+    }
+}
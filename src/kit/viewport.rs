@@ -0,0 +1,187 @@
+//! Lengths relative to the viewport, for resolution-independent layouts,
+//! eg. of a HUD.
+//!
+//! Lengths are kept in their relative form, and resolved against the
+//! viewport when drawing, so that a layout follows the window as it is
+//! resized.
+//!
+//! # Examples
+//!
+//! ```
+//! use rgx::kit::viewport::{px, vh, vw, Viewport};
+//!
+//! let viewport = Viewport::new(800, 600);
+//!
+//! // A bar across the bottom of the screen, with a 10 pixel margin.
+//! let bar = viewport.rect(px(10.), px(10.), vw(100.) - px(20.), vh(10.));
+//!
+//! assert_eq!(bar.x2, 790.);
+//! assert_eq!(bar.y2, 70.);
+//! ```
+use std::ops::{Add, Mul, Neg, Sub};
+
+use crate::kit;
+use crate::math::{Matrix4, Point2};
+use crate::rect::Rect;
+
+///////////////////////////////////////////////////////////////////////////
+// Length
+///////////////////////////////////////////////////////////////////////////
+
+/// A length in pixels and percentages of the viewport. Lengths in different
+/// units can be added together, eg. `vw(100.) - px(20.)`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Length {
+    /// Pixels.
+    pub px: f32,
+    /// Percentage of the viewport width.
+    pub vw: f32,
+    /// Percentage of the viewport height.
+    pub vh: f32,
+    /// Percentage of the smaller viewport dimension.
+    pub vmin: f32,
+    /// Percentage of the larger viewport dimension.
+    pub vmax: f32,
+}
+
+impl Length {
+    pub const ZERO: Self = Self {
+        px: 0.,
+        vw: 0.,
+        vh: 0.,
+        vmin: 0.,
+        vmax: 0.,
+    };
+
+    /// The length in pixels, in the given viewport.
+    pub fn resolve(self, viewport: &Viewport) -> f32 {
+        let (w, h) = (viewport.w as f32, viewport.h as f32);
+
+        self.px + (self.vw * w + self.vh * h + self.vmin * w.min(h) + self.vmax * w.max(h)) / 100.
+    }
+}
+
+impl Add for Length {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            px: self.px + other.px,
+            vw: self.vw + other.vw,
+            vh: self.vh + other.vh,
+            vmin: self.vmin + other.vmin,
+            vmax: self.vmax + other.vmax,
+        }
+    }
+}
+
+impl Sub for Length {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl Neg for Length {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self * -1.
+    }
+}
+
+impl Mul<f32> for Length {
+    type Output = Self;
+
+    fn mul(self, s: f32) -> Self {
+        Self {
+            px: self.px * s,
+            vw: self.vw * s,
+            vh: self.vh * s,
+            vmin: self.vmin * s,
+            vmax: self.vmax * s,
+        }
+    }
+}
+
+/// A length in pixels.
+pub fn px(v: f32) -> Length {
+    Length {
+        px: v,
+        ..Length::ZERO
+    }
+}
+
+/// A length in percent of the viewport width.
+pub fn vw(v: f32) -> Length {
+    Length {
+        vw: v,
+        ..Length::ZERO
+    }
+}
+
+/// A length in percent of the viewport height.
+pub fn vh(v: f32) -> Length {
+    Length {
+        vh: v,
+        ..Length::ZERO
+    }
+}
+
+/// A length in percent of the smaller viewport dimension.
+pub fn vmin(v: f32) -> Length {
+    Length {
+        vmin: v,
+        ..Length::ZERO
+    }
+}
+
+/// A length in percent of the larger viewport dimension.
+pub fn vmax(v: f32) -> Length {
+    Length {
+        vmax: v,
+        ..Length::ZERO
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Viewport
+///////////////////////////////////////////////////////////////////////////
+
+/// The viewport lengths are resolved against, usually the size of the swap
+/// chain. Positions are relative to the bottom-left corner, like with
+/// `kit::ortho`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Viewport {
+    pub w: u32,
+    pub h: u32,
+}
+
+impl Viewport {
+    pub fn new(w: u32, h: u32) -> Self {
+        Self { w, h }
+    }
+
+    /// The orthographic projection of the viewport.
+    pub fn ortho(&self) -> Matrix4<f32> {
+        kit::ortho(self.w, self.h)
+    }
+
+    /// Resolve a length to pixels.
+    pub fn resolve(&self, length: Length) -> f32 {
+        length.resolve(self)
+    }
+
+    /// Resolve a position to pixels.
+    pub fn point(&self, x: Length, y: Length) -> Point2<f32> {
+        Point2::new(x.resolve(self), y.resolve(self))
+    }
+
+    /// Resolve a rectangle at the given position, of the given size.
+    pub fn rect(&self, x: Length, y: Length, w: Length, h: Length) -> Rect<f32> {
+        let (x, y) = (x.resolve(self), y.resolve(self));
+
+        Rect::new(x, y, x + w.resolve(self), y + h.resolve(self))
+    }
+}
@@ -20,6 +20,7 @@ pub use num_traits::{cast, Float, One, Zero};
 /// 2D vector.
 #[repr(C)]
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector2<S> {
     pub x: S,
     pub y: S,
@@ -119,6 +120,26 @@ where
     }
 }
 
+impl<S> std::ops::AddAssign<Vector2<S>> for Vector2<S>
+where
+    S: std::ops::AddAssign + Copy,
+{
+    fn add_assign(&mut self, other: Vector2<S>) {
+        self.x += other.x;
+        self.y += other.y;
+    }
+}
+
+impl<S> std::ops::SubAssign<Vector2<S>> for Vector2<S>
+where
+    S: std::ops::SubAssign + Copy,
+{
+    fn sub_assign(&mut self, other: Vector2<S>) {
+        self.x -= other.x;
+        self.y -= other.y;
+    }
+}
+
 impl<S> std::ops::Mul<S> for Vector2<S>
 where
     S: std::ops::Mul<Output = S> + Copy,
@@ -142,6 +163,7 @@ impl<S> From<Point2<S>> for Vector2<S> {
 /// 3D vector.
 #[repr(C)]
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector3<S> {
     pub x: S,
     pub y: S,
@@ -157,6 +179,7 @@ impl<S> Vector3<S> {
 /// 4D vector.
 #[repr(C)]
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector4<S> {
     pub x: S,
     pub y: S,
@@ -204,6 +227,7 @@ where
 
 #[repr(C)]
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point2<S> {
     pub x: S,
     pub y: S,
@@ -256,6 +280,16 @@ where
     }
 }
 
+impl<S> std::ops::AddAssign<Vector2<S>> for Point2<S>
+where
+    S: std::ops::AddAssign + Copy,
+{
+    fn add_assign(&mut self, other: Vector2<S>) {
+        self.x += other.x;
+        self.y += other.y;
+    }
+}
+
 impl<S> std::ops::Sub<Vector2<S>> for Point2<S>
 where
     S: std::ops::Sub<Output = S> + Copy,
@@ -275,6 +309,7 @@ where
 /// This type is marked as `#[repr(C)]`.
 #[repr(C)]
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Matrix4<S> {
     /// The first column of the matrix.
     pub x: Vector4<S>,
@@ -369,6 +404,17 @@ where
     }
 }
 
+impl<S> std::ops::Mul<Vector4<S>> for Matrix4<S>
+where
+    S: std::ops::Mul<Output = S> + std::ops::Add<Output = S> + Copy,
+{
+    type Output = Vector4<S>;
+
+    fn mul(self, v: Vector4<S>) -> Vector4<S> {
+        self.x * v.x + self.y * v.y + self.z * v.z + self.w * v.w
+    }
+}
+
 /// An orthographic projection with arbitrary left/right/bottom/top distances
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Ortho<S> {
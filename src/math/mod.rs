@@ -9,3 +9,5 @@ pub use num_traits::{Float, One, Zero};
 pub use cgmath::prelude::*;
 #[cfg(feature = "cgmath")]
 pub use cgmath::*;
+
+pub mod portable;
@@ -0,0 +1,158 @@
+//! Portable trigonometric functions, giving bit-identical results on all
+//! platforms.
+//!
+//! The standard library defers to the platform's math library for
+//! functions such as `sin` or `atan2`, whose results can differ in the
+//! last bits across platforms. The functions here only rely on basic
+//! arithmetic and square roots, which are exactly specified by IEEE 754.
+//! They are computed in double precision, so they are also accurate to
+//! within one unit in the last place.
+//!
+//! # Examples
+//!
+//! ```
+//! use rgx::math::portable;
+//!
+//! // These values are the same on every platform.
+//! assert_eq!(portable::sin(1.0).to_bits(), 0x3f57_6aa4);
+//! assert_eq!(portable::cos(1.0).to_bits(), 0x3f0a_5140);
+//! assert_eq!(portable::atan2(1.0, 2.0).to_bits(), 0x3eed_6338);
+//! ```
+
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_6, PI};
+
+/// `π/2`, split into a high part with trailing zero bits, so that
+/// multiples of it are exact, and the remainder.
+const FRAC_PI_2_HI: f64 = 1.570_796_326_734_125_6;
+const FRAC_PI_2_LO: f64 = 6.077_100_506_506_192e-11;
+
+/// The sine and cosine of `x`, in radians.
+pub fn sin_cos(x: f32) -> (f32, f32) {
+    if !x.is_finite() {
+        return (f32::NAN, f32::NAN);
+    }
+    let x = x as f64;
+    // Reduce the angle to `[-π/4, π/4]`, and track the quadrant.
+    let k = (x / FRAC_PI_2).round();
+    let r = (x - k * FRAC_PI_2_HI) - k * FRAC_PI_2_LO;
+    let (s, c) = (sin_poly(r), cos_poly(r));
+
+    let (s, c) = match (k as i64).rem_euclid(4) {
+        0 => (s, c),
+        1 => (c, -s),
+        2 => (-s, -c),
+        _ => (-c, s),
+    };
+    (s as f32, c as f32)
+}
+
+/// The sine of `x`, in radians.
+pub fn sin(x: f32) -> f32 {
+    sin_cos(x).0
+}
+
+/// The cosine of `x`, in radians.
+pub fn cos(x: f32) -> f32 {
+    sin_cos(x).1
+}
+
+/// The tangent of `x`, in radians.
+pub fn tan(x: f32) -> f32 {
+    let (s, c) = sin_cos(x);
+    s / c
+}
+
+/// The four quadrant arctangent of `y` and `x`, in the `[-π, π]` range.
+pub fn atan2(y: f32, x: f32) -> f32 {
+    if x.is_nan() || y.is_nan() {
+        return f32::NAN;
+    }
+    let (y, x) = (y as f64, x as f64);
+
+    let a = if x == 0. {
+        if y == 0. {
+            // Follow the standard library for signed zeroes.
+            return if x.is_sign_negative() {
+                (PI as f32).copysign(y as f32)
+            } else {
+                0f32.copysign(y as f32)
+            };
+        }
+        FRAC_PI_2.copysign(y)
+    } else {
+        let a = atan(y / x);
+
+        if x > 0. {
+            a
+        } else if y.is_sign_positive() {
+            a + PI
+        } else {
+            a - PI
+        }
+    };
+    a as f32
+}
+
+/// The arctangent of `x`.
+fn atan(x: f64) -> f64 {
+    if x.is_infinite() {
+        return FRAC_PI_2.copysign(x);
+    }
+    let (x, sign) = (x.abs(), x.signum());
+
+    // Reduce to `[0, 1]`, with `atan(x) = π/2 - atan(1/x)`, then to
+    // `[0, tan(π/12)]`, with `atan(x) = π/6 + atan((x√3 - 1) / (x + √3))`.
+    let (x, base, flip) = if x > 1. {
+        (1. / x, FRAC_PI_2, true)
+    } else {
+        (x, 0., false)
+    };
+    let sqrt3 = 3f64.sqrt();
+    let (x, offset) = if x > 2. - sqrt3 {
+        ((x * sqrt3 - 1.) / (x + sqrt3), FRAC_PI_6)
+    } else {
+        (x, 0.)
+    };
+    let a = offset + atan_poly(x);
+
+    sign * if flip { base - a } else { a }
+}
+
+/// Taylor series of `sin(x)`, for `|x| <= π/4`.
+fn sin_poly(x: f64) -> f64 {
+    let x2 = x * x;
+    let mut term = x;
+    let mut sum = x;
+
+    for n in 1..10 {
+        term *= -x2 / ((2 * n) * (2 * n + 1)) as f64;
+        sum += term;
+    }
+    sum
+}
+
+/// Taylor series of `cos(x)`, for `|x| <= π/4`.
+fn cos_poly(x: f64) -> f64 {
+    let x2 = x * x;
+    let mut term = 1.;
+    let mut sum = 1.;
+
+    for n in 1..10 {
+        term *= -x2 / ((2 * n - 1) * (2 * n)) as f64;
+        sum += term;
+    }
+    sum
+}
+
+/// Taylor series of `atan(x)`, for `|x| <= tan(π/12)`.
+fn atan_poly(x: f64) -> f64 {
+    let x2 = x * x;
+    let mut power = x;
+    let mut sum = x;
+
+    for n in 1..16 {
+        power *= -x2;
+        sum += power / (2 * n + 1) as f64;
+    }
+    sum
+}
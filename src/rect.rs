@@ -3,6 +3,7 @@ use crate::math::{Point2, Vector2};
 
 /// A generic rectangle with bottom left and top right coordinates.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rect<T> {
     pub x1: T,
     pub y1: T,